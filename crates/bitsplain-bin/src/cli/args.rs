@@ -1,9 +1,14 @@
 use std::collections::HashMap;
+use std::ops::Range;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+use bitsplain::bitcoin::{Network, ScriptBuf};
+use bitsplain::decode::DecoderFilter;
+use bitsplain::query::Query;
+use bitsplain::tree::Category;
 use bitsplain_format::*;
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum, ValueHint};
 
 #[derive(Parser, Debug)]
 #[command(about = "Decodes Bitcoin-related binary data")]
@@ -14,17 +19,132 @@ use clap::{Parser, ValueEnum};
 #[command(bin_name = "bitsplain")]
 #[command(version, author)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Value to parse (hex, base64, base58, …)
     pub input: Option<String>,
 
+    /// Download input from URL
+    #[arg(long, conflicts_with_all = ["input", "file", "watch"])]
+    pub url: Option<String>,
+
+    /// Request object ('txid', 'block' or 'header') from configured Bitcoin Core or Esplora
+    #[arg(long, num_args = 2, value_names = ["OBJECT", "ID"], conflicts_with_all = ["input", "file", "watch", "url"])]
+    pub rpc: Vec<String>,
+
+    /// Add amounts, addresses and script types of outputs spent by transaction and sighashes and validity of its signatures, obtained from configured Bitcoin Core or Esplora
+    #[arg(long, default_value = "false")]
+    pub enrich: bool,
+
+    /// Amount (in satoshis) of output spent by transaction, used to calculate fee and verify signatures; repeated for every input, in order of inputs
+    #[arg(long = "amount", value_name = "SAT")]
+    pub amounts: Vec<u64>,
+
+    /// Script (in hex) of output spent by transaction, used to interpret witness and verify signatures; repeated for every input, in order of inputs
+    #[arg(long = "spent-script", value_name = "HEX", value_parser = script_hex)]
+    pub spent_scripts: Vec<ScriptBuf>,
+
+    /// Add step-by-step trace of execution of scripts of transaction; scripts of inputs are traced if spent outputs are known (see --enrich, --amount and --spent-script)
+    #[arg(long, default_value = "false")]
+    pub trace: bool,
+
+    /// Snapshot of Lightning network graph (JSON of 'lightning-cli listnodes' or 'lncli describegraph') from which aliases of node IDs are taken; bitsplain/nodes.json in configuration directory is used if it exists
+    #[arg(long, value_name = "FILE")]
+    pub node_aliases: Option<PathBuf>,
+
     /// Level of details to display
     #[arg(long, short)]
     pub details: Option<Detail>,
 
+    /// When to use colors; 'auto' uses them on terminal unless NO_COLOR is set
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    pub color: ColorChoice,
+
+    /// Network for which addresses are shown (bitcoin, testnet, signet or regtest)
+    #[arg(long)]
+    pub network: Option<Network>,
+
     #[arg(long, default_value = "false")]
     /// Show all known decoders
     pub list_decoders: bool,
 
+    #[arg(long, default_value = "false")]
+    /// Show all known output formats
+    pub list_formats: bool,
+
+    #[arg(long, default_value = "false", conflicts_with_all = ["identify", "all"])]
+    /// Verify that input decodes completely and its checksums, signatures etc. are valid; exit with 1 if verification fails, 2 if input cannot be decoded
+    pub check: bool,
+
+    #[arg(long, default_value = "false")]
+    /// Only list decoders that accept input, with encoding and length of decoded data (tab-separated)
+    pub identify: bool,
+
+    #[arg(long, default_value = "false", conflicts_with_all = ["input", "file", "lines", "print_hex"])]
+    /// Decode every new content of clipboard or, if standard input is not terminal, every line of it
+    pub watch: bool,
+
+    #[arg(long, default_value = "false")]
+    /// Decode every line of input separately
+    pub lines: bool,
+
+    /// Use only selected decoders (e. g. 'btc/tx,ln/*')
+    #[arg(long, value_name = "DECODERS")]
+    pub only: Option<DecoderFilter>,
+
+    /// Decode only by this decoder (e. g. 'btc/tx') and explain why it fails
+    #[arg(long, value_name = "DECODER", conflicts_with = "only", value_parser = single_decoder)]
+    pub decoder: Option<DecoderFilter>,
+
+    /// Load decoder plugins from <config dir>/bitsplain/plugins
+    #[arg(long)]
+    pub plugins: bool,
+
+    /// Load decoder plugins from directory
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub plugin_dir: Option<PathBuf>,
+
+    /// Show only nodes matching the query (e. g. '**/Sequence', '**/[datatype=txid]')
+    #[arg(long, value_name = "QUERY")]
+    pub query: Option<Query>,
+
+    /// Show only nodes of the categories (identifier, amount, signature, key, time, locktime, fee), e. g. 'amount,fee'
+    #[arg(long, value_name = "CATEGORIES", value_delimiter = ',')]
+    pub only_category: Vec<Category>,
+
+    /// Print only plain values of nodes matching the query, one per line (e. g. 'Output List/0/Amount')
+    #[arg(long, value_name = "QUERY", conflicts_with = "query")]
+    pub select: Option<Query>,
+
+    /// Write raw bytes of the node matching the query (e. g. '**/Witness Program')
+    #[arg(long, value_name = "QUERY", conflicts_with_all = ["query", "select"])]
+    pub extract: Option<Query>,
+
+    /// Change value of a leaf and re-encode the data (e. g. 'Lock Time=800000'); may be repeated
+    #[arg(long, value_name = "QUERY=VALUE")]
+    pub set: Vec<Assignment>,
+
+    /// Annotate only this many levels of structures in detail, deeper ones are shown as single values
+    #[arg(long, value_name = "LEVELS")]
+    pub depth: Option<usize>,
+
+    /// Annotate structures overlapping the bytes in detail regardless of --depth (e. g. '1200..1450' or '1200')
+    #[arg(long, value_name = "RANGE", value_parser = byte_range)]
+    pub focus: Option<Range<usize>>,
+
+    /// Render candidate of the given number instead of the first one (see the list of candidates)
+    #[arg(long, value_name = "N", conflicts_with = "all")]
+    pub candidate: Option<usize>,
+
+    #[arg(long, default_value = "false")]
+    /// Render all candidates, one after another
+    pub all: bool,
+
+    #[arg(long, default_value = "false")]
+    /// Accept decoders that leave unparsed data at the end
+    pub partial: bool,
+
     #[arg(long, default_value = "false")]
     /// Hex dump raw bytes
     pub print_hex: bool,
@@ -38,36 +158,111 @@ pub struct Args {
     pub show_ids: Option<bool>,
 
     /// Read data from file
-    #[arg(short = 'i', display_order = 0)]
+    #[arg(short = 'i', display_order = 0, value_hint = ValueHint::FilePath)]
     pub file: Option<PathBuf>,
 
     /// Save result into file
-    #[arg(short = 'o')]
+    #[arg(short = 'o', value_hint = ValueHint::FilePath)]
     pub outfile: Option<PathBuf>,
 
-    /// Output format
+    /// Output format (see --list-formats)
     #[arg(
         long,
         short = 'f',
         value_name = "FORMAT",
         display_order = 100,
         default_value = "pretty",
-        value_enum
+        value_parser = output_format
     )]
-    pub format: Format,
+    pub format: &'static OutputFormat,
 
     /// Set format parameter.
-    #[arg(short = 'P', value_name = "KEY=VALUE")]
+    #[arg(short = 'P', value_name = "KEY=VALUE", long_help = params_help())]
     pub params: Vec<Param>,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
-pub enum Format {
-    Pretty,
-    Json,
-    Html,
-    Png,
-    Xml,
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Explore decoded data in interactive terminal interface
+    Tui {
+        /// Value to parse (hex, base64, base58, …)
+        input: Option<String>,
+
+        /// Read data from file
+        #[arg(short = 'i')]
+        file: Option<PathBuf>,
+    },
+
+    /// Compare two inputs decoded by the same decoder
+    Diff {
+        /// Original value (hex, base64, base58, …)
+        old: String,
+
+        /// Changed value (hex, base64, base58, …)
+        new: String,
+    },
+
+    /// Print script of shell completions, including known decoders and formats
+    Completions {
+        /// Shell for which the script is generated
+        shell: clap_complete::Shell,
+    },
+}
+
+/// When output is colored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Find registered output format of the given name.
+fn output_format(name: &str) -> Result<&'static OutputFormat, String> {
+    find_format(name).ok_or_else(|| format!("unknown format '{name}' (see --list-formats)"))
+}
+
+/// Description of `-P` listing parameters of all formats that have some.
+fn params_help() -> String {
+    let mut help = "Set format parameter.".to_string();
+    all_formats()
+        .iter()
+        .filter(|f| !f.params.is_empty())
+        .for_each(|f| {
+            help.push_str(&format!("\n\n{}:", f.name));
+            f.params
+                .iter()
+                .for_each(|p| help.push_str(&format!("\n  {:10} {}", p.name, p.description)));
+        });
+    help
+}
+
+/// Parse pattern 'group/symbol' selecting exactly one decoder.
+fn single_decoder(s: &str) -> Result<DecoderFilter, String> {
+    match s.parse()? {
+        f @ DecoderFilter::Symbol { .. } => Ok(f),
+        _ => Err(format!(
+            "'{s}' does not select single decoder, expected 'group/symbol'"
+        )),
+    }
+}
+
+/// Parse script from hex.
+fn script_hex(s: &str) -> Result<ScriptBuf, String> {
+    ScriptBuf::from_hex(s).map_err(|_| format!("'{s}' is not a script in hex"))
+}
+
+/// Parse range of bytes 'from..to' or a single byte 'offset'.
+fn byte_range(s: &str) -> Result<Range<usize>, String> {
+    let offset = |o: &str| {
+        o.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("'{o}' is not a byte offset"))
+    };
+    match s.split_once("..") {
+        Some((from, to)) => Ok(offset(from)?..offset(to)?),
+        None => offset(s).map(|o| o..o + 1),
+    }
 }
 
 /// A simple key-value parameter that can be specified by command line
@@ -100,6 +295,28 @@ impl FromStr for Param {
     }
 }
 
+/// New value of leaves selected by query.
+#[derive(Clone, Debug)]
+pub struct Assignment {
+    pub query: Query,
+    pub value: String,
+}
+
+impl FromStr for Assignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Query may contain '=' in filters, value is after the last one.
+        match s.rsplit_once('=') {
+            Some((query, value)) => Ok(Assignment {
+                query: query.parse()?,
+                value: value.to_string(),
+            }),
+            None => Err("expected QUERY=VALUE".to_string()),
+        }
+    }
+}
+
 impl<'a> FromIterator<&'a Param> for HashMap<String, String> {
     fn from_iter<T: IntoIterator<Item = &'a Param>>(iter: T) -> Self {
         iter.into_iter()
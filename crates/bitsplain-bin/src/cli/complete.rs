@@ -0,0 +1,55 @@
+//! Shell completions generated at runtime from definition of command-line
+//! arguments, so values of `--decoder`, `--only` and `--format` contain
+//! decoders and formats that are actually available, including plugins.
+
+use std::io::Write;
+
+use bitsplain::decode::all_decoders;
+use bitsplain_format::all_formats;
+use clap::builder::{PossibleValue, PossibleValuesParser};
+use clap::{Arg, CommandFactory};
+use clap_complete::Shell;
+
+use crate::args::Args;
+
+/// Writes script of completions for the shell, with known decoders and
+/// formats offered as values of the options that select them.
+pub fn generate<W: Write>(shell: Shell, out: &mut W) {
+    let mut decoders = all_decoders()
+        .iter()
+        .map(|d| PossibleValue::new(format!("{}/{}", d.group, d.symbol)).help(d.title))
+        .collect::<Vec<_>>();
+    decoders.sort_by_key(|v| v.get_name().to_string());
+
+    let mut groups = all_decoders()
+        .iter()
+        .map(|d| format!("{}/*", d.group))
+        .collect::<Vec<_>>();
+    groups.sort();
+    groups.dedup();
+
+    let formats = all_formats()
+        .iter()
+        .map(|f| PossibleValue::new(f.name).help(f.description))
+        .collect::<Vec<_>>();
+
+    let values = |values: Vec<PossibleValue>| {
+        move |arg: Arg| arg.value_parser(PossibleValuesParser::new(values))
+    };
+
+    let mut command = Args::command()
+        .mut_arg("decoder", values(decoders.clone()))
+        .mut_arg(
+            "only",
+            values(
+                groups
+                    .into_iter()
+                    .map(PossibleValue::new)
+                    .chain(decoders)
+                    .collect(),
+            ),
+        )
+        .mut_arg("format", values(formats));
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
@@ -0,0 +1,67 @@
+//! Listing of differences between two inputs decoded by the same decoder.
+
+use std::io::{Result, Write};
+
+use bitsplain::decode::Candidate;
+use bitsplain::diff::{diff, Change};
+use bitsplain::tree::{Leaf, Node, Tree};
+use termion::{color, style};
+
+/// Labels of nodes on the way to the node.
+fn name(tree: &Tree, node: &Node) -> String {
+    tree.labels(node.path()).join(" › ")
+}
+
+/// Range of bytes of real leaf.
+fn location(node: &Node) -> String {
+    match node {
+        Node::Leaf(Leaf::Real(l)) => format!("{}..{}", l.location.from, l.location.to),
+        _ => String::new(),
+    }
+}
+
+pub fn run<W: Write>(old: &Candidate, new: &Candidate, out: &mut W) -> Result<()> {
+    writeln!(out, "{}{}{}", style::Bold, old.decoder.title, style::Reset)?;
+
+    let changes = diff(&old.annotations, &new.annotations);
+    if changes.is_empty() {
+        return writeln!(out, "No differences.");
+    }
+
+    changes.iter().try_for_each(|change| match change {
+        Change::Added(n) => writeln!(
+            out,
+            "{}+ {}: {}{}",
+            color::Fg(color::Green),
+            name(&new.annotations, n),
+            n.information().value.preview(),
+            color::Fg(color::Reset)
+        ),
+        Change::Removed(o) => writeln!(
+            out,
+            "{}- {}: {}{}",
+            color::Fg(color::Red),
+            name(&old.annotations, o),
+            o.information().value.preview(),
+            color::Fg(color::Reset)
+        ),
+        Change::Changed { old: o, new: n } => writeln!(
+            out,
+            "{}~ {}: {} → {}{}",
+            color::Fg(color::Yellow),
+            name(&new.annotations, n),
+            o.information().value.preview(),
+            n.information().value.preview(),
+            color::Fg(color::Reset)
+        ),
+        Change::Moved { old: o, new: n } => writeln!(
+            out,
+            "{}  {}: moved from {} to {}{}",
+            style::Faint,
+            name(&new.annotations, n),
+            location(o),
+            location(n),
+            style::Reset
+        ),
+    })
+}
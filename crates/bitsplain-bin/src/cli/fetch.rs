@@ -0,0 +1,221 @@
+//! Obtaining of input from network, either from URL or by asking
+//! Bitcoin Core (JSON-RPC) or Esplora for an object by its ID.
+//!
+//! Requests are made by command `curl`. Endpoints are configured
+//! in configuration file:
+//!
+//! ```toml
+//! [rpc]
+//! url = "http://127.0.0.1:8332"
+//! cookie = "/home/user/.bitcoin/.cookie" # or user and password
+//!
+//! [esplora]
+//! url = "https://blockstream.info/api"
+//! ```
+//!
+//! If both are configured, Bitcoin Core is used.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+use bitsplain::bitcoin::consensus::deserialize;
+use bitsplain::bitcoin::{Transaction, Txid};
+use bitsplain::decode::Input;
+use serde::Deserialize;
+
+/// Connection to Bitcoin Core.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RpcConfig {
+    pub url: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub cookie: Option<PathBuf>,
+}
+
+/// Connection to Esplora.
+#[derive(Clone, Debug, Deserialize)]
+pub struct EsploraConfig {
+    pub url: String,
+}
+
+/// Kind of object that can be requested by its ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Object {
+    /// Transaction by its txid.
+    Tx,
+    /// Block by its hash.
+    Block,
+    /// Block header by hash of the block.
+    Header,
+}
+
+impl FromStr for Object {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tx" | "txid" => Ok(Object::Tx),
+            "block" => Ok(Object::Block),
+            "header" => Ok(Object::Header),
+            _ => Err(format!(
+                "unknown object '{s}', expected 'txid', 'block' or 'header'"
+            )),
+        }
+    }
+}
+
+/// Downloads content of the URL.
+pub fn url(url: &str) -> Result<Input, String> {
+    let body = curl(&["--location", url])?;
+    // Textual responses (e. g. hex) are decoded as strings, without white space
+    // around them, as they often end with new line, which is not part of data.
+    Ok(match std::str::from_utf8(&body).map(str::trim) {
+        Ok(text) if !text.is_empty() && text.bytes().all(|b| b.is_ascii_graphic()) => {
+            Input::String(text.to_string())
+        }
+        _ => Input::Binary(body.into()),
+    })
+}
+
+/// Requests object from Bitcoin Core or, if it is not configured, from Esplora.
+pub fn object(
+    rpc: Option<&RpcConfig>,
+    esplora: Option<&EsploraConfig>,
+    object: Object,
+    id: &str,
+) -> Result<Input, String> {
+    // IDs are put into URLs and requests, so they are checked beforehand.
+    if id.len() != 64 || hex::decode(id).is_err() {
+        return Err(format!("'{id}' is not a valid ID"));
+    }
+
+    match (rpc, esplora) {
+        (Some(rpc), _) => core(rpc, object, id),
+        (None, Some(esplora)) => {
+            let path = match object {
+                Object::Tx => format!("tx/{id}/raw"),
+                Object::Block => format!("block/{id}/raw"),
+                Object::Header => format!("block/{id}/header"),
+            };
+            url(&format!("{}/{path}", esplora.url.trim_end_matches('/')))
+        }
+        (None, None) => Err("neither [rpc] nor [esplora] is configured".to_string()),
+    }
+}
+
+/// Requests transaction by its txid, see [`object`].
+pub fn transaction(
+    rpc: Option<&RpcConfig>,
+    esplora: Option<&EsploraConfig>,
+    txid: &Txid,
+) -> Result<Transaction, String> {
+    let bytes = match object(rpc, esplora, Object::Tx, &txid.to_string())? {
+        Input::String(hex) => hex::decode(hex.trim()).map_err(|e| e.to_string())?,
+        Input::Binary(bytes) => bytes.to_vec(),
+    };
+    let tx = deserialize::<Transaction>(&bytes).map_err(|e| format!("transaction {txid}: {e}"))?;
+    if tx.compute_txid() == *txid {
+        Ok(tx)
+    } else {
+        Err(format!("received transaction is not {txid}"))
+    }
+}
+
+fn core(rpc: &RpcConfig, object: Object, id: &str) -> Result<Input, String> {
+    let (method, params) = match object {
+        Object::Tx => ("getrawtransaction", format!("[\"{id}\"]")),
+        Object::Block => ("getblock", format!("[\"{id}\", 0]")),
+        Object::Header => ("getblockheader", format!("[\"{id}\", false]")),
+    };
+    let request =
+        format!(r#"{{"jsonrpc":"1.0","id":"bitsplain","method":"{method}","params":{params}}}"#);
+
+    let credentials = match (&rpc.cookie, &rpc.user, &rpc.password) {
+        (Some(cookie), _, _) => std::fs::read_to_string(cookie)
+            .map(|c| c.trim().to_string())
+            .map_err(|e| format!("could not read cookie {}: {e}", cookie.display()))?,
+        (None, Some(user), password) => {
+            format!("{user}:{}", password.as_deref().unwrap_or_default())
+        }
+        (None, None, _) => return Err("[rpc] needs either cookie or user".to_string()),
+    };
+
+    // Credentials are passed in configuration on standard input, so that
+    // they do not appear in list of processes.
+    let config = format!(
+        "user = \"{}\"\n",
+        credentials.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+
+    // Bitcoin Core answers errors with status 500, the body explains them.
+    let response = curl_any(
+        &config,
+        &[
+            "--header",
+            "Content-Type: application/json",
+            "--data-binary",
+            &request,
+            &rpc.url,
+        ],
+    )?;
+
+    #[derive(Deserialize)]
+    struct Response {
+        result: Option<String>,
+        error: Option<RpcError>,
+    }
+
+    #[derive(Deserialize)]
+    struct RpcError {
+        message: String,
+    }
+
+    match serde_json::from_slice::<Response>(&response) {
+        Ok(Response {
+            result: Some(hex), ..
+        }) => Ok(Input::String(hex)),
+        Ok(Response { error: Some(e), .. }) => Err(format!("Bitcoin Core: {}", e.message)),
+        _ => Err("unexpected response of Bitcoin Core".to_string()),
+    }
+}
+
+/// Body of successful response.
+fn curl(args: &[&str]) -> Result<Vec<u8>, String> {
+    curl_with(&["--fail"], "", args)
+}
+
+/// Body of response, regardless of its status. Curl reads additional
+/// options from `config` (in format of its configuration file).
+fn curl_any(config: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+    curl_with(&["--config", "-"], config, args)
+}
+
+fn curl_with(flags: &[&str], config: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error"])
+        .args(flags)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run curl: {e}"))?;
+
+    // Dropping stdin after writing closes it, so that curl does not wait for more.
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(config.as_bytes())
+            .map_err(|e| format!("could not pass configuration to curl: {e}"))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("could not run curl: {e}"))?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
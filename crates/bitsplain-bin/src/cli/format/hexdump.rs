@@ -0,0 +1,177 @@
+//! Hex dump in which every byte is colored by the leaf it belongs to,
+//! followed by a legend of the leaves and warnings and links, if there are any.
+//!
+//! Supported parameters:
+//!
+//! - `width` – number of bytes in a row, default 16.
+
+use std::io::Write;
+
+use bitsplain::decode::Candidate;
+use bitsplain::tree::Leaf;
+use bitsplain_format::*;
+use bitsplain_format_pretty::Plain;
+use termion::{color, style};
+
+/// Maximum number of characters of value in legend.
+const MAX_VALUE: usize = 48;
+
+fn paint(palette: &Palette, index: Option<usize>, text: &str) -> String {
+    match index {
+        Some(i) => {
+            let [r, g, b] = palette.rgb(i);
+            format!(
+                "{}{}{text}{}{}",
+                color::Bg(color::Rgb(r, g, b)),
+                color::Fg(color::Black),
+                color::Fg(color::Reset),
+                color::Bg(color::Reset)
+            )
+        }
+        None => text.to_string(),
+    }
+}
+
+pub fn render<W: Write>(candidate: Candidate, ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
+    if ctx.settings.format.pretty.use_color {
+        render_colored(candidate, ctx, out)
+    } else {
+        render_colored(candidate, ctx, &mut Plain::new(out))
+    }
+}
+
+fn render_colored<W: Write>(
+    candidate: Candidate,
+    ctx: &Ctx,
+    out: &mut W,
+) -> Result<(), FormatError> {
+    let width = match ctx.param_or("width", 16usize)? {
+        0 => return Err(FormatError::invalid_param("width", 0)),
+        w => w,
+    };
+
+    let data: &[u8] = candidate.data.as_ref();
+    let leaves = candidate.annotations.leaves();
+
+    // Index of leaf for every byte.
+    let mut owners = vec![None; data.len()];
+    leaves.iter().for_each(|l| {
+        if let Leaf::Real(l) = l {
+            owners[l.location.range()]
+                .iter_mut()
+                .for_each(|o| *o = Some(l.location.index));
+        }
+    });
+
+    writeln!(
+        out,
+        "{}{}{}",
+        style::Bold,
+        candidate.decoder.title,
+        style::Reset
+    )?;
+    writeln!(out)?;
+
+    for (row, bytes) in data.chunks(width).enumerate() {
+        let from = row * width;
+        let hex = bytes
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let owner = owners[from + i];
+                // Space between bytes is colored if both belong to the same leaf.
+                let space = match owners.get(from + i + 1) {
+                    Some(next) if i + 1 < bytes.len() && *next == owner => {
+                        paint(&ctx.palette, owner, " ")
+                    }
+                    _ => " ".to_string(),
+                };
+                format!("{}{space}", paint(&ctx.palette, owner, &format!("{b:02x}")))
+            })
+            .collect::<String>();
+        let ascii = bytes
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let c = if b.is_ascii_graphic() || *b == b' ' {
+                    *b as char
+                } else {
+                    '.'
+                };
+                paint(&ctx.palette, owners[from + i], &c.to_string())
+            })
+            .collect::<String>();
+        let missing = width - bytes.len();
+        writeln!(out, "{from:08x}  {hex}{} {ascii}", " ".repeat(missing * 3))?;
+    }
+
+    writeln!(out)?;
+
+    let label_width = leaves
+        .iter()
+        .map(|l| l.information().label.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    leaves.iter().try_for_each(|l| {
+        let information = l.information();
+        let value = ctx.format.text_in(&information.value, information.unit);
+        let value = if value.chars().count() > MAX_VALUE {
+            format!("{}…", value.chars().take(MAX_VALUE).collect::<String>())
+        } else {
+            value
+        };
+        let location = match l {
+            Leaf::Real(l) => match l.location.bits {
+                Some(b) => format!(
+                    "{:>6}:{:<7}",
+                    l.location.from,
+                    format!("{}..{}", b.offset, b.offset + b.len)
+                ),
+                None => format!("{:>6}..{:<6}", l.location.from, l.location.to),
+            },
+            Leaf::Virtual(_) => " ".repeat(14),
+        };
+        writeln!(
+            out,
+            "{} {location} {:label_width$}  {value}",
+            paint(&ctx.palette, l.index(), "  "),
+            information.label
+        )
+    })?;
+
+    let warnings = candidate.annotations.warnings();
+    if !warnings.is_empty() {
+        writeln!(out)?;
+        warnings.iter().try_for_each(|(i, w)| {
+            writeln!(
+                out,
+                "{}{}⚠ {}: {}{}",
+                color::Fg(color::Yellow),
+                style::Bold,
+                i.label,
+                w.message,
+                style::Reset
+            )
+        })?;
+    }
+
+    let tree = &candidate.annotations;
+    let links = tree.links();
+    if !links.is_empty() {
+        writeln!(out)?;
+        links.iter().try_for_each(|(node, link)| {
+            writeln!(
+                out,
+                "{}{} → {}: {}{}",
+                color::Fg(color::Cyan),
+                tree.labels(node.path()).join(" › "),
+                link.label,
+                tree.labels(&link.path).join(" › "),
+                color::Fg(color::Reset)
+            )
+        })?;
+    }
+
+    Ok(())
+}
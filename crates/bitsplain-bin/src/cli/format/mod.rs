@@ -1 +1,75 @@
-pub(crate) mod pretty;
+use std::io::Write;
+
+use bitsplain::catalog::Catalog;
+use bitsplain::decode::Candidate;
+use bitsplain_format::*;
+
+pub(crate) mod hexdump;
+
+output_format!(
+    name = "hexdump",
+    extension = "txt",
+    description = "Hex dump colored by annotations",
+    params = {
+        width = "Number of bytes in a row (default 16)",
+    },
+    hexdump::render
+);
+
+output_format!(
+    name = "hex",
+    extension = "hex",
+    description = "Hexadecimal string of the decoded data",
+    render_hex
+);
+
+output_format!(
+    name = "xml",
+    extension = "xml",
+    description = "XML tree of annotations",
+    render_xml
+);
+
+output_format!(
+    name = "catalog",
+    extension = "toml",
+    description = "Catalog of documentation of annotations, template of docs.toml",
+    render_catalog
+);
+
+fn render_xml<W: Write>(candidate: Candidate, _ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
+    bitsplain::output::xml::tree_to_xml(&candidate, out)
+        .map_err(|e| FormatError::Other(format!("{e:?}")))
+}
+
+fn render_hex<W: Write>(candidate: Candidate, _ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
+    writeln!(out, "{}", hex::encode(&*candidate.data))?;
+    Ok(())
+}
+
+fn render_catalog<W: Write>(
+    candidate: Candidate,
+    _ctx: &Ctx,
+    out: &mut W,
+) -> Result<(), FormatError> {
+    let decoder = format!("{}/{}", candidate.decoder.group, candidate.decoder.symbol);
+    let catalog = Catalog::of(&candidate.annotations, &decoder);
+    writeln!(
+        out,
+        "# Documentation of {} ({decoder})",
+        candidate.decoder.title
+    )?;
+    for (id, entry) in catalog.entries {
+        writeln!(out, "\n[{}]", toml_string(&id))?;
+        match entry.doc {
+            Some(doc) => writeln!(out, "doc = {}", toml_string(&doc))?,
+            None => writeln!(out, "# doc = \"\"")?,
+        }
+    }
+    Ok(())
+}
+
+/// String quoted and escaped as basic string of TOML, which is the same as JSON string.
+fn toml_string(s: &str) -> String {
+    serde_json::Value::String(s.to_string()).to_string()
+}
@@ -1,18 +1,78 @@
-use std::io::{Read, Write};
+use std::io::{IsTerminal, Read, Write};
 use std::path::PathBuf;
 
-use bitsplain::decode::{all_decoders, decode_input, input_to_binaries, Input};
+use bitsplain::bitcoin::{Amount, ScriptBuf, TxOut};
+use bitsplain::decode::{
+    all_decoders, input_to_binaries, try_decode_input, Candidate, DecodeError, DecodeOptions,
+    Failure, Input, Preferences,
+};
+use bitsplain::diff::decode_pair;
+use bitsplain::encode::replace;
+use bitsplain::parse::Budget;
+use bitsplain::plugin::load_plugins;
+use bitsplain::tree::{Leaf, Node, Tree};
 use bitsplain_format::*;
-use clap::Parser;
+use bitsplain_format_pretty::Plain;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 
 use crate::args::*;
 
 mod args;
+mod complete;
+mod diff;
+mod fetch;
 mod format;
+mod tui;
+mod watch;
+
+// Output formats register themselves, their crates only need to be linked.
+use {
+    bitsplain_format_dot as _, bitsplain_format_html as _, bitsplain_format_image as _,
+    bitsplain_format_json as _, bitsplain_format_kaitai as _, bitsplain_format_pattern as _,
+    bitsplain_format_svg as _,
+};
 
 fn main() {
     let args: Args = Args::parse();
 
+    // Plugins run their code in the process, so they are loaded only on request.
+    let plugin_dir = match (&args.plugin_dir, args.plugins) {
+        (Some(dir), _) => Some(dir.clone()),
+        (None, true) => dirs::config_dir().map(|d| d.join("bitsplain/plugins")),
+        (None, false) => None,
+    };
+
+    if let Some(dir) = plugin_dir {
+        load_plugins(&dir).into_iter().for_each(|p| {
+            if let Err(e) = p {
+                eprintln!("Could not load plugin {e}");
+            }
+        });
+    }
+
+    if let Some(only) = &args.only {
+        if only.decoders().is_empty() {
+            Args::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    "no decoder matches value of --only (see --list-decoders)",
+                )
+                .exit();
+        }
+    }
+
+    if let Some(decoder) = &args.decoder {
+        if decoder.decoders().is_empty() {
+            Args::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    "unknown value of --decoder (see --list-decoders)",
+                )
+                .exit();
+        }
+    }
+
     if args.list_decoders {
         all_decoders()
             .iter()
@@ -21,77 +81,546 @@ fn main() {
         return;
     }
 
-    let input: Input = args
-        .input
-        .map(Input::String)
-        .or_else(|| args.file.map(read_file).map(|b| Input::Binary(b.into())))
-        .unwrap_or_else(|| Input::Binary(read_stdin().into()));
-
-    if args.print_hex {
-        input_to_binaries(input).iter().take(1).for_each(|bin| {
-            let mut out = std::io::stdout();
-            let mut printer = hexyl::PrinterBuilder::new(&mut out).build();
-            let _ = printer.print_all::<&[u8]>(bin.as_ref());
+    if args.list_formats {
+        all_formats().iter().for_each(|f| {
+            println!("{:10} .{:7} {}", f.name, f.extension, f.description);
+            f.params
+                .iter()
+                .for_each(|p| println!("{:19} {:10} {}", "", p.name, p.description));
         });
+        return;
+    }
 
+    if let Some(Command::Completions { shell }) = args.command {
+        complete::generate(shell, &mut std::io::stdout());
         return;
     }
 
-    let conf_file = dirs::config_dir()
-        .expect("Could not find directory with configuration files.")
-        .join("bitsplain/config.toml");
+    let conf = bitsplain_bin::config().unwrap();
+
+    let rpc = conf.get::<fetch::RpcConfig>("rpc").ok();
+    let esplora = conf.get::<fetch::EsploraConfig>("esplora").ok();
+
+    let mut settings = conf.try_deserialize::<Settings>().unwrap();
+
+    let preferences = settings.decoders.preferences().unwrap_or_else(|e| {
+        eprintln!("Invalid [decoders] in configuration: {e}");
+        Preferences::default()
+    });
 
-    let dark_theme = dirs::config_dir()
-        .expect("Could not find directory with configuration files.")
-        .join("bitsplain/dark.toml");
+    // Colors configured for terminal are not used when writing into file.
+    settings.format.pretty.use_color = match args.color {
+        ColorChoice::Auto => {
+            settings.format.pretty.use_color && use_color(args.color, args.outfile.is_none())
+        }
+        choice => use_color(choice, args.outfile.is_none()),
+    };
+
+    if let Some(Command::Tui { input, file }) = args.command {
+        let options = DecodeOptions {
+            preferences,
+            ..Default::default()
+        };
+        match try_decode_input(read_input(input, file), &options)
+            .0
+            .first()
+        {
+            Some(candidate) => tui::run(candidate).expect("Could not run terminal interface."),
+            None => eprintln!("Could not decode input."),
+        }
+        return;
+    }
+
+    if let Some(Command::Diff { old, new }) = args.command {
+        let options = DecodeOptions {
+            filter: args.only.unwrap_or_default(),
+            preferences,
+            ..Default::default()
+        };
+        let mut out: Box<dyn Write> = if use_color(args.color, true) {
+            Box::new(std::io::stdout())
+        } else {
+            Box::new(Plain::new(std::io::stdout()))
+        };
+        match decode_pair(Input::String(old), Input::String(new), &options) {
+            Some((old, new)) => {
+                diff::run(&old, &new, &mut out).expect("Could not write differences.")
+            }
+            None => eprintln!("Could not decode both inputs by the same decoder."),
+        }
+        return;
+    }
+
+    if args.print_hex {
+        input_to_binaries(read_input(args.input, args.file))
+            .iter()
+            .take(1)
+            .for_each(|bin| {
+                let mut out = std::io::stdout();
+                let mut printer = hexyl::PrinterBuilder::new(&mut out).build();
+                let _ = printer.print_all::<&[u8]>(bin.as_ref());
+            });
 
-    let conf = config::Config::builder()
-        .add_source(config::File::from(dark_theme))
-        .add_source(config::File::from(conf_file))
-        .build()
-        .unwrap();
+        return;
+    }
 
-    let settings = conf.try_deserialize::<Settings>().unwrap();
+    args.params
+        .iter()
+        .filter(|p| !args.format.params.iter().any(|fp| fp.name == p.key))
+        .for_each(|p| {
+            eprintln!(
+                "Format {} does not have parameter '{}', it is ignored.",
+                args.format.name, p.key
+            )
+        });
 
+    let ctx = bitsplain_bin::ctx(settings, args.node_aliases.as_deref());
     let ctx = Ctx {
-        detail: args.details.or(settings.details).unwrap_or(Detail::Short),
-        format: Fmt {
-            btcunit: BtcUnit,
-            num: NumFmt,
-            hex: HexFmt {
-                max_len: Some(66),
-                append_len: true,
-            },
-        },
-        settings,
+        detail: args.details.unwrap_or(ctx.detail),
+        network: args.network.unwrap_or(ctx.network),
         params: args.params.iter().collect(),
+        ..ctx
     };
 
-    decode_input(input)
-        .into_iter()
-        .take(1)
-        .for_each(|candidate| {
-            let mut output: Box<dyn Write> = {
-                if let Some(f) = &args.outfile {
-                    Box::new(std::fs::File::create(f).unwrap())
-                } else {
-                    Box::new(std::io::stdout())
+    // Inputs with their numbers, starting at 1.
+    let inputs: Box<dyn Iterator<Item = (usize, Input)>> = if args.watch {
+        Box::new(watch::inputs().zip(1..).map(|(input, n)| (n, input)))
+    } else {
+        let input = match (&args.url, args.rpc.as_slice()) {
+            (Some(url), _) => fetch::url(url),
+            (None, [object, id]) => object
+                .parse()
+                .and_then(|o| fetch::object(rpc.as_ref(), esplora.as_ref(), o, id)),
+            _ => Ok(read_input(args.input, args.file)),
+        };
+        let input = match input {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("Could not fetch input: {e}");
+                return;
+            }
+        };
+        if args.lines {
+            Box::new(split_lines(input).into_iter())
+        } else {
+            Box::new(std::iter::once((1, input)))
+        }
+    };
+
+    // Line-oriented output is meant for processing of many results,
+    // therefore it receives all candidates, not only the best one.
+    let all = args.all || args.format.name == "ndjson";
+
+    let mut output: Box<dyn Write> = {
+        if let Some(f) = &args.outfile {
+            Box::new(std::fs::File::create(f).unwrap())
+        } else {
+            Box::new(std::io::stdout())
+        }
+    };
+
+    let options = DecodeOptions {
+        allow_trailing: args.partial,
+        filter: args
+            .decoder
+            .clone()
+            .or_else(|| args.only.clone())
+            .unwrap_or_default(),
+        budget: Budget {
+            depth: args.depth,
+            focus: args.focus.clone(),
+        },
+        preferences,
+    };
+
+    // Transaction is enriched if any of the flags providing spent outputs or traces is given.
+    let enriching =
+        args.enrich || !args.amounts.is_empty() || !args.spent_scripts.is_empty() || args.trace;
+    let enriched = |candidate: Candidate| {
+        if enriching {
+            enrich(
+                candidate,
+                args.enrich,
+                &args.amounts,
+                &args.spent_scripts,
+                args.trace,
+                rpc.as_ref(),
+                esplora.as_ref(),
+            )
+        } else {
+            candidate
+        }
+    };
+
+    // Exit status of --check, the worst of all inputs.
+    let mut status = 0;
+
+    inputs.for_each(|(line, input)| {
+        let (candidates, failures) = try_decode_input(input, &options);
+
+        // Lines are numbered so results of many inputs can be told apart.
+        let prefix = if args.lines {
+            format!("{line}\t")
+        } else {
+            String::new()
+        };
+
+        if args.check {
+            // Signatures of inputs are verified only if transaction is enriched.
+            let candidate = candidates
+                .into_iter()
+                .nth(args.candidate.unwrap_or(1).wrapping_sub(1))
+                .map(&enriched);
+            let (code, results) = check(candidate);
+            if let Err(e) = results
+                .iter()
+                .try_for_each(|r| writeln!(output, "{prefix}{r}"))
+            {
+                eprintln!("Could not write output: {e}");
+            }
+            status = status.max(code);
+            return;
+        }
+
+        if args.identify {
+            if let Err(e) = candidates.iter().try_for_each(|c| {
+                writeln!(
+                    output,
+                    "{prefix}{}/{}\t{}\t{}\t{}",
+                    c.decoder.group,
+                    c.decoder.symbol,
+                    c.data.encoding(),
+                    c.data.len(),
+                    c.decoder.title
+                )
+            }) {
+                eprintln!("Could not write output: {e}");
+            }
+            return;
+        }
+
+        if candidates.is_empty() {
+            if args.decoder.is_some() {
+                report_decoder_failures(failures);
+            } else {
+                report_failures(failures);
+            }
+            return;
+        }
+
+        let candidates = if all {
+            candidates
+        } else {
+            let n = args.candidate.unwrap_or(1);
+            if candidates.len() > 1 {
+                list_candidates(&candidates, n);
+            }
+            match candidates.into_iter().nth(n.wrapping_sub(1)) {
+                Some(c) => vec![c],
+                None => {
+                    eprintln!("There is no candidate {n}.");
+                    return;
                 }
-            };
+            }
+        };
 
-            match args.format {
-                // TODO: Figure out what to do with outputs other than stdout for pretty
-                Format::Pretty => format::pretty::render(candidate, &ctx),
-                Format::Html => {
-                    bitsplain_format_html::render(candidate, &ctx, &mut output).unwrap()
+        candidates.into_iter().for_each(|mut candidate| {
+            if !args.set.is_empty() {
+                candidate = match tweak(candidate, &args.set, &options.budget) {
+                    Ok(c) => c,
+                    Err(e) => {
+                        eprintln!("Could not change data: {e}");
+                        return;
+                    }
+                };
+            }
+            candidate = enriched(candidate);
+            if let Some(query) = &args.extract {
+                let nodes = candidate.annotations.query(query);
+                match nodes.as_slice() {
+                    [node] => match node.byte_range() {
+                        Some(range) => {
+                            if let Err(e) = output.write_all(&candidate.data[range]) {
+                                eprintln!("Could not write output: {e}");
+                            }
+                        }
+                        None => eprintln!(
+                            "{} is derived from other data and does not have its own bytes.",
+                            node.information().label
+                        ),
+                    },
+                    [] => eprintln!("No node of {} matches the query.", candidate.decoder.title),
+                    _ => eprintln!(
+                        "{} nodes of {} match the query, only one can be extracted.",
+                        nodes.len(),
+                        candidate.decoder.title
+                    ),
+                }
+                return;
+            }
+            if let Some(query) = &args.select {
+                let candidate = match ctx.prepare(candidate) {
+                    Ok(candidate) => candidate,
+                    Err(e) => {
+                        eprintln!("{e}");
+                        return;
+                    }
+                };
+                let nodes = candidate.annotations.query(query);
+                if nodes.is_empty() {
+                    eprintln!("No node of {} matches the query.", candidate.decoder.title);
                 }
-                Format::Png => {
-                    bitsplain_format_image::render(candidate, &ctx, &mut output).unwrap()
+                if let Err(e) = nodes
+                    .iter()
+                    .try_for_each(|n| writeln!(output, "{}", n.information().value.plain()))
+                {
+                    eprintln!("Could not write output: {e}");
                 }
-                Format::Json => todo!(),
-                Format::Xml => bitsplain::output::xml::tree_to_xml(&candidate),
+                return;
+            }
+            if let Some(query) = &args.query {
+                let nodes = candidate
+                    .annotations
+                    .query(query)
+                    .into_iter()
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if nodes.is_empty() {
+                    eprintln!("No node of {} matches the query.", candidate.decoder.title);
+                    return;
+                }
+                candidate.annotations = Tree::from_nodes(nodes);
+            }
+            if !args.only_category.is_empty() {
+                candidate.annotations = candidate.annotations.with_categories(&args.only_category);
+                if candidate.annotations.is_empty() {
+                    eprintln!(
+                        "No node of {} is of the categories.",
+                        candidate.decoder.title
+                    );
+                    return;
+                }
+            }
+            if candidate.partial {
+                eprintln!(
+                    "Note: {} did not decode all data, see 'Unparsed remainder'.",
+                    candidate.decoder.title
+                );
+            }
+            if let Err(e) = (args.format.render)(candidate, &ctx, &mut output) {
+                eprintln!("Could not render output: {e}");
             }
         });
+
+        // Watched inputs come one by one, each of them is shown immediately.
+        let _ = output.flush();
+    });
+
+    if status != 0 {
+        let _ = output.flush();
+        std::process::exit(status);
+    }
+}
+
+/// Exit status of --check when data were decoded, however some verification failed.
+const CHECK_FAILED: i32 = 1;
+
+/// Exit status of --check when data could not be decoded completely.
+const CHECK_UNDECODED: i32 = 2;
+
+/// Verifies that the candidate decoded all data and that all invariants
+/// of the data hold. Returns exit status and tab-separated results.
+fn check(candidate: Option<Candidate>) -> (i32, Vec<String>) {
+    let Some(candidate) = candidate else {
+        return (CHECK_UNDECODED, vec!["undecoded".to_string()]);
+    };
+
+    let decoder = format!("{}/{}", candidate.decoder.group, candidate.decoder.symbol);
+    let tree = &candidate.annotations;
+
+    if candidate.partial {
+        let offset = tree
+            .real_leaves()
+            .last()
+            .map(|l| l.location.from)
+            .unwrap_or_default();
+        return (
+            CHECK_UNDECODED,
+            vec![format!(
+                "partial\t{decoder}\tunparsed data after byte {offset}"
+            )],
+        );
+    }
+
+    let failed = tree
+        .checks()
+        .into_iter()
+        .filter(|(_, passed)| !passed)
+        .map(|(node, _)| {
+            let information = node.information();
+            format!(
+                "failed\t{decoder}\t{}\t{}",
+                tree.labels(node.path()).join(" › "),
+                information
+                    .warnings
+                    .first()
+                    .map(|w| w.message.as_str())
+                    .unwrap_or(&information.label)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if failed.is_empty() {
+        (0, vec![format!("ok\t{decoder}")])
+    } else {
+        (CHECK_FAILED, failed)
+    }
+}
+
+/// Changes values of leaves selected by assignments and decodes
+/// the re-encoded data by the same decoder again.
+fn tweak(
+    candidate: Candidate,
+    assignments: &[Assignment],
+    budget: &Budget,
+) -> Result<Candidate, String> {
+    let data = assignments.iter().try_fold(
+        candidate.data.to_vec(),
+        |data, Assignment { query, value }| {
+            let leaves = candidate
+                .annotations
+                .query(query)
+                .into_iter()
+                .filter_map(|n| match n {
+                    Node::Leaf(Leaf::Real(l)) => Some(l),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            match leaves.as_slice() {
+                [leaf] => replace(&data, leaf, value)
+                    .map_err(|e| format!("{}: {e}", leaf.information.label)),
+                [] => Err("no leaf matches the query".to_string()),
+                _ => Err(format!("{} leaves match the query", leaves.len())),
+            }
+        },
+    )?;
+
+    candidate.with_bytes(data, budget).map_err(|e| {
+        format!(
+            "changed data cannot be decoded by {}, {e}",
+            candidate.decoder.title
+        )
+    })
+}
+
+/// Whether output should be colored. Automatically it is colored only
+/// on terminal and when user did not opt out by variable `NO_COLOR`.
+fn use_color(choice: ColorChoice, to_stdout: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            to_stdout
+                && std::io::stdout().is_terminal()
+                && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        }
+    }
+}
+
+/// Tell which other decoders succeeded, so ambiguous input does not
+/// silently look like the chosen one only.
+fn list_candidates(candidates: &[Candidate], chosen: usize) {
+    eprintln!("Input can be decoded in {} ways:", candidates.len());
+    candidates.iter().enumerate().for_each(|(i, c)| {
+        eprintln!(
+            "{} {}. [{}/{}] {}",
+            if i + 1 == chosen { '*' } else { ' ' },
+            i + 1,
+            c.decoder.group,
+            c.decoder.symbol,
+            c.decoder.title
+        )
+    });
+    eprintln!("Use --candidate N to show another one or --all to show all of them.");
+}
+
+/// Maximum number of failed decoders to report.
+const MAX_FAILURES: usize = 5;
+
+/// Explain why input could not be decoded. Only decoders that parsed at least
+/// a part of the input are reported, others most likely do not support it.
+/// Decoders that got furthest are listed first.
+fn report_failures(failures: Vec<Failure>) {
+    eprintln!("Could not decode input.");
+
+    // Furthest failure of every decoder, out of all interpretations of input.
+    let mut furthest: Vec<(usize, Failure)> = vec![];
+    failures.into_iter().for_each(|f| {
+        let offset = match f.error {
+            DecodeError::Mismatch | DecodeError::Failed { offset: 0, .. } => return,
+            DecodeError::Failed { offset, .. } | DecodeError::Trailing { offset, .. } => offset,
+        };
+        match furthest
+            .iter_mut()
+            .find(|(_, g)| std::ptr::eq(g.decoder, f.decoder))
+        {
+            Some(g) if g.0 < offset => *g = (offset, f),
+            Some(_) => {}
+            None => furthest.push((offset, f)),
+        }
+    });
+    furthest.sort_by_key(|(offset, _)| std::cmp::Reverse(*offset));
+
+    furthest.iter().take(MAX_FAILURES).for_each(|(_, f)| {
+        eprintln!(
+            "  decoder {}/{} as {}: {}",
+            f.decoder.group,
+            f.decoder.symbol,
+            f.data.encoding(),
+            f.error
+        )
+    });
+}
+
+/// Explain why the only chosen decoder could not decode any
+/// interpretation of input, including those it did not accept at all.
+fn report_decoder_failures(failures: Vec<Failure>) {
+    match failures.first() {
+        Some(f) => eprintln!(
+            "Could not decode input by {}/{}.",
+            f.decoder.group, f.decoder.symbol
+        ),
+        None => eprintln!("Could not decode input."),
+    }
+
+    failures
+        .iter()
+        .for_each(|f| eprintln!("  as {}: {}", f.data.encoding(), f.error));
+}
+
+/// Split input into lines, each of which is decoded as a separate input.
+/// Empty lines are skipped, but they are counted in numbers of lines.
+fn split_lines(input: Input) -> Vec<(usize, Input)> {
+    let text = match input {
+        Input::String(s) => s,
+        Input::Binary(b) => String::from_utf8_lossy(&b).into_owned(),
+    };
+
+    text.lines()
+        .zip(1..)
+        .map(|(l, n)| (n, l.trim()))
+        .filter(|(_, l)| !l.is_empty())
+        .map(|(n, l)| (n, Input::String(l.to_string())))
+        .collect()
+}
+
+/// Obtain input from command-line argument, file or, if neither is provided,
+/// from standard input.
+fn read_input(input: Option<String>, file: Option<PathBuf>) -> Input {
+    input
+        .map(Input::String)
+        .or_else(|| file.map(read_file).map(|b| Input::Binary(b.into())))
+        .unwrap_or_else(|| Input::Binary(read_stdin().into()))
 }
 
 //TODO: Error handling
@@ -109,3 +638,91 @@ fn read_stdin() -> Vec<u8> {
 
     buffer
 }
+
+/// Candidate with information about outputs spent by transaction
+/// and traces of execution of its scripts.
+fn enrich(
+    mut candidate: Candidate,
+    fetch_prevouts: bool,
+    given_amounts: &[u64],
+    given_scripts: &[ScriptBuf],
+    trace: bool,
+    rpc: Option<&fetch::RpcConfig>,
+    esplora: Option<&fetch::EsploraConfig>,
+) -> Candidate {
+    if let Some(tx) = bitsplain::enrich::transaction(&candidate) {
+        let mut prevouts = vec![None; tx.input.len()];
+        let mut amounts = vec![None; tx.input.len()];
+
+        if fetch_prevouts {
+            let errors;
+            (prevouts, errors) =
+                bitsplain::enrich::prevouts(&tx, |txid| fetch::transaction(rpc, esplora, txid));
+            errors
+                .iter()
+                .for_each(|e| eprintln!("Could not obtain spent output: {e}"));
+            amounts = prevouts
+                .iter()
+                .map(|p| p.as_ref().map(|p| p.value.to_sat()))
+                .collect();
+            candidate.annotations =
+                bitsplain::enrich::with_prevouts(candidate.annotations, &prevouts);
+        }
+
+        if !given_amounts.is_empty() {
+            if given_amounts.len() != tx.input.len() {
+                eprintln!(
+                    "Transaction has {} inputs, but {} amounts were given.",
+                    tx.input.len(),
+                    given_amounts.len()
+                );
+            }
+            amounts
+                .iter_mut()
+                .zip(given_amounts)
+                .for_each(|(a, given)| *a = Some(*given));
+        }
+
+        candidate.annotations = bitsplain::enrich::with_fee(candidate.annotations, &tx, &amounts);
+
+        if !given_scripts.is_empty() && given_scripts.len() != tx.input.len() {
+            eprintln!(
+                "Transaction has {} inputs, but {} spent scripts were given.",
+                tx.input.len(),
+                given_scripts.len()
+            );
+        }
+        let scripts = prevouts
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                given_scripts
+                    .get(i)
+                    .cloned()
+                    .or_else(|| p.as_ref().map(|p| p.script_pubkey.clone()))
+            })
+            .collect::<Vec<_>>();
+        candidate.annotations =
+            bitsplain::enrich::with_witnesses(candidate.annotations, &tx, &scripts);
+
+        // Spent outputs, fetched or given, known only where both amount and script are.
+        let spent = amounts
+            .iter()
+            .zip(&scripts)
+            .map(|(amount, script)| {
+                Some(TxOut {
+                    value: Amount::from_sat((*amount)?),
+                    script_pubkey: script.clone()?,
+                })
+            })
+            .collect::<Vec<_>>();
+        candidate.annotations =
+            bitsplain::enrich::with_signatures(candidate.annotations, &tx, &spent);
+
+        if trace {
+            candidate.annotations =
+                bitsplain::trace::with_traces(candidate.annotations, &tx, &spent);
+        }
+    }
+    candidate
+}
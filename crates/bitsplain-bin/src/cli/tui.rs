@@ -0,0 +1,314 @@
+//! Interactive terminal user interface. Shows hex dump of the data next to
+//! the tree of annotations. Selecting an item in one pane highlights
+//! the corresponding part in the other one.
+
+use std::io::Result;
+use std::ops::Range;
+
+use bitsplain::decode::Candidate;
+use bitsplain::tree::{Cursor, Leaf, Node, Tree};
+use bitsplain_format::Fmt;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+use ratatui::{DefaultTerminal, Frame};
+
+/// Number of bytes in one row of hex pane.
+const BYTES_PER_ROW: usize = 16;
+
+/// Width of hex pane, i. e. offset, bytes and separator.
+const HEX_WIDTH: usize = 8 + BYTES_PER_ROW * 3 + 2;
+
+/// One line of tree pane.
+struct Row<'a> {
+    depth: usize,
+    node: &'a Node,
+}
+
+impl Row<'_> {
+    /// Range of bytes covered by the row, if it is represented in data.
+    fn range(&self) -> Option<Range<usize>> {
+        match self.node {
+            Node::Group { location, .. } => Some(location.byte_from..location.byte_to),
+            Node::Leaf(Leaf::Real(leaf)) => Some(leaf.location.range()),
+            Node::Leaf(Leaf::Virtual(_)) => None,
+        }
+    }
+
+    fn text(&self) -> String {
+        let information = self.node.information();
+        let marker = match self.node {
+            Node::Group { .. } => "▾ ",
+            Node::Leaf(Leaf::Real(_)) => "",
+            Node::Leaf(Leaf::Virtual(_)) => "~ ",
+        };
+        format!(
+            "{}{marker}{}: {}",
+            "  ".repeat(self.depth),
+            information.label,
+            Fmt::default().text_in(&information.value, information.unit)
+        )
+    }
+}
+
+fn flatten<'a>(nodes: &'a [Node], depth: usize, rows: &mut Vec<Row<'a>>) {
+    nodes.iter().for_each(|node| {
+        rows.push(Row { depth, node });
+        if let Node::Group { children, .. } = node {
+            flatten(children, depth + 1, rows);
+        }
+    });
+}
+
+#[derive(PartialEq, Eq)]
+enum Focus {
+    Tree,
+    Hex,
+}
+
+struct State<'a> {
+    data: &'a [u8],
+    tree: &'a Tree,
+    rows: Vec<Row<'a>>,
+    focus: Focus,
+    /// Selected row of tree pane.
+    selected: usize,
+    /// Selected byte of hex pane.
+    cursor: usize,
+    tree_scroll: usize,
+    hex_scroll: usize,
+}
+
+impl<'a> State<'a> {
+    /// Range of bytes to highlight.
+    fn highlight(&self) -> Option<Range<usize>> {
+        self.rows.get(self.selected).and_then(|r| r.range())
+    }
+
+    fn select_row(&mut self, row: usize) {
+        self.selected = row.min(self.rows.len().saturating_sub(1));
+        if let Some(range) = self.highlight() {
+            self.cursor = range.start.min(self.data.len().saturating_sub(1));
+        }
+    }
+
+    /// Selects the node to which tree cursor moves from the selected row.
+    fn step(&mut self, step: fn(&Cursor<'a>) -> Option<Cursor<'a>>) {
+        if let Some(row) = self
+            .rows
+            .get(self.selected)
+            .and_then(|r| self.tree.cursor_at(r.node.path()))
+            .and_then(|c| step(&c))
+            .and_then(|c| {
+                self.rows
+                    .iter()
+                    .position(|r| std::ptr::eq(r.node, c.node()))
+            })
+        {
+            self.select_row(row);
+        }
+    }
+
+    fn move_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor.min(self.data.len().saturating_sub(1));
+        // The most nested leaf containing the byte.
+        if let Some(row) = self
+            .tree
+            .node_at(self.cursor)
+            .and_then(|node| self.rows.iter().position(|r| std::ptr::eq(r.node, node)))
+        {
+            self.selected = row;
+        }
+    }
+
+    fn scroll(&mut self, height: usize) {
+        if self.selected < self.tree_scroll {
+            self.tree_scroll = self.selected;
+        } else if self.selected >= self.tree_scroll + height {
+            self.tree_scroll = self.selected + 1 - height;
+        }
+
+        let cursor_row = self.cursor / BYTES_PER_ROW;
+        if cursor_row < self.hex_scroll {
+            self.hex_scroll = cursor_row;
+        } else if cursor_row >= self.hex_scroll + height {
+            self.hex_scroll = cursor_row + 1 - height;
+        }
+    }
+
+    fn hex_line(&self, row: usize) -> Line<'static> {
+        let from = row * BYTES_PER_ROW;
+        if from >= self.data.len() {
+            return Line::default();
+        }
+        let highlight = self.highlight().unwrap_or(0..0);
+        let mut spans = vec![Span::raw(format!("{from:06x}  "))];
+        self.data[from..(from + BYTES_PER_ROW).min(self.data.len())]
+            .iter()
+            .enumerate()
+            .for_each(|(i, b)| {
+                let offset = from + i;
+                let mut style = Style::new();
+                if offset == self.cursor && self.focus == Focus::Hex {
+                    style = style.add_modifier(Modifier::UNDERLINED);
+                }
+                if highlight.contains(&offset) {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                if i > 0 {
+                    spans.push(Span::raw(" "));
+                }
+                spans.push(Span::styled(format!("{b:02x}"), style));
+            });
+        Line::from(spans)
+    }
+
+    fn tree_line(&self, row: usize) -> Line<'static> {
+        match self.rows.get(row) {
+            Some(r) if row == self.selected => {
+                let style = if self.focus == Focus::Tree {
+                    Modifier::REVERSED
+                } else {
+                    Modifier::BOLD
+                };
+                Line::styled(r.text(), style)
+            }
+            Some(r) => Line::raw(r.text()),
+            None => Line::default(),
+        }
+    }
+
+    fn status(&self) -> String {
+        self.rows
+            .get(self.selected)
+            .map(|r| {
+                let information = r.node.information();
+                information
+                    .splain
+                    .clone()
+                    .or_else(|| information.doc.clone())
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default()
+    }
+
+    fn draw(&mut self, frame: &mut Frame, title: &str) {
+        let [title_area, body, status_area, help_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [hex_area, tree_area] =
+            Layout::horizontal([Constraint::Length(HEX_WIDTH as u16), Constraint::Min(0)])
+                .areas(body);
+
+        let height = body.height as usize;
+        self.scroll(height);
+
+        let hex = (0..height)
+            .map(|i| self.hex_line(self.hex_scroll + i))
+            .collect::<Vec<_>>();
+        let tree = (0..height)
+            .map(|i| self.tree_line(self.tree_scroll + i))
+            .collect::<Vec<_>>();
+
+        frame.render_widget(Line::styled(title, Modifier::BOLD), title_area);
+        frame.render_widget(Paragraph::new(hex), hex_area);
+        frame.render_widget(Paragraph::new(tree), tree_area);
+        frame.render_widget(Line::styled(self.status(), Modifier::ITALIC), status_area);
+        frame.render_widget(
+            Line::styled(
+                "q: quit  tab: switch pane  ↑↓←→/jkhl: move  PgUp/PgDn: page  u: up  []: sibling  nN: leaf",
+                Modifier::DIM,
+            ),
+            help_area,
+        );
+    }
+}
+
+/// Run interactive terminal interface for the candidate until user quits.
+pub fn run(candidate: &Candidate) -> Result<()> {
+    let mut rows = vec![];
+    flatten(&candidate.annotations, 0, &mut rows);
+
+    let mut state = State {
+        data: candidate.data.as_ref(),
+        tree: &candidate.annotations,
+        rows,
+        focus: Focus::Tree,
+        selected: 0,
+        cursor: 0,
+        tree_scroll: 0,
+        hex_scroll: 0,
+    };
+    state.select_row(0);
+
+    // Keys are read from terminal directly as standard input may carry data.
+    let mut terminal = ratatui::init();
+    let result = run_loop(&mut terminal, &mut state, candidate.decoder.title);
+    ratatui::restore();
+    result
+}
+
+/// Draws the state and handles keys until user quits.
+fn run_loop(terminal: &mut DefaultTerminal, state: &mut State, title: &str) -> Result<()> {
+    loop {
+        terminal.draw(|frame| state.draw(frame, title))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let page = terminal.size()?.height.saturating_sub(3) as usize;
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match (key.code, &state.focus) {
+            (KeyCode::Char('c'), _) if ctrl => break,
+            (KeyCode::Char('q') | KeyCode::Esc, _) => break,
+            (KeyCode::Tab, Focus::Tree) => state.focus = Focus::Hex,
+            (KeyCode::Tab, Focus::Hex) => state.focus = Focus::Tree,
+            (KeyCode::Down | KeyCode::Char('j'), Focus::Tree) => {
+                state.select_row(state.selected + 1)
+            }
+            (KeyCode::Up | KeyCode::Char('k'), Focus::Tree) => {
+                state.select_row(state.selected.saturating_sub(1))
+            }
+            (KeyCode::PageDown, Focus::Tree) => state.select_row(state.selected + page),
+            (KeyCode::PageUp, Focus::Tree) => state.select_row(state.selected.saturating_sub(page)),
+            (KeyCode::Char('u') | KeyCode::Backspace, Focus::Tree) => state.step(Cursor::parent),
+            (KeyCode::Char(']'), Focus::Tree) => state.step(Cursor::next_sibling),
+            (KeyCode::Char('['), Focus::Tree) => state.step(Cursor::prev_sibling),
+            (KeyCode::Char('n'), Focus::Tree) => state.step(Cursor::next_leaf),
+            (KeyCode::Char('N'), Focus::Tree) => state.step(Cursor::prev_leaf),
+            (KeyCode::Home | KeyCode::Char('g'), Focus::Tree) => state.select_row(0),
+            (KeyCode::End | KeyCode::Char('G'), Focus::Tree) => state.select_row(usize::MAX),
+            (KeyCode::Right | KeyCode::Char('l'), Focus::Hex) => {
+                state.move_cursor(state.cursor + 1)
+            }
+            (KeyCode::Left | KeyCode::Char('h'), Focus::Hex) => {
+                state.move_cursor(state.cursor.saturating_sub(1))
+            }
+            (KeyCode::Down | KeyCode::Char('j'), Focus::Hex) => {
+                state.move_cursor(state.cursor + BYTES_PER_ROW)
+            }
+            (KeyCode::Up | KeyCode::Char('k'), Focus::Hex) => {
+                state.move_cursor(state.cursor.saturating_sub(BYTES_PER_ROW))
+            }
+            (KeyCode::PageDown, Focus::Hex) => {
+                state.move_cursor(state.cursor + BYTES_PER_ROW * page)
+            }
+            (KeyCode::PageUp, Focus::Hex) => {
+                state.move_cursor(state.cursor.saturating_sub(BYTES_PER_ROW * page))
+            }
+            (KeyCode::Home | KeyCode::Char('g'), Focus::Hex) => state.move_cursor(0),
+            (KeyCode::End | KeyCode::Char('G'), Focus::Hex) => state.move_cursor(usize::MAX),
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! Inputs that appear over time, either in clipboard or on standard input,
+//! so they can be decoded as soon as they are available.
+//!
+//! Clipboard is read by the usual command-line tools of the platform
+//! (`wl-paste`, `xclip`, `xsel`, `pbpaste` or PowerShell).
+
+use std::io::IsTerminal;
+use std::process::Command;
+use std::thread::sleep;
+use std::time::Duration;
+
+use bitsplain::decode::Input;
+
+/// How often clipboard is checked for new content.
+const INTERVAL: Duration = Duration::from_millis(500);
+
+/// Commands printing content of clipboard.
+const CLIPBOARD: &[(&str, &[&str])] = &[
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-out"]),
+    ("xsel", &["--clipboard", "--output"]),
+    ("pbpaste", &[]),
+    ("powershell", &["-NoProfile", "-Command", "Get-Clipboard"]),
+];
+
+/// Inputs read from standard input line by line or, if it is terminal,
+/// from clipboard whenever its content changes.
+pub fn inputs() -> Box<dyn Iterator<Item = Input>> {
+    if std::io::stdin().is_terminal() {
+        clipboard()
+    } else {
+        Box::new(
+            std::io::stdin()
+                .lines()
+                .map_while(Result::ok)
+                .map(|l| l.trim().to_string())
+                .filter(|l| !l.is_empty())
+                .map(Input::String),
+        )
+    }
+}
+
+fn clipboard() -> Box<dyn Iterator<Item = Input>> {
+    let Some(command) = CLIPBOARD.iter().find(|c| read(c).is_some()) else {
+        eprintln!(
+            "Could not read clipboard, none of {} is available.",
+            CLIPBOARD
+                .iter()
+                .map(|(c, _)| *c)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        return Box::new(std::iter::empty());
+    };
+
+    eprintln!("Watching clipboard, press Ctrl+C to stop.");
+
+    let mut last = None;
+    Box::new(std::iter::from_fn(move || loop {
+        match read(command).map(|c| c.trim().to_string()) {
+            Some(content) if !content.is_empty() && last.as_ref() != Some(&content) => {
+                last = Some(content.clone());
+                if std::io::stdout().is_terminal() {
+                    print!("{}{}", termion::clear::All, termion::cursor::Goto(1, 1));
+                }
+                return Some(Input::String(content));
+            }
+            _ => sleep(INTERVAL),
+        }
+    }))
+}
+
+/// Content of clipboard obtained by the command, if it succeeded.
+fn read((command, args): &(&str, &[&str])) -> Option<String> {
+    Command::new(command)
+        .args(*args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+}
@@ -1,8 +1,13 @@
+use std::cell::Cell;
 use std::convert::identity;
+use std::path::PathBuf;
 use std::rc::Rc;
 
-use bitsplain::decode::{decode_input, Input};
+use bitsplain::decode::{decode_input, Candidate, Input};
+use bitsplain::encode::replace;
+use bitsplain::parse::Budget;
 use bitsplain::tree::*;
+use bitsplain_format::{find_format, OutputFormat};
 use gtk::glib::clone;
 use gtk::prelude::*;
 use gtk::{gdk, gio};
@@ -20,6 +25,7 @@ pub enum AppModel {
         hexy: Rc<Controller<HexyModel>>,
     },
     Full {
+        candidate: Rc<Candidate>,
         annotations: Rc<Tree>,
         doc: Rc<Controller<DocModel>>,
         tree: Rc<Controller<TreeModel>>,
@@ -46,12 +52,85 @@ impl AppModel {
             AppModel::Full { tree, .. } => tree,
         }
     }
+
+    /// Decodes the input and shows the most likely candidate.
+    fn open(&mut self, input: Input) {
+        let candidates = decode_input(input);
+
+        if let Some(c) = candidates.into_iter().next() {
+            self.show(c);
+        }
+    }
+
+    /// Shows the candidate in all views.
+    fn show(&mut self, c: Candidate) {
+        let annotations = Rc::new(c.annotations.clone());
+        let bytes = Rc::new(c.data.to_vec());
+
+        *self = AppModel::Full {
+            candidate: Rc::new(c),
+            annotations: annotations.clone(),
+            doc: self.doc().clone(),
+            hexy: self.hexy().clone(),
+            tree: self.tree().clone(),
+        };
+
+        self.tree().emit(TreeMsg::Open {
+            annotations: annotations.clone(),
+        });
+        self.hexy().emit(HexyMsg::Open { annotations, bytes });
+    }
+
+    /// Changes value of the leaf at the path and shows the re-encoded data
+    /// decoded by the same decoder.
+    fn edit(&mut self, path: &[String], value: &str) {
+        let AppModel::Full { candidate, .. } = &*self else {
+            return;
+        };
+        let Some(Node::Leaf(Leaf::Real(leaf))) = candidate.annotations.select(path) else {
+            return;
+        };
+        let changed = replace(&candidate.data.to_vec(), leaf, value)
+            .map_err(|e| e.to_string())
+            .and_then(|data| {
+                candidate
+                    .with_bytes(data, &Budget::default())
+                    .map_err(|e| format!("changed data cannot be decoded, {e}"))
+            });
+        match changed {
+            Ok(c) => self.show(c),
+            Err(e) => show_error(&format!("Could not change {}: {e}", leaf.information.label)),
+        }
+    }
+}
+
+/// Shows message about an error in a dialog.
+fn show_error(message: &str) {
+    let dialog = gtk::MessageDialog::builder()
+        .modal(true)
+        .message_type(gtk::MessageType::Error)
+        .buttons(gtk::ButtonsType::Close)
+        .text(message)
+        .build();
+    dialog.set_transient_for(relm4::main_application().active_window().as_ref());
+    dialog.connect_response(|d, _| d.destroy());
+    dialog.show();
 }
 
 #[derive(Debug)]
 pub enum AppMsg {
     Select(Vec<String>),
     Open(String),
+    /// Shows dialog for choosing file to be opened.
+    Choose,
+    OpenFile(PathBuf),
+    /// Shows dialog for choosing file to which the candidate is exported
+    /// in the format of given name.
+    Export(&'static str),
+    ExportTo(&'static OutputFormat, PathBuf),
+    Search(String),
+    /// Changes value of the leaf at the path.
+    Edit(Vec<String>, String),
     Unselect,
     Paste,
     Quit,
@@ -74,7 +153,16 @@ impl SimpleComponent for AppModel {
 		    set_start_child: Some(model.hexy().widget()),
 		    set_end_child: Some(model.doc().widget())
 		},
-		set_end_child: Some(model.tree().widget())
+		#[wrap(Some)]
+		set_end_child = &gtk::Box::new(gtk::Orientation::Vertical, 0) {
+		    gtk::SearchEntry {
+			set_placeholder_text: Some("Search label, value or data type"),
+			connect_search_changed[sender] => move |e| {
+			    sender.input(AppMsg::Search(e.text().to_string()))
+			},
+		    },
+		    append: model.tree().widget(),
+		}
             }
 	}
     }
@@ -102,10 +190,14 @@ impl SimpleComponent for AppModel {
 
         let app = relm4::main_application();
 
+        app.set_accelerators_for_action::<crate::OpenAction>(&["<primary>O"]);
         app.set_accelerators_for_action::<crate::QuitAction>(&["<primary>Q"]);
         app.set_accelerators_for_action::<crate::PasteAction>(&["<primary>V"]);
 
         let mut win = RelmActionGroup::<crate::WindowActionGroup>::new();
+        let open: RelmAction<crate::OpenAction> = RelmAction::new_stateless(
+            clone!(@strong sender => move |_| sender.input(AppMsg::Choose)),
+        );
         let quit: RelmAction<crate::QuitAction> = RelmAction::new_stateless(
             clone!(@strong sender => move |_| sender.input(AppMsg::Quit)),
         );
@@ -113,6 +205,19 @@ impl SimpleComponent for AppModel {
             clone!(@strong sender => move |_| sender.input(AppMsg::Paste)),
         );
 
+        win.add_action(open);
+        win.add_action(RelmAction::<crate::ExportHtmlAction>::new_stateless(
+            clone!(@strong sender => move |_| sender.input(AppMsg::Export("html"))),
+        ));
+        win.add_action(RelmAction::<crate::ExportSvgAction>::new_stateless(
+            clone!(@strong sender => move |_| sender.input(AppMsg::Export("svg"))),
+        ));
+        win.add_action(RelmAction::<crate::ExportPngAction>::new_stateless(
+            clone!(@strong sender => move |_| sender.input(AppMsg::Export("png"))),
+        ));
+        win.add_action(RelmAction::<crate::ExportJsonAction>::new_stateless(
+            clone!(@strong sender => move |_| sender.input(AppMsg::Export("json"))),
+        ));
         win.add_action(quit);
         win.add_action(paste);
 
@@ -122,6 +227,19 @@ impl SimpleComponent for AppModel {
 
         widgets.main_window.set_show_menubar(true);
 
+        // Files dropped onto the window are opened.
+        let drop = gtk::DropTarget::new(gio::File::static_type(), gdk::DragAction::COPY);
+        drop.connect_drop(clone!(@strong sender => move |_, value, _, _| {
+            match value.get::<gio::File>().ok().and_then(|f| f.path()) {
+                Some(path) => {
+                    sender.input(AppMsg::OpenFile(path));
+                    true
+                }
+                None => false,
+            }
+        }));
+        widgets.main_window.add_controller(drop);
+
         if let Some(s) = candidate {
             sender.input(AppMsg::Open(s));
         }
@@ -131,24 +249,94 @@ impl SimpleComponent for AppModel {
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
-            AppMsg::Open(s) => {
-                let candidates = decode_input(Input::String(s));
-
-                if let Some(c) = candidates.into_iter().next() {
-                    let annotations = Rc::new(c.annotations);
-                    let bytes = Rc::new(c.data.to_vec());
-
-                    *self = AppModel::Full {
-                        annotations: annotations.clone(),
-                        doc: self.doc().clone(),
-                        hexy: self.hexy().clone(),
-                        tree: self.tree().clone(),
+            AppMsg::Open(s) => self.open(Input::String(s)),
+            AppMsg::Choose => {
+                let dialog = gtk::FileChooserNative::new(
+                    Some("Open file"),
+                    relm4::main_application().active_window().as_ref(),
+                    gtk::FileChooserAction::Open,
+                    Some("Open"),
+                    Some("Cancel"),
+                );
+                // Dialog is kept alive by its own handler until it responds,
+                // then the handler lets it go.
+                let keep = Cell::new(Some(dialog.clone()));
+                dialog.connect_response(clone!(@strong sender => move |d, response| {
+                    if response == gtk::ResponseType::Accept {
+                        if let Some(path) = d.file().and_then(|f| f.path()) {
+                            sender.input(AppMsg::OpenFile(path));
+                        }
+                    }
+                    d.destroy();
+                    keep.take();
+                }));
+                dialog.show();
+            }
+            AppMsg::OpenFile(path) => match std::fs::read(&path) {
+                Ok(bytes) => self.open(Input::Binary(bytes.into())),
+                Err(e) => eprintln!("Could not read {}: {e}", path.display()),
+            },
+            AppMsg::Export(name) => {
+                let (Some(format), AppModel::Full { candidate, .. }) = (find_format(name), &*self)
+                else {
+                    return;
+                };
+                let dialog = gtk::FileChooserNative::new(
+                    Some("Export"),
+                    relm4::main_application().active_window().as_ref(),
+                    gtk::FileChooserAction::Save,
+                    Some("Export"),
+                    Some("Cancel"),
+                );
+                dialog.set_current_name(&format!(
+                    "{}.{}",
+                    candidate.decoder.symbol, format.extension
+                ));
+                // Dialog is kept alive by its own handler until it responds,
+                // then the handler lets it go.
+                let keep = Cell::new(Some(dialog.clone()));
+                dialog.connect_response(clone!(@strong sender => move |d, response| {
+                    if response == gtk::ResponseType::Accept {
+                        if let Some(path) = d.file().and_then(|f| f.path()) {
+                            sender.input(AppMsg::ExportTo(format, path));
+                        }
+                    }
+                    d.destroy();
+                    keep.take();
+                }));
+                dialog.show();
+            }
+            AppMsg::ExportTo(format, path) => {
+                if let AppModel::Full { candidate, .. } = self {
+                    let candidate = candidate.as_ref().clone();
+                    if let Err(e) = crate::export::export(candidate, format, &path) {
+                        show_error(&format!("Could not export to {}: {e}", path.display()));
+                    }
+                }
+            }
+            AppMsg::Search(query) => {
+                self.tree().emit(TreeMsg::Search(query.clone()));
+                if let AppModel::Full {
+                    ref annotations, ..
+                } = self
+                {
+                    let query = query.to_lowercase();
+                    let matching = match query.as_str() {
+                        "" => vec![],
+                        q => annotations.find(|i| matches(i, q)),
                     };
-
-                    self.tree().emit(TreeMsg::Open {
-                        annotations: annotations.clone(),
-                    });
-                    self.hexy().emit(HexyMsg::Open { annotations, bytes });
+                    // The first match is selected and its bytes are shown.
+                    if let Some(node) = matching.first() {
+                        let index = match node {
+                            Node::Group { location, .. } => Some(location.index_from),
+                            Node::Leaf(Leaf::Real(leaf)) => Some(leaf.location.index),
+                            Node::Leaf(Leaf::Virtual(_)) => None,
+                        };
+                        if let Some(index) = index {
+                            self.hexy().emit(HexyMsg::Reveal(index as u32));
+                        }
+                        sender.input(AppMsg::Select(node.path().to_vec()));
+                    }
                 }
             }
             AppMsg::Select(path) => {
@@ -184,6 +372,7 @@ impl SimpleComponent for AppModel {
                     };
                 }
             }
+            AppMsg::Edit(path, value) => self.edit(&path, &value),
             AppMsg::Unselect => {
                 self.hexy().emit(HexyMsg::Unselect);
             }
@@ -2,6 +2,7 @@ use std::rc::Rc;
 
 use bitsplain::tree::Information;
 use bitsplain::value::Value;
+use bitsplain_format::Fmt;
 use gtk::prelude::*;
 use relm4::prelude::*;
 
@@ -10,6 +11,7 @@ pub struct DocModel {
     subtitle: Option<String>,
     data_type: Option<String>,
     doc: Option<String>,
+    warnings: String,
     value: Option<Value>,
     bytes: Rc<Vec<u8>>,
     range: Option<(usize, usize)>,
@@ -37,6 +39,7 @@ impl SimpleComponent for DocModel {
             subtitle: None,
             data_type: None,
             doc: None,
+            warnings: String::new(),
             value: None,
             bytes: Rc::new(vec![]),
             range: None,
@@ -50,8 +53,17 @@ impl SimpleComponent for DocModel {
         match msg {
             DocMsg::T(range, s) => {
                 self.subtitle = Some(s.label.to_string());
-                self.data_type = s.data.get("datatype").cloned();
+                self.data_type = match s.datatype() {
+                    Some(datatype) => Some(datatype.to_string()),
+                    None => s.data.get("datatype").cloned(),
+                };
                 self.doc = s.doc;
+                self.warnings = s
+                    .warnings
+                    .iter()
+                    .map(|w| format!("⚠ {}", w.message))
+                    .collect::<Vec<_>>()
+                    .join("\n");
                 self.value = Some(s.value);
                 self.range = range;
             }
@@ -110,7 +122,7 @@ impl SimpleComponent for DocModel {
 		    add_css_class: "label"
 		},
 		attach[2, 3, 1, 1] = &gtk::Label {
-		    #[watch] set_label: &model.value.as_ref().map(|v| v.preview()).unwrap_or_else(String::new) ,
+		    #[watch] set_label: &model.value.as_ref().map(|v| Fmt::default().text(v)).unwrap_or_else(String::new) ,
 		    set_hexpand: true,
 		    set_xalign: 0.0,
 		    set_wrap_mode: gtk::pango::WrapMode::Char,
@@ -143,6 +155,20 @@ impl SimpleComponent for DocModel {
 		    set_xalign: 0.0,
 		    add_css_class: "value"
 		},
+		attach[1, 6, 1, 1] = &gtk::Label {
+		    set_label: "Warnings",
+		    set_width_request: 50,
+		    set_xalign: 1.0,
+		    set_yalign: 0.8,
+		    add_css_class: "label"
+		},
+		attach[2, 6, 1, 1] = &gtk::Label {
+		    #[watch] set_label: &model.warnings ,
+		    set_hexpand: true,
+		    set_xalign: 0.0,
+		    add_css_class: "value",
+		    add_css_class: "warning"
+		},
 	    }
 	}
     }
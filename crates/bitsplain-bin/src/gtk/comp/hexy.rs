@@ -22,6 +22,8 @@ pub enum HexyMsg {
         bytes: Rc<Vec<u8>>,
     },
     Select(u32, u32),
+    /// Scrolls to bytes of leaf with the index.
+    Reveal(u32),
     Unselect,
 }
 
@@ -50,6 +52,7 @@ impl Component for HexyModel {
                     HexyMsg::Select(from, to) => {
                         *selection = Some((from, to));
                     }
+                    HexyMsg::Reveal(index) => hexy.reveal(index),
                     HexyMsg::Unselect => {
                         *selection = None;
                     }
@@ -57,15 +60,16 @@ impl Component for HexyModel {
                         hexy.clear();
                         let h = bytes.as_ref();
 
-                        annotations
-                            .as_ref()
-                            .real_leaves()
-                            .iter()
-                            .enumerate()
-                            .for_each(|(idx, leaf)| {
-                                hexy.add_group(idx, &h[leaf.location.from..leaf.location.to]);
+                        annotations.as_ref().real_leaves().iter().enumerate().fold(
+                            0,
+                            |end, (idx, leaf)| {
+                                // Bytes shared with previous leaf (leaves of bits) are shown only once.
+                                let from = leaf.location.from.max(end).min(leaf.location.to);
+                                hexy.add_group(idx, &h[from..leaf.location.to]);
                                 // send!(sender, "");
-                            });
+                                end.max(leaf.location.to)
+                            },
+                        );
                         *self = HexyModel::Full {
                             annotations,
                             bytes,
@@ -79,15 +83,16 @@ impl Component for HexyModel {
                     hexy.clear();
                     let h = bytes.as_ref();
 
-                    annotations
-                        .as_ref()
-                        .real_leaves()
-                        .iter()
-                        .enumerate()
-                        .for_each(|(idx, leaf)| {
-                            hexy.add_group(idx, &h[leaf.location.from..leaf.location.to]);
+                    annotations.as_ref().real_leaves().iter().enumerate().fold(
+                        0,
+                        |end, (idx, leaf)| {
+                            // Bytes shared with previous leaf (leaves of bits) are shown only once.
+                            let from = leaf.location.from.max(end).min(leaf.location.to);
+                            hexy.add_group(idx, &h[from..leaf.location.to]);
                             // send!(sender, "");
-                        });
+                            end.max(leaf.location.to)
+                        },
+                    );
                     *self = HexyModel::Full {
                         annotations,
                         bytes,
@@ -1,6 +1,9 @@
 use std::rc::Rc;
 
+use bitsplain::datatype::Datatype;
 use bitsplain::tree::*;
+use bitsplain_format::Fmt;
+use gtk::glib::clone;
 use gtk::glib::prelude::*;
 use gtk::prelude::*;
 use lazy_static::lazy_static;
@@ -15,6 +18,8 @@ struct Row {
     length: Option<usize>,
     data_type: Option<String>,
     value: String,
+    /// Whether value can be changed and re-encoded.
+    editable: bool,
     attrs: gtk::pango::AttrList,
     path: Vec<String>,
     tags: Vec<bitsplain::tree::Tag>,
@@ -38,23 +43,77 @@ pub enum TreeModel {
 //     }
 // }
 
+impl TreeModel {
+    /// Shows rows of annotations that match the query, with their parents.
+    /// Matching rows are highlighted. Empty query matches all rows.
+    fn show(
+        &mut self,
+        annotations: Rc<Tree>,
+        query: &str,
+        tree: &gtk::ColumnView,
+        sender: ComponentSender<Self>,
+    ) {
+        let root = tree_to_model(&annotations, &query.to_lowercase());
+        let tree_list_model = gtk::TreeListModel::new(root, false, true, get_children);
+        let selection = gtk::SingleSelection::builder()
+            .model(&tree_list_model)
+            .build();
+        selection.connect_selected_item_notify(move |sel| {
+            let object = sel
+                .selected_item()
+                .and_downcast::<gtk::TreeListRow>()
+                .and_then(|r| r.item())
+                .and_downcast::<gtk::glib::BoxedAnyObject>();
+
+            if let Some(object) = object {
+                let r = object.borrow::<Row>();
+                sender.input(TreeMsg::Select(Some(r.path.clone())));
+            } else {
+                sender.input(TreeMsg::Select(None));
+            };
+        });
+        tree.set_model(Some(&selection));
+
+        *self = TreeModel::Full {
+            annotations,
+            store: tree_list_model,
+        };
+    }
+}
+
+/// Whether label, value or data type of annotation contains the query,
+/// which is expected in lowercase.
+pub fn matches(information: &Information, query: &str) -> bool {
+    let datatype = information
+        .data
+        .get("datatype")
+        .map(|d| match Datatype::from_name(d) {
+            Some(datatype) => format!("{d} {datatype}"),
+            None => d.clone(),
+        })
+        .unwrap_or_default();
+    [&information.label, &information.value.plain(), &datatype]
+        .iter()
+        .any(|s| s.to_lowercase().contains(query))
+}
+
 lazy_static! {
-    static ref THEME: Vec<String> = vec![
-        "#8be9fd".to_string(),
-        "#ffb86c".to_string(),
-        "#50fa7b".to_string(),
-        "#ff79c6".to_string(),
-        "#bd93f9".to_string(),
-        "#ff5555".to_string(),
-        "#f1fa8c".to_string(),
-    ];
+    static ref THEME: Vec<String> = {
+        let palette = crate::export::palette();
+        (0..palette.len()).map(|i| palette.hex(i)).collect()
+    };
     static ref THEME_SIZE: usize = THEME.len();
 }
 
 #[derive(Debug)]
 pub enum TreeMsg {
-    Open { annotations: Rc<Tree> },
+    Open {
+        annotations: Rc<Tree>,
+    },
+    Search(String),
     Select(Option<Vec<String>>),
+    /// Value of node at the path was changed.
+    Edit(Vec<String>, String),
 }
 
 #[relm4::component(pub)]
@@ -101,7 +160,9 @@ impl Component for TreeModel {
         col_length_factory.connect_bind(on_bind_length);
 
         let col_value_factory = gtk::SignalListItemFactory::new();
-        col_value_factory.connect_setup(on_setup_value);
+        col_value_factory.connect_setup(clone!(@strong sender => move |_, list_item| {
+            on_setup_value(list_item, sender.clone())
+        }));
         col_value_factory.connect_bind(on_bind_value);
 
         let widgets = view_output!();
@@ -111,39 +172,16 @@ impl Component for TreeModel {
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, tree: &Self::Root) {
         match msg {
-            TreeMsg::Open { annotations } => {
-                let root = tree_to_model(&annotations);
-                let tree_list_model = gtk::TreeListModel::new(root, false, true, get_children);
-                let selection = gtk::SingleSelection::builder()
-                    .model(&tree_list_model)
-                    .build();
-                selection.connect_selected_item_notify(move |sel| {
-                    let object = sel
-                        .selected_item()
-                        .and_downcast::<gtk::TreeListRow>()
-                        .and_then(|r| r.item())
-                        .and_downcast::<gtk::glib::BoxedAnyObject>();
-
-                    if let Some(object) = object {
-                        let r = object.borrow::<Row>();
-                        sender.input(TreeMsg::Select(Some(r.path.clone())));
-                    } else {
-                        sender.input(TreeMsg::Select(None));
-                    };
-                });
-                tree.set_model(Some(&selection));
-
-                // *self = TreeModel::Full {
-                //     annotations: annotations.clone(),
-                //     store: self.store().clone(),
-                // };
-
-                // self.store().clear();
-
-                // tree.expand_all();
+            TreeMsg::Open { annotations } => self.show(annotations, "", tree, sender),
+            TreeMsg::Search(query) => {
+                if let TreeModel::Full { annotations, .. } = self {
+                    let annotations = annotations.clone();
+                    self.show(annotations, &query, tree, sender);
+                }
             }
             TreeMsg::Select(Some(path)) => sender.output(AppMsg::Select(path)).unwrap(),
             TreeMsg::Select(None) => sender.output(AppMsg::Unselect).unwrap(),
+            TreeMsg::Edit(path, value) => sender.output(AppMsg::Edit(path, value)).unwrap(),
         }
     }
 }
@@ -165,8 +203,22 @@ fn get_children(obj: &gtk::glib::Object) -> Option<gtk::gio::ListModel> {
     }
 }
 
-fn tree_to_row(tree: &Node) -> Row {
-    match tree {
+/// Row of the node and its children that match the query, `None` if
+/// neither the node nor any of its descendants match.
+fn tree_to_row(tree: &Node, query: &str) -> Option<Row> {
+    let rows = match tree {
+        Node::Group { children, .. } => children
+            .iter()
+            .filter_map(|ch| tree_to_row(ch, query))
+            .collect(),
+        _ => vec![],
+    };
+    let matching = !query.is_empty() && matches(tree.information(), query);
+    if !query.is_empty() && !matching && rows.is_empty() {
+        return None;
+    }
+
+    let row = match tree {
         Node::Group {
             path,
             location: GroupLocation {
@@ -176,11 +228,12 @@ fn tree_to_row(tree: &Node) -> Row {
                 Information {
                     label: annotation,
                     value,
+                    unit,
                     data,
                     tags,
                     ..
                 },
-            children,
+            ..
         } => {
             let attrs = gtk::pango::AttrList::new();
             let mut font_desc = gtk::pango::FontDescription::new();
@@ -190,19 +243,23 @@ fn tree_to_row(tree: &Node) -> Row {
                 annotation: annotation.clone(),
                 length: Some(byte_to - byte_from),
                 data_type: None,
-                value: value.preview(),
+                value: Fmt::default().text_in(value, *unit),
+                editable: false,
                 attrs,
                 path: path.clone(),
                 tags: tags.clone(),
-                children: children.iter().map(tree_to_row).collect(),
+                children: rows,
             }
         }
         Node::Leaf(Leaf::Real(RealLeaf {
-            location: LeafLocation { from, to, index },
+            location: LeafLocation {
+                from, to, index, ..
+            },
             information:
                 Information {
                     label: annotation,
                     value,
+                    unit,
                     data,
                     tags,
                     ..
@@ -222,8 +279,12 @@ fn tree_to_row(tree: &Node) -> Row {
             Row {
                 annotation: annotation.clone(),
                 length: Some(to - from),
-                data_type: data.get("datatype").cloned(),
-                value: value.preview(),
+                data_type: data.get("datatype").map(|d| match Datatype::from_name(d) {
+                    Some(datatype) => datatype.to_string(),
+                    None => d.clone(),
+                }),
+                value: Fmt::default().text_in(value, *unit),
+                editable: data.contains_key("datatype"),
                 attrs,
                 path: path.clone(),
                 tags: tags.clone(),
@@ -235,6 +296,7 @@ fn tree_to_row(tree: &Node) -> Row {
                 Information {
                     label: annotation,
                     value,
+                    unit,
                     ..
                 },
             path,
@@ -243,21 +305,31 @@ fn tree_to_row(tree: &Node) -> Row {
             annotation: annotation.clone(),
             length: None,
             data_type: None,
-            value: value.preview(),
+            value: Fmt::default().text_in(value, *unit),
+            editable: false,
             attrs: gtk::pango::AttrList::new(),
             path: path.clone(),
             tags: vec![],
             children: vec![],
         },
+    };
+
+    if matching {
+        row.attrs
+            .insert(gtk::pango::AttrInt::new_weight(gtk::pango::Weight::Bold));
     }
+    Some(row)
 }
 
-fn tree_to_model(items: &[Node]) -> gtk::gio::ListModel {
+fn tree_to_model(items: &[Node], query: &str) -> gtk::gio::ListModel {
     let store = gtk::gio::ListStore::new::<gtk::glib::BoxedAnyObject>();
 
-    items.iter().for_each(|t| {
-        store.append(&gtk::glib::BoxedAnyObject::new(tree_to_row(t)));
-    });
+    items
+        .iter()
+        .filter_map(|t| tree_to_row(t, query))
+        .for_each(|row| {
+            store.append(&gtk::glib::BoxedAnyObject::new(row));
+        });
 
     store.into()
 }
@@ -337,20 +409,45 @@ fn on_bind_length(_factory: &gtk::SignalListItemFactory, list_item: &gtk::ListIt
     }
 }
 
-fn on_setup_value(_factory: &gtk::SignalListItemFactory, list_item: &gtk::ListItem) {
-    list_item.set_child(Some(&gtk::Label::builder().xalign(0.0).build()))
+/// Value is shown by label, or by editable label if it can be changed.
+/// Changed value is sent to the tree once editing is finished.
+fn on_setup_value(list_item: &gtk::ListItem, sender: ComponentSender<TreeModel>) {
+    let bx = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+    bx.append(&gtk::Label::builder().xalign(0.0).build());
+
+    let editable = gtk::EditableLabel::new("");
+    editable.set_alignment(0.0);
+    editable.connect_editing_notify(clone!(@weak list_item => move |editable| {
+        if editable.is_editing() {
+            return;
+        }
+        if let Some(object) = get_object(&list_item) {
+            let row: &Row = &object.borrow();
+            let value = editable.text().to_string();
+            if value != row.value {
+                sender.input(TreeMsg::Edit(row.path.clone(), value));
+            }
+        }
+    }));
+    bx.append(&editable);
+
+    list_item.set_child(Some(&bx))
 }
 
 fn on_bind_value(_factory: &gtk::SignalListItemFactory, list_item: &gtk::ListItem) {
     if let Some(object) = get_object(list_item) {
         let row: &Row = &object.borrow();
-        list_item
-            .child()
-            .and_downcast::<gtk::Label>()
-            .iter()
-            .for_each(|lbl| {
-                lbl.set_label(&row.value);
-                lbl.set_attributes(Some(&row.attrs));
-            })
+        let Some(bx) = list_item.child().and_downcast::<gtk::Box>() else {
+            return;
+        };
+        if let Some(lbl) = bx.first_child().and_downcast::<gtk::Label>() {
+            lbl.set_label(&row.value);
+            lbl.set_attributes(Some(&row.attrs));
+            lbl.set_visible(!row.editable);
+        }
+        if let Some(editable) = bx.last_child().and_downcast::<gtk::EditableLabel>() {
+            editable.set_text(&row.value);
+            editable.set_visible(row.editable);
+        }
     }
 }
@@ -0,0 +1,30 @@
+//! Export of the shown candidate by output formats, the same ones that
+//! the CLI offers.
+
+use std::path::Path;
+
+use bitsplain::decode::Candidate;
+use bitsplain_format::*;
+// Output formats register themselves, their crates only need to be linked.
+use {
+    bitsplain_format_html as _, bitsplain_format_image as _, bitsplain_format_json as _,
+    bitsplain_format_svg as _,
+};
+
+/// Renders candidate in the format into file.
+pub fn export(candidate: Candidate, format: &OutputFormat, path: &Path) -> Result<(), String> {
+    let ctx = bitsplain_bin::ctx(bitsplain_bin::settings()?, None);
+    let mut file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+    (format.render)(candidate, &ctx, &mut file).map_err(|e| e.to_string())
+}
+
+/// Palette selected in configuration, the default one if configuration
+/// cannot be read.
+pub fn palette() -> Palette {
+    bitsplain_bin::settings()
+        .and_then(|s| s.palette())
+        .unwrap_or_else(|e| {
+            eprintln!("{e}");
+            Palette::default()
+        })
+}
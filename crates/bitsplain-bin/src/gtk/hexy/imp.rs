@@ -41,15 +41,12 @@ impl Default for HexyLookImpl {
 }
 
 lazy_static! {
-    static ref THEME: Vec<gdk::RGBA> = vec![
-        gdk::RGBA::parse("#8be9fd").unwrap(),
-        gdk::RGBA::parse("#ffb86c").unwrap(),
-        gdk::RGBA::parse("#50fa7b").unwrap(),
-        gdk::RGBA::parse("#ff79c6").unwrap(),
-        gdk::RGBA::parse("#bd93f9").unwrap(),
-        gdk::RGBA::parse("#ff5555").unwrap(),
-        gdk::RGBA::parse("#f1fa8c").unwrap(),
-    ];
+    static ref THEME: Vec<gdk::RGBA> = {
+        let palette = crate::export::palette();
+        (0..palette.len())
+            .map(|i| gdk::RGBA::parse(&palette.hex(i)).unwrap())
+            .collect()
+    };
     static ref THEME_SIZE: usize = THEME.len();
 }
 
@@ -162,6 +159,17 @@ impl HexyLookImpl {
         }
     }
 
+    /// Scrolls to the first byte of leaf with the index.
+    pub(super) fn reveal(&self, index: u32) {
+        let view = self.hexview.borrow();
+        if let Some(tag) = self.tags.borrow().get(&index) {
+            let mut iter = view.buffer().start_iter();
+            if iter.starts_tag(Some(tag)) || iter.forward_to_tag_toggle(Some(tag)) {
+                view.scroll_to_iter(&mut iter, 0.0, false, 0.0, 0.0);
+            }
+        }
+    }
+
     pub(super) fn no_highlight(&self) {
         let mut hl = self.highlighted.borrow_mut();
         if let Some(Highlighted(min, max)) = hl.as_ref() {
@@ -31,6 +31,10 @@ impl HexyLook {
         self.imp().highlight(from, to);
     }
 
+    pub fn reveal(&self, index: u32) {
+        self.imp().reveal(index);
+    }
+
     pub fn no_highlight(&self) {
         self.imp().no_highlight();
     }
@@ -6,11 +6,17 @@ use relm4::gtk;
 
 mod app;
 mod comp;
+mod export;
 mod hexy;
 mod rich_label;
 mod tag;
 
 relm4::new_action_group!(WindowActionGroup, "win");
+relm4::new_stateless_action!(OpenAction, WindowActionGroup, "open");
+relm4::new_stateless_action!(ExportHtmlAction, WindowActionGroup, "export-html");
+relm4::new_stateless_action!(ExportSvgAction, WindowActionGroup, "export-svg");
+relm4::new_stateless_action!(ExportPngAction, WindowActionGroup, "export-png");
+relm4::new_stateless_action!(ExportJsonAction, WindowActionGroup, "export-json");
 relm4::new_stateless_action!(QuitAction, WindowActionGroup, "quit");
 relm4::new_stateless_action!(PasteAction, WindowActionGroup, "paste");
 
@@ -19,6 +25,13 @@ fn main() {
 
     relm4::menu! {
     file_menu: {
+        "Open…" => OpenAction,
+        section! {
+            "Export HTML…" => ExportHtmlAction,
+            "Export SVG…" => ExportSvgAction,
+            "Export PNG…" => ExportPngAction,
+            "Export JSON…" => ExportJsonAction,
+        },
         "Quit" => QuitAction
     },
     edit_menu: {
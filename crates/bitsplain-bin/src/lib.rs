@@ -0,0 +1,137 @@
+//! Reading of configuration files, shared by the CLI and the GTK interface
+//! so both render candidates in the same context.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use bitsplain::bitcoin::{Network, PublicKey};
+use bitsplain::catalog::Catalog;
+use bitsplain::timestamp::TimeFormat;
+use bitsplain_format::*;
+
+/// Directory `bitsplain` in user's configuration directory.
+pub fn config_dir() -> Result<PathBuf, String> {
+    dirs::config_dir()
+        .map(|d| d.join("bitsplain"))
+        .ok_or_else(|| "Could not find directory with configuration files.".to_string())
+}
+
+/// Configuration merged from the dark theme and `config.toml`.
+pub fn config() -> Result<config::Config, String> {
+    let dir = config_dir()?;
+    config::Config::builder()
+        .add_source(config::File::from(dir.join("dark.toml")))
+        .add_source(config::File::from(dir.join("config.toml")))
+        .build()
+        .map_err(|e| format!("Invalid configuration: {e}"))
+}
+
+/// Settings read from configuration files.
+pub fn settings() -> Result<Settings, String> {
+    config()?
+        .try_deserialize::<Settings>()
+        .map_err(|e| format!("Invalid configuration: {e}"))
+}
+
+/// Context of rendering built from settings and from dictionaries of labels,
+/// documentation and aliases of nodes found in configuration directory.
+/// Aliases are read from `node_aliases` if provided, which then has to exist.
+/// Problems with the dictionaries are reported and they are left empty.
+pub fn ctx(settings: Settings, node_aliases: Option<&Path>) -> Ctx {
+    let dir = config_dir().ok();
+
+    // Labels are written as `"<public key, address, txid…>" = "<label>"`.
+    let labels = match dir.as_ref().map(|d| d.join("labels.toml")) {
+        Some(file) => config::Config::builder()
+            .add_source(config::File::from(file).required(false))
+            .build()
+            .and_then(|c| c.try_deserialize::<HashMap<String, String>>())
+            .unwrap_or_else(|e| {
+                eprintln!("Invalid dictionary of labels: {e}");
+                HashMap::new()
+            }),
+        None => HashMap::new(),
+    };
+
+    // Documentation is written as `["<annotation ID>"]` followed by `doc = "…"`
+    // and `splain = "…"`, see output format `catalog`.
+    let catalog = match dir.as_ref().map(|d| d.join("docs.toml")) {
+        Some(file) => config::Config::builder()
+            .add_source(config::File::from(file).required(false))
+            .build()
+            .and_then(|c| c.try_deserialize::<Catalog>())
+            .unwrap_or_else(|e| {
+                eprintln!("Invalid catalog of documentation: {e}");
+                Catalog::default()
+            }),
+        None => Catalog::default(),
+    };
+
+    // Default snapshot does not have to exist, explicitly provided one does.
+    let aliases_file = match node_aliases {
+        Some(file) => Some(file.to_path_buf()),
+        None => dir.map(|d| d.join("nodes.json")).filter(|f| f.is_file()),
+    };
+    let aliases = match aliases_file {
+        Some(file) => read_node_aliases(&file).unwrap_or_else(|e| {
+            eprintln!(
+                "Could not read aliases of nodes from {}: {e}",
+                file.display()
+            );
+            HashMap::new()
+        }),
+        None => HashMap::new(),
+    };
+
+    let time_format = settings.time.time_format().unwrap_or_else(|e| {
+        eprintln!("Invalid [time] in configuration: {e}");
+        TimeFormat::default()
+    });
+
+    let palette = settings.palette().unwrap_or_else(|e| {
+        eprintln!("Invalid theme in configuration: {e}");
+        Palette::default()
+    });
+
+    Ctx {
+        detail: settings.details.unwrap_or(Detail::Short),
+        format: Fmt {
+            btcunit: BtcUnit,
+            num: NumFmt,
+            hex: HexFmt {
+                max_len: None,
+                append_len: true,
+            },
+            time: time_format,
+        },
+        network: settings.network.unwrap_or(Network::Bitcoin),
+        palette,
+        settings,
+        params: HashMap::new(),
+        labels,
+        aliases,
+        catalog,
+    }
+}
+
+/// Aliases of nodes from snapshot of Lightning network graph, as written
+/// by `lightning-cli listnodes` (Core Lightning) or `lncli describegraph`
+/// (LND). Nodes without alias are skipped.
+fn read_node_aliases(path: &Path) -> Result<HashMap<PublicKey, String>, String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let graph: serde_json::Value =
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| e.to_string())?;
+
+    let nodes = graph["nodes"]
+        .as_array()
+        .ok_or("there is no list of nodes")?;
+
+    Ok(nodes
+        .iter()
+        .filter_map(|n| {
+            let id = n["nodeid"].as_str().or_else(|| n["pub_key"].as_str())?;
+            let alias = n["alias"].as_str().filter(|a| !a.is_empty())?;
+            Some((id.parse().ok()?, alias.to_string()))
+        })
+        .collect())
+}
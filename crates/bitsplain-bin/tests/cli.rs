@@ -0,0 +1,59 @@
+use std::process::{Command, Output};
+
+/// Transaction spending P2WPKH output of 100000 sats, signed by key 0x11…11.
+const TX: &str = concat!(
+    "0200000000010122222222222222222222222222222222222222222222222222222222222222220000000000",
+    "ffffffff01b882010000000000160014fc7250a211deddc70ee5a2738de5f07817351cef02483045022100d3",
+    "eaff84cdf7f596dbd85c3beb38a12e401780e0e628ba7d27ac53151259916a022052c61bd24793777c482b70",
+    "a3d839956e4391c454ba60fd4b93bd2ef70785cc130121034f355bdcb7cc0af728ef3cceb9615d90684bb5b2",
+    "ca5f859ab0f0b704075871aa00000000",
+);
+
+/// Script of the output spent by [`TX`].
+const SPENT_SCRIPT: &str = "0014fc7250a211deddc70ee5a2738de5f07817351cef";
+
+/// Runs the CLI with configuration shipped in the repository.
+fn bitsplain(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bitsplain"))
+        .env(
+            "XDG_CONFIG_HOME",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/../../config"),
+        )
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn signature_verified_with_given_spent_output() {
+    let out = bitsplain(&["--amount", "100000", "--spent-script", SPENT_SCRIPT, TX]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success());
+    assert!(stdout.contains("(Verification): valid"), "{stdout}");
+}
+
+#[test]
+fn check_fails_on_invalid_signature() {
+    let out = bitsplain(&[
+        "--check",
+        "--amount",
+        "100000",
+        "--spent-script",
+        SPENT_SCRIPT,
+        TX,
+    ]);
+    assert_eq!(out.status.code(), Some(0));
+
+    // Sighash commits to the amount, so the signature is not valid for a different one.
+    let out = bitsplain(&[
+        "--check",
+        "--amount",
+        "100001",
+        "--spent-script",
+        SPENT_SCRIPT,
+        TX,
+    ]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("Verification"), "{stdout}");
+}
@@ -0,0 +1,262 @@
+//! Derive macro [`Splain`], which generates annotated parser of a struct
+//! out of its definition. Use it through `bitsplain::dsl::Splain`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Field, Fields, Ident, LitInt, LitStr};
+
+/// Generates function `parse(Span) -> Parsed<Self>`, which parses fields
+/// of the struct one after another, in order of their definition.
+///
+/// Every field is parsed by parser given in attribute `#[splain(parse = …)]`,
+/// which can be any expression that evaluates to a parser. If it is missing,
+/// `<Type>::parse` is used, so fields may be structs deriving `Splain` too.
+///
+/// Fields with `label` are annotated by [`ann`], the other attributes
+/// correspond to methods of [`Ann`]:
+///
+/// - `label = "…"` – label of the field,
+/// - `value = …` – value of the field (default is `auto()`),
+/// - `doc = "…"` – documentation (default is doc comment of the field),
+/// - `splain = …` – splain of the field,
+/// - `bip = 141` – reference to a BIP, may be repeated,
+/// - `bolt = 4` or `bolt = (4, "section")` – reference to a BOLT, may be repeated,
+/// - `slip = 132` – reference to a SLIP, may be repeated,
+/// - `www = "…"` – reference to a web page, may be repeated,
+/// - `unit = Sat` – unit of numeric value,
+/// - `category = Amount` – semantic category, may be repeated,
+/// - `warn = …` – generator of warning, may be repeated.
+///
+/// Fields without `label` are not annotated, their parser is expected
+/// to annotate itself.
+///
+/// ```ignore
+/// #[derive(Splain)]
+/// pub struct Act {
+///     #[splain(parse = version)]
+///     pub version: u8,
+///     /// Ephemeral key generated by the sender.
+///     #[splain(label = "Ephemeral public key", parse = public_key)]
+///     pub ephemeral_key: PublicKey,
+/// }
+/// ```
+///
+/// [`ann`]: https://docs.rs/bitsplain/latest/bitsplain/dsl/fn.ann.html
+/// [`Ann`]: https://docs.rs/bitsplain/latest/bitsplain/dsl/struct.Ann.html
+#[proc_macro_derive(Splain, attributes(splain))]
+pub fn derive_splain(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match splain(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn splain(input: DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "Splain cannot be derived for generic structs",
+        ));
+    }
+
+    let fields = match input.data {
+        Data::Struct(data) => match data.fields {
+            Fields::Named(fields) => fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "Splain can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "Splain can only be derived for structs",
+            ))
+        }
+    };
+
+    let name = input.ident;
+    let mut steps = Vec::new();
+    let mut names = Vec::new();
+
+    for field in fields.iter() {
+        let ident = field.ident.clone().expect("named field");
+        let attrs = FieldAttrs::of(field)?;
+        let parser = attrs.parser(field);
+        let step = match attrs.annotation(field) {
+            Some(ann) => quote! { ::bitsplain::parse::parse(#parser, #ann)(s)? },
+            None => quote! { (#parser)(s)? },
+        };
+        steps.push(quote! { let (s, #ident) = #step; });
+        names.push(ident);
+    }
+
+    let doc = format!("Parser of [`{name}`], generated by `#[derive(Splain)]`.");
+
+    Ok(quote! {
+        impl #name {
+            #[doc = #doc]
+            pub fn parse(s: ::bitsplain::parse::Span) -> ::bitsplain::parse::Parsed<Self> {
+                #(#steps)*
+                Ok((s, #name { #(#names),* }))
+            }
+        }
+    })
+}
+
+/// Contents of attributes `#[splain(…)]` of one field.
+#[derive(Default)]
+struct FieldAttrs {
+    label: Option<LitStr>,
+    parse: Option<Expr>,
+    value: Option<Expr>,
+    doc: Option<LitStr>,
+    splain: Option<Expr>,
+    bips: Vec<LitInt>,
+    bolts: Vec<Expr>,
+    slips: Vec<LitInt>,
+    wwws: Vec<LitStr>,
+    unit: Option<Ident>,
+    categories: Vec<Ident>,
+    warns: Vec<Expr>,
+}
+
+impl FieldAttrs {
+    fn of(field: &Field) -> syn::Result<FieldAttrs> {
+        let mut attrs = FieldAttrs::default();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("splain")) {
+            attr.parse_nested_meta(|meta| {
+                let key = meta
+                    .path
+                    .get_ident()
+                    .map(|i| i.to_string())
+                    .unwrap_or_default();
+                match key.as_str() {
+                    "label" => attrs.label = Some(meta.value()?.parse()?),
+                    "parse" => attrs.parse = Some(meta.value()?.parse()?),
+                    "value" => attrs.value = Some(meta.value()?.parse()?),
+                    "doc" => attrs.doc = Some(meta.value()?.parse()?),
+                    "splain" => attrs.splain = Some(meta.value()?.parse()?),
+                    "bip" => attrs.bips.push(meta.value()?.parse()?),
+                    "bolt" => attrs.bolts.push(meta.value()?.parse()?),
+                    "slip" => attrs.slips.push(meta.value()?.parse()?),
+                    "www" => attrs.wwws.push(meta.value()?.parse()?),
+                    "unit" => attrs.unit = Some(meta.value()?.parse()?),
+                    "category" => attrs.categories.push(meta.value()?.parse()?),
+                    "warn" => attrs.warns.push(meta.value()?.parse()?),
+                    _ => return Err(meta.error("unknown splain attribute")),
+                }
+                Ok(())
+            })?;
+        }
+
+        let annotating = [
+            attrs.value.is_some(),
+            attrs.doc.is_some(),
+            attrs.splain.is_some(),
+            !attrs.bips.is_empty(),
+            !attrs.bolts.is_empty(),
+            !attrs.slips.is_empty(),
+            !attrs.wwws.is_empty(),
+            attrs.unit.is_some(),
+            !attrs.categories.is_empty(),
+            !attrs.warns.is_empty(),
+        ];
+        if attrs.label.is_none() && annotating.contains(&true) {
+            return Err(syn::Error::new(
+                field.span(),
+                "annotations of field require its label",
+            ));
+        }
+
+        Ok(attrs)
+    }
+
+    fn parser(&self, field: &Field) -> TokenStream2 {
+        match &self.parse {
+            Some(parse) => quote! { #parse },
+            None => {
+                let ty = &field.ty;
+                quote! { <#ty>::parse }
+            }
+        }
+    }
+
+    fn annotation(&self, field: &Field) -> Option<TokenStream2> {
+        let label = self.label.as_ref()?;
+        let value = match &self.value {
+            Some(value) => quote! { #value },
+            None => quote! { ::bitsplain::dsl::auto() },
+        };
+        let mut ann = quote! { ::bitsplain::dsl::ann(#label, #value) };
+
+        if let Some(doc) = self
+            .doc
+            .as_ref()
+            .map(|d| d.value())
+            .or_else(|| doc_comment(field))
+        {
+            ann = quote! { #ann.doc(#doc) };
+        }
+        if let Some(splain) = &self.splain {
+            ann = quote! { #ann.splain(#splain) };
+        }
+        for bip in &self.bips {
+            ann = quote! { #ann.bip(#bip) };
+        }
+        for bolt in &self.bolts {
+            ann = match bolt {
+                Expr::Tuple(t) if t.elems.len() == 2 => {
+                    let (n, section) = (&t.elems[0], &t.elems[1]);
+                    quote! { #ann.bolt_section(#n, #section) }
+                }
+                n => quote! { #ann.bolt(#n) },
+            };
+        }
+        for slip in &self.slips {
+            ann = quote! { #ann.slip(#slip) };
+        }
+        for www in &self.wwws {
+            ann = quote! { #ann.www(#www) };
+        }
+        if let Some(unit) = &self.unit {
+            ann = quote! { #ann.unit(::bitsplain::value::Unit::#unit) };
+        }
+        for category in &self.categories {
+            ann = quote! { #ann.category(::bitsplain::tree::Category::#category) };
+        }
+        for warn in &self.warns {
+            ann = quote! { #ann.warn(#warn) };
+        }
+
+        Some(ann)
+    }
+}
+
+/// Doc comment of the field as one line.
+fn doc_comment(field: &Field) -> Option<String> {
+    let lines = field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .filter_map(|a| match &a.meta.require_name_value().ok()?.value {
+            Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s),
+                ..
+            }) => Some(s.value().trim().to_string()),
+            _ => None,
+        })
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join(" "))
+    }
+}
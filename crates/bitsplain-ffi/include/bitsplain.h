@@ -0,0 +1,35 @@
+/*
+ * C interface of bitsplain, library explaining Bitcoin-related binary data.
+ *
+ * Decoding functions return JSON array of candidates, from the most likely
+ * one, or NULL on invalid input. Returned strings have to be released
+ * by bitsplain_free.
+ *
+ * Panics inside of the library are caught, the decoding functions return
+ * NULL in such case instead of aborting the calling program.
+ */
+
+#ifndef BITSPLAIN_H
+#define BITSPLAIN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Decodes NUL-terminated string (hex, base64, bech32, ...). */
+char *bitsplain_decode(const char *input);
+
+/* Decodes len bytes of raw data. */
+char *bitsplain_decode_bytes(const uint8_t *data, size_t len);
+
+/* Releases string returned by bitsplain_decode or bitsplain_decode_bytes. */
+void bitsplain_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BITSPLAIN_H */
@@ -0,0 +1,101 @@
+//! C interface of bitsplain, so programs written in other languages can
+//! explain binary data. Results are returned as JSON strings of the same
+//! shape as JSON output of bitsplain, i. e. array of candidates, from the
+//! most likely one. Declarations are in `include/bitsplain.h`.
+//!
+//! Strings returned by the functions are owned by the caller and have to be
+//! released by [`bitsplain_free`].
+//!
+//! Panics of decoders do not cross the C boundary, where they would abort
+//! the host program. Decoding functions return NULL instead.
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use bitsplain::decode::{decode_input, CandidateSummary, Input};
+
+/// Decodes NUL-terminated string (hex, base64, bech32, …) and returns JSON
+/// array of candidates. Returns NULL if the input is not valid UTF-8
+/// or if decoding panicked.
+///
+/// # Safety
+///
+/// `input` has to be a valid pointer to NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bitsplain_decode(input: *const c_char) -> *mut c_char {
+    if input.is_null() {
+        return ptr::null_mut();
+    }
+    match CStr::from_ptr(input).to_str() {
+        Ok(s) => candidates(Input::String(s.to_string())),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Decodes `len` bytes of raw data and returns JSON array of candidates.
+/// Returns NULL if decoding panicked.
+///
+/// # Safety
+///
+/// `data` has to be a valid pointer to at least `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bitsplain_decode_bytes(data: *const u8, len: usize) -> *mut c_char {
+    if data.is_null() {
+        return ptr::null_mut();
+    }
+    let data = std::slice::from_raw_parts(data, len);
+    candidates(Input::Binary(data.to_vec().into()))
+}
+
+/// Releases string returned by other functions of this library.
+///
+/// # Safety
+///
+/// `s` has to be a pointer returned by this library, or NULL,
+/// and it must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn bitsplain_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// JSON of candidates of the input, NULL if it cannot be produced.
+fn candidates(input: Input) -> *mut c_char {
+    // Nothing is shared with the caller, so state broken by panic is dropped.
+    catch_unwind(AssertUnwindSafe(|| {
+        let candidates = decode_input(input)
+            .iter()
+            .map(|c| c.summary())
+            .collect::<Vec<CandidateSummary>>();
+
+        serde_json::to_string(&candidates)
+            .ok()
+            .and_then(|json| CString::new(json).ok())
+    }))
+    .ok()
+    .flatten()
+    .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_and_free() {
+        let input = CString::new("0014fc7250a211deddc70ee5a2738de5f07817351cef").unwrap();
+        unsafe {
+            let json = bitsplain_decode(input.as_ptr());
+            assert!(!json.is_null());
+            let candidates: serde_json::Value =
+                serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+            assert!(!candidates.as_array().unwrap().is_empty());
+            bitsplain_free(json);
+
+            assert!(bitsplain_decode(ptr::null()).is_null());
+            bitsplain_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Graphviz DOT output of the annotation tree. Groups are rendered as clusters
+//! and leaves as nodes labeled with a preview of their values. Links between
+//! nodes are rendered as labeled edges.
+//!
+//! Supported parameters:
+//!
+//! - `rankdir` – direction of the graph layout (`LR`, `TB`, …), default `LR`,
+//! - `max_value` – maximum number of characters of value preview, default 32.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use bitsplain::decode::Candidate;
+use bitsplain::tree::{Information, Leaf, Node};
+use bitsplain_format::names::escape;
+use bitsplain_format::*;
+
+output_format!(
+    name = "dot",
+    extension = "dot",
+    description = "Graphviz DOT graph of the annotation tree",
+    params = {
+        rankdir = "Direction of graph: LR (default), TB, RL or BT",
+        max_value = "Maximum length of values, longer are shortened (default 32)",
+    },
+    render
+);
+
+pub fn render<W: Write>(candidate: Candidate, ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
+    let dot = generate(candidate, ctx)?;
+    Ok(out.write_all(dot.as_bytes())?)
+}
+
+pub fn generate(candidate: Candidate, ctx: &Ctx) -> Result<String, FormatError> {
+    let rankdir = ctx.param_or("rankdir", "LR".to_string())?;
+    let max_value = ctx.param_or("max_value", 32usize)?;
+
+    let mut dot = Dot {
+        out: String::new(),
+        next_id: 0,
+        leaves: vec![],
+        anchors: HashMap::new(),
+        max_value,
+    };
+
+    dot.line(0, "digraph bitsplain {");
+    dot.line(
+        1,
+        &format!(
+            "graph [rankdir={rankdir}, compound=true, label=\"{}\", labelloc=t, fontname=\"sans-serif\"];",
+            escape(candidate.decoder.title)
+        ),
+    );
+    dot.line(
+        1,
+        "node [shape=box, style=\"rounded,filled\", fillcolor=\"#f8f8f2\", fontname=\"monospace\"];",
+    );
+
+    candidate
+        .annotations
+        .iter()
+        .for_each(|node| dot.node(node, 1));
+
+    // Invisible edges keep leaves in the order in which they appear in data.
+    let edges = dot
+        .leaves
+        .windows(2)
+        .map(|w| format!("{} -> {} [style=invis];", w[0], w[1]))
+        .collect::<Vec<_>>();
+    edges.iter().for_each(|e| dot.line(1, e));
+
+    let links = candidate
+        .annotations
+        .links()
+        .iter()
+        .filter_map(|(node, link)| {
+            let (from, tail) = dot.anchors.get(node.path())?;
+            let (to, head) = dot.anchors.get(&link.path)?;
+            let tail = tail
+                .as_ref()
+                .map(|c| format!(", ltail={c}"))
+                .unwrap_or_default();
+            let head = head
+                .as_ref()
+                .map(|c| format!(", lhead={c}"))
+                .unwrap_or_default();
+            Some(format!(
+                "{from} -> {to} [label=\"{}\", color=\"#6272a4\", fontname=\"sans-serif\", constraint=false{tail}{head}];",
+                escape(&link.label)
+            ))
+        })
+        .collect::<Vec<_>>();
+    links.iter().for_each(|e| dot.line(1, e));
+
+    dot.line(0, "}");
+
+    Ok(dot.out)
+}
+
+struct Dot {
+    out: String,
+    next_id: usize,
+    leaves: Vec<String>,
+    /// Node at which edges of links to node of the path start or end,
+    /// and cluster of the node, if it is a group.
+    anchors: HashMap<Vec<String>, (String, Option<String>)>,
+    max_value: usize,
+}
+
+impl Dot {
+    fn line(&mut self, indent: usize, line: &str) {
+        self.out.push_str(&"  ".repeat(indent));
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+
+    fn id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{prefix}{}", self.next_id)
+    }
+
+    fn node(&mut self, node: &Node, indent: usize) {
+        match node {
+            Node::Group {
+                information,
+                children,
+                ..
+            } => {
+                let id = self.id("cluster_");
+                self.line(indent, &format!("subgraph {id} {{"));
+                self.line(
+                    indent + 1,
+                    &format!(
+                        "label=\"{}\"{};",
+                        escape(&with_warnings(
+                            label(
+                                &information.label,
+                                &information.value.preview(),
+                                self.max_value
+                            ),
+                            information
+                        )),
+                        warning_style(information, "; fontcolor=\"#ff5555\"")
+                    ),
+                );
+                let first = self.leaves.len();
+                children.iter().for_each(|ch| self.node(ch, indent + 1));
+                self.line(indent, "}");
+                if let Some(leaf) = self.leaves.get(first).cloned() {
+                    self.anchors.insert(node.path().to_vec(), (leaf, Some(id)));
+                }
+            }
+            Node::Leaf(leaf) => {
+                let id = self.id("n");
+                let information = leaf.information();
+                let style = match leaf {
+                    Leaf::Real(_) => "",
+                    Leaf::Virtual(_) => ", style=\"rounded,dashed,filled\"",
+                };
+                self.line(
+                    indent,
+                    &format!(
+                        "{id} [label=\"{}\"{style}{}];",
+                        escape(&with_warnings(
+                            label(
+                                &information.label,
+                                &information.value.preview(),
+                                self.max_value
+                            ),
+                            information
+                        )),
+                        warning_style(information, ", color=\"#ff5555\", penwidth=2")
+                    ),
+                );
+                self.anchors
+                    .insert(node.path().to_vec(), (id.clone(), None));
+                self.leaves.push(id);
+            }
+        }
+    }
+}
+
+/// Label followed by warnings of the node, one per line.
+fn with_warnings(label: String, information: &Information) -> String {
+    information
+        .warnings
+        .iter()
+        .fold(label, |l, w| format!("{l}\n⚠ {}", w.message))
+}
+
+/// Style of node with warnings, otherwise empty.
+fn warning_style(information: &Information, style: &'static str) -> &'static str {
+    if information.warnings.is_empty() {
+        ""
+    } else {
+        style
+    }
+}
+
+/// Label consisting of name and, if not empty, value preview.
+fn label(name: &str, value: &str, max_len: usize) -> String {
+    if value.is_empty() {
+        name.to_string()
+    } else if value.chars().count() > max_len {
+        format!(
+            "{name}\n{}…",
+            value.chars().take(max_len).collect::<String>()
+        )
+    } else {
+        format!("{name}\n{value}")
+    }
+}
@@ -0,0 +1,32 @@
+// Hovering a chunk of bytes or a row of legend highlights both of them,
+// clicking a group collapses or expands its rows.
+(function () {
+  function highlight(from, to, on) {
+    document.querySelectorAll("[data-leaf]").forEach(function (el) {
+      var i = Number(el.dataset.leaf);
+      if (i >= from && i <= to) {
+        el.classList.toggle("hl", on);
+      }
+    });
+  }
+
+  document.querySelectorAll("[data-leaf], tr[data-from]").forEach(function (el) {
+    var from = Number(el.dataset.leaf !== undefined ? el.dataset.leaf : el.dataset.from);
+    var to = Number(el.dataset.leaf !== undefined ? el.dataset.leaf : el.dataset.to);
+    el.addEventListener("mouseenter", function () { highlight(from, to, true); });
+    el.addEventListener("mouseleave", function () { highlight(from, to, false); });
+  });
+
+  var collapsed = {};
+
+  document.querySelectorAll("tr.group").forEach(function (group) {
+    group.addEventListener("click", function () {
+      var id = group.dataset.group;
+      collapsed[id] = !collapsed[id];
+      group.classList.toggle("collapsed", collapsed[id]);
+      document.querySelectorAll("tr[data-groups]").forEach(function (row) {
+        row.hidden = row.dataset.groups.split(" ").some(function (g) { return collapsed[g]; });
+      });
+    });
+  });
+})();
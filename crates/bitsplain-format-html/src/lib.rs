@@ -1,32 +1,63 @@
 use std::io::Write;
 
 use bitsplain::decode::Candidate;
-use bitsplain::dsl::Reference;
+use bitsplain::output::fmt::Style;
 use bitsplain::output::hexblock::*;
-use bitsplain::tree::Leaf;
+use bitsplain::tree::{Information, Leaf, Node};
 use bitsplain_format::*;
 
-lazy_static::lazy_static! {
-    static ref THEME: Vec<String> =
-        vec![
-            "#8be9fd".to_string(),
-            "#ffb86c".to_string(),
-            "#50fa7b".to_string(),
-            "#ff79c6".to_string(),
-            "#bd93f9".to_string(),
-            "#ff5555".to_string(),
-            "#f1fa8c".to_string(),
-        ];
-    static ref THEME_SIZE: usize = THEME.len();
+/// Whether the output is a static document or contains JavaScript
+/// for exploring the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Plain HTML without any scripts.
+    Static,
+    /// Hovering a legend row highlights the corresponding bytes and vice
+    /// versa, groups of legend can be collapsed.
+    Interactive,
 }
 
+impl Mode {
+    /// Obtain mode from parameter `mode` (`static` or `interactive`).
+    /// Static is used if the parameter is missing.
+    pub fn from_ctx(ctx: &Ctx) -> Result<Mode, FormatError> {
+        match ctx.param::<String>("mode")?.as_deref() {
+            None | Some("static") => Ok(Mode::Static),
+            Some("interactive") => Ok(Mode::Interactive),
+            Some(m) => Err(FormatError::invalid_param("mode", m)),
+        }
+    }
+}
+
+output_format!(
+    name = "html",
+    extension = "html",
+    description = "HTML page with colored bytes and legend",
+    params = {
+        mode = "static (default) or interactive",
+    },
+    render
+);
+
 pub fn render<W: Write>(candidate: Candidate, ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
-    let html = generate(candidate, ctx);
+    let html = generate(candidate, ctx)?;
     Ok(out.write_all(html.as_bytes())?)
 }
 
-pub fn generate(candidate: Candidate, ctx: &Ctx) -> String {
+pub fn generate(candidate: Candidate, ctx: &Ctx) -> Result<String, FormatError> {
+    let mode = Mode::from_ctx(ctx)?;
     let hexblock = HexBlock::from_candidate(&candidate);
+    let (legend, interactive) = match mode {
+        Mode::Static => (make_legend(&candidate, ctx), String::new()),
+        Mode::Interactive => (
+            make_tree_legend(&candidate, ctx),
+            format!(
+                "<style>\n{}</style>\n<script>\n{}</script>",
+                include_str!("interactive.css"),
+                include_str!("interactive.js")
+            ),
+        ),
+    };
     let background = include_str!("background.base64");
     let html = format!(
         r#"<html>
@@ -109,14 +140,36 @@ table.legend dl {{
     margin: 0;
 }}
 
+table.legend dd.warning, table.legend span.warning, ul.warnings {{
+  color: #ff5555;
+  font-weight: bold;
+}}
+
+ul.links {{
+  color: #8be9fd;
+}}
+
+dd.value span.number {{ color: #ff79c6; }}
+dd.value span.hash {{ color: #50fa7b; }}
+dd.value span.hex {{ font-family: 'DejaVu Sans Mono', monospace; }}
+dd.value span.time, dd.value span.opcode {{ color: #f1fa8c; }}
+dd.value span.address {{ font-weight: bold; }}
+dd.value span.amount {{ color: #f2a900; font-weight: bold; }}
+dd.value span.missing {{ font-style: italic; }}
+dd.value span.hash-opcode {{ color: #ff5555; }}
+dd.value span.equal-opcode {{ color: #8be9fd; }}
+
 {}
         </style>
+        {}
     </header>
     <body>
         <code class="hex">
 {}
         </code>
         <h2>{}</h2>
+        {}
+        {}
         <table class="legend">
 <tr><th>Name</th><th>Type</th><th>Length</th><th>Description</th></tr>
 {}
@@ -125,35 +178,144 @@ table.legend dl {{
 </html>
 "#,
         background,
-        make_theme(),
-        make_code(&hexblock),
+        make_theme(&ctx.palette),
+        interactive,
+        make_code(&hexblock, &ctx.palette),
         candidate.decoder.title,
-        make_legend(&candidate)
+        make_warnings(&candidate),
+        make_links(&candidate),
+        legend
     );
 
-    html
+    Ok(html)
 }
 
-fn make_legend(candidate: &Candidate) -> String {
+/// List of all warnings, so they are not overlooked.
+fn make_warnings(candidate: &Candidate) -> String {
+    let warnings = candidate.annotations.warnings();
+    if warnings.is_empty() {
+        String::new()
+    } else {
+        let items = warnings
+            .iter()
+            .map(|(i, w)| format!("<li>⚠ {}: {}</li>", i.label, w.message))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(r#"<ul class="warnings">{items}</ul>"#)
+    }
+}
+
+/// List of all links between nodes, identified by labels on paths to them.
+fn make_links(candidate: &Candidate) -> String {
+    let tree = &candidate.annotations;
+    let links = tree.links();
+    if links.is_empty() {
+        String::new()
+    } else {
+        let items = links
+            .iter()
+            .map(|(node, link)| {
+                format!(
+                    "<li>{} → {}: {}</li>",
+                    tree.labels(node.path()).join(" › "),
+                    link.label,
+                    tree.labels(&link.path).join(" › ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(r#"<ul class="links">{items}</ul>"#)
+    }
+}
+
+fn make_legend(candidate: &Candidate, ctx: &Ctx) -> String {
     candidate
         .annotations
         .leaves()
         .iter()
-        .map(|&l| make_legend_row(l))
+        .map(|&l| make_legend_row(l, "", ctx))
         .collect::<Vec<_>>()
         .join("\n")
 }
 
-fn make_legend_row(leaf: &Leaf) -> String {
-    let x = leaf.index().map(|i| i % *THEME_SIZE).unwrap_or(1000);
+/// Legend that contains rows of groups in addition to leaves. Rows are marked
+/// by data attributes, which are used by script of interactive mode.
+fn make_tree_legend(candidate: &Candidate, ctx: &Ctx) -> String {
+    let mut rows = vec![];
+    let mut next_group = 0;
+    candidate
+        .annotations
+        .iter()
+        .for_each(|node| make_tree_rows(node, &[], &mut next_group, &mut rows, ctx));
+    rows.join("\n")
+}
+
+fn make_tree_rows(
+    node: &Node,
+    groups: &[String],
+    next_group: &mut usize,
+    rows: &mut Vec<String>,
+    ctx: &Ctx,
+) {
+    let depth = groups.len();
+    let in_groups = groups.join(" ");
+    match node {
+        Node::Group {
+            information,
+            children,
+            ..
+        } => {
+            let id = format!("g{next_group}");
+            *next_group += 1;
+            let (from, to) = node.index_range();
+            let warnings = information
+                .warnings
+                .iter()
+                .map(|w| format!(r#" <span class="warning">⚠ {}</span>"#, w.message))
+                .collect::<String>();
+            rows.push(format!(
+                r#"<tr class="group" data-group="{id}" data-groups="{in_groups}" data-from="{from}" data-to="{to}" style="--depth: {depth}"><td class="name" colspan="4">{}{warnings}</td></tr>"#,
+                information.label
+            ));
+            let groups = [groups, &[id]].concat();
+            children
+                .iter()
+                .for_each(|child| make_tree_rows(child, &groups, next_group, rows, ctx));
+        }
+        Node::Leaf(leaf) => {
+            let data_leaf = leaf
+                .index()
+                .map(|i| format!(r#" data-leaf="{i}""#))
+                .unwrap_or_default();
+            rows.push(make_legend_row(
+                leaf,
+                &format!(r#" data-groups="{in_groups}"{data_leaf} style="--depth: {depth}""#),
+                ctx,
+            ));
+        }
+    }
+}
+
+/// Row of legend describing a leaf. Attributes `attrs` are added to the row.
+fn make_legend_row(leaf: &Leaf, attrs: &str, ctx: &Ctx) -> String {
+    let x = leaf.index().map(|i| i % ctx.palette.len()).unwrap_or(1000);
     let desc = format!(
         r#"
 <dl>
   <dt>Value</dt><dd class="value">{}</dd>
   {}
   {}
+  {}
 </dl>"#,
-        leaf.information().value.preview(),
+        make_value(leaf.information(), &ctx.format),
+        leaf.information()
+            .warnings
+            .iter()
+            .map(|w| format!(
+                r#"<dt>Warning</dt><dd class="warning">⚠ {}</dd>"#,
+                w.message
+            ))
+            .collect::<String>(),
         leaf.information()
             .doc
             .clone()
@@ -162,12 +324,7 @@ fn make_legend_row(leaf: &Leaf) -> String {
                     .information()
                     .refs
                     .iter()
-                    .map(|r| match r {
-                        Reference::Bip(n) => {
-                            format!(r#"<a href="https://bips.xyz/{n}">BIP{n}</a>"#)
-                        }
-                        Reference::Www(www) => format!(r#"<a href="{www}">WWW</a>"#),
-                    })
+                    .map(|r| format!(r#"<a href="{}">{}</a>"#, r.url(), r.label()))
                     .collect::<Vec<_>>();
                 format!(r#"<dt>Doc</dt><dd class="doc">{s} {}</dd>"#, refs.join(" "))
             })
@@ -179,48 +336,96 @@ fn make_legend_row(leaf: &Leaf) -> String {
             .unwrap_or_default()
     );
     format!(
-        r#"<tr><td class="name"><code class="fg{x} bg{x}">{}</code></td><td class="type">{}</td><td class="length">{}</td><td class="description">{}</td></tr>"#,
+        r#"<tr{attrs}><td class="name"><code class="fg{x} bg{x}">{}</code></td><td class="type">{}</td><td class="length">{}</td><td class="description">{}</td></tr>"#,
         leaf.information().label,
-        leaf.information()
-            .data
-            .get("datatype")
-            .map(|s| s.as_str())
-            .unwrap_or_default(),
+        match leaf.information().datatype() {
+            Some(datatype) => datatype.to_string(),
+            None => leaf
+                .information()
+                .data
+                .get("datatype")
+                .cloned()
+                .unwrap_or_default(),
+        },
         leaf.length().map(|l| l.to_string()).unwrap_or_default(),
         desc
     )
 }
 
-fn make_code(hexblock: &HexBlock) -> String {
+/// Value as spans of classes according to their style.
+fn make_value(information: &Information, fmt: &Fmt) -> String {
+    fmt.spans_in(&information.value, information.unit)
+        .iter()
+        .map(|span| {
+            let text = escape(&span.text);
+            let class = match span.style {
+                Style::Plain => return text,
+                Style::Number => "number",
+                Style::Hash => "hash",
+                Style::Hex => "hex",
+                Style::Time => "time",
+                Style::Address => "address",
+                Style::Amount => "amount",
+                Style::Missing => "missing",
+                Style::Opcode => "opcode",
+                Style::HashOpcode => "hash-opcode",
+                Style::EqualOpcode => "equal-opcode",
+                Style::Colored {
+                    foreground,
+                    background,
+                } => {
+                    let rgb = |[r, g, b]: [u8; 3]| format!("#{r:02x}{g:02x}{b:02x}");
+                    let style = foreground
+                        .map(|c| format!("color: {};", rgb(c)))
+                        .into_iter()
+                        .chain(background.map(|c| format!("background-color: {};", rgb(c))))
+                        .collect::<String>();
+                    return format!(r#"<span style="{style}">{text}</span>"#);
+                }
+            };
+            format!(r#"<span class="{class}">{text}</span>"#)
+        })
+        .collect()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn make_code(hexblock: &HexBlock, palette: &Palette) -> String {
     hexblock
         .rows()
         .iter()
-        .map(make_row)
+        .map(|r| make_row(r, palette))
         .collect::<Vec<_>>()
         .join("<br />\n")
 }
 
-fn make_row(row: &Row) -> String {
+fn make_row(row: &Row, palette: &Palette) -> String {
     row.chunks()
         .iter()
-        .map(make_chunk)
+        .map(|c| make_chunk(c, palette))
         .collect::<Vec<_>>()
         .join("")
 }
 
-fn make_chunk(chunk: &Chunk) -> String {
-    let x = chunk.index() % *THEME_SIZE;
-    format!(r#"<span class="fg{x} bg{x}">{}</span>"#, chunk.content())
+fn make_chunk(chunk: &Chunk, palette: &Palette) -> String {
+    let x = chunk.index() % palette.len();
+    format!(
+        r#"<span class="fg{x} bg{x}" data-leaf="{}">{}</span>"#,
+        chunk.index(),
+        chunk.content()
+    )
 }
 
-fn make_theme() -> String {
+fn make_theme(palette: &Palette) -> String {
     use colors_transform::*;
 
-    THEME
-        .iter()
-        .enumerate()
-        .map(|(idx, color)| {
-            let bg = Rgb::from_hex_str(color).unwrap();
+    (0..palette.len())
+        .map(|idx| {
+            let bg = Rgb::from_hex_str(&palette.hex(idx)).unwrap();
             let fg = bg.lighten(-40.0).to_css_hex_string();
             let bg = bg.lighten(10.0).to_css_hex_string();
 
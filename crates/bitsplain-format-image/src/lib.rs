@@ -5,26 +5,20 @@ use bitsplain::decode::Candidate;
 use bitsplain_format::*;
 use wkhtmlapp::{ImgApp, ImgFormat, WkhtmlInput};
 
+output_format!(
+    name = "png",
+    extension = "png",
+    description = "PNG image of the HTML page",
+    params = {
+        width = "Width of image in pixels (default 1024)",
+        zoom = "Zoom factor of the page (default 1.0)",
+    },
+    render
+);
+
 pub fn render<W: Write>(candidate: Candidate, ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
-    let width: u32 = if let Some(w) = ctx.params.get("width") {
-        w.parse().map_err(|_| {
-            FormatError::Param(format!(
-                "Could not parse 'width' parameter with value '{w}' as an integer"
-            ))
-        })?
-    } else {
-        1024
-    };
-
-    let zoom: f32 = if let Some(z) = ctx.params.get("zoom") {
-        z.parse().map_err(|_| {
-            FormatError::Param(format!(
-                "Could not parse 'zoom' parameter with value '{z}' as a float"
-            ))
-        })?
-    } else {
-        1.0
-    };
+    let width = ctx.param_or("width", 1024u32)?;
+    let zoom = ctx.param_or("zoom", 1.0f32)?;
 
     let mut html = Vec::new();
     bitsplain_format_html::render(candidate, ctx, &mut html)?;
@@ -0,0 +1,54 @@
+//! JSON output of decoded data. Every candidate is represented by its
+//! [summary](bitsplain::decode::CandidateSummary), i. e. decoder, data and
+//! the annotation tree.
+//!
+//! Format `json` emits one pretty-printed document, while `ndjson` emits
+//! every candidate as a single line (JSON Lines), which is suitable for
+//! processing many candidates or inputs in shell pipelines.
+
+use std::io::Write;
+
+use bitsplain::decode::Candidate;
+use bitsplain_format::*;
+
+output_format!(
+    name = "json",
+    extension = "json",
+    description = "JSON document with annotation tree",
+    render
+);
+
+output_format!(
+    name = "ndjson",
+    extension = "ndjson",
+    description = "One line of JSON per candidate (JSON Lines)",
+    render_line
+);
+
+pub fn render<W: Write>(candidate: Candidate, _ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
+    serde_json::to_writer_pretty(&mut *out, &candidate.summary()).map_err(to_error)?;
+    Ok(writeln!(out)?)
+}
+
+/// Renders candidate as a single line terminated by a newline, so
+/// outputs of multiple candidates can be concatenated.
+pub fn render_line<W: Write>(
+    candidate: Candidate,
+    _ctx: &Ctx,
+    out: &mut W,
+) -> Result<(), FormatError> {
+    serde_json::to_writer(&mut *out, &candidate.summary()).map_err(to_error)?;
+    Ok(writeln!(out)?)
+}
+
+pub fn generate(candidate: Candidate, _ctx: &Ctx) -> Result<String, FormatError> {
+    serde_json::to_string_pretty(&candidate.summary()).map_err(to_error)
+}
+
+fn to_error(e: serde_json::Error) -> FormatError {
+    if e.is_io() {
+        FormatError::Io(e.into())
+    } else {
+        FormatError::Other(e.to_string())
+    }
+}
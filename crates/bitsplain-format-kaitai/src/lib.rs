@@ -0,0 +1,221 @@
+//! Dump of the annotation tree in YAML compatible with output of Kaitai
+//! Struct's `ksdump`, so the results can be compared with Kaitai definitions
+//! or consumed by Kaitai-based tooling.
+//!
+//! Groups are turned into objects and leaves into their fields, named by
+//! labels converted to Kaitai identifiers. Enumerated children of a group
+//! form an array `items`. Positions of fields are listed in `_debug`
+//! of every object, same as Kaitai does in debug mode.
+
+use std::collections::HashSet;
+use std::io::Write;
+
+use bitsplain::decode::Candidate;
+use bitsplain::tree::{Leaf, Node};
+use bitsplain::value::Value;
+use bitsplain_format::names::{escape, identifier, unique};
+use bitsplain_format::*;
+
+output_format!(
+    name = "kaitai",
+    extension = "yaml",
+    description = "YAML compatible with Kaitai Struct's ksdump",
+    render
+);
+
+pub fn render<W: Write>(candidate: Candidate, ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
+    let yaml = generate(candidate, ctx)?;
+    Ok(out.write_all(yaml.as_bytes())?)
+}
+
+pub fn generate(candidate: Candidate, _ctx: &Ctx) -> Result<String, FormatError> {
+    let mut yaml = format!("# {}, generated by bitsplain\n", candidate.decoder.title);
+    object(&candidate.annotations)
+        .iter()
+        .for_each(|line| yaml.push_str(&format!("{line}\n")));
+    Ok(yaml)
+}
+
+/// Member of an object.
+enum Entry<'a> {
+    Field(String, &'a Node),
+    Array(String, Vec<&'a Node>),
+}
+
+fn entries(nodes: &[Node]) -> Vec<Entry<'_>> {
+    let mut taken = HashSet::new();
+    let mut entries: Vec<Entry> = vec![];
+
+    nodes.iter().for_each(|node| {
+        if node.information().has_data("list", "enumerate") {
+            if let Some(Entry::Array(_, items)) = entries.last_mut() {
+                items.push(node);
+                return;
+            }
+            let id = unique("items", &taken);
+            taken.insert(id.clone());
+            entries.push(Entry::Array(id, vec![node]));
+        } else {
+            let id = unique(&identifier(&node.information().label), &taken);
+            taken.insert(id.clone());
+            entries.push(Entry::Field(id, node));
+        }
+    });
+
+    entries
+}
+
+/// Lines of object consisting of the nodes, without indentation.
+fn object(nodes: &[Node]) -> Vec<String> {
+    let entries = entries(nodes);
+    let mut lines = vec![];
+
+    entries.iter().for_each(|entry| match entry {
+        Entry::Field(id, node) => lines.extend(member(id, node)),
+        Entry::Array(id, items) => {
+            lines.push(format!("{id}:"));
+            items
+                .iter()
+                .for_each(|item| lines.extend(sequence_item(item)));
+        }
+    });
+
+    let debug = entries
+        .iter()
+        .flat_map(|entry| match entry {
+            Entry::Field(id, node) => position(node)
+                .map(|(start, end)| {
+                    vec![
+                        format!("{id}:"),
+                        format!("  start: {start}"),
+                        format!("  end: {end}"),
+                    ]
+                })
+                .unwrap_or_default(),
+            Entry::Array(id, items) => {
+                let positions = items.iter().filter_map(|i| position(i)).collect::<Vec<_>>();
+                let start = positions.iter().map(|p| p.0).min();
+                let end = positions.iter().map(|p| p.1).max();
+                match (start, end) {
+                    (Some(start), Some(end)) => {
+                        let mut lines = vec![
+                            format!("{id}:"),
+                            format!("  start: {start}"),
+                            format!("  end: {end}"),
+                            "  arr:".to_string(),
+                        ];
+                        positions.iter().for_each(|(start, end)| {
+                            lines.push(format!("    - start: {start}"));
+                            lines.push(format!("      end: {end}"));
+                        });
+                        lines
+                    }
+                    _ => vec![],
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if !debug.is_empty() {
+        lines.push("_debug:".to_string());
+        lines.extend(indent(debug, "  "));
+    }
+
+    lines
+}
+
+/// Warnings of node as comments.
+fn warnings(node: &Node) -> Vec<String> {
+    node.information()
+        .warnings
+        .iter()
+        .map(|w| format!("# ⚠ {}", w.message))
+        .collect()
+}
+
+/// Lines of a named member of object, preceded by its warnings.
+fn member(id: &str, node: &Node) -> Vec<String> {
+    let mut lines = warnings(node);
+    lines.extend(match node {
+        Node::Group { children, .. } => {
+            let object = object(children);
+            if object.is_empty() {
+                vec![format!("{id}: {{}}")]
+            } else {
+                let mut lines = vec![format!("{id}:")];
+                lines.extend(indent(object, "  "));
+                lines
+            }
+        }
+        Node::Leaf(leaf) => vec![format!("{id}: {}", scalar(&leaf.information().value))],
+    });
+    lines
+}
+
+/// Lines of an item of array, preceded by its warnings.
+fn sequence_item(node: &Node) -> Vec<String> {
+    let mut lines = indent(warnings(node), "  ");
+    lines.extend(match node {
+        Node::Group { children, .. } => {
+            let object = object(children);
+            if object.is_empty() {
+                vec!["  - {}".to_string()]
+            } else {
+                object
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, l)| {
+                        if i == 0 {
+                            format!("  - {l}")
+                        } else {
+                            format!("    {l}")
+                        }
+                    })
+                    .collect()
+            }
+        }
+        Node::Leaf(leaf) => vec![format!("  - {}", scalar(&leaf.information().value))],
+    });
+    lines
+}
+
+/// Position of node in the input, if it is represented there.
+fn position(node: &Node) -> Option<(usize, usize)> {
+    match node {
+        Node::Group { location, .. } => Some((location.byte_from, location.byte_to)),
+        Node::Leaf(Leaf::Real(leaf)) => Some((leaf.location.from, leaf.location.to)),
+        Node::Leaf(Leaf::Virtual(_)) => None,
+    }
+}
+
+fn indent(lines: Vec<String>, prefix: &str) -> Vec<String> {
+    lines.into_iter().map(|l| format!("{prefix}{l}")).collect()
+}
+
+/// YAML scalar of value. Numbers are preferred, so alternatives
+/// with a number are represented by it and amounts, fee rates and
+/// durations by their numbers without units.
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Num(n) => n.to_string(),
+        Value::MSat(_) | Value::FeeRate(_) | Value::Duration(_) | Value::Bool(_) => value.plain(),
+        Value::Nil => "null".to_string(),
+        Value::Alt(v1, v2) => match (v1.as_ref(), v2.as_ref()) {
+            (_, Value::Num(_)) => scalar(v2),
+            _ => scalar(v1),
+        },
+        v => quote(&v.preview()),
+    }
+}
+
+/// Double-quoted YAML string.
+fn quote(s: &str) -> String {
+    let escaped = escape(s)
+        .chars()
+        .map(|c| match c {
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect::<String>();
+    format!("\"{escaped}\"")
+}
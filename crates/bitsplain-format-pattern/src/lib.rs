@@ -0,0 +1,377 @@
+//! Export of the annotation tree into languages of hex editors, so the
+//! explanation can be loaded next to the data. Groups are turned into structs
+//! and leaves into byte arrays of corresponding sizes, named by their labels
+//! and commented by their documentation and warnings.
+//!
+//! The generated pattern describes the particular input, i. e. lists and
+//! variable-length fields have the sizes they were parsed with.
+//!
+//! Supported parameters:
+//!
+//! - `dialect` – `imhex` for ImHex pattern or `010` for 010 Editor
+//!   template, default `imhex`.
+
+use std::collections::HashSet;
+use std::io::Write;
+
+use bitsplain::datatype::{Base, Datatype, Endianness};
+use bitsplain::decode::Candidate;
+use bitsplain::tree::{Information, Leaf, Node};
+use bitsplain_format::names::{self, escape, unique};
+use bitsplain_format::*;
+
+/// Target language of the pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// Pattern language of ImHex.
+    ImHex,
+    /// Binary template of 010 Editor.
+    Template010,
+}
+
+impl Dialect {
+    pub fn from_ctx(ctx: &Ctx) -> Result<Dialect, FormatError> {
+        match ctx.param::<String>("dialect")?.as_deref() {
+            None | Some("imhex") => Ok(Dialect::ImHex),
+            Some("010") => Ok(Dialect::Template010),
+            Some(d) => Err(FormatError::invalid_param("dialect", d)),
+        }
+    }
+}
+
+output_format!(
+    name = "pattern",
+    extension = "hexpat",
+    description = "ImHex pattern or 010 Editor template",
+    params = {
+        dialect = "imhex (default) for ImHex or 010 for 010 Editor",
+    },
+    render
+);
+
+pub fn render<W: Write>(candidate: Candidate, ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
+    let pattern = generate(candidate, ctx)?;
+    Ok(out.write_all(pattern.as_bytes())?)
+}
+
+pub fn generate(candidate: Candidate, ctx: &Ctx) -> Result<String, FormatError> {
+    let dialect = Dialect::from_ctx(ctx)?;
+
+    let mut builder = Builder::default();
+    let mut cursor = 0;
+    let root = builder.structure(candidate.decoder.title, &candidate.annotations, &mut cursor);
+    let root_name = builder.structs[root].name.clone();
+
+    let mut out = format!("// {}, generated by bitsplain\n\n", candidate.decoder.title);
+
+    builder
+        .structs
+        .iter()
+        .for_each(|s| out.push_str(&s.render(dialect)));
+
+    let instance = identifier(candidate.decoder.symbol);
+    match dialect {
+        Dialect::ImHex => out.push_str(&format!("{root_name} {instance} @ 0x00;\n")),
+        Dialect::Template010 => out.push_str(&format!("{root_name} {instance};\n")),
+    }
+
+    Ok(out)
+}
+
+enum Kind {
+    /// Array of bytes of given length.
+    Bytes(usize),
+    /// Number of given size in bytes.
+    Number {
+        signed: bool,
+        size: usize,
+        big_endian: bool,
+    },
+    /// Text of given length in bytes.
+    Text(usize),
+    /// Bytes not covered by any leaf.
+    Padding(usize),
+    /// Nested struct of given name.
+    Struct(String),
+}
+
+struct Field {
+    name: String,
+    label: String,
+    doc: Option<String>,
+    kind: Kind,
+}
+
+struct Struct {
+    name: String,
+    label: String,
+    fields: Vec<Field>,
+}
+
+impl Struct {
+    fn render(&self, dialect: Dialect) -> String {
+        let fields = self
+            .fields
+            .iter()
+            .map(|f| format!("    {}\n", f.render(dialect)))
+            .collect::<String>();
+
+        match dialect {
+            Dialect::ImHex => format!(
+                "// {}\nstruct {} {{\n{fields}}};\n\n",
+                self.label, self.name
+            ),
+            Dialect::Template010 => {
+                format!(
+                    "// {}\ntypedef struct {{\n{fields}}} {};\n\n",
+                    self.label, self.name
+                )
+            }
+        }
+    }
+}
+
+impl Field {
+    fn render(&self, dialect: Dialect) -> String {
+        let (typ, name) = match (&self.kind, dialect) {
+            (Kind::Bytes(1), Dialect::ImHex) => ("u8".to_string(), self.name.clone()),
+            (Kind::Bytes(1), Dialect::Template010) => ("uchar".to_string(), self.name.clone()),
+            (Kind::Bytes(n), Dialect::ImHex) => ("u8".to_string(), format!("{}[{n}]", self.name)),
+            (Kind::Bytes(n), Dialect::Template010) => {
+                ("uchar".to_string(), format!("{}[{n}]", self.name))
+            }
+            (
+                Kind::Number {
+                    signed,
+                    size,
+                    big_endian,
+                },
+                Dialect::ImHex,
+            ) => {
+                let sign = if *signed { "s" } else { "u" };
+                let order = if *big_endian { "be " } else { "" };
+                (format!("{order}{sign}{}", size * 8), self.name.clone())
+            }
+            (Kind::Number { signed, size, .. }, Dialect::Template010) => {
+                let typ = match (signed, size) {
+                    (false, 1) => "uchar",
+                    (false, 2) => "ushort",
+                    (false, 4) => "uint",
+                    (false, _) => "uint64",
+                    (true, 1) => "char",
+                    (true, 2) => "short",
+                    (true, 4) => "int",
+                    (true, _) => "int64",
+                };
+                (typ.to_string(), self.name.clone())
+            }
+            (Kind::Text(n), _) => ("char".to_string(), format!("{}[{n}]", self.name)),
+            (Kind::Padding(n), Dialect::ImHex) => return format!("padding[{n}];"),
+            (Kind::Padding(n), Dialect::Template010) => {
+                return format!("uchar {}[{n}] <hidden=true>;", self.name)
+            }
+            (Kind::Struct(s), _) => (s.clone(), self.name.clone()),
+        };
+
+        let label = escape(&self.label);
+        let doc = self.doc.as_deref().map(escape);
+        match dialect {
+            Dialect::ImHex => match doc {
+                Some(doc) => format!("{typ} {name} [[name(\"{label}\"), comment(\"{doc}\")]];"),
+                None => format!("{typ} {name} [[name(\"{label}\")]];"),
+            },
+            Dialect::Template010 => {
+                let field = match doc {
+                    Some(doc) => format!("{typ} {name} <name=\"{label}\", comment=\"{doc}\">;"),
+                    None => format!("{typ} {name} <name=\"{label}\">;"),
+                };
+                // 010 Editor reads numbers in little endian unless told otherwise.
+                match self.kind {
+                    Kind::Number {
+                        big_endian: true, ..
+                    } => format!("BigEndian(); {field} LittleEndian();"),
+                    _ => field,
+                }
+            }
+        }
+    }
+}
+
+/// Kind of field of `len` bytes according to its datatype. Numbers of
+/// variable length are represented as bytes.
+fn kind(datatype: Option<&Datatype>, len: usize) -> Kind {
+    match datatype.map(|d| (d.base, d.endianness)) {
+        Some((Base::Uint(size), endianness)) if size == len => Kind::Number {
+            signed: false,
+            size,
+            big_endian: endianness == Some(Endianness::Big),
+        },
+        Some((Base::Int(size), endianness)) if size == len => Kind::Number {
+            signed: true,
+            size,
+            big_endian: endianness == Some(Endianness::Big),
+        },
+        Some((Base::Text, _)) => Kind::Text(len),
+        _ => Kind::Bytes(len),
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    /// Structs in order of their definition, i. e. nested structs
+    /// precede those that contain them.
+    structs: Vec<Struct>,
+    names: HashSet<String>,
+}
+
+impl Builder {
+    /// Unique name of struct derived from label.
+    fn struct_name(&mut self, label: &str) -> String {
+        let base = identifier(label)
+            .split('_')
+            .filter(|w| !w.is_empty())
+            .map(|w| {
+                let mut chars = w.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect::<String>();
+        let base = match base.chars().next() {
+            Some(c) if c.is_ascii_alphabetic() => base,
+            _ => format!("S{base}"),
+        };
+        let name = unique(&base, &self.names);
+        self.names.insert(name.clone());
+        name
+    }
+
+    /// Builds struct from nodes and returns its index. Cursor is offset of
+    /// the next byte, which is used to detect bytes not covered by leaves.
+    fn structure(&mut self, label: &str, nodes: &[Node], cursor: &mut usize) -> usize {
+        let mut fields: Vec<Field> = vec![];
+        let mut field_names = HashSet::new();
+
+        nodes.iter().for_each(|node| {
+            let information = node.information();
+            let kind = match node {
+                Node::Group { children, .. } => {
+                    let index = self.structure(&information.label, children, cursor);
+                    Kind::Struct(self.structs[index].name.clone())
+                }
+                Node::Leaf(Leaf::Real(leaf))
+                    if leaf.location.to > leaf.location.from && leaf.location.from >= *cursor =>
+                {
+                    if leaf.location.from > *cursor {
+                        let name = unique("padding", &field_names);
+                        field_names.insert(name.clone());
+                        fields.push(Field {
+                            name,
+                            label: String::new(),
+                            doc: None,
+                            kind: Kind::Padding(leaf.location.from - *cursor),
+                        });
+                    }
+                    *cursor = leaf.location.to;
+                    kind(
+                        leaf.information.datatype(),
+                        leaf.location.to - leaf.location.from,
+                    )
+                }
+                // Virtual and empty leaves do not occupy any bytes, leaves of bits
+                // sharing bytes with previous leaf are covered by it.
+                Node::Leaf(_) => return,
+            };
+
+            let name = unique(&identifier(&information.label), &field_names);
+            field_names.insert(name.clone());
+            fields.push(Field {
+                name,
+                label: information.label.clone(),
+                doc: doc(information),
+                kind,
+            });
+        });
+
+        let name = self.struct_name(label);
+        self.structs.push(Struct {
+            name,
+            label: label.to_string(),
+            fields,
+        });
+        self.structs.len() - 1
+    }
+}
+
+/// Comment of field consisting of warnings and documentation.
+fn doc(information: &Information) -> Option<String> {
+    let warnings = information
+        .warnings
+        .iter()
+        .map(|w| format!("⚠ {}", w.message));
+    let lines = warnings.chain(information.doc.clone()).collect::<Vec<_>>();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+/// Words that cannot be used as identifiers in at least one of the dialects.
+const RESERVED: &[&str] = &[
+    "be",
+    "bitfield",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "false",
+    "float",
+    "fn",
+    "for",
+    "if",
+    "import",
+    "in",
+    "int",
+    "le",
+    "local",
+    "match",
+    "namespace",
+    "null",
+    "out",
+    "padding",
+    "parent",
+    "ref",
+    "return",
+    "signed",
+    "sizeof",
+    "string",
+    "struct",
+    "switch",
+    "this",
+    "true",
+    "typedef",
+    "uchar",
+    "union",
+    "unsigned",
+    "using",
+    "void",
+    "while",
+];
+
+/// Converts label to identifier valid in both dialects.
+fn identifier(label: &str) -> String {
+    let id = names::identifier(label);
+    if RESERVED.contains(&id.as_str()) {
+        format!("{id}_")
+    } else {
+        id
+    }
+}
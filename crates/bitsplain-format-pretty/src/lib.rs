@@ -1,18 +1,31 @@
-use bitsplain::bitcoin::blockdata::opcodes::Ordinary::*;
-use bitsplain::bitcoin::blockdata::opcodes::{Class, ClassifyContext};
-use bitsplain::bitcoin::blockdata::script::*;
+//! Tree of annotations for terminal, styled according to settings
+//! of format `pretty` and colored unless colors are disabled.
+
+mod plain;
+
+use std::io::Write;
+
 use bitsplain::decode::Candidate;
+use bitsplain::dsl::Reference;
+use bitsplain::output::fmt::{self, Span};
 use bitsplain::tree::*;
 use bitsplain::value::*;
 use bitsplain::*;
-use bitsplain_format::ctx::*;
-use human_size::{Byte, SpecificSize};
+use bitsplain_format::*;
 use pretty::termcolor::*;
 use pretty::RcDoc;
 use termion::{color, style};
-use time::OffsetDateTime;
 
-pub fn render(candidate: Candidate, ctx: &Ctx) {
+pub use plain::Plain;
+
+output_format!(
+    name = "pretty",
+    extension = "txt",
+    description = "Colored tree for terminal",
+    render
+);
+
+pub fn render<W: Write>(candidate: Candidate, ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
     let header = RcDoc::line()
         .append(RcDoc::text(candidate.decoder.title))
         .append(RcDoc::line())
@@ -24,24 +37,33 @@ pub fn render(candidate: Candidate, ctx: &Ctx) {
         .fold(header, |doc, t| {
             doc.append(RcDoc::line())
                 .append(RcDoc::as_string("- "))
-                .append(pretty_tree(t, candidate.data.as_ref(), ctx))
+                .append(pretty_tree(
+                    t,
+                    &candidate.annotations,
+                    candidate.data.as_ref(),
+                    ctx,
+                ))
         })
         .nest(4);
 
-    doc.render_colored(100, StandardStream::stdout(ColorChoice::Auto))
-        .unwrap();
+    if ctx.settings.format.pretty.use_color {
+        doc.render_colored(100, Ansi::new(out))?;
+    } else {
+        doc.render_colored(100, NoColor::new(Plain::new(out)))?;
+    }
+    Ok(())
 }
 
-pub fn pretty_tree(t: &Node, data: &[u8], ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
+pub fn pretty_tree(t: &Node, tree: &Tree, data: &[u8], ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
     match t {
         Node::Group {
             path,
             location,
             information,
             children,
-        } => pretty_group(path, location, information, children, data, ctx),
-        Node::Leaf(Leaf::Real(leaf)) => pretty_real_leaf(leaf, data, ctx),
-        Node::Leaf(Leaf::Virtual(leaf)) => pretty_virtual_leaf(leaf, ctx),
+        } => pretty_group(path, location, information, children, tree, data, ctx),
+        Node::Leaf(Leaf::Real(leaf)) => pretty_real_leaf(leaf, tree, data, ctx),
+        Node::Leaf(Leaf::Virtual(leaf)) => pretty_virtual_leaf(leaf, tree, ctx),
     }
 }
 
@@ -70,6 +92,7 @@ fn pretty_group(
     location: &GroupLocation,
     information: &Information,
     children: &[Node],
+    tree: &Tree,
     data: &[u8],
     ctx: &Ctx,
 ) -> RcDoc<'static, ColorSpec> {
@@ -82,7 +105,7 @@ fn pretty_group(
     ))
 	.append(pretty_tags(&information.tags, ctx))
         .append(RcDoc::space())
-        .append(pretty_value(&information.value, ctx))
+        .append(pretty_value(&information.value, information.unit, ctx))
         .append(if ctx.detail == Detail::Debug {
             RcDoc::text(format!(
                 "          {}{{from={}, to={}, len={}, index_from={}, index_to={}, path={}, data={:?}}}{}",
@@ -99,12 +122,17 @@ fn pretty_group(
         } else {
             RcDoc::nil()
         })
+        .append(pretty_warnings(&information.warnings))
+        .append(pretty_links(&information.links, tree))
+        .append(pretty_doc(&information.doc, ctx))
+        .append(pretty_refs(&information.refs, ctx))
+        .append(pretty_doc(&information.splain, ctx))
         .append(RcDoc::hardline())
         .append(RcDoc::intersperse(
             children.iter().map(|v| {
                 Some(RcDoc::as_string("-")
                      .append(RcDoc::space())
-                     .append(pretty_tree(v, data, ctx))
+                     .append(pretty_tree(v, tree, data, ctx))
                 )
             }),
             RcDoc::hardline(),
@@ -119,6 +147,7 @@ fn pretty_real_leaf(
         location,
         information,
     }: &RealLeaf,
+    tree: &Tree,
     data: &[u8],
     ctx: &Ctx,
 ) -> RcDoc<'static, ColorSpec> {
@@ -131,15 +160,19 @@ fn pretty_real_leaf(
     ))
     .append(RcDoc::as_string(":"))
     .append(RcDoc::space())
-    .append(pretty_value(&information.value, ctx))
+    .append(pretty_value(&information.value, information.unit, ctx))
     .append(pretty_tags(&information.tags, ctx))
     .append(if ctx.detail == Detail::Debug {
         RcDoc::text(format!(
-            "          {}{{from={}, to={}, len={}, index={}, path={}, data={:?}}}{}",
+            "          {}{{from={}, to={}, len={}, {}index={}, path={}, data={:?}}}{}",
             color::Fg(color::LightBlack),
             location.from,
             location.to,
             location.to - location.from,
+            location
+                .bits
+                .map(|b| format!("bits={}..{}, ", b.offset, b.offset + b.len))
+                .unwrap_or_default(),
             location.index,
             path.join("/"),
             information.data,
@@ -148,7 +181,10 @@ fn pretty_real_leaf(
     } else {
         RcDoc::nil()
     })
+    .append(pretty_warnings(&information.warnings))
+    .append(pretty_links(&information.links, tree))
     .append(pretty_doc(&information.doc, ctx))
+    .append(pretty_refs(&information.refs, ctx))
     .append(pretty_doc(&information.splain, ctx))
     .append(pretty_segment(location, data, ctx))
 }
@@ -156,6 +192,7 @@ fn pretty_real_leaf(
 /// Render virtual leaf.
 fn pretty_virtual_leaf(
     VirtualLeaf { path, information }: &VirtualLeaf,
+    tree: &Tree,
     ctx: &Ctx,
 ) -> RcDoc<'static, ColorSpec> {
     if ctx.settings.format.pretty.r#virtual.show {
@@ -181,7 +218,8 @@ fn pretty_virtual_leaf(
         ))
         .append(RcDoc::as_string(":"))
         .append(RcDoc::space())
-        .append(pretty_value(&information.value, ctx))
+        .append(pretty_value(&information.value, information.unit, ctx))
+        .append(pretty_tags(&information.tags, ctx))
         .append(if ctx.detail == Detail::Debug {
             RcDoc::text(format!(
                 "          {}{{path={}, data={:?}}}{}",
@@ -193,81 +231,60 @@ fn pretty_virtual_leaf(
         } else {
             RcDoc::nil()
         })
+        .append(pretty_warnings(&information.warnings))
+        .append(pretty_links(&information.links, tree))
         .append(pretty_doc(&information.doc, ctx))
+        .append(pretty_refs(&information.refs, ctx))
         .append(pretty_doc(&information.splain, ctx))
     } else {
         RcDoc::nil()
     }
 }
 
-fn pretty_value(value: &Value, ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
+fn pretty_value(value: &Value, unit: Option<Unit>, ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
     match value {
-        Value::Num(n) => {
-            RcDoc::as_string(n).annotate(ColorSpec::new().set_fg(Some(Color::Magenta)).clone())
-        }
-        Value::Hash(h) => RcDoc::text(format!("{}{}{}", color::Fg(color::Green), h, style::Reset)),
-        Value::Bytes(h) if h.is_empty() => {
-            RcDoc::text(format!("{}(empty){}", style::Italic, style::Reset))
-        }
-        Value::Bytes(h) => pretty_hex(h, ctx),
-        Value::Nil => RcDoc::nil(),
-        Value::Script(s) => pretty_script(s),
-        Value::Timestamp(ts) => RcDoc::text(format!(
-            "{}{}{}",
-            color::Fg(color::Yellow),
-            format_time(ts),
-            style::Reset
-        )),
-        Value::Text {
-            text,
-            foreground,
-            background,
-        } => {
-            let mut f = String::new();
-            if let Some([r, g, b]) = foreground {
-                f.push_str(&color::Fg(color::Rgb(*r, *g, *b)).to_string());
-            };
-            if let Some([r, g, b]) = background {
-                f.push_str(&color::Bg(color::Rgb(*r, *g, *b)).to_string());
-            };
-            f.push_str(text);
-            f.push_str(style::Reset.as_ref());
-            RcDoc::as_string(f)
-        }
-        Value::Addr(Some(a)) => RcDoc::text(format!("{}{}{}", style::Bold, a, style::Reset)),
-        Value::Addr(None) => RcDoc::text(format!("{}(No address){}", style::Italic, style::Reset)),
-        Value::Size(s) => RcDoc::as_string(SpecificSize::new(*s as u32, Byte).unwrap())
-            .annotate(ColorSpec::new().set_fg(Some(Color::Magenta)).clone()),
-        Value::Signature(s) => pretty_hex(&s.serialize_compact(), ctx),
-        Value::PublicKey(k) => pretty_hex(&k.to_bytes(), ctx),
-        Value::Sat(s) => RcDoc::text(format!(
-            "{}{}{}{}",
-            color::Fg(color::Rgb(242, 169, 0)),
-            style::Bold,
-            s.as_str(),
-            style::Reset
-        )),
-        Value::Alt(v1, v2) => pretty_value(v1, ctx)
+        // Every instruction of script on its own line.
+        Value::Script(script) => match ctx.format.instructions(script) {
+            Some(instructions) => RcDoc::hardline()
+                .append(RcDoc::intersperse(
+                    instructions.iter().map(|s| pretty_span(s, ctx)),
+                    RcDoc::hardline(),
+                ))
+                .nest(4),
+            None => pretty_spans(&ctx.format.spans(value), ctx),
+        },
+        Value::Alt(v1, v2) => pretty_value(v1, unit, ctx)
             .append(RcDoc::text(" ("))
-            .append(pretty_value(v2, ctx))
+            .append(pretty_value(v2, unit, ctx))
             .append(RcDoc::text(")")),
+        _ => pretty_spans(&ctx.format.spans_in(value, unit), ctx),
     }
 }
 
-fn pretty_hex(h: &[u8], ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
-    if h.len() > 32 {
+/// Render spans of value, byte arrays split into groups of 8 bytes
+/// and lines of 32 bytes.
+fn pretty_spans(spans: &[Span], ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
+    RcDoc::concat(spans.iter().map(|span| match span.style {
+        fmt::Style::Hex => pretty_hex(span, ctx),
+        _ => pretty_span(span, ctx),
+    }))
+}
+
+fn pretty_hex(span: &Span, ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
+    let chars = span.text.chars().collect::<Vec<_>>();
+    if chars.len() > 64 {
         RcDoc::hardline()
     } else {
         RcDoc::nil()
     }
     .append(RcDoc::intersperse(
-        h.chunks(32).map(|bs| {
+        chars.chunks(64).map(|line| {
             RcDoc::intersperse(
-                bs.chunks(8).map(|b| {
+                line.chunks(16).map(|group| {
                     RcDoc::text(format!(
                         "{}{}{}",
                         ctx.settings.format.pretty.hex.font,
-                        hex::encode(b),
+                        group.iter().collect::<String>(),
                         style::Reset
                     ))
                 }),
@@ -279,6 +296,36 @@ fn pretty_hex(h: &[u8], ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
     .nest(4)
 }
 
+fn pretty_span(span: &Span, ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
+    let start = match span.style {
+        fmt::Style::Plain => return RcDoc::text(span.text.clone()),
+        fmt::Style::Number => color::Fg(color::Magenta).to_string(),
+        fmt::Style::Hash => color::Fg(color::Green).to_string(),
+        fmt::Style::Hex => ctx.settings.format.pretty.hex.font.to_string(),
+        fmt::Style::Time => color::Fg(color::Yellow).to_string(),
+        fmt::Style::Address => style::Bold.to_string(),
+        fmt::Style::Amount => format!("{}{}", color::Fg(color::Rgb(242, 169, 0)), style::Bold),
+        fmt::Style::Missing => style::Italic.to_string(),
+        fmt::Style::Opcode => color::Fg(color::Yellow).to_string(),
+        fmt::Style::HashOpcode => color::Fg(color::Red).to_string(),
+        fmt::Style::EqualOpcode => color::Fg(color::Blue).to_string(),
+        fmt::Style::Colored {
+            foreground,
+            background,
+        } => {
+            let mut start = String::new();
+            if let Some([r, g, b]) = foreground {
+                start.push_str(&color::Fg(color::Rgb(r, g, b)).to_string());
+            };
+            if let Some([r, g, b]) = background {
+                start.push_str(&color::Bg(color::Rgb(r, g, b)).to_string());
+            };
+            start
+        }
+    };
+    RcDoc::text(format!("{start}{}{}", span.text, style::Reset))
+}
+
 /// Render segment of bytes.
 fn pretty_segment(location: &LeafLocation, data: &[u8], ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
     let range = location.range();
@@ -296,6 +343,56 @@ fn pretty_segment(location: &LeafLocation, data: &[u8], ctx: &Ctx) -> RcDoc<'sta
     }
 }
 
+/// Render warnings. Unlike documentation, they are always shown.
+fn pretty_warnings(warnings: &[Warning]) -> RcDoc<'static, ColorSpec> {
+    RcDoc::concat(warnings.iter().map(|w| {
+        RcDoc::hardline()
+            .append(RcDoc::text(format!(
+                "{}{}⚠ {}{}",
+                style::Bold,
+                color::Fg(color::Yellow),
+                w.message,
+                style::Reset
+            )))
+            .nest(2)
+    }))
+}
+
+/// Render links to other nodes as labels of the nodes on the way to them.
+fn pretty_links(links: &[Link], tree: &Tree) -> RcDoc<'static, ColorSpec> {
+    RcDoc::concat(links.iter().map(|l| {
+        RcDoc::hardline()
+            .append(RcDoc::text(format!(
+                "{}→ {}: {}{}",
+                color::Fg(color::Cyan),
+                l.label,
+                tree.labels(&l.path).join(" › "),
+                style::Reset
+            )))
+            .nest(2)
+    }))
+}
+
+/// Render references to specifications and other documents, only
+/// in extra detail as they are rather long.
+fn pretty_refs(refs: &[Reference], ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
+    if !matches!(ctx.detail, Detail::Extra | Detail::Debug) || !ctx.settings.format.pretty.doc.show
+    {
+        return RcDoc::nil();
+    }
+    RcDoc::concat(refs.iter().map(|r| {
+        RcDoc::hardline()
+            .append(RcDoc::text(format!(
+                "{}↗ {}: {}{}",
+                ctx.settings.format.pretty.doc.font,
+                r.label(),
+                r.url(),
+                style::Reset
+            )))
+            .nest(2)
+    }))
+}
+
 fn pretty_doc(doc: &Option<String>, ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
     match doc {
         Some(doc) if ctx.settings.format.pretty.doc.show => RcDoc::hardline()
@@ -309,68 +406,3 @@ fn pretty_doc(doc: &Option<String>, ctx: &Ctx) -> RcDoc<'static, ColorSpec> {
         _ => RcDoc::nil(),
     }
 }
-
-fn pretty_script(s: &Script) -> RcDoc<'static, ColorSpec> {
-    let x: Result<Vec<RcDoc<'static, ColorSpec>>, _> = s
-        .instructions()
-        .scan(false, |is_op_return, r| {
-            let x = r.map(|i| match i {
-                Instruction::PushBytes(b) if b.is_empty() => RcDoc::as_string("OP_0"),
-                Instruction::PushBytes(b) if *is_op_return => pretty_utf8(b.as_bytes()),
-                Instruction::PushBytes(b) => RcDoc::text(hex::encode(b)),
-                Instruction::Op(op) => {
-                    let c: Box<dyn color::Color> = match op.classify(ClassifyContext::Legacy) {
-                        Class::Ordinary(
-                            OP_RIPEMD160
-                            | OP_SHA1
-                            | OP_SHA256
-                            | OP_HASH160
-                            | OP_HASH256
-                            | OP_CODESEPARATOR
-                            | OP_CHECKSIG
-                            | OP_CHECKSIGVERIFY
-                            | OP_CHECKMULTISIG
-                            | OP_CHECKMULTISIGVERIFY,
-                        ) => Box::new(color::Red),
-                        Class::Ordinary(OP_EQUAL | OP_EQUALVERIFY) => Box::new(color::Blue),
-                        Class::ReturnOp => {
-                            *is_op_return = true;
-                            Box::new(color::Yellow)
-                        }
-                        _ => Box::new(color::Yellow),
-                    };
-                    RcDoc::text(format!("{}{}{}", color::Fg(c.as_ref()), op, style::Reset))
-                }
-            });
-            Some(x)
-        })
-        .collect();
-    match x {
-        Ok(s) => RcDoc::hardline()
-            .append(RcDoc::intersperse(s, RcDoc::hardline()))
-            .nest(4),
-        Err(_) => RcDoc::as_string("Bad"),
-    }
-}
-
-fn pretty_utf8(bs: &[u8]) -> RcDoc<'static, ColorSpec> {
-    RcDoc::text(
-        String::from_utf8_lossy(bs)
-            .chars()
-            .map(|c| {
-                if c.is_alphabetic() || c.is_ascii_graphic() || c.is_ascii_punctuation() {
-                    c
-                } else {
-                    '.'
-                }
-            })
-            .collect::<String>(),
-    )
-}
-
-fn format_time(time: &OffsetDateTime) -> String {
-    time.format(
-        &time::format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]").unwrap(),
-    )
-    .unwrap()
-}
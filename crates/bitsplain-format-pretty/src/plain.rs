@@ -0,0 +1,54 @@
+use std::io::Write;
+
+/// Writer that drops terminal escape sequences, so output of renderers,
+/// which always style their text, can be written in plain text.
+pub struct Plain<W> {
+    inner: W,
+    state: Escape,
+}
+
+/// Position within escape sequence.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    Start,
+    Sequence,
+}
+
+impl<W: Write> Plain<W> {
+    pub fn new(inner: W) -> Plain<W> {
+        Plain {
+            inner,
+            state: Escape::None,
+        }
+    }
+}
+
+impl<W: Write> Write for Plain<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // Sequences may be split between writes, hence the state.
+        let plain = buf
+            .iter()
+            .filter(|&&b| {
+                let (state, keep) = match (self.state, b) {
+                    (Escape::None, 0x1b) => (Escape::Start, false),
+                    (Escape::None, _) => (Escape::None, true),
+                    (Escape::Start, b'[') => (Escape::Sequence, false),
+                    (Escape::Start, _) => (Escape::None, false),
+                    // Control sequence ends by byte in range '@'..='~'.
+                    (Escape::Sequence, 0x40..=0x7e) => (Escape::None, false),
+                    (Escape::Sequence, _) => (Escape::Sequence, false),
+                };
+                self.state = state;
+                keep
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        self.inner.write_all(&plain)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,163 @@
+use bitsplain::decode::Candidate;
+use bitsplain::tree::{BitRange, Leaf, RealLeaf};
+use svg::node::element::*;
+
+use crate::Canvas;
+
+/// Size of font of legend relative to font of bytes.
+const SCALE: f32 = 0.75;
+
+/// Height of a line relative to font size.
+const LINE: f32 = 1.4;
+
+/// Color of warnings.
+const WARNING: &str = "#ff5555";
+
+pub(crate) struct Legend<'a> {
+    title: &'a str,
+    lines: Vec<Line>,
+    warnings: Vec<String>,
+    canvas: &'a Canvas,
+}
+
+impl<'a> Legend<'a> {
+    pub(crate) fn from_candidate(candidate: &'a Candidate, canvas: &'a Canvas) -> Legend<'a> {
+        let lines = candidate
+            .annotations
+            .leaves()
+            .iter()
+            .filter_map(|l| match l {
+                Leaf::Real(l) => Some(Line::from_leaf(l)),
+                Leaf::Virtual(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        let warnings = candidate
+            .annotations
+            .warnings()
+            .iter()
+            .map(|(i, w)| format!("⚠ {}: {}", i.label, w.message))
+            .collect();
+
+        Legend {
+            title: candidate.decoder.title,
+            lines,
+            warnings,
+            canvas,
+        }
+    }
+
+    fn font_size(&self) -> f32 {
+        self.canvas.font_size * SCALE
+    }
+
+    fn line_height(&self) -> f32 {
+        self.font_size() * LINE
+    }
+
+    /// Height of the legend in pixels, including title and warnings.
+    pub(crate) fn height(&self) -> f32 {
+        (self.lines.len() + self.warnings.len() + 1) as f32 * self.line_height()
+    }
+
+    pub(crate) fn to_group(&self) -> Group {
+        let font_size = self.font_size();
+        let char_width = self.canvas.char_width * SCALE;
+        let field_width = field_width(&self.lines) as f32 * char_width + font_size * 2.0;
+
+        let title = Text::new(self.title)
+            .set("font-family", "DejaVu Sans")
+            .set("font-weight", "bold")
+            .set("dominant-baseline", "hanging")
+            .set("fill", self.canvas.theme.foreground);
+
+        let group = self.lines.iter().enumerate().fold(
+            Group::new().set("font-size", font_size).add(title),
+            |g, (i, l)| {
+                g.add(l.to_group(self.canvas, font_size, field_width).set(
+                    "transform",
+                    format!("translate(0, {})", (i + 1) as f32 * self.line_height()),
+                ))
+            },
+        );
+
+        self.warnings.iter().enumerate().fold(group, |g, (i, w)| {
+            g.add(
+                Text::new(w.as_str())
+                    .set("y", (self.lines.len() + i + 1) as f32 * self.line_height())
+                    .set("font-family", "DejaVu Sans")
+                    .set("font-weight", "bold")
+                    .set("dominant-baseline", "hanging")
+                    .set("fill", WARNING),
+            )
+        })
+    }
+}
+
+struct Line {
+    index: usize,
+    field: String,
+    datatype: Option<String>,
+    length: usize,
+    bits: Option<BitRange>,
+}
+
+impl Line {
+    fn from_leaf(leaf: &RealLeaf) -> Line {
+        Line {
+            index: leaf.location.index,
+            field: leaf.information.label.clone(),
+            datatype: match leaf.information.datatype() {
+                Some(datatype) => Some(datatype.to_string()),
+                None => leaf.information.data.get("datatype").cloned(),
+            },
+            length: leaf.location.to - leaf.location.from,
+            bits: leaf.location.bits,
+        }
+    }
+
+    fn to_group(&self, canvas: &Canvas, font_size: f32, field_width: f32) -> Group {
+        let (bg, _) = canvas.theme.colors(&canvas.palette, self.index);
+        let fg = canvas.theme.foreground;
+        let length = match self.bits {
+            Some(bits) => format!("{} b", bits.len),
+            None => format!("{} B", self.length),
+        };
+        let length = match &self.datatype {
+            Some(d) => format!("{length} ({d})"),
+            None => length,
+        };
+
+        Group::new()
+            .set("dominant-baseline", "hanging")
+            .add(
+                Rectangle::new()
+                    .set("x", 0)
+                    .set("y", 0)
+                    .set("width", font_size)
+                    .set("height", font_size)
+                    .set("fill", bg),
+            )
+            .add(
+                Text::new(self.field.as_str())
+                    .set("x", font_size * 1.5)
+                    .set("font-family", "DejaVu Sans Mono")
+                    .set("fill", fg),
+            )
+            .add(
+                Text::new(length)
+                    .set("x", font_size * 1.5 + field_width)
+                    .set("font-family", "DejaVu Sans")
+                    .set("fill", fg),
+            )
+    }
+}
+
+/// Returns number of characters of longest field name.
+fn field_width(lines: &[Line]) -> usize {
+    lines
+        .iter()
+        .map(|l| l.field.chars().count())
+        .max()
+        .unwrap_or_default()
+}
@@ -0,0 +1,331 @@
+//! SVG output of decoded data. Bytes are laid out in rows, colored by
+//! the leaf they belong to, and followed by a legend of all leaves.
+//!
+//! Supported parameters:
+//!
+//! - `width` – width of the canvas in pixels, default 700; the number of bytes
+//!   in a row is derived from it,
+//! - `font_size` – size of the font of bytes in pixels, default 16,
+//! - `theme` – `light` or `dark`, default `light`.
+
+mod legend;
+
+use std::io::Write;
+
+use bitsplain::decode::Candidate;
+use bitsplain::tree::Leaf;
+use bitsplain_format::*;
+use colors_transform::*;
+use svg::node::element::*;
+use ttf_parser::Face;
+
+/// Height of a row relative to font size.
+const HEIGHT: f32 = 1.5;
+const VERT: f32 = HEIGHT / 2.0;
+
+/// Space around the content of canvas in pixels.
+const MARGIN: f32 = 20.0;
+
+/// Colors of the canvas.
+struct Theme {
+    background: &'static str,
+    foreground: &'static str,
+    border: &'static str,
+    /// How much to lighten palette color to obtain background of bytes.
+    bg_lighten: f32,
+    /// How much to lighten palette color to obtain color of bytes.
+    fg_lighten: f32,
+}
+
+const LIGHT: Theme = Theme {
+    background: "#ffffff",
+    foreground: "#282a36",
+    border: "#000000",
+    bg_lighten: 10.0,
+    fg_lighten: -40.0,
+};
+
+const DARK: Theme = Theme {
+    background: "#282a36",
+    foreground: "#f8f8f2",
+    border: "#44475a",
+    bg_lighten: -30.0,
+    fg_lighten: 20.0,
+};
+
+impl Theme {
+    fn from_name(name: &str) -> Option<&'static Theme> {
+        match name {
+            "light" => Some(&LIGHT),
+            "dark" => Some(&DARK),
+            _ => None,
+        }
+    }
+
+    /// Background and foreground color of leaf with the index.
+    fn colors(&self, palette: &Palette, index: usize) -> (String, String) {
+        let color = Rgb::from_hex_str(&palette.hex(index)).unwrap();
+        (
+            color.lighten(self.bg_lighten).to_css_hex_string(),
+            color.lighten(self.fg_lighten).to_css_hex_string(),
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Block {
+    content: String,
+    index: usize,
+    len: usize,
+    offset: usize,
+}
+
+#[derive(Debug, Default)]
+struct Row {
+    num: usize,
+    blocks: Vec<Block>,
+}
+
+impl Row {
+    fn len(&self) -> usize {
+        self.blocks.iter().map(|r| r.len).sum()
+    }
+
+    fn with_num(num: usize) -> Row {
+        Row {
+            num,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Rows {
+    /// Width of rows in bytes. All rows will be
+    /// aligned to this amount, except the last one.
+    width: usize,
+
+    len: usize,
+
+    /// Rows.
+    rows: Vec<Row>,
+}
+
+impl Rows {
+    fn from_candidate(candidate: &Candidate, width: usize) -> Rows {
+        let data = candidate.data.to_vec();
+
+        candidate.annotations.leaves().iter().fold(
+            Rows {
+                width,
+                len: 0,
+                rows: vec![],
+            },
+            |r, l| match l {
+                Leaf::Real(l) => {
+                    // Bytes shared with previous leaf (leaves of bits) are drawn only once.
+                    let from = l.location.from.max(r.len).min(l.location.to);
+                    r.add_leaf(l.location.index, &data[from..l.location.to])
+                }
+                Leaf::Virtual(_) => r,
+            },
+        )
+    }
+
+    fn add_leaf(self, index: usize, data: &[u8]) -> Rows {
+        let mut rows = self.rows;
+
+        let mut buf = data;
+        let mut new_len = self.len;
+
+        while !buf.is_empty() {
+            let available = self.width - new_len % self.width;
+
+            let (current, rest) = buf.split_at(available.min(buf.len()));
+            buf = rest;
+
+            let mut block = Block {
+                content: hex::encode(current),
+                index,
+                len: current.len(),
+                offset: 0,
+            };
+
+            match rows.last_mut() {
+                // We still have space in the last block.
+                Some(r) if r.len() < self.width => {
+                    block.offset = r.len();
+                    r.blocks.push(block);
+                }
+                // We need to create new row.
+                optrow => {
+                    let mut r = match optrow {
+                        // Not a first row.
+                        Some(r) => Row::with_num(r.num + 1),
+
+                        // First row.
+                        None => Row::default(),
+                    };
+                    block.offset = 0;
+                    r.blocks.push(block);
+                    rows.push(r);
+                }
+            };
+
+            new_len += current.len();
+        }
+
+        Rows {
+            width: self.width,
+            len: new_len,
+            rows,
+        }
+    }
+}
+
+/// Parameters of rendering.
+struct Canvas {
+    width: f32,
+    font_size: f32,
+    theme: &'static Theme,
+    palette: Palette,
+    /// Width of one character in pixels.
+    char_width: f32,
+}
+
+impl Canvas {
+    fn from_ctx(ctx: &Ctx) -> Result<Canvas, FormatError> {
+        let width = positive(ctx, "width", 700.0)?;
+        let font_size = positive(ctx, "font_size", 16.0)?;
+        let theme = match ctx.param::<String>("theme")? {
+            Some(t) => {
+                Theme::from_name(&t).ok_or_else(|| FormatError::invalid_param("theme", t))?
+            }
+            None => &LIGHT,
+        };
+
+        let font: &[u8] = include_bytes!("../DejaVuSansMono.ttf");
+        let face = Face::parse(font, 0).map_err(|e| FormatError::Other(e.to_string()))?;
+        let char_width = face
+            .glyph_index('0')
+            .and_then(|id| face.glyph_hor_advance(id))
+            .map(|adv| adv as f32 / face.units_per_em() as f32 * font_size)
+            .unwrap_or(font_size * 0.6);
+
+        Ok(Canvas {
+            width,
+            font_size,
+            theme,
+            palette: ctx.palette.clone(),
+            char_width,
+        })
+    }
+
+    /// Number of bytes that fit into one row.
+    fn bytes_per_row(&self) -> usize {
+        (((self.width - 2.0 * MARGIN) / (2.0 * self.char_width)) as usize).max(1)
+    }
+
+    fn row_height(&self) -> f32 {
+        self.font_size * 1.1 * HEIGHT
+    }
+}
+
+fn positive(ctx: &Ctx, name: &str, default: f32) -> Result<f32, FormatError> {
+    match ctx.param_or(name, default)? {
+        v if v > 0.0 => Ok(v),
+        v => Err(FormatError::invalid_param(name, v)),
+    }
+}
+
+output_format!(
+    name = "svg",
+    extension = "svg",
+    description = "SVG image with colored bytes and legend",
+    params = {
+        width = "Width of image in pixels (default 700)",
+        font_size = "Size of font of bytes in pixels (default 16)",
+        theme = "light (default) or dark",
+    },
+    render
+);
+
+pub fn render<W: Write>(candidate: Candidate, ctx: &Ctx, out: &mut W) -> Result<(), FormatError> {
+    let svg = generate(candidate, ctx)?;
+    Ok(out.write_all(svg.as_bytes())?)
+}
+
+pub fn generate(candidate: Candidate, ctx: &Ctx) -> Result<String, FormatError> {
+    let canvas = Canvas::from_ctx(ctx)?;
+    let rows = Rows::from_candidate(&candidate, canvas.bytes_per_row());
+
+    let hex_height = rows.rows.len() as f32 * canvas.row_height();
+    let legend = legend::Legend::from_candidate(&candidate, &canvas);
+    let height = 2.0 * MARGIN + hex_height + canvas.font_size + legend.height();
+
+    let doc = svg::Document::new()
+        .set("height", height)
+        .set("width", canvas.width)
+        .set("viewBox", (0, 0, canvas.width, height))
+        .add(
+            Group::new().set("id", "canvas").add(
+                Rectangle::new()
+                    .set("x", 0)
+                    .set("y", 0)
+                    .set("width", canvas.width)
+                    .set("height", height)
+                    .set("fill", canvas.theme.background)
+                    .set("stroke", canvas.theme.border)
+                    .set("stroke-width", "0.5"),
+            ),
+        );
+
+    let doc = rows
+        .rows
+        .iter()
+        .flat_map(|r| {
+            r.blocks.iter().map(|b| {
+                let (bg, fg) = canvas.theme.colors(&canvas.palette, b.index);
+                group(&b.content, &bg, &fg, &canvas).set(
+                    "transform",
+                    format!(
+                        "translate({},{})",
+                        MARGIN + 2.0 * b.offset as f32 * canvas.char_width,
+                        MARGIN + canvas.row_height() * r.num as f32
+                    ),
+                )
+            })
+        })
+        .fold(doc, |svg, g| svg.add(g));
+
+    let doc = doc.add(legend.to_group().set(
+        "transform",
+        format!(
+            "translate({MARGIN},{})",
+            MARGIN + hex_height + canvas.font_size
+        ),
+    ));
+
+    Ok(doc.to_string())
+}
+
+fn group(content: &str, bg: &str, fg: &str, canvas: &Canvas) -> Group {
+    Group::new()
+        .set("font-size", canvas.font_size)
+        .set("font-family", "DejaVu Sans Mono")
+        .set("dominant-baseline", "central")
+        .add(
+            Rectangle::new()
+                .set("x", 0)
+                .set("y", 0)
+                .set("width", content.len() as f32 * canvas.char_width)
+                .set("height", format!("{}em", HEIGHT))
+                .set("fill", bg),
+        )
+        .add(
+            Text::new(content)
+                .set("x", 0)
+                .set("y", format!("{}em", VERT))
+                .set("fill", fg),
+        )
+}
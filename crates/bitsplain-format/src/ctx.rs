@@ -1,9 +1,17 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 
+use bitsplain::bitcoin::{Network, PublicKey};
+use bitsplain::catalog::Catalog;
+use bitsplain::decode::Candidate;
+use bitsplain::enrich::{with_derived_addresses, with_node_aliases, Derivation};
+pub use bitsplain::output::fmt::{BtcUnit, Fmt, HexFmt, NumFmt};
 use clap::ValueEnum;
 use serde::Deserialize;
 
+use crate::error::FormatError;
 use crate::settings::Settings;
+use crate::theme::Palette;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
 #[serde(rename_all = "lowercase")]
@@ -14,24 +22,71 @@ pub enum Detail {
     Debug,
 }
 
-pub struct BtcUnit;
+/// Number of addresses derived from extended key in each branch, unless
+/// format parameter `addresses` says otherwise.
+const DEFAULT_ADDRESSES: u32 = 5;
 
-pub struct NumFmt;
-
-pub struct HexFmt {
-    pub max_len: Option<usize>,
-    pub append_len: bool,
-}
-
-pub struct Fmt {
-    pub btcunit: BtcUnit,
-    pub num: NumFmt,
-    pub hex: HexFmt,
-}
+/// Largest accepted value of format parameter `addresses`.
+const MAX_ADDRESSES: u32 = 1000;
 
 pub struct Ctx {
     pub detail: Detail,
+    /// Rendering of values.
     pub format: Fmt,
     pub settings: Settings,
     pub params: HashMap<String, String>,
+
+    /// Network for which addresses are rendered.
+    pub network: Network,
+
+    /// Colors of leaves.
+    pub palette: Palette,
+
+    /// Labels of public keys, addresses, hashes and other values,
+    /// keyed by their textual form.
+    pub labels: HashMap<String, String>,
+
+    /// Aliases of Lightning nodes by their IDs.
+    pub aliases: HashMap<PublicKey, String>,
+
+    /// Documentation overriding the one written in parsers.
+    pub catalog: Catalog,
+}
+
+impl Ctx {
+    /// Adjusts candidate to the context before it is rendered.
+    pub fn prepare(&self, mut candidate: Candidate) -> Result<Candidate, FormatError> {
+        if let Some(derivation) = Derivation::of(&candidate.data) {
+            let count = self.param_or("addresses", DEFAULT_ADDRESSES)?;
+            if count > MAX_ADDRESSES {
+                return Err(FormatError::invalid_param("addresses", count));
+            }
+            candidate.annotations =
+                with_derived_addresses(candidate.annotations, &derivation, &candidate.data, count);
+        }
+        let decoder = format!("{}/{}", candidate.decoder.group, candidate.decoder.symbol);
+        candidate.annotations = with_node_aliases(candidate.annotations, &self.aliases)
+            .with_network(self.network)
+            .with_labels(&self.labels)
+            .with_catalog(&decoder, &self.catalog);
+        Ok(candidate)
+    }
+
+    /// Value of format parameter `name` parsed as `T`, `None` if the parameter
+    /// is not set.
+    pub fn param<T: FromStr>(&self, name: &str) -> Result<Option<T>, FormatError> {
+        self.params
+            .get(name)
+            .map(|v| {
+                v.parse::<T>()
+                    .map_err(|_| FormatError::invalid_param(name, v))
+            })
+            .transpose()
+    }
+
+    /// Value of format parameter `name` parsed as `T`, `default` if the
+    /// parameter is not set.
+    pub fn param_or<T: FromStr>(&self, name: &str, default: T) -> Result<T, FormatError> {
+        self.param(name).map(|v| v.unwrap_or(default))
+    }
 }
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use thiserror::Error;
 
 /// An error coming for the work of an output format function.
@@ -7,7 +9,8 @@ pub enum FormatError {
     #[error("error during I/O operation")]
     Io(#[from] std::io::Error),
 
-    #[error("x")]
+    /// Invalid or missing format parameter.
+    #[error("invalid format parameter: {0}")]
     Param(String),
 
     #[error("an error occured")]
@@ -16,3 +19,10 @@ pub enum FormatError {
     #[error("an error occurred: {0}")]
     Other(String),
 }
+
+impl FormatError {
+    /// Error of format parameter `name` whose value is not valid.
+    pub fn invalid_param(name: &str, value: impl Display) -> FormatError {
+        FormatError::Param(format!("'{value}' is not a valid value of '{name}'"))
+    }
+}
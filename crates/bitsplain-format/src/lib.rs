@@ -1,14 +1,60 @@
 pub mod ctx;
 pub mod error;
+pub mod names;
+pub mod registry;
 pub mod settings;
+pub mod theme;
 
 pub use ctx::*;
 pub use error::FormatError;
+pub use registry::{all_formats, find_format, FormatParam, OutputFormat};
 pub use settings::Settings;
-pub use thiserror;
+pub use theme::Palette;
+pub use {inventory, thiserror};
 
-// macro_rules! param {
-//     ($param: literal, $type: ty, $typedesc: literal) => {
-//         ;
-//     };
-// }
+/// Registers output format, defined by its render function, under a specified name.
+/// The function has to have the same signature as
+/// `fn render<W: Write>(Candidate, &Ctx, &mut W) -> Result<(), FormatError>`.
+/// The function receives candidate already adjusted to the context
+/// (see [`Ctx::prepare`]). Parameters accepted by the format are declared
+/// as `params = { name = "description", … }`, parameter of all formats
+/// [`ADDRESSES_PARAM`](registry::ADDRESSES_PARAM) is added to them.
+#[rustfmt::skip]
+#[macro_export]
+macro_rules! output_format {
+    (
+        name = $name: literal,
+        extension = $extension: literal,
+        description = $description: literal,
+        params = { $($param: ident = $param_description: literal),* $(,)? },
+        $func: path $(,)?) => {
+        $crate::inventory::submit! {
+            $crate::registry::OutputFormat {
+                name: $name,
+                extension: $extension,
+                description: $description,
+                params: &[
+                    $crate::registry::ADDRESSES_PARAM,
+                    $($crate::registry::FormatParam {
+                        name: stringify!($param),
+                        description: $param_description,
+                    }),*
+                ],
+                render: |candidate, ctx, mut out| $func(ctx.prepare(candidate)?, ctx, &mut out),
+            }
+        }
+    };
+    (
+        name = $name: literal,
+        extension = $extension: literal,
+        description = $description: literal,
+        $func: path $(,)?) => {
+        $crate::output_format!(
+            name = $name,
+            extension = $extension,
+            description = $description,
+            params = {},
+            $func
+        );
+    };
+}
@@ -0,0 +1,46 @@
+//! Helpers for formats that generate source code of other languages,
+//! where labels have to become identifiers and texts string literals.
+
+use std::collections::HashSet;
+
+/// Converts label to identifier, i. e. lowercase ASCII words separated
+/// by underscore, prefixed by underscore if it does not start with letter.
+pub fn identifier(label: &str) -> String {
+    let id = label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .split('_')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+
+    match id.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => id,
+        _ => format!("_{id}"),
+    }
+}
+
+/// Appends numeric suffix to name if it is already taken.
+pub fn unique(name: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|i| format!("{name}_{i}"))
+        .find(|n| !taken.contains(n))
+        .unwrap_or_default()
+}
+
+/// Escape string so it can be used inside double-quoted string.
+pub fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,65 @@
+//! Registry of output formats. Formats register themselves using macro
+//! [`output_format!`](crate::output_format), so user interfaces can offer
+//! them without knowing them in advance.
+
+use std::io::Write;
+
+use bitsplain::decode::Candidate;
+
+use crate::{Ctx, FormatError};
+
+/// Description of an output format.
+pub struct OutputFormat {
+    /// Name of the format, which users use to select it (e. g. 'html').
+    pub name: &'static str,
+
+    /// Usual extension of files in this format, without leading dot.
+    pub extension: &'static str,
+
+    /// Short description of the format.
+    pub description: &'static str,
+
+    /// Parameters that the format accepts (see [`Ctx::param`]).
+    pub params: &'static [FormatParam],
+
+    /// Rendering function.
+    pub render: fn(Candidate, &Ctx, &mut dyn Write) -> Result<(), FormatError>,
+}
+
+/// Parameter of an output format, set by user as `name=value`.
+#[derive(Clone, Copy, Debug)]
+pub struct FormatParam {
+    pub name: &'static str,
+
+    /// Short description including possible and default values.
+    pub description: &'static str,
+}
+
+impl std::fmt::Debug for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputFormat")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+// So instances of OutputFormat struct can be registered and used.
+inventory::collect!(OutputFormat);
+
+/// List of all known output formats, ordered by name.
+pub fn all_formats() -> Vec<&'static OutputFormat> {
+    let mut formats = inventory::iter::<OutputFormat>().collect::<Vec<_>>();
+    formats.sort_by_key(|f| f.name);
+    formats
+}
+
+/// Output format of the given name, if it is known.
+pub fn find_format(name: &str) -> Option<&'static OutputFormat> {
+    inventory::iter::<OutputFormat>().find(|f| f.name == name)
+}
+
+/// Parameter accepted by all formats, as it is used by [`Ctx::prepare`].
+pub const ADDRESSES_PARAM: FormatParam = FormatParam {
+    name: "addresses",
+    description: "Number of addresses derived from extended key in each branch (0–1000, default 5)",
+};
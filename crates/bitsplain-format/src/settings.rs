@@ -1,9 +1,15 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter, Result};
 
+use bitsplain::bitcoin::Network;
+use bitsplain::decode::{DecoderFilter, Preferences};
+use bitsplain::time::OffsetDateTime;
+use bitsplain::timestamp::{self, TimeFormat, Zone, DEFAULT_FORMAT};
 use serde::*;
 use termion::color::Color;
 
 use crate::ctx::Detail;
+use crate::theme::Palette;
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -15,9 +21,88 @@ pub enum FormatType {
 #[derive(Clone, Debug, Deserialize)]
 pub struct Settings {
     pub details: Option<Detail>,
+    pub network: Option<Network>,
+    #[serde(default)]
+    pub decoders: DecoderSettings,
+    /// Name of palette of colors of leaves.
+    pub theme: Option<String>,
+    /// User-defined palettes, colors written as `#rrggbb`.
+    #[serde(default)]
+    pub palettes: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub time: TimeSettings,
     pub format: Format,
 }
 
+impl Settings {
+    /// Palette selected by `theme`, the default one if there is none.
+    pub fn palette(&self) -> std::result::Result<Palette, String> {
+        match &self.theme {
+            Some(name) => Palette::named(name, &self.palettes),
+            None => Ok(Palette::default()),
+        }
+    }
+}
+
+/// Selection and order of decoders, written as patterns `group/symbol`
+/// (e. g. `btc/script` or `ln/*`).
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct DecoderSettings {
+    /// Decoders not to be used unless selected explicitly.
+    #[serde(default)]
+    pub disabled: Vec<String>,
+
+    /// Decoders whose results are preferred, in this order.
+    #[serde(default)]
+    pub order: Vec<String>,
+}
+
+impl DecoderSettings {
+    pub fn preferences(&self) -> std::result::Result<Preferences, String> {
+        let parse = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| p.parse::<DecoderFilter>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+        };
+        Ok(Preferences {
+            disabled: parse(&self.disabled)?,
+            order: parse(&self.order)?,
+        })
+    }
+}
+
+/// Rendering of timestamps.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TimeSettings {
+    /// Format description of the `time` crate,
+    /// e. g. `[year]-[month]-[day] [hour]:[minute]`.
+    pub format: Option<String>,
+
+    /// `utc`, `local` or offset such as `+02:00`.
+    pub timezone: Option<String>,
+
+    #[serde(default)]
+    pub style: timestamp::Style,
+}
+
+impl TimeSettings {
+    /// Format of timestamps, which are shown relatively to the time when
+    /// it is called.
+    pub fn time_format(&self) -> std::result::Result<TimeFormat, String> {
+        let zone = match &self.timezone {
+            Some(z) => Zone::parse(z)?,
+            None => Zone::Utc,
+        };
+        TimeFormat::new(
+            self.format.as_deref().unwrap_or(DEFAULT_FORMAT),
+            zone,
+            self.style,
+            OffsetDateTime::now_utc(),
+        )
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Format {
     pub default: FormatType,
@@ -0,0 +1,101 @@
+//! Palettes of colors, which renderers use to tell apart neighbouring
+//! leaves (e. g. background of bytes in hex views).
+//!
+//! Besides built-in palettes, users can define their own ones in
+//! configuration file and select them by name:
+//!
+//! ```toml
+//! theme = "mine"
+//!
+//! [palettes]
+//! mine = ["#ff0000", "#00ff00", "#0000ff"]
+//! ```
+
+use std::collections::HashMap;
+
+use bitsplain::hex;
+
+/// Colors assigned to leaves in rotation by their index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette(Vec<[u8; 3]>);
+
+/// Built-in palettes.
+const BUILTIN: &[(&str, &[&str])] = &[
+    (
+        "dracula",
+        &[
+            "#8be9fd", "#ffb86c", "#50fa7b", "#ff79c6", "#bd93f9", "#ff5555", "#f1fa8c",
+        ],
+    ),
+    (
+        "solarized",
+        &[
+            "#268bd2", "#cb4b16", "#859900", "#d33682", "#6c71c4", "#dc322f", "#b58900", "#2aa198",
+        ],
+    ),
+    (
+        "gruvbox",
+        &[
+            "#83a598", "#fe8019", "#b8bb26", "#d3869b", "#8ec07c", "#fb4934", "#fabd2f",
+        ],
+    ),
+];
+
+impl Palette {
+    /// Palette of colors written as `#rrggbb`.
+    pub fn from_hex<S: AsRef<str>>(colors: &[S]) -> Result<Palette, String> {
+        if colors.is_empty() {
+            return Err("palette does not have any color".to_string());
+        }
+        colors
+            .iter()
+            .map(|c| {
+                let c = c.as_ref();
+                c.strip_prefix('#')
+                    .and_then(|h| hex::decode(h).ok())
+                    .and_then(|b| <[u8; 3]>::try_from(b).ok())
+                    .ok_or_else(|| format!("'{c}' is not a color '#rrggbb'"))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Palette)
+    }
+
+    /// Palette of the name, either one of user-defined palettes or a built-in one.
+    pub fn named(name: &str, palettes: &HashMap<String, Vec<String>>) -> Result<Palette, String> {
+        match palettes.get(name) {
+            Some(colors) => Palette::from_hex(colors),
+            None => BUILTIN
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, colors)| Palette::from_hex(colors))
+                .unwrap_or_else(|| Err(format!("unknown theme '{name}'"))),
+        }
+    }
+
+    /// Names of built-in palettes.
+    pub fn builtin() -> impl Iterator<Item = &'static str> {
+        BUILTIN.iter().map(|(n, _)| *n)
+    }
+
+    /// Number of colors.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Color of leaf with the index.
+    pub fn rgb(&self, index: usize) -> [u8; 3] {
+        self.0[index % self.0.len()]
+    }
+
+    /// Color of leaf with the index as `#rrggbb`.
+    pub fn hex(&self, index: usize) -> String {
+        format!("#{}", hex::encode(self.rgb(index)))
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::from_hex(BUILTIN[0].1).expect("Built-in palette is valid.")
+    }
+}
@@ -0,0 +1,70 @@
+//! WebAssembly bindings of bitsplain, so data can be explained purely
+//! on client side of a web page.
+//!
+//! Build with `wasm-pack build --target web crates/bitsplain-wasm` and use
+//! from JavaScript:
+//!
+//! ```js
+//! import init, { decode } from "./pkg/bitsplain_wasm.js";
+//!
+//! await init();
+//! const candidates = decode("0200000001…");
+//! console.log(candidates[0].title, candidates[0].annotations);
+//! ```
+//!
+//! Candidates are plain objects of the same shape as JSON output
+//! of bitsplain (see `CandidateSummary`).
+
+use bitsplain::decode::{all_decoders, decode_input, CandidateSummary, Input};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Description of a decoder.
+#[derive(Serialize)]
+struct DecoderInfo {
+    title: &'static str,
+    group: &'static str,
+    symbol: &'static str,
+}
+
+/// Decodes string (hex, base64, bech32, …) and returns array of candidates,
+/// from the most likely one.
+#[wasm_bindgen]
+pub fn decode(input: &str) -> Result<JsValue, JsError> {
+    candidates(Input::String(input.to_string()))
+}
+
+/// Decodes raw bytes and returns array of candidates, from the most likely one.
+#[wasm_bindgen(js_name = decodeBytes)]
+pub fn decode_bytes(input: &[u8]) -> Result<JsValue, JsError> {
+    candidates(Input::Binary(input.to_vec().into()))
+}
+
+/// Returns array of all known decoders.
+#[wasm_bindgen]
+pub fn decoders() -> Result<JsValue, JsError> {
+    let decoders = all_decoders()
+        .iter()
+        .map(|d| DecoderInfo {
+            title: d.title,
+            group: d.group,
+            symbol: d.symbol,
+        })
+        .collect::<Vec<_>>();
+    to_js(&decoders)
+}
+
+fn candidates(input: Input) -> Result<JsValue, JsError> {
+    let candidates = decode_input(input)
+        .iter()
+        .map(|c| c.summary())
+        .collect::<Vec<CandidateSummary>>();
+    to_js(&candidates)
+}
+
+/// Converts value to plain JavaScript objects (i. e. maps are objects, not `Map`).
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
@@ -0,0 +1,60 @@
+//! Parsing of large inputs, which should take time proportional to their size.
+
+use bitsplain::binary::Binary;
+use bitsplain::bitcoin::absolute::LockTime;
+use bitsplain::bitcoin::consensus::serialize;
+use bitsplain::bitcoin::transaction::Version;
+use bitsplain::bitcoin::{
+    Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+};
+use bitsplain::decode::{Decoder, DecoderFilter};
+use bitsplain::parse::Budget;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Serialized transaction with one input and given number of P2WPKH outputs.
+fn transaction(outputs: usize) -> Binary {
+    let tx = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::MAX,
+            witness: Witness::from_slice(&[vec![0xaa; 72], vec![0xbb; 33]]),
+        }],
+        output: (0..outputs)
+            .map(|i| TxOut {
+                value: Amount::from_sat(1000 + i as u64),
+                script_pubkey: ScriptBuf::from_bytes([&[0x00, 0x14][..], &[0x22; 20]].concat()),
+            })
+            .collect(),
+    };
+    Binary::Raw(serialize(&tx).into())
+}
+
+fn decoder() -> &'static Decoder {
+    "btc/tx"
+        .parse::<DecoderFilter>()
+        .ok()
+        .and_then(|f| f.decoders().into_iter().next())
+        .expect("decoder of transactions is registered")
+}
+
+fn parse_transaction(c: &mut Criterion) {
+    let decoder = decoder();
+    let mut group = c.benchmark_group("transaction");
+    group.sample_size(10);
+
+    [50, 500, 5000].into_iter().for_each(|outputs| {
+        let tx = transaction(outputs);
+        group.throughput(Throughput::Elements(outputs as u64));
+        group.bench_with_input(BenchmarkId::new("outputs", outputs), &tx, |b, tx| {
+            b.iter(|| (decoder.decode)(tx, &Budget::default()).expect("transaction is valid"))
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, parse_transaction);
+criterion_main!(benches);
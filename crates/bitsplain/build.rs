@@ -0,0 +1,22 @@
+//! Records compiler and target, so that plugins built differently
+//! can be rejected (see module `plugin`).
+
+use std::process::Command;
+
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|| "unknown rustc".to_string());
+
+    println!("cargo:rustc-env=BITSPLAIN_RUSTC={version}");
+    println!(
+        "cargo:rustc-env=BITSPLAIN_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
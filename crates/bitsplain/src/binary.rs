@@ -2,18 +2,126 @@
 
 use std::ops::Deref;
 
+use bech32::primitives::checksum::Checksum;
 use bech32::primitives::decode::CheckedHrpstring;
-use bech32::NoChecksum;
+use bech32::{Bech32, Bech32m, Hrp, NoChecksum};
+use bitcoin::hashes::{sha256d, Hash};
 use bytes::Bytes;
 
+use crate::inflate;
+
 /// Binary data with information about their origin.
 #[derive(Clone, Debug)]
 pub enum Binary {
     Hex(Bytes),
+    /// Hexdump, as printed by `xxd` or `hexdump -C`.
+    Hexdump(Bytes),
     Base58Check(Bytes),
     Base64(Bytes),
-    Bech32(String, Bytes),
+    /// Base43, which Electrum uses in QR codes of transactions.
+    Base43(Bytes),
+    /// String of bits, e. g. `0101 1100`.
+    Bits(Bytes),
+    Bech32(String, Bytes, Bech32Checksum),
     Raw(Bytes),
+    /// Data taken from payment URI, encoded by the inner binary.
+    Uri(Box<Uri>, Box<Binary>),
+    /// Data decompressed from the compressed bytes, encoded by the inner
+    /// binary (which holds the decompressed data).
+    Compressed(Compression, Bytes, Box<Binary>),
+    /// Data taken from string field of JSON document at the path
+    /// (e. g. `.vin[0].txinwitness[1]`), encoded by the inner binary.
+    Json(String, Box<Binary>),
+    /// Extended key taken from output descriptor, encoded by the inner binary.
+    Descriptor(Box<Descriptor>, Box<Binary>),
+}
+
+/// Format of compressed data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Zlib,
+    Gzip,
+}
+
+impl Compression {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::Zlib => "zlib",
+            Compression::Gzip => "gzip",
+        }
+    }
+}
+
+/// Output descriptor (BIP-380) of single key, e. g.
+/// `wpkh([d34db33f/84h/0h/0h]xpub…/<0;1>/*)#cjjspncu`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Descriptor {
+    /// Script expression around the key, e. g. `wpkh` or `sh(wpkh)`.
+    pub script: String,
+    /// Fingerprint of master key and path to the key, without brackets.
+    pub origin: Option<String>,
+    /// Steps of derivation following the key, e. g. `0`, `<0;1>` or `*`.
+    pub path: Vec<String>,
+    /// Checksum following `#`, if any, and whether it is valid.
+    pub checksum: Option<(String, bool)>,
+}
+
+/// Script expressions of single key understood in descriptors.
+const DESCRIPTOR_SCRIPTS: &[&str] = &["pkh", "wpkh", "sh(wpkh)", "tr"];
+
+/// Magic bytes at the beginning of PSBT (BIP-174).
+pub const PSBT_MAGIC: &[u8] = b"psbt\xff";
+
+/// Payment URI, e. g. `bitcoin:` (BIP-21) or `lightning:`, from which
+/// data were taken.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Uri {
+    /// Scheme of the URI in lowercase, without colon.
+    pub scheme: String,
+    /// Part of the URI between scheme and parameters, e. g. address.
+    pub path: String,
+    /// Percent-decoded parameters of the URI in order of their appearance.
+    pub params: Vec<(String, String)>,
+    /// Parameter from which the data were taken, `None` if from path.
+    pub source: Option<String>,
+}
+
+/// Schemes of URIs whose payloads are decoded.
+const URI_SCHEMES: &[&str] = &["bitcoin", "lightning", "lnurl"];
+
+/// Parameters of URIs that carry payloads to be decoded besides path,
+/// i. e. Lightning fallbacks of `bitcoin:` URIs (BIP-21, BIP-321).
+const URI_PAYLOADS: &[&str] = &["lightning", "lno"];
+
+impl Uri {
+    /// Path and payload parameters of the URI, with their sources.
+    pub fn payloads(&self) -> Vec<(Option<&str>, &str)> {
+        let path = Some((None, self.path.as_str())).filter(|(_, p)| !p.is_empty());
+        let params = self
+            .params
+            .iter()
+            .filter(|(k, _)| URI_PAYLOADS.contains(&k.to_lowercase().as_str()))
+            .map(|(k, v)| (Some(k.as_str()), v.as_str()));
+        path.into_iter().chain(params).collect()
+    }
+
+    /// The same URI with payload taken from `source`.
+    pub fn with_source(&self, source: Option<&str>) -> Uri {
+        Uri {
+            source: source.map(str::to_string),
+            ..self.clone()
+        }
+    }
+}
+
+/// Checksum of Bech32-encoded string, i. e. its last six characters,
+/// and variant of Bech32 by which it was verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Bech32Checksum {
+    Bech32(String),
+    Bech32m(String),
+    /// String has no checksum (e. g. BOLT 12).
+    None,
 }
 
 impl Deref for Binary {
@@ -22,17 +130,235 @@ impl Deref for Binary {
     fn deref(&self) -> &Self::Target {
         match self {
             Binary::Hex(v) => v,
+            Binary::Hexdump(v) => v,
             Binary::Base58Check(v) => v,
             Binary::Base64(v) => v,
+            Binary::Base43(v) => v,
+            Binary::Bits(v) => v,
             Binary::Raw(v) => v,
-            Binary::Bech32(_, v) => v,
+            Binary::Bech32(_, v, _) => v,
+            Binary::Uri(_, b) => b,
+            Binary::Compressed(_, _, b) => b,
+            Binary::Json(_, b) => b,
+            Binary::Descriptor(_, b) => b,
         }
     }
 }
 
-/// Attempt to decode string as hexadecimal string.
+impl Binary {
+    /// The data, which can be cheaply cloned and sliced.
+    pub fn bytes(&self) -> &Bytes {
+        match self {
+            Binary::Hex(v) => v,
+            Binary::Hexdump(v) => v,
+            Binary::Base58Check(v) => v,
+            Binary::Base64(v) => v,
+            Binary::Base43(v) => v,
+            Binary::Bits(v) => v,
+            Binary::Raw(v) => v,
+            Binary::Bech32(_, v, _) => v,
+            Binary::Uri(_, b) => b.bytes(),
+            Binary::Compressed(_, _, b) => b.bytes(),
+            Binary::Json(_, b) => b.bytes(),
+            Binary::Descriptor(_, b) => b.bytes(),
+        }
+    }
+
+    /// Name of encoding from which the data were obtained.
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Binary::Hex(_) => "hex",
+            Binary::Hexdump(_) => "hexdump",
+            Binary::Base58Check(_) => "base58check",
+            Binary::Base64(_) => "base64",
+            Binary::Base43(_) => "base43",
+            Binary::Bits(_) => "bits",
+            Binary::Bech32(..) => "bech32",
+            Binary::Raw(_) => "raw",
+            Binary::Uri(_, b) => b.encoding(),
+            Binary::Compressed(_, _, b) => b.encoding(),
+            Binary::Json(_, b) => b.encoding(),
+            Binary::Descriptor(_, b) => b.encoding(),
+        }
+    }
+
+    /// Other bytes of the same origin. Changed data are not compressed again.
+    pub fn with_bytes(&self, bytes: impl Into<Bytes>) -> Binary {
+        let bytes = bytes.into();
+        match self {
+            Binary::Hex(_) => Binary::Hex(bytes),
+            Binary::Hexdump(_) => Binary::Hexdump(bytes),
+            Binary::Base58Check(_) => Binary::Base58Check(bytes),
+            Binary::Base64(_) => Binary::Base64(bytes),
+            Binary::Base43(_) => Binary::Base43(bytes),
+            Binary::Bits(_) => Binary::Bits(bytes),
+            Binary::Bech32(hrp, _, checksum) => {
+                let checksum = bech32_checksum(hrp, &bytes, checksum);
+                Binary::Bech32(hrp.clone(), bytes, checksum)
+            }
+            Binary::Raw(_) => Binary::Raw(bytes),
+            Binary::Uri(uri, b) => Binary::Uri(uri.clone(), Box::new(b.with_bytes(bytes))),
+            Binary::Compressed(_, _, b) => b.with_bytes(bytes),
+            Binary::Json(path, b) => Binary::Json(path.clone(), Box::new(b.with_bytes(bytes))),
+            Binary::Descriptor(descriptor, b) => {
+                Binary::Descriptor(descriptor.clone(), Box::new(b.with_bytes(bytes)))
+            }
+        }
+    }
+
+    /// The data as they were encoded, without URI, JSON document or
+    /// descriptor they were taken from and without compression.
+    pub fn payload(&self) -> &Binary {
+        match self {
+            Binary::Json(_, b) => b.payload(),
+            Binary::Descriptor(_, b) => b.payload(),
+            Binary::Uri(_, b) => b.payload(),
+            Binary::Compressed(_, _, b) => b.payload(),
+            b => b,
+        }
+    }
+
+    /// URI from which the data were taken, if any.
+    pub fn uri(&self) -> Option<&Uri> {
+        match self {
+            Binary::Json(_, b) => b.uri(),
+            Binary::Uri(uri, _) => Some(uri),
+            _ => None,
+        }
+    }
+
+    /// Format of compression and compressed bytes, if the data were
+    /// decompressed.
+    pub fn compression(&self) -> Option<(Compression, &Bytes)> {
+        match self {
+            Binary::Json(_, b) => b.compression(),
+            Binary::Uri(_, b) => b.compression(),
+            Binary::Compressed(compression, bytes, _) => Some((*compression, bytes)),
+            _ => None,
+        }
+    }
+
+    /// Output descriptor from which the data were taken, if any.
+    pub fn descriptor(&self) -> Option<&Descriptor> {
+        match self {
+            Binary::Json(_, b) => b.descriptor(),
+            Binary::Descriptor(descriptor, _) => Some(descriptor),
+            _ => None,
+        }
+    }
+
+    /// Path of JSON field from which the data were taken, if any.
+    pub fn json_path(&self) -> Option<&str> {
+        match self {
+            Binary::Json(path, _) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Whether the data are PSBT, i. e. start with its magic bytes.
+    pub fn is_psbt(&self) -> bool {
+        self.starts_with(PSBT_MAGIC)
+    }
+
+    /// Checksum of Base58Check-encoded data, i. e. first four bytes
+    /// of double SHA-256 of the data.
+    pub fn base58_checksum(&self) -> Option<[u8; 4]> {
+        match self.payload() {
+            Binary::Base58Check(b) => {
+                let hash = sha256d::Hash::hash(b).to_byte_array();
+                Some([hash[0], hash[1], hash[2], hash[3]])
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Attempt to decode string as hexadecimal string. Besides plain hex
+/// strings, it accepts hex as it is often copied from debuggers and logs:
+/// with `0x` prefixes, broken by whitespace or newlines, or as list
+/// of bytes separated by colons or commas (e. g. `0xde, 0xad` or `de:ad`).
 pub fn string_to_hex(s: &str) -> Option<Binary> {
-    hex::decode(s).ok().map(|b| Binary::Hex(b.into()))
+    let s = s.trim();
+    let bytes = if s.contains([',', ':']) {
+        s.trim_end_matches([',', ':'])
+            .split([',', ':'])
+            .map(|b| without_0x(b.trim()))
+            .map(|b| match b.len() {
+                1 | 2 if b.chars().all(|c| c.is_ascii_hexdigit()) => u8::from_str_radix(b, 16).ok(),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?
+    } else {
+        let hex = s.split_whitespace().map(without_0x).collect::<String>();
+        hex::decode(hex).ok()?
+    };
+    Some(Binary::Hex(bytes.into()))
+}
+
+/// Largest size of data decoded from hexdump with lines omitted by `hexdump`.
+const MAX_SQUEEZED: usize = 16 * 1024 * 1024;
+
+/// Attempt to decode string as hexdump, i. e. lines starting with offset
+/// followed by columns of hex bytes and optionally by ASCII representation
+/// of the bytes, as printed by `xxd` or `hexdump -C`. Lines omitted
+/// by `hexdump` (`*`) are restored as long as the whole data are not larger
+/// than [`MAX_SQUEEZED`] bytes. Offsets of lines must be consistent.
+pub fn string_to_hexdump(s: &str) -> Option<Binary> {
+    let mut bytes: Vec<u8> = vec![];
+    let mut previous = 0..0;
+    let mut squeezed = false;
+
+    for line in s.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if line == "*" {
+            squeezed = true;
+            continue;
+        }
+
+        let (offset, rest) = line.split_once([' ', ':']).unwrap_or((line, ""));
+        if offset.len() < 4 {
+            return None;
+        }
+        let offset = usize::from_str_radix(offset, 16).ok()?;
+
+        if squeezed {
+            // Omitted lines are the same as the previous one, so they
+            // fill the gap exactly.
+            let line = bytes[previous.clone()].to_vec();
+            let gap = offset.checked_sub(bytes.len())?;
+            if line.is_empty() || gap % line.len() != 0 || bytes.len() + gap > MAX_SQUEEZED {
+                return None;
+            }
+            bytes.extend(line.repeat(gap / line.len()));
+            squeezed = false;
+        }
+        if offset != bytes.len() {
+            return None;
+        }
+
+        // ASCII gutter is enclosed in '|' (hexdump) or separated
+        // by two spaces (xxd).
+        let columns = match rest.split_once('|') {
+            Some((columns, _)) => columns,
+            None => rest.trim_start().split("  ").next().unwrap_or_default(),
+        };
+        let start = bytes.len();
+        for column in columns.split_whitespace() {
+            bytes.extend(hex::decode(column).ok()?);
+        }
+        previous = start..bytes.len();
+    }
+
+    if bytes.is_empty() || squeezed {
+        None
+    } else {
+        Some(Binary::Hexdump(bytes.into()))
+    }
+}
+
+fn without_0x(s: &str) -> &str {
+    s.strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s)
 }
 
 /// Attempt to decode string as Base64-encoded string.
@@ -44,20 +370,473 @@ pub fn string_to_base64(s: &str) -> Option<Binary> {
         .map(|b| Binary::Base64(b.into()))
 }
 
+/// Attempt to decode string as Base43-encoded string (as in Electrum).
+/// Strings that are valid hex as well are not decoded, they are much
+/// more likely to be hex.
+pub fn string_to_base43(s: &str) -> Option<Binary> {
+    const ALPHABET: &[u8; 43] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ$*+-./:";
+
+    if s.is_empty() || s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    // Big-endian number in base 43 converted to little-endian bytes.
+    let mut bytes: Vec<u8> = vec![];
+    for c in s.bytes() {
+        let mut carry = ALPHABET.iter().position(|a| *a == c)? as u32;
+        for b in bytes.iter_mut() {
+            carry += u32::from(*b) * 43;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    // Leading zeros are encoded as leading zero digits.
+    let zeros = s.bytes().take_while(|c| *c == ALPHABET[0]).count();
+    bytes.extend(std::iter::repeat_n(0, zeros));
+    bytes.reverse();
+
+    Some(Binary::Base43(bytes.into()))
+}
+
+/// Attempt to decode string of at least eight bits (characters `0` and `1`),
+/// optionally separated by whitespace. Bits are read from the most
+/// significant one, if their number is not multiple of eight, the first
+/// byte is padded by zeros (as bits of a number).
+pub fn string_to_bits(s: &str) -> Option<Binary> {
+    let bits = s
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '0' => Some(false),
+            '1' => Some(true),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if bits.len() < 8 {
+        return None;
+    }
+
+    let padding = (8 - bits.len() % 8) % 8;
+    let bytes = std::iter::repeat_n(false, padding)
+        .chain(bits)
+        .collect::<Vec<_>>()
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0, |b, bit| b << 1 | u8::from(*bit)))
+        .collect::<Vec<_>>();
+    Some(Binary::Bits(bytes.into()))
+}
+
 /// Attempt to decode string as Base58-encoded string.
 pub fn string_to_base58(s: &str) -> Option<Binary> {
     use bitcoin::base58::*;
     decode_check(s).ok().map(|b| Binary::Base58Check(b.into()))
 }
 
-/// Attempt to decode string as Bech32-encoded string without checksum.
+/// Bech32 without limit of length. Lightning invoices (BOLT 11) are
+/// often longer than 1023 characters, which Bech32 limits strings to.
+enum LongBech32 {}
+
+impl Checksum for LongBech32 {
+    type MidstateRepr = u32;
+    const CODE_LENGTH: usize = usize::MAX;
+    const CHECKSUM_LENGTH: usize = Bech32::CHECKSUM_LENGTH;
+    const GENERATOR_SH: [u32; 5] = Bech32::GENERATOR_SH;
+    const TARGET_RESIDUE: u32 = Bech32::TARGET_RESIDUE;
+}
+
+/// Bech32m without limit of length, see [`LongBech32`].
+enum LongBech32m {}
+
+impl Checksum for LongBech32m {
+    type MidstateRepr = u32;
+    const CODE_LENGTH: usize = usize::MAX;
+    const CHECKSUM_LENGTH: usize = Bech32m::CHECKSUM_LENGTH;
+    const GENERATOR_SH: [u32; 5] = Bech32m::GENERATOR_SH;
+    const TARGET_RESIDUE: u32 = Bech32m::TARGET_RESIDUE;
+}
+
+/// Checksum of the same variant as `checksum` of `bytes` with `hrp`.
+fn bech32_checksum(hrp: &str, bytes: &[u8], checksum: &Bech32Checksum) -> Bech32Checksum {
+    let last_six = |s: String| s[s.len() - 6..].to_string();
+    let encoded = |s: Result<String, _>| s.map(last_six).unwrap_or_default();
+    match (Hrp::parse(hrp), checksum) {
+        (Ok(hrp), Bech32Checksum::Bech32(_)) => {
+            Bech32Checksum::Bech32(encoded(bech32::encode::<LongBech32>(hrp, bytes)))
+        }
+        (Ok(hrp), Bech32Checksum::Bech32m(_)) => {
+            Bech32Checksum::Bech32m(encoded(bech32::encode::<LongBech32m>(hrp, bytes)))
+        }
+        _ => Bech32Checksum::None,
+    }
+}
+
+/// Attempt to decode string as Bech32-encoded string. Checksum of Bech32m
+/// or Bech32 is verified if the string has one, otherwise the string is
+/// decoded without checksum. Length of the string is not limited.
 pub fn string_to_bech32(s: &str) -> Option<Binary> {
-    CheckedHrpstring::new::<NoChecksum>(s)
-        .ok()
-        .map(|ch| Binary::Bech32(ch.hrp().to_string(), ch.byte_iter().collect()))
+    let checksum = || s[s.len() - 6..].to_lowercase();
+    let binary = |ch: CheckedHrpstring, checksum| {
+        Binary::Bech32(ch.hrp().to_string(), ch.byte_iter().collect(), checksum)
+    };
+    if let Ok(ch) = CheckedHrpstring::new::<LongBech32m>(s) {
+        Some(binary(ch, Bech32Checksum::Bech32m(checksum())))
+    } else if let Ok(ch) = CheckedHrpstring::new::<LongBech32>(s) {
+        Some(binary(ch, Bech32Checksum::Bech32(checksum())))
+    } else {
+        CheckedHrpstring::new::<NoChecksum>(s)
+            .ok()
+            .map(|ch| binary(ch, Bech32Checksum::None))
+    }
+}
+
+/// Attempt to parse string as payment URI with one of known schemes
+/// (`bitcoin:`, `lightning:` or `lnurl:`). Scheme is case-insensitive.
+pub fn string_to_uri(s: &str) -> Option<Uri> {
+    let (scheme, rest) = s.trim().split_once(':')?;
+    let scheme = scheme.to_lowercase();
+    if !URI_SCHEMES.contains(&scheme.as_str()) {
+        return None;
+    }
+
+    let rest = rest.strip_prefix("//").unwrap_or(rest);
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let params = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| p.split_once('=').unwrap_or((p, "")))
+        .map(|(k, v)| (percent_decode(k), percent_decode(v)))
+        .collect();
+
+    Some(Uri {
+        scheme,
+        path: percent_decode(path),
+        params,
+        source: None,
+    })
+}
+
+/// Attempt to parse string as output descriptor of single key, see
+/// [`Descriptor`]. Returns the descriptor and its key, as it is written.
+pub fn string_to_descriptor(s: &str) -> Option<(Descriptor, &str)> {
+    let s = s.trim();
+    let (body, checksum) = match s.split_once('#') {
+        Some((body, checksum)) => (body, Some(checksum)),
+        None => (s, None),
+    };
+
+    let (script, key) = DESCRIPTOR_SCRIPTS.iter().find_map(|script| {
+        let depth = script.matches('(').count() + 1;
+        let key = body
+            .strip_prefix(&script.replace(')', ""))?
+            .strip_prefix('(')?
+            .strip_suffix(&")".repeat(depth))?;
+        Some((script, key))
+    })?;
+
+    let (origin, key) = match key.strip_prefix('[') {
+        Some(rest) => {
+            let (origin, key) = rest.split_once(']')?;
+            (Some(origin.to_string()), key)
+        }
+        None => (None, key),
+    };
+    let mut steps = key.split('/');
+    let key = steps.next().filter(|k| !k.is_empty())?;
+    let path = steps.map(str::to_string).collect::<Vec<_>>();
+    let step = |p: &String| {
+        !p.is_empty()
+            && p.chars()
+                .all(|c| c.is_ascii_digit() || "*'h<;>".contains(c))
+    };
+    if !path.iter().all(step) {
+        return None;
+    }
+
+    Some((
+        Descriptor {
+            script: script.to_string(),
+            origin,
+            path,
+            checksum: checksum.map(|c| {
+                (
+                    c.to_string(),
+                    descriptor_checksum(body).is_some_and(|d| d == c),
+                )
+            }),
+        },
+        key,
+    ))
+}
+
+/// Checksum of output descriptor as defined by BIP-380, `None` if the
+/// descriptor contains characters not allowed in descriptors.
+fn descriptor_checksum(descriptor: &str) -> Option<String> {
+    const INPUT: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+    const CHECKSUM: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+    fn polymod(c: u64, value: u64) -> u64 {
+        let top = c >> 35;
+        let c = ((c & 0x7_ffff_ffff) << 5) ^ value;
+        [
+            0xf5dee51989,
+            0xa9fdca3312,
+            0x1bab10e32d,
+            0x3706b1677a,
+            0x644d626ffd,
+        ]
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| top >> i & 1 != 0)
+        .fold(c, |c, (_, g)| c ^ g)
+    }
+
+    let mut c = 1;
+    let mut classes = vec![];
+    for ch in descriptor.chars() {
+        let position = INPUT.find(ch)? as u64;
+        c = polymod(c, position & 31);
+        classes.push(position >> 5);
+        if classes.len() == 3 {
+            c = polymod(c, classes[0] * 9 + classes[1] * 3 + classes[2]);
+            classes.clear();
+        }
+    }
+    if !classes.is_empty() {
+        c = polymod(c, classes.iter().fold(0, |cls, c| cls * 3 + c));
+    }
+    let c = (0..8).fold(c, |c, _| polymod(c, 0)) ^ 1;
+
+    Some(
+        (0..8)
+            .map(|i| CHECKSUM[(c >> (5 * (7 - i)) & 31) as usize] as char)
+            .collect(),
+    )
+}
+
+/// Replaces percent-encoded bytes (`%20`) of URI by the bytes.
+fn percent_decode(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        let hex = tail
+            .get(..2)
+            .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|h| std::str::from_utf8(h).ok());
+        match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            Some(byte) if b == b'%' => {
+                bytes.push(byte);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(b);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Attempt to decompress data compressed in zlib or gzip format.
+/// Data are accepted only if their checksum is valid.
+pub fn decompress(binary: &Binary) -> Option<Binary> {
+    match binary {
+        Binary::Uri(uri, b) => decompress(b).map(|b| Binary::Uri(uri.clone(), Box::new(b))),
+        Binary::Json(path, b) => decompress(b).map(|b| Binary::Json(path.clone(), Box::new(b))),
+        Binary::Compressed(..) | Binary::Descriptor(..) => None,
+        b => {
+            let (compression, data) = None
+                .or_else(|| inflate::zlib(b).map(|d| (Compression::Zlib, d)))
+                .or_else(|| inflate::gzip(b).map(|d| (Compression::Gzip, d)))?;
+            Some(Binary::Compressed(
+                compression,
+                b.bytes().clone(),
+                Box::new(b.with_bytes(data)),
+            ))
+        }
+    }
+}
+
+/// Attempt to parse string as JSON document (object or array) and collect
+/// all its string fields with their paths, e. g. `.vin[0].txid`.
+pub fn string_to_json_fields(s: &str) -> Option<Vec<(String, String)>> {
+    let s = s.trim();
+    if !(s.starts_with('{') || s.starts_with('[')) {
+        return None;
+    }
+
+    fn walk(value: &serde_json::Value, path: String, fields: &mut Vec<(String, String)>) {
+        match value {
+            serde_json::Value::String(s) => fields.push((path, s.clone())),
+            serde_json::Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .for_each(|(i, v)| walk(v, format!("{path}[{i}]"), fields)),
+            serde_json::Value::Object(entries) => entries
+                .iter()
+                .for_each(|(k, v)| walk(v, format!("{path}.{k}"), fields)),
+            _ => {}
+        }
+    }
+
+    let document = serde_json::from_str(s).ok()?;
+    let mut fields = vec![];
+    walk(&document, String::new(), &mut fields);
+    Some(fields)
 }
 
 /// Attempt to decode raw byets as string.
 pub fn binary_to_string(b: &[u8]) -> Option<String> {
     String::from_utf8(b.to_vec()).ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bytes 0, 7, 14, … (multiples of 7 modulo 256) encoded as Bech32
+    /// with HRP `lnbc`, 1131 characters long.
+    const LONG_BECH32: &str = concat!(
+        "lnbc1qqrsu9guyv4rzwplgex4gkmzd9c8wl593jfe4gdg47mtm3xt6tv7pelw7h7qxzs3rq0jvtf58dpyj5zhtej",
+        "kcum6sxygl95a5j4m9wwqcl8dth8ratcl3lcxp52pkg3fxqmnu32v2ddxz6r0we7cfzujnxs20t44hnpu45wcmln",
+        "wma8mqgy3q9c7y5krxwjpfp84vhtydde8nqy8362eega2kxutl3kd6nd7960s7llq2rqnrgsjstek84zyk5jevpn",
+        "kuatusw9frxyl56kmfw7ze8gd0hh9anel5qggputp6fptxgu5q36w24wxx6n30plcdrv5nw32nv9hhmzue576u85",
+        "wlahaqs93yxfqyuhr20zrffg4shmxd468hq5fjzteafdvkwavrjx06mw7f6ljlyqqwrs4rs3j5vfc8ary64zmvf5",
+        "hqam7skxf8x4p4zhmd0wye0fdnc88am6lcqc2zyvp7f3dxsa5yj2s2a0x2mrn02qc3ruknkj2hv4ecrrua4wuu04",
+        "0r78lqcx3gxez9ycrw0j9f3f45ctgdam8mpytj2v6pfawkk7v8jk3mr07dm05lvpqjyqhrcjjcve6g9yy74jav34",
+        "hy7vqs78ft89r42cm307xeh2dhchf7rmlupgvzvdzz2p0xc75gj6jt9sxwmn40jpc4yvcn7n2md9mctyap477uhk",
+        "087sppq83v8fy9verjsz8fe24ccm2w9u8lp5djjd692dsk7lvtnxnmts73mlkl5zqkyseyqnjudfugd99zkzlvek",
+        "hg7uz3xgf08494jem4swgeltdme8t7tusqpcwz5wzx2338ql5vn25td3xjurh06zceyu65x52ld4acn9a9k0qulh",
+        "0tlqrpgg3s8ex956rksjf2pt4uetvwdagrzy0j6w6f2ajh8qv0nk4mn374u0clurq69qmyg5nqde7g4x9xknpdph",
+        "hvlvy3wffng98466mes7268vdlehd7nasyzgszu0z2tpn8fq5sn6kt4jxkuneszrca9vu5w4trw9lcmxafklza8c",
+        "00ls9psf35gfg9umr63zt2fvkqemww47g8z53nz06dtd5h0pvn5xhmmj7eul6qyyq79sacrye6c",
+    );
+
+    fn long_bech32_bytes() -> Vec<u8> {
+        (0..700).map(|i| (i * 7 % 256) as u8).collect()
+    }
+
+    #[test]
+    fn long_bech32() {
+        let Some(Binary::Bech32(hrp, bytes, checksum)) = string_to_bech32(LONG_BECH32) else {
+            panic!("long Bech32 string not decoded");
+        };
+        assert_eq!(hrp, "lnbc");
+        assert_eq!(bytes, long_bech32_bytes());
+        assert_eq!(checksum, Bech32Checksum::Bech32("crye6c".to_string()));
+
+        let binary = Binary::Bech32(hrp, bytes, checksum);
+        let Binary::Bech32(_, _, same) = binary.with_bytes(long_bech32_bytes()) else {
+            unreachable!()
+        };
+        assert_eq!(
+            same,
+            Bech32Checksum::Bech32(LONG_BECH32[LONG_BECH32.len() - 6..].to_string())
+        );
+
+        let mut changed = long_bech32_bytes();
+        changed[0] = 0xff;
+        let Binary::Bech32(_, _, checksum) = binary.with_bytes(changed) else {
+            unreachable!()
+        };
+        assert_eq!(checksum, Bech32Checksum::Bech32("muyynq".to_string()));
+    }
+
+    /// Transaction f4184fc5… (the first transaction between two people,
+    /// block 170) as Electrum encodes it in QR codes.
+    const BASE43_TX: &str = concat!(
+        "*6J:Y1QTAQUM9+6G9I+1TQRJQ0L8LQW:$2Q/5PYH17-ZOXHZV.CV/6*L1-Q69OMH..57JIJNQCNDOY$W",
+        "9NM:LQI:7VNE1GQ.OT9DMV34X/N4BFA5HJGM$S0S6W8+LY0V:G6P$.W:XIOLAG9GQ.IIZSO9JZ+LSBAU",
+        "IE+0H-4/1DN/SIT4HJFD919/.D*92S0P6O:QJ-G*24AZ7KV76U9BFRK4KDLXEF203PZNXRH334/0K-Y-",
+        "5U3:O4FM:ROJC$8/5+2H/W0.AOWFXWL7V2DTH7K/JA7.GC+O7G*O6L3KKVQ1B-/P329FE*9U.LEB3TBG",
+        "IYIW:-D:4E$CLYSII4RFJ.KS$6:S+*A$B++8Q4OZF3MLSW-8Y4A5D:M75WY*QCMPF*SLWONY0B$HN6+9",
+        "U86M",
+    );
+    const BASE43_TX_HEX: &str = concat!(
+        "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000",
+        "004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd4102",
+        "20181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200",
+        "ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa2",
+        "8414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee00",
+        "00000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0",
+        "eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000",
+    );
+
+    #[test]
+    fn base43() {
+        let Some(Binary::Base43(bytes)) = string_to_base43(BASE43_TX) else {
+            panic!("Base43 string not decoded");
+        };
+        assert_eq!(hex::encode(bytes), BASE43_TX_HEX);
+
+        assert_eq!(
+            string_to_base43("00Z").as_deref(),
+            Some([0, 0, 35].as_slice())
+        );
+        assert!(string_to_base43("0123abcd").is_none());
+        assert!(string_to_base43("ABC?").is_none());
+    }
+
+    const DESCRIPTOR_XPUB: &str = "xpub6DJ2dNUysrn5Vt36jH2KLBT2i1auw1tTSSomg8PhqNiUtx8QX2SvC9nrHu81fT41fvDUnhMjEzQgXnQjKEu3oaqMSzhSrHMxyyoEAmUHQbY";
+
+    #[test]
+    fn descriptor() {
+        // Test vector of BIP-380.
+        assert_eq!(
+            descriptor_checksum("raw(deadbeef)").as_deref(),
+            Some("89f8spxm")
+        );
+
+        let s = format!("wpkh([d34db33f/84h/0h/0h]{DESCRIPTOR_XPUB}/<0;1>/*)#duqelnha");
+        let Some((descriptor, key)) = string_to_descriptor(&s) else {
+            panic!("descriptor not decoded");
+        };
+        assert_eq!(key, DESCRIPTOR_XPUB);
+        assert_eq!(descriptor.script, "wpkh");
+        assert_eq!(descriptor.origin.as_deref(), Some("d34db33f/84h/0h/0h"));
+        assert_eq!(descriptor.path, ["<0;1>", "*"]);
+        assert_eq!(descriptor.checksum, Some(("duqelnha".to_string(), true)));
+
+        let Some((descriptor, _)) = string_to_descriptor(&s.replace("#duqelnha", "#duqelnhq"))
+        else {
+            panic!("descriptor with invalid checksum not decoded");
+        };
+        assert_eq!(descriptor.checksum, Some(("duqelnhq".to_string(), false)));
+
+        assert!(string_to_descriptor(&format!("wsh({DESCRIPTOR_XPUB}/0/*)")).is_none());
+    }
+
+    #[test]
+    fn hexdump() {
+        let dump = "\
+00000000  00 01 02 03 00 01 02 03  |........|
+*
+00000010  ff                       |.|
+00000011
+";
+        let Some(Binary::Hexdump(bytes)) = string_to_hexdump(dump) else {
+            panic!("hexdump not decoded");
+        };
+        assert_eq!(bytes, [[0, 1, 2, 3].repeat(4), vec![0xff]].concat());
+
+        // Gap is not whole number of omitted lines.
+        assert!(string_to_hexdump(&dump.replace("00000010", "00000012")).is_none());
+        // Gap is too large.
+        assert!(string_to_hexdump(&dump.replace("00000010", "10000000")).is_none());
+
+        // Gaps of 12 MiB are small enough each, but not together.
+        let dump = "\
+00000000  00 01 02 03 00 01 02 03
+*
+00c00000  00 01 02 03 00 01 02 03
+*
+01800000  ff
+";
+        assert!(string_to_hexdump(dump).is_none());
+        assert!(string_to_hexdump(&dump.replace("01800000", "00c00010")).is_some());
+    }
+}
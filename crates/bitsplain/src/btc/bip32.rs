@@ -0,0 +1,290 @@
+//! Extended keys of hierarchical deterministic wallets (BIP-32) and their
+//! SLIP-132 variants, which tell what type of addresses are derived from
+//! the key (`ypub` for P2SH-wrapped SegWit, `zpub` for native SegWit).
+
+use bitcoin::bip32::{ChildNumber, Xpriv, Xpub};
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::{Address, CompressedPublicKey, Network};
+use bytes::Bytes;
+
+use crate::binary::Binary;
+use crate::dsl::{ann, auto};
+use crate::nom::combinator::{map_opt, verify};
+use crate::nom::number::complete::*;
+use crate::parse::*;
+use crate::types::*;
+use crate::value::Value;
+
+/// Type of addresses derived from extended key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    P2pkh,
+    P2shP2wpkh,
+    P2wpkh,
+    /// Only in output descriptors, there is no version of key for it.
+    P2tr,
+}
+
+/// Meaning of version bytes of extended key.
+#[derive(Clone, Copy, Debug)]
+struct Version {
+    bytes: u32,
+    name: &'static str,
+    network: Network,
+    private: bool,
+    kind: Kind,
+}
+
+#[rustfmt::skip]
+const VERSIONS: [Version; 12] = [
+    version(0x0488b21e, "xpub", Network::Bitcoin, false, Kind::P2pkh),
+    version(0x0488ade4, "xprv", Network::Bitcoin, true, Kind::P2pkh),
+    version(0x049d7cb2, "ypub", Network::Bitcoin, false, Kind::P2shP2wpkh),
+    version(0x049d7878, "yprv", Network::Bitcoin, true, Kind::P2shP2wpkh),
+    version(0x04b24746, "zpub", Network::Bitcoin, false, Kind::P2wpkh),
+    version(0x04b2430c, "zprv", Network::Bitcoin, true, Kind::P2wpkh),
+    version(0x043587cf, "tpub", Network::Testnet, false, Kind::P2pkh),
+    version(0x04358394, "tprv", Network::Testnet, true, Kind::P2pkh),
+    version(0x044a5262, "upub", Network::Testnet, false, Kind::P2shP2wpkh),
+    version(0x044a4e28, "uprv", Network::Testnet, true, Kind::P2shP2wpkh),
+    version(0x045f1cf6, "vpub", Network::Testnet, false, Kind::P2wpkh),
+    version(0x045f18bc, "vprv", Network::Testnet, true, Kind::P2wpkh),
+];
+
+const fn version(
+    bytes: u32,
+    name: &'static str,
+    network: Network,
+    private: bool,
+    kind: Kind,
+) -> Version {
+    Version {
+        bytes,
+        name,
+        network,
+        private,
+        kind,
+    }
+}
+
+fn find_version(bytes: u32) -> Option<Version> {
+    VERSIONS.iter().find(|v| v.bytes == bytes).copied()
+}
+
+impl Version {
+    /// Version of BIP-32 key of the same network and privacy, which
+    /// is the only one understood by [`Xpub`] and [`Xpriv`].
+    fn standard(&self) -> u32 {
+        match (self.network, self.private) {
+            (Network::Bitcoin, false) => 0x0488b21e,
+            (Network::Bitcoin, true) => 0x0488ade4,
+            (_, false) => 0x043587cf,
+            (_, true) => 0x04358394,
+        }
+    }
+
+    fn splain(&self) -> String {
+        format!(
+            "{} {} key, addresses derived from it are {}.",
+            match self.network {
+                Network::Bitcoin => "Mainnet",
+                _ => "Testnet",
+            },
+            if self.private { "private" } else { "public" },
+            match self.kind {
+                Kind::P2pkh => "P2PKH (BIP-44)",
+                Kind::P2shP2wpkh => "P2WPKH nested in P2SH (BIP-49)",
+                Kind::P2wpkh => "P2WPKH (BIP-84)",
+                Kind::P2tr => "P2TR (BIP-86)",
+            }
+        )
+    }
+}
+
+pub fn extended_key(s: Span) -> Parsed<()> {
+    let (s, version) = parse(
+        map_opt(uint32_be, find_version),
+        ann("Version", |v: &Version| {
+            Value::Alt(
+                Box::new(Value::bytes(v.bytes.to_be_bytes().to_vec())),
+                Box::new(Value::text(v.name)),
+            )
+        })
+        .doc("Network of the key, whether it is public or private and, in SLIP-132 variants, type of addresses derived from it.")
+        .splain(|v: &Version| v.splain())
+        .bip(32)
+        .slip(132),
+    )(s)?;
+    let (s, _depth) = parse(
+        u8,
+        ann("Depth", auto()).doc("Number of derivations from master key, 0 for master key."),
+    )(s)?;
+    let (s, _fingerprint) = parse(
+        bytes(4usize),
+        ann("Parent Fingerprint", |b: &Bytes| Value::bytes(b.clone()))
+            .doc("First 4 bytes of HASH160 of public key of parent key, zeros for master key."),
+    )(s)?;
+    let (s, _child) = parse(
+        uint32_be,
+        ann("Child Number", auto())
+            .doc("Index of this key among children of its parent. Indices from 2³¹ denote hardened keys, which cannot be derived from public key of the parent.")
+            .splain(|n: &u32| match n.checked_sub(1 << 31) {
+                Some(h) => format!("Hardened child {h}'"),
+                None => format!("Normal child {n}"),
+            }),
+    )(s)?;
+    let (s, _chain_code) = parse(
+        bytes(32usize),
+        ann("Chain Code", |b: &Bytes| Value::bytes(b.clone())).doc(
+            "Extra 32 bytes of entropy, which together with the key allow to derive child keys.",
+        ),
+    )(s)?;
+    let s = if version.private {
+        let (s, _key) = parse(
+            verify(bytes(33usize), |b: &Bytes| b[0] == 0),
+            ann("Private Key", |b: &Bytes| Value::bytes(b.slice(1..)))
+                .doc("Private key, prefixed by zero byte to have the same length as public key."),
+        )(s)?;
+        s
+    } else {
+        let (s, _key) = parse(
+            public_key,
+            ann("Public Key", auto()).doc("Public key in compressed form."),
+        )(s)?;
+        s
+    };
+
+    Ok((s, ()))
+}
+
+/// How addresses are derived from extended key: their type and paths
+/// relative to the key.
+#[derive(Clone, Debug)]
+pub struct Derivation {
+    kind: Kind,
+    network: Network,
+    /// Paths of branches whose children are the addresses or, if the
+    /// derivation is not ranged, paths of the addresses themselves.
+    branches: Vec<Vec<ChildNumber>>,
+    ranged: bool,
+}
+
+impl Derivation {
+    /// Derivation of extended key decoded from `binary`. If the key was taken
+    /// from output descriptor, the descriptor determines type of addresses and
+    /// their paths. Otherwise the type is given by version of the key and
+    /// addresses are derived in receiving (`0/*`) and change (`1/*`) branches.
+    /// `None` if the data are not an extended key or the descriptor does not
+    /// describe addresses.
+    pub fn of(binary: &Binary) -> Option<Derivation> {
+        let version = find_version(u32::from_be_bytes(binary.get(..4)?.try_into().ok()?))?;
+        let Some(descriptor) = binary.descriptor() else {
+            return Some(Derivation {
+                kind: version.kind,
+                network: version.network,
+                branches: vec![vec![ChildNumber::from(0)], vec![ChildNumber::from(1)]],
+                ranged: true,
+            });
+        };
+
+        let kind = match descriptor.script.as_str() {
+            "pkh" => Kind::P2pkh,
+            "sh(wpkh)" => Kind::P2shP2wpkh,
+            "wpkh" => Kind::P2wpkh,
+            "tr" => Kind::P2tr,
+            _ => return None,
+        };
+        let (ranged, steps) = match descriptor.path.split_last() {
+            Some((last, steps)) if last == "*" => (true, steps),
+            // Hardened wildcard.
+            Some((last, _)) if last.starts_with('*') => return None,
+            _ => (false, &descriptor.path[..]),
+        };
+
+        let mut branches = vec![vec![]];
+        for step in steps {
+            let children = match step.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+                Some(alternatives) => alternatives
+                    .split(';')
+                    .map(child_number)
+                    .collect::<Option<Vec<_>>>()?,
+                None => vec![child_number(step)?],
+            };
+            branches = branches
+                .into_iter()
+                .flat_map(|b| children.iter().map(move |c| [&b[..], &[*c]].concat()))
+                .collect();
+        }
+
+        Some(Derivation {
+            kind,
+            network: version.network,
+            branches,
+            ranged,
+        })
+    }
+
+    /// Addresses of the first `count` children of each branch of serialized
+    /// extended key `key` or, if the derivation is not ranged, the addresses
+    /// at paths of the branches. Every address comes with its path.
+    pub fn addresses(&self, key: &[u8], count: u32) -> Vec<(String, Address)> {
+        let Some(version) = key
+            .get(..4)
+            .and_then(|v| find_version(u32::from_be_bytes(v.try_into().ok()?)))
+        else {
+            return vec![];
+        };
+        let data = [&version.standard().to_be_bytes(), &key[4..]].concat();
+        let secp = Secp256k1::new();
+        // Hardened children can be derived only from private key.
+        let xpriv = Xpriv::decode(&data).ok().filter(|_| version.private);
+        let Some(xpub) = xpriv
+            .map(|k| Xpub::from_priv(&secp, &k))
+            .or_else(|| Xpub::decode(&data).ok())
+        else {
+            return vec![];
+        };
+        let derive = |path: &[ChildNumber]| match xpriv {
+            Some(k) => k
+                .derive_priv(&secp, &path)
+                .ok()
+                .map(|k| Xpub::from_priv(&secp, &k).public_key),
+            None => xpub.derive_pub(&secp, &path).ok().map(|k| k.public_key),
+        };
+
+        let paths = self.branches.iter().flat_map(|branch| match self.ranged {
+            true => (0..count)
+                .filter_map(|i| {
+                    Some([&branch[..], &[ChildNumber::from_normal_idx(i).ok()?]].concat())
+                })
+                .collect(),
+            false => vec![branch.clone()],
+        });
+
+        paths
+            .filter_map(|path| {
+                let key = CompressedPublicKey(derive(&path)?);
+                let address = match self.kind {
+                    Kind::P2pkh => Address::p2pkh(key, self.network),
+                    Kind::P2shP2wpkh => Address::p2shwpkh(&key, self.network),
+                    Kind::P2wpkh => Address::p2wpkh(&key, self.network),
+                    Kind::P2tr => Address::p2tr(&secp, key.0.into(), None, self.network),
+                };
+                let path = path
+                    .iter()
+                    .map(ChildNumber::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/");
+                Some((path, address))
+            })
+            .collect()
+    }
+}
+
+/// Step of derivation path, e. g. `1` or hardened `0'` or `0h`.
+fn child_number(step: &str) -> Option<ChildNumber> {
+    match step.strip_suffix(['\'', 'h']) {
+        Some(index) => ChildNumber::from_hardened_idx(index.parse().ok()?).ok(),
+        None => ChildNumber::from_normal_idx(step.parse().ok()?).ok(),
+    }
+}
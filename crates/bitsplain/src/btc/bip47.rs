@@ -1,3 +1,5 @@
+use bytes::Bytes;
+
 use crate::dsl::{ann, auto};
 use crate::nom::combinator::value;
 use crate::nom::number::complete::*;
@@ -23,7 +25,7 @@ pub fn payment_code(s: Span) -> Parsed<()> {
     let (s, _chain) = parse(chain_hash_le, ann("Chain code", auto()))(s)?;
     let (s, _reserved) = parse(
         bytes(13usize),
-        ann("Reserved for future", |b: &Vec<_>| Value::bytes(b.clone())),
+        ann("Reserved for future", |b: &Bytes| Value::bytes(b.clone())),
     )(s)?;
 
     Ok((s, ()))
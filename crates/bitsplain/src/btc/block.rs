@@ -3,15 +3,71 @@ use bitcoin::hash_types::TxMerkleNode;
 use bitcoin::hashes::Hash;
 use bitcoin::*;
 
+use crate::btc::tx::tx;
 use crate::dsl::{ann, auto};
+use crate::nom::multi::many_m_n;
 use crate::parse::*;
+use crate::tree::BitRange;
 use crate::types::*;
 use crate::value::Value;
 use crate::*;
 
+/// Parser of block, i. e. its header followed by all its transactions.
+/// Merkle root in header is verified against the transactions.
+pub fn block(s: Span) -> Parsed<()> {
+    let (s, (header, merkle_root)) = parse(header, ann("Block Header", Value::Nil))(s)?;
+    let (s, txdata) = parse(transactions, ann("Transactions", Value::Nil))(s)?;
+
+    let block = Block { header, txdata };
+    if let Some(computed) = block.compute_merkle_root() {
+        let valid = computed == header.merkle_root;
+        s.insert_check_at(
+            &merkle_root,
+            valid,
+            ann(
+                "Verification",
+                Value::text(if valid { "valid" } else { "INVALID" }),
+            )
+            .doc("Result of comparison of merkle root in header with merkle root computed from transactions of this block.")
+            .splain(if valid {
+                format!("Merkle root computed from transactions is {computed}, the same as in header.")
+            } else {
+                format!("Merkle root computed from transactions is {computed}, but header contains {}. Either the transactions or the header were altered.", header.merkle_root)
+            }),
+            "Merkle root in header does not match transactions of the block",
+        );
+    }
+
+    Ok((s, ()))
+}
+
+fn transactions(s: Span) -> Parsed<Vec<Transaction>> {
+    let (s, tx_n) = parse(
+        varint,
+        ann("Transaction Count", auto()).doc("Number of transactions in this block."),
+    )(s)?;
+    many_m_n(
+        tx_n as usize,
+        tx_n as usize,
+        parse(
+            with("list", "enumerate", tx),
+            ann("Transaction", |t: &Transaction| {
+                Value::Hash(t.compute_txid().to_raw_hash())
+            }),
+        ),
+    )(s)
+}
+
 pub fn block_header(s: Span) -> Parsed<()> {
+    let (s, _) = header(s)?;
+    Ok((s, ()))
+}
+
+/// Parser of block header, returns also bookmark of merkle root, so that
+/// it can be verified once transactions are known.
+fn header(s: Span) -> Parsed<(block::Header, Bookmark)> {
     let (s, (_, version)) = parse(
-        alt(bytes_be(4u32), int32),
+        version_bits,
         ann("Version", auto())
             .bip(9)
             .www("https://scribe.rip/fcats-blockchain-incubator/understanding-the-bitcoin-blockchain-header-a2b0db06b515")
@@ -32,6 +88,7 @@ pub fn block_header(s: Span) -> Parsed<()> {
         ann("Merkle root", auto())
             .doc("Hash of the root of merkle tree of all transactions within this block."),
     )(s)?;
+    let merkle_bm = s.bookmark();
 
     let (s, time) = parse(
         timestamp(uint32),
@@ -73,14 +130,16 @@ pub fn block_header(s: Span) -> Parsed<()> {
 
     // TODO: The condition is here only to ensure that parsing of non-block header data does not fail.
     // Might be improved by preconditions or other sort of validations.
-    if block_header.target().difficulty(Network::Bitcoin) > 0 {
+    // Difficulty of very small targets does not fit into i128.
+    let difficulty = Some(block_header.target())
+        .filter(|t| *t != Target::ZERO)
+        .and_then(|t| i128::try_from(t.difficulty(Network::Bitcoin)).ok())
+        .filter(|d| *d > 0);
+    if let Some(difficulty) = difficulty {
         s.insert(
-            ann(
-                "Difficulty",
-                Value::Num(i128::try_from(block_header.difficulty(Network::Bitcoin)).unwrap()),
-            )
-            .www("https://en.bitcoin.it/wiki/Difficulty")
-            .doc("Measure of how difficult it is to find a hash below a given target. "),
+            ann("Difficulty", Value::Num(difficulty))
+                .www("https://en.bitcoin.it/wiki/Difficulty")
+                .doc("Measure of how difficult it is to find a hash below a given target. "),
         );
     };
 
@@ -98,11 +157,97 @@ pub fn block_header(s: Span) -> Parsed<()> {
             }),
     );
 
+    let target = block_header.target();
+    let hash = block_header.block_hash();
+    let valid = target.is_met_by(hash);
+    let (hash_zeros, target_zeros) = (
+        leading_zeros(&hash.to_byte_array()),
+        leading_zeros(&target.to_le_bytes()),
+    );
+    s.insert_check_at(
+        &s.bookmark(),
+        valid,
+        ann(
+            "Proof of Work",
+            Value::text(if valid { "valid" } else { "INVALID" }),
+        )
+        .www("https://en.bitcoin.it/wiki/Proof_of_work")
+        .doc("Whether hash of this header is equal or less than target, i. e. whether enough work was done to produce it.")
+        .splain(format!(
+            "Hash of this header has {hash_zeros} leading zero bits, target has {target_zeros}. Hash is {} the target, therefore proof of work is {}.",
+            if valid { "not above" } else { "above" },
+            if valid { "valid" } else { "not valid" },
+        )),
+        "Hash of header is above target, proof of work is not valid",
+    );
+
     s.insert(
         ann("Work", Value::display(block_header.work())).doc("Work that this block contributes."),
     );
 
-    Ok((s, ()))
+    Ok((s, (block_header, merkle_bm)))
+}
+
+/// Number of leading zero bits of 256-bit number in little endian.
+fn leading_zeros(le: &[u8; 32]) -> u32 {
+    let zero_bytes = le.iter().rev().take_while(|b| **b == 0).count();
+    match le.iter().rev().nth(zero_bytes) {
+        Some(b) => zero_bytes as u32 * 8 + b.leading_zeros(),
+        None => 256,
+    }
+}
+
+/// Parser of block version, which annotates also its top bits and bits by
+/// which miners signal readiness for soft forks (BIP-9). The version is
+/// little-endian, so bits 0–7 are in the first byte and the top bits
+/// are in the last one.
+fn version_bits(s: Span) -> Parsed<(Vec<u8>, i32)> {
+    let (mut s, (bytes, version)) = alt(bytes_be(4u32), int32)(s)?;
+    let bits = version as u32;
+
+    // Top bits occupy the beginning of the last byte, so they go before bits 24–28.
+    let fields = [
+        (4, 0, 8, 0, "Bits 0–7"),
+        (3, 0, 8, 8, "Bits 8–15"),
+        (2, 0, 8, 16, "Bits 16–23"),
+        (1, 0, 3, 29, "Top Bits"),
+        (1, 3, 5, 24, "Bits 24–28"),
+    ];
+
+    for (back, offset, len, first, label) in fields {
+        let field = bits >> first & ((1 << len) - 1);
+        let value = move |f: &u32| Value::text(format!("{f:0len$b}", len = len));
+        let annotation = if first == 29 {
+            ann(label, value)
+                .doc("Top three bits of version. Value 001 means that the other bits are used for signaling readiness for soft forks.")
+                .splain(|f: &u32| match f {
+                    1 => "Version follows BIP-9, the other bits signal readiness for soft forks.".to_string(),
+                    _ => "Version does not follow BIP-9, the other bits have no meaning for soft forks.".to_string(),
+                })
+        } else {
+            ann(label, value)
+                .doc("Bits by which miners signal readiness for soft forks, each deployment is assigned one bit.")
+                .splain(move |f: &u32| splain_signal_bits(first, *f))
+        };
+        (s, _) = parse(
+            parsed_bits(back, BitRange { offset, len }, field),
+            annotation,
+        )(s)?;
+    }
+
+    Ok((s, (bytes, version)))
+}
+
+fn splain_signal_bits(first: usize, field: u32) -> String {
+    let set = (0..32)
+        .filter(|b| field & 1 << b > 0)
+        .map(|b| (first + b).to_string())
+        .collect::<Vec<_>>();
+    match set.len() {
+        0 => "No bit is set.".to_string(),
+        1 => format!("Bit {} is set.", set[0]),
+        _ => format!("Bits {} are set.", set.join(", ")),
+    }
 }
 
 /// Generate splain for block header version.
@@ -1,18 +1,249 @@
+use bitcoin::opcodes::all::OP_RETURN;
+use bitcoin::opcodes::{Class, ClassifyContext, Opcode};
+use bitcoin::secp256k1::ecdsa::Signature;
+use bitcoin::sighash::EcdsaSighashType;
+use bytes::Bytes;
+
 use crate::bitcoin::ScriptBuf;
 use crate::dsl::*;
+use crate::ln::scripts::LnScript;
+use crate::nom::combinator::peek;
+use crate::nom::number::complete::*;
+use crate::nom::InputLength;
 use crate::parse::*;
+use crate::tree::Tag;
 use crate::types::*;
 use crate::value::Value;
 
+/// DER-encoded ECDSA signature and sighash flag, if data are a signature
+/// as pushed by scripts.
+fn der_signature(data: &[u8]) -> Option<(&[u8], u8)> {
+    let (flag, der) = data.split_last()?;
+    Signature::from_der(der).ok().map(|_| (der, *flag))
+}
+
+/// Name of sighash flag, e. g. `SIGHASH_ALL|SIGHASH_ANYONECANPAY`.
+fn sighash_flag_name(flag: u8) -> String {
+    match EcdsaSighashType::from_standard(flag.into()) {
+        Ok(sighash_type) => sighash_type.to_string(),
+        Err(_) => format!(
+            "{} (non-standard)",
+            EcdsaSighashType::from_consensus(flag.into())
+        ),
+    }
+}
+
+/// Generate splain for sighash flag of ECDSA signature.
+fn splain_sighash_flag(flag: u8) -> String {
+    use EcdsaSighashType::*;
+    let sighash_type = EcdsaSighashType::from_consensus(flag.into());
+    let outputs = match sighash_type {
+        None | NonePlusAnyoneCanPay => "none of outputs, so anyone can change where the funds go",
+        Single | SinglePlusAnyoneCanPay => "only the output with the same index as this input",
+        _ if EcdsaSighashType::from_standard(flag.into()).is_err() => {
+            "all outputs (undefined flags are treated as SIGHASH_ALL, but they are not standard)"
+        }
+        All | AllPlusAnyoneCanPay => "all outputs",
+    };
+    let inputs = match sighash_type {
+        AllPlusAnyoneCanPay | NonePlusAnyoneCanPay | SinglePlusAnyoneCanPay => {
+            "only this input, so other inputs can be added or removed"
+        }
+        All | None | Single => "all inputs",
+    };
+    format!("Signature commits to {inputs} and to {outputs}.")
+}
+
 pub fn script(input: Span) -> Parsed<ScriptBuf> {
     let (s, len) = parse(varint, ann("Length", auto()))(input)?;
-    let (s, x) = parse(
-        bytes(len),
-        ann("Script Data", |b: &Vec<_>| Value::bytes(b.to_vec())),
-    )(s)?;
-    let script: ScriptBuf = x.to_vec().into();
+    let bm = s.bookmark();
+    let (s, script) = instructions(len as usize, ClassifyContext::Legacy)(s)?;
     if !script.is_empty() {
-        s.insert(ann("Script", Value::Script(script.clone())));
+        s.insert_at(&bm, ann("Script", Value::Script(script.clone())));
     }
+    let s = match LnScript::recognize(&script) {
+        Some(ln) => {
+            ln.annotate(&s);
+            s.add_tag(ln.tag())
+        }
+        None => s,
+    };
     Ok((s.with("datatype", "script"), script))
 }
+
+/// Parser of `len` bytes of script, which annotates every instruction
+/// separately. Opcodes are classified in `context`.
+pub fn instructions<'a>(
+    len: usize,
+    context: ClassifyContext,
+) -> impl Fn(Span<'a>) -> Parsed<'a, ScriptBuf> {
+    move |mut s: Span<'a>| {
+        let end = s.input_len().saturating_sub(len);
+        let mut script = vec![];
+        while s.input_len() > end {
+            let (next, instruction) = instruction(context, s.input_len() - end)(s)?;
+            script.extend_from_slice(&instruction);
+            s = next;
+        }
+        Ok((s, script.into()))
+    }
+}
+
+/// Parser of one instruction of script, i. e. opcode and data it pushes,
+/// out of `rest` remaining bytes of the script. Push that does not fit
+/// into the script takes all the remaining bytes.
+fn instruction<'a>(
+    context: ClassifyContext,
+    rest: usize,
+) -> impl Fn(Span<'a>) -> Parsed<'a, Bytes> {
+    move |s: Span<'a>| {
+        let (s, op) = peek(u8)(s)?;
+        let opcode = Opcode::from(op);
+
+        // Little-endian length of data of OP_PUSHDATA, which follows the opcode.
+        let length = |size: usize| {
+            s.get(1..1 + size)
+                .map(|b| b.iter().rev().fold(0, |n, b| n << 8 | *b as usize))
+                .unwrap_or(usize::MAX)
+        };
+        let (header, len) = match op {
+            0x4c => (2usize, Some(length(1))),
+            0x4d => (3, Some(length(2))),
+            0x4e => (5, Some(length(4))),
+            _ => match opcode.classify(context) {
+                Class::PushBytes(n) => (1, Some(n as usize)),
+                _ => (1, None),
+            },
+        };
+
+        if header.saturating_add(len.unwrap_or(0)) > rest {
+            return parse(
+                with("datatype", "push", |s: Span<'a>| {
+                    let (s, b) = bytes(rest)(s)?;
+                    Ok((
+                        s.warn("Push exceeds end of script, the script fails if it is executed."),
+                        b,
+                    ))
+                }),
+                ann(opcode.to_string(), |b: &Bytes| Value::bytes(b.clone()))
+                    .doc(opcode_doc(opcode, context)),
+            )(s);
+        }
+
+        let (s, b) = parse(
+            with(
+                "datatype",
+                if len.is_some() { "push" } else { "opcode" },
+                bytes(header + len.unwrap_or(0)),
+            ),
+            ann(opcode.to_string(), move |b: &Bytes| match len {
+                Some(_) => Value::bytes(b.slice(header..)),
+                None => Value::bytes(b.clone()),
+            })
+            .doc(opcode_doc(opcode, context)),
+        )(s)?;
+
+        if let Some((der, flag)) = der_signature(&b[header..]).filter(|_| len.is_some()) {
+            s.insert(
+                ann("Signature", Value::bytes(der.to_vec()))
+                    .tag(Tag {
+                        label: "signature".to_string(),
+                        color: None,
+                        doc: Some("Push of ECDSA signature with sighash flag".to_string()),
+                    })
+                    .doc("DER-encoded ECDSA signature pushed by this script, without its sighash flag."),
+            );
+            s.insert(
+                ann(
+                    "Sighash Flag",
+                    Value::alt(Value::bytes(vec![flag]), Value::text(sighash_flag_name(flag))),
+                )
+                .doc("The last byte of pushed signature, which determines what parts of transaction the signature commits to.")
+                .splain(splain_sighash_flag(flag)),
+            );
+        }
+
+        Ok((s, b))
+    }
+}
+
+/// Explanation of what opcode does when it is executed in `context`.
+fn opcode_doc(opcode: Opcode, context: ClassifyContext) -> String {
+    let doc = match opcode.classify(context) {
+        Class::PushBytes(0) => "Pushes empty array, which means number 0 or false.",
+        Class::PushBytes(n) => return format!("Pushes the following {n} bytes."),
+        Class::PushNum(n) => return format!("Pushes number {n}."),
+        Class::IllegalOp => "Makes the script fail, even if it is not executed.",
+        Class::SuccessOp => "Makes the script succeed, even if it is not executed. It is reserved for future upgrades of tapscript (BIP-342).",
+        Class::ReturnOp if opcode == OP_RETURN => "Makes the script fail. Outputs with such script cannot be spent, they are used to carry data.",
+        Class::ReturnOp => "Makes the script fail if it is executed.",
+        Class::NoOp | Class::Ordinary(_) => match opcode.to_u8() {
+            0x4c => "Pushes data whose length is given by the following byte.",
+            0x4d => "Pushes data whose length is given by the following 2 bytes (little-endian).",
+            0x4e => "Pushes data whose length is given by the following 4 bytes (little-endian).",
+            0x61 => "Does nothing.",
+            0x63 => "Removes the top stack item and executes the following instructions only if it is true.",
+            0x64 => "Removes the top stack item and executes the following instructions only if it is false.",
+            0x67 => "Executes the following instructions only if those after the matching OP_IF or OP_NOTIF were not executed.",
+            0x68 => "Ends block of OP_IF or OP_NOTIF.",
+            0x69 => "Fails the script unless the top stack item is true, removes the item otherwise.",
+            0x6b => "Moves the top stack item to the alternative stack.",
+            0x6c => "Moves the top item of the alternative stack to the stack.",
+            0x6d => "Removes the top two stack items.",
+            0x6e => "Duplicates the top two stack items.",
+            0x6f => "Duplicates the top three stack items.",
+            0x70 => "Copies the third and fourth stack items to the top.",
+            0x71 => "Moves the fifth and sixth stack items to the top.",
+            0x72 => "Swaps the top two pairs of stack items.",
+            0x73 => "Duplicates the top stack item if it is not zero.",
+            0x74 => "Pushes number of stack items.",
+            0x75 => "Removes the top stack item.",
+            0x76 => "Duplicates the top stack item.",
+            0x77 => "Removes the second stack item.",
+            0x78 => "Copies the second stack item to the top.",
+            0x79 => "Removes the top stack item n and copies the item n places down the stack to the top.",
+            0x7a => "Removes the top stack item n and moves the item n places down the stack to the top.",
+            0x7b => "Moves the third stack item to the top.",
+            0x7c => "Swaps the top two stack items.",
+            0x7d => "Copies the top stack item below the second one.",
+            0x82 => "Pushes length of the top stack item, which is kept.",
+            0x87 => "Replaces the top two stack items by 1 if they are equal, by 0 otherwise.",
+            0x88 => "Fails the script unless the top two stack items are equal, removes them otherwise.",
+            0x8b => "Adds 1 to the top stack item.",
+            0x8c => "Subtracts 1 from the top stack item.",
+            0x8f => "Negates the top stack item.",
+            0x90 => "Replaces the top stack item by its absolute value.",
+            0x91 => "Replaces the top stack item by 1 if it is 0, by 0 otherwise.",
+            0x92 => "Replaces the top stack item by 0 if it is 0, by 1 otherwise.",
+            0x93 => "Replaces the top two stack items by their sum.",
+            0x94 => "Replaces the top two stack items a, b by a − b.",
+            0x9a => "Replaces the top two stack items by 1 if both of them are not zero, by 0 otherwise.",
+            0x9b => "Replaces the top two stack items by 1 if any of them is not zero, by 0 otherwise.",
+            0x9c => "Replaces the top two stack items by 1 if they are equal numbers, by 0 otherwise.",
+            0x9d => "Fails the script unless the top two stack items are equal numbers, removes them otherwise.",
+            0x9e => "Replaces the top two stack items by 1 if they are different numbers, by 0 otherwise.",
+            0x9f => "Replaces the top two stack items a, b by 1 if a < b, by 0 otherwise.",
+            0xa0 => "Replaces the top two stack items a, b by 1 if a > b, by 0 otherwise.",
+            0xa1 => "Replaces the top two stack items a, b by 1 if a ≤ b, by 0 otherwise.",
+            0xa2 => "Replaces the top two stack items a, b by 1 if a ≥ b, by 0 otherwise.",
+            0xa3 => "Replaces the top two stack items by the smaller of them.",
+            0xa4 => "Replaces the top two stack items by the larger of them.",
+            0xa5 => "Replaces the top three stack items x, min, max by 1 if min ≤ x < max, by 0 otherwise.",
+            0xa6 => "Replaces the top stack item by its RIPEMD-160 hash.",
+            0xa7 => "Replaces the top stack item by its SHA-1 hash.",
+            0xa8 => "Replaces the top stack item by its SHA-256 hash.",
+            0xa9 => "Replaces the top stack item by its HASH160, i. e. RIPEMD-160 of SHA-256.",
+            0xaa => "Replaces the top stack item by its double SHA-256 hash.",
+            0xab => "Makes signatures checked later commit only to the part of script after this instruction.",
+            0xac => "Replaces signature and public key (the top item) by 1 if the signature is valid for the key and transaction, by 0 otherwise.",
+            0xad => "Fails the script unless signature is valid for public key (the top item) and transaction, removes them otherwise.",
+            0xae => "Replaces number of keys n, n public keys, number of signatures m and m signatures by 1 if every signature is valid for one of the keys, in the same order, by 0 otherwise. Due to a bug, it removes one more item.",
+            0xaf => "Fails the script unless OP_CHECKMULTISIG would succeed, removes its items otherwise.",
+            0xb1 => "Fails the script unless transaction cannot be mined before height or time given by the top stack item (BIP-65).",
+            0xb2 => "Fails the script unless input cannot be mined before relative lock time given by the top stack item passes (BIP-112).",
+            0xba => "Adds 1 to number n (the second stack item) if signature (the third item) is valid for public key (the top item), replacing all of them by the result (BIP-342).",
+            _ => "Does nothing. It is reserved for future upgrades.",
+        },
+    };
+    doc.to_string()
+}
@@ -0,0 +1,776 @@
+//! Symbolic interpreter of Bitcoin script, which records state of stack
+//! after every instruction.
+//!
+//! Values that cannot be known from the script alone are represented by
+//! symbols: items missing at the bottom of stack when script is executed
+//! alone (`<input 1>`, `<input 2>`, …) and results of operations on them
+//! or of signature checks, which require a sighash. Whenever a decision
+//! depends on a symbol, it is assumed to go the way that lets the script
+//! succeed and the assumption is recorded.
+
+use std::fmt;
+
+use bitcoin::blockdata::opcodes::all::OP_PUSHNUM_16;
+use bitcoin::blockdata::opcodes::{Class, ClassifyContext, Ordinary};
+use bitcoin::hashes::{hash160, ripemd160, sha1, sha256, sha256d, Hash};
+use bitcoin::script::Instruction;
+use bitcoin::Script;
+
+/// Maximal number of items on main and alternative stack together.
+const MAX_STACK_SIZE: usize = 1000;
+
+/// Maximal number of non-push opcodes in a script, not applied to tapscript.
+const MAX_OPS_PER_SCRIPT: usize = 201;
+
+/// Maximal number of keys of OP_CHECKMULTISIG.
+const MAX_PUBKEYS_PER_MULTISIG: i64 = 20;
+
+/// Maximal number of recorded steps. Tapscript has no limit of opcodes,
+/// so execution continues without recording once it is reached.
+pub const MAX_STEPS: usize = 1000;
+
+/// Item of stack.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Data(Vec<u8>),
+
+    /// Value that is not known, described by expression.
+    Symbol(String),
+}
+
+impl Item {
+    fn num(n: i64) -> Item {
+        Item::Data(encode_num(n))
+    }
+
+    fn bool(b: bool) -> Item {
+        Item::num(b.into())
+    }
+
+    /// Truth value, `None` if not known.
+    fn truth(&self) -> Option<bool> {
+        match self {
+            Item::Data(d) => Some(
+                d.iter()
+                    .enumerate()
+                    .any(|(i, b)| *b != 0 && !(i == d.len() - 1 && *b == 0x80)),
+            ),
+            Item::Symbol(_) => None,
+        }
+    }
+
+    /// Item as part of expression of a symbol, i. e. without brackets.
+    fn expr(&self) -> String {
+        match self {
+            Item::Symbol(s) => s.clone(),
+            data => data.to_string(),
+        }
+    }
+
+    fn as_num(&self) -> Result<Option<i64>, String> {
+        match self {
+            Item::Data(d) if d.len() > 4 => Err(format!(
+                "{} is longer than 4 bytes and cannot be used as number",
+                self
+            )),
+            Item::Data(d) => Ok(Some(decode_num(d))),
+            Item::Symbol(_) => Ok(None),
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Data(d) if d.is_empty() => write!(f, "(empty)"),
+            Item::Data(d) if d.len() > 20 => {
+                write!(f, "{}…({} bytes)", hex::encode(&d[..8]), d.len())
+            }
+            Item::Data(d) => write!(f, "{}", hex::encode(d)),
+            Item::Symbol(s) => write!(f, "<{s}>"),
+        }
+    }
+}
+
+/// State after execution of one instruction.
+#[derive(Clone, Debug)]
+pub struct Step {
+    /// Index of program to which the instruction belongs.
+    pub program: usize,
+
+    /// Executed instruction, e. g. `OP_DUP` or pushed data.
+    pub instruction: String,
+
+    /// Stack after the instruction, top is the last.
+    pub stack: Vec<Item>,
+
+    /// Explanation of what happened, if not obvious.
+    pub note: Option<String>,
+}
+
+/// How execution of script ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Script succeeds, provided that assumptions hold.
+    Success {
+        assumptions: Vec<String>,
+    },
+    Failure(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Success { assumptions } if assumptions.is_empty() => write!(f, "success"),
+            Outcome::Success { assumptions } => {
+                write!(f, "success if {}", assumptions.join(" and "))
+            }
+            Outcome::Failure(reason) => write!(f, "failure: {reason}"),
+        }
+    }
+}
+
+/// Complete record of execution.
+#[derive(Clone, Debug)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    pub outcome: Outcome,
+
+    /// Number of executed steps that were not recorded, see [`MAX_STEPS`].
+    pub skipped: usize,
+}
+
+/// Script to execute with context in which its opcodes are interpreted.
+pub struct Program<'a> {
+    /// Name of the script, e. g. `Output Script`.
+    pub name: &'static str,
+
+    pub script: &'a Script,
+
+    pub context: ClassifyContext,
+
+    /// Whether result of previous script must be true and is removed
+    /// before this script is executed, as with redeem script of P2SH.
+    pub verify_first: bool,
+}
+
+/// Executes scripts one after another on the same stack, starting with
+/// `stack`. If `symbolic` is true, items missing on stack are supplied as
+/// symbols instead of failing, so a script can be executed alone.
+pub fn execute(programs: &[Program], stack: Vec<Item>, symbolic: bool) -> Trace {
+    let mut machine = Machine {
+        stack,
+        alt: vec![],
+        exec: vec![],
+        symbolic,
+        inputs: 0,
+        assumptions: vec![],
+        steps: vec![],
+        skipped: 0,
+        program: 0,
+        ops: 0,
+    };
+
+    for (index, program) in programs.iter().enumerate() {
+        machine.program = index;
+        if let Err(reason) = machine.run(program) {
+            return Trace {
+                steps: machine.steps,
+                outcome: Outcome::Failure(reason),
+                skipped: machine.skipped,
+            };
+        }
+    }
+
+    let outcome = if !machine.exec.is_empty() {
+        Outcome::Failure("unbalanced conditional".to_string())
+    } else {
+        match machine.stack.last().map(|i| (i.truth(), i)) {
+            None => Outcome::Failure("stack is empty at the end".to_string()),
+            Some((Some(false), _)) => {
+                Outcome::Failure("top of stack is false at the end".to_string())
+            }
+            Some((Some(true), _)) => Outcome::Success {
+                assumptions: machine.assumptions,
+            },
+            Some((None, item)) => {
+                machine.assumptions.push(item.expr());
+                Outcome::Success {
+                    assumptions: machine.assumptions,
+                }
+            }
+        }
+    };
+
+    Trace {
+        steps: machine.steps,
+        outcome,
+        skipped: machine.skipped,
+    }
+}
+
+struct Machine {
+    stack: Vec<Item>,
+    alt: Vec<Item>,
+    /// Whether branches of currently open conditionals are executed.
+    exec: Vec<bool>,
+    symbolic: bool,
+    /// Number of items supplied as symbols so far.
+    inputs: usize,
+    assumptions: Vec<String>,
+    steps: Vec<Step>,
+    /// Number of steps not recorded after reaching [`MAX_STEPS`].
+    skipped: usize,
+    /// Index of currently executed program.
+    program: usize,
+    /// Number of opcodes counted towards limit in currently executed program.
+    ops: usize,
+}
+
+impl Machine {
+    fn run(&mut self, program: &Program) -> Result<(), String> {
+        if program.verify_first {
+            let note = self
+                .verify()
+                .inspect_err(|e| self.step("(result)".to_string(), Some(e.clone())))?;
+            self.step(
+                "(result)".to_string(),
+                note.or(Some(format!(
+                    "Result is true, {} is executed",
+                    program.name
+                ))),
+            );
+        }
+
+        self.ops = 0;
+        for instruction in program.script.instructions() {
+            let instruction = instruction.map_err(|e| format!("invalid script: {e}"))?;
+            let executing = self.exec.iter().all(|e| *e);
+
+            let (name, note) = match instruction {
+                Instruction::PushBytes(b) => {
+                    if executing {
+                        self.stack.push(Item::Data(b.as_bytes().to_vec()));
+                    }
+                    if b.is_empty() {
+                        ("OP_0".to_string(), None)
+                    } else {
+                        (Item::Data(b.as_bytes().to_vec()).to_string(), None)
+                    }
+                }
+                Instruction::Op(op) => {
+                    if op.to_u8() > OP_PUSHNUM_16.to_u8() {
+                        self.ops += 1;
+                    }
+                    let class = op.classify(program.context);
+                    let control = matches!(
+                        class,
+                        Class::Ordinary(
+                            Ordinary::OP_IF
+                                | Ordinary::OP_NOTIF
+                                | Ordinary::OP_ELSE
+                                | Ordinary::OP_ENDIF
+                        )
+                    );
+                    let note = if executing || control {
+                        self.op(class, op.to_u8())
+                            .inspect_err(|e| self.step(op.to_string(), Some(e.clone())))?
+                    } else {
+                        Some("Not executed".to_string())
+                    };
+                    (op.to_string(), note)
+                }
+            };
+
+            self.step(name, note);
+
+            if matches!(program.context, ClassifyContext::Legacy) && self.ops > MAX_OPS_PER_SCRIPT {
+                return Err(format!("script has more than {MAX_OPS_PER_SCRIPT} opcodes"));
+            }
+            if self.stack.len() + self.alt.len() > MAX_STACK_SIZE {
+                return Err(format!("stack has more than {MAX_STACK_SIZE} items"));
+            }
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, instruction: String, note: Option<String>) {
+        if self.steps.len() >= MAX_STEPS {
+            self.skipped += 1;
+            return;
+        }
+        self.steps.push(Step {
+            program: self.program,
+            instruction,
+            stack: self.stack.clone(),
+            note,
+        });
+    }
+
+    /// Makes sure stack has at least `n` items.
+    fn need(&mut self, n: usize) -> Result<(), String> {
+        if self.stack.len() >= n {
+            return Ok(());
+        }
+        if !self.symbolic {
+            return Err("not enough items on stack".to_string());
+        }
+        if n + self.alt.len() > MAX_STACK_SIZE {
+            return Err(format!(
+                "stack cannot have more than {MAX_STACK_SIZE} items"
+            ));
+        }
+        while self.stack.len() < n {
+            self.inputs += 1;
+            self.stack
+                .insert(0, Item::Symbol(format!("input {}", self.inputs)));
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Item, String> {
+        self.need(1)?;
+        Ok(self.stack.pop().unwrap())
+    }
+
+    fn pop_num(&mut self) -> Result<(Item, Option<i64>), String> {
+        let item = self.pop()?;
+        let n = item.as_num()?;
+        Ok((item, n))
+    }
+
+    /// Pops item and fails unless it is true. Returns note about assumption
+    /// if the item is not known.
+    fn verify(&mut self) -> Result<Option<String>, String> {
+        let item = self.pop()?;
+        match item.truth() {
+            Some(true) => Ok(None),
+            Some(false) => Err("verification failed".to_string()),
+            None => {
+                let assumption = item.expr();
+                self.assumptions.push(assumption.clone());
+                Ok(Some(format!("Assuming {assumption}")))
+            }
+        }
+    }
+
+    /// Executes opcode, returns note about it.
+    fn op(&mut self, class: Class, code: u8) -> Result<Option<String>, String> {
+        use Ordinary::*;
+
+        let ordinary = match class {
+            Class::PushNum(n) => {
+                self.stack.push(Item::num(n.into()));
+                return Ok(None);
+            }
+            Class::NoOp => {
+                return Ok(match code {
+                    0xb1 => Some("Lock time is checked against transaction, not evaluated here".to_string()),
+                    0xb2 => Some("Relative lock time is checked against transaction, not evaluated here".to_string()),
+                    _ => None,
+                })
+            }
+            Class::PushBytes(_) => return Err("truncated push of data".to_string()),
+            Class::ReturnOp => return Err("script is terminated by this opcode".to_string()),
+            Class::IllegalOp => return Err("opcode is disabled or illegal".to_string()),
+            Class::SuccessOp => return Err("OP_SUCCESS makes script succeed unconditionally, it is reserved for future upgrades".to_string()),
+            Class::Ordinary(o) => o,
+        };
+        let executing = self.exec.iter().all(|e| *e);
+
+        match ordinary {
+            OP_PUSHDATA1 | OP_PUSHDATA2 | OP_PUSHDATA4 => {
+                return Err("truncated push of data".to_string())
+            }
+
+            OP_IF | OP_NOTIF => {
+                if !executing {
+                    self.exec.push(false);
+                    return Ok(Some("Not executed".to_string()));
+                }
+                let item = self.pop()?;
+                let (taken, note) = match item.truth() {
+                    Some(t) => (t == (ordinary == OP_IF), None),
+                    None => {
+                        let (assumption, value) = if ordinary == OP_IF {
+                            (item.expr(), "true")
+                        } else {
+                            (format!("NOT({})", item.expr()), "false")
+                        };
+                        self.assumptions.push(assumption);
+                        (
+                            true,
+                            Some(format!(
+                                "Value {item} is not known, assuming it is {value} and the branch is executed"
+                            )),
+                        )
+                    }
+                };
+                self.exec.push(taken);
+                return Ok(note);
+            }
+            OP_ELSE => {
+                let last = self
+                    .exec
+                    .last_mut()
+                    .ok_or_else(|| "OP_ELSE without OP_IF".to_string())?;
+                *last = !*last;
+                return Ok(None);
+            }
+            OP_ENDIF => {
+                self.exec
+                    .pop()
+                    .ok_or_else(|| "OP_ENDIF without OP_IF".to_string())?;
+                return Ok(None);
+            }
+            OP_VERIFY => return self.verify(),
+
+            OP_TOALTSTACK => {
+                let item = self.pop()?;
+                self.alt.push(item);
+            }
+            OP_FROMALTSTACK => {
+                let item = self
+                    .alt
+                    .pop()
+                    .ok_or_else(|| "alternative stack is empty".to_string())?;
+                self.stack.push(item);
+            }
+            OP_2DROP => {
+                self.need(2)?;
+                self.stack.truncate(self.stack.len() - 2);
+            }
+            OP_2DUP => self.copy(2, 2)?,
+            OP_3DUP => self.copy(3, 3)?,
+            OP_2OVER => self.copy(4, 2)?,
+            OP_2ROT => {
+                self.need(6)?;
+                let at = self.stack.len() - 6;
+                let items = self.stack.drain(at..at + 2).collect::<Vec<_>>();
+                self.stack.extend(items);
+            }
+            OP_2SWAP => {
+                self.need(4)?;
+                let at = self.stack.len() - 4;
+                let items = self.stack.drain(at..at + 2).collect::<Vec<_>>();
+                self.stack.extend(items);
+            }
+            OP_IFDUP => {
+                self.need(1)?;
+                let top = self.stack.last().unwrap().clone();
+                match top.truth() {
+                    Some(true) => self.stack.push(top),
+                    Some(false) => {}
+                    None => {
+                        return Ok(Some(format!(
+                            "Value {top} is not known, it is not duplicated"
+                        )))
+                    }
+                }
+            }
+            OP_DEPTH => {
+                if self.symbolic {
+                    self.stack.push(Item::Symbol("depth of stack".to_string()));
+                } else {
+                    self.stack.push(Item::num(self.stack.len() as i64));
+                }
+            }
+            OP_DROP => {
+                self.pop()?;
+            }
+            OP_DUP => self.copy(1, 1)?,
+            OP_NIP => {
+                self.need(2)?;
+                let at = self.stack.len() - 2;
+                self.stack.remove(at);
+            }
+            OP_OVER => self.copy(2, 1)?,
+            OP_PICK | OP_ROLL => {
+                let (item, n) = self.pop_num()?;
+                let n = n.ok_or_else(|| format!("position {item} is not known"))?;
+                let n = usize::try_from(n).map_err(|_| "negative position".to_string())?;
+                self.need(n + 1)?;
+                let at = self.stack.len() - 1 - n;
+                let item = if ordinary == OP_PICK {
+                    self.stack[at].clone()
+                } else {
+                    self.stack.remove(at)
+                };
+                self.stack.push(item);
+            }
+            OP_ROT => {
+                self.need(3)?;
+                let at = self.stack.len() - 3;
+                let item = self.stack.remove(at);
+                self.stack.push(item);
+            }
+            OP_SWAP => {
+                self.need(2)?;
+                let len = self.stack.len();
+                self.stack.swap(len - 1, len - 2);
+            }
+            OP_TUCK => {
+                self.need(2)?;
+                let top = self.stack.last().unwrap().clone();
+                let at = self.stack.len() - 2;
+                self.stack.insert(at, top);
+            }
+            OP_SIZE => {
+                self.need(1)?;
+                let size = match self.stack.last().unwrap() {
+                    Item::Data(d) => Item::num(d.len() as i64),
+                    Item::Symbol(s) => Item::Symbol(format!("SIZE({s})")),
+                };
+                self.stack.push(size);
+            }
+
+            OP_EQUAL | OP_EQUALVERIFY => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let result = match (&a, &b) {
+                    (Item::Data(a), Item::Data(b)) => Item::bool(a == b),
+                    _ => Item::Symbol(format!("{} = {}", a.expr(), b.expr())),
+                };
+                self.stack.push(result);
+                if ordinary == OP_EQUALVERIFY {
+                    return self.verify();
+                }
+            }
+
+            OP_1ADD | OP_1SUB | OP_NEGATE | OP_ABS | OP_NOT | OP_0NOTEQUAL => {
+                let (item, n) = self.pop_num()?;
+                let result = match n {
+                    Some(n) => Item::num(match ordinary {
+                        OP_1ADD => n + 1,
+                        OP_1SUB => n - 1,
+                        OP_NEGATE => -n,
+                        OP_ABS => n.abs(),
+                        OP_NOT => (n == 0).into(),
+                        _ => (n != 0).into(),
+                    }),
+                    None => Item::Symbol(format!(
+                        "{}({})",
+                        ordinary.to_string().trim_start_matches("OP_"),
+                        item.expr()
+                    )),
+                };
+                self.stack.push(result);
+            }
+            OP_ADD
+            | OP_SUB
+            | OP_BOOLAND
+            | OP_BOOLOR
+            | OP_NUMEQUAL
+            | OP_NUMEQUALVERIFY
+            | OP_NUMNOTEQUAL
+            | OP_LESSTHAN
+            | OP_GREATERTHAN
+            | OP_LESSTHANOREQUAL
+            | OP_GREATERTHANOREQUAL
+            | OP_MIN
+            | OP_MAX => {
+                let (b, nb) = self.pop_num()?;
+                let (a, na) = self.pop_num()?;
+                let result = match (na, nb) {
+                    (Some(a), Some(b)) => Item::num(match ordinary {
+                        OP_ADD => a + b,
+                        OP_SUB => a - b,
+                        OP_BOOLAND => (a != 0 && b != 0).into(),
+                        OP_BOOLOR => (a != 0 || b != 0).into(),
+                        OP_NUMEQUAL | OP_NUMEQUALVERIFY => (a == b).into(),
+                        OP_NUMNOTEQUAL => (a != b).into(),
+                        OP_LESSTHAN => (a < b).into(),
+                        OP_GREATERTHAN => (a > b).into(),
+                        OP_LESSTHANOREQUAL => (a <= b).into(),
+                        OP_GREATERTHANOREQUAL => (a >= b).into(),
+                        OP_MIN => a.min(b),
+                        _ => a.max(b),
+                    }),
+                    _ => Item::Symbol(format!(
+                        "{}({}, {})",
+                        ordinary.to_string().trim_start_matches("OP_"),
+                        a.expr(),
+                        b.expr()
+                    )),
+                };
+                self.stack.push(result);
+                if ordinary == OP_NUMEQUALVERIFY {
+                    return self.verify();
+                }
+            }
+            OP_WITHIN => {
+                let (max, nmax) = self.pop_num()?;
+                let (min, nmin) = self.pop_num()?;
+                let (x, nx) = self.pop_num()?;
+                let result = match (nx, nmin, nmax) {
+                    (Some(x), Some(min), Some(max)) => Item::bool(min <= x && x < max),
+                    _ => Item::Symbol(format!(
+                        "WITHIN({}, {}, {})",
+                        x.expr(),
+                        min.expr(),
+                        max.expr()
+                    )),
+                };
+                self.stack.push(result);
+            }
+
+            OP_RIPEMD160 | OP_SHA1 | OP_SHA256 | OP_HASH160 | OP_HASH256 => {
+                let item = self.pop()?;
+                let result = match &item {
+                    Item::Data(d) => Item::Data(match ordinary {
+                        OP_RIPEMD160 => ripemd160::Hash::hash(d).to_byte_array().to_vec(),
+                        OP_SHA1 => sha1::Hash::hash(d).to_byte_array().to_vec(),
+                        OP_SHA256 => sha256::Hash::hash(d).to_byte_array().to_vec(),
+                        OP_HASH160 => hash160::Hash::hash(d).to_byte_array().to_vec(),
+                        _ => sha256d::Hash::hash(d).to_byte_array().to_vec(),
+                    }),
+                    Item::Symbol(_) => Item::Symbol(format!(
+                        "{}({})",
+                        ordinary.to_string().trim_start_matches("OP_"),
+                        item.expr()
+                    )),
+                };
+                self.stack.push(result);
+            }
+            OP_CODESEPARATOR => {}
+
+            OP_CHECKSIG | OP_CHECKSIGVERIFY => {
+                let key = self.pop()?;
+                let signature = self.pop()?;
+                self.stack.push(Item::Symbol(format!(
+                    "signature {} by {} is valid",
+                    signature.expr(),
+                    key.expr()
+                )));
+                if ordinary == OP_CHECKSIGVERIFY {
+                    return self.verify();
+                }
+                return Ok(Some(
+                    "Result of signature check depends on transaction, it is not evaluated"
+                        .to_string(),
+                ));
+            }
+            OP_CHECKSIGADD => {
+                let key = self.pop()?;
+                let (n, _) = self.pop_num()?;
+                let signature = self.pop()?;
+                self.stack.push(Item::Symbol(format!(
+                    "{} + (1 if signature {} by {} is valid)",
+                    n.expr(),
+                    signature.expr(),
+                    key.expr()
+                )));
+            }
+            OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
+                let (item, keys) = self.pop_num()?;
+                let keys = keys.ok_or_else(|| format!("number of keys {item} is not known"))?;
+                if !(0..=MAX_PUBKEYS_PER_MULTISIG).contains(&keys) {
+                    return Err(format!(
+                        "number of keys {keys} is not between 0 and {MAX_PUBKEYS_PER_MULTISIG}"
+                    ));
+                }
+                self.ops += keys as usize;
+                let keys = (0..keys)
+                    .map(|_| self.pop())
+                    .collect::<Result<Vec<_>, _>>()?;
+                let (item, signatures) = self.pop_num()?;
+                let signatures = signatures
+                    .ok_or_else(|| format!("number of signatures {item} is not known"))?;
+                if !(0..=keys.len() as i64).contains(&signatures) {
+                    return Err(format!(
+                        "number of signatures {signatures} is not between 0 and {}",
+                        keys.len()
+                    ));
+                }
+                (0..signatures).try_for_each(|_| self.pop().map(|_| ()))?;
+                // Bug of original implementation consumes one more item.
+                self.pop()?;
+                self.stack.push(Item::Symbol(format!(
+                    "{signatures} of {} signatures are valid",
+                    keys.len()
+                )));
+                if ordinary == OP_CHECKMULTISIGVERIFY {
+                    return self.verify();
+                }
+                return Ok(Some(
+                    "One extra item is removed from stack due to a bug in the original implementation"
+                        .to_string(),
+                ));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Copies `count` items starting `depth` items from the top to the top.
+    fn copy(&mut self, depth: usize, count: usize) -> Result<(), String> {
+        self.need(depth)?;
+        let at = self.stack.len() - depth;
+        let items = self.stack[at..at + count].to_vec();
+        self.stack.extend(items);
+        Ok(())
+    }
+}
+
+/// Number in script encoding, i. e. little endian with sign bit.
+fn encode_num(n: i64) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut abs = n.unsigned_abs();
+    while abs > 0 {
+        bytes.push((abs & 0xff) as u8);
+        abs >>= 8;
+    }
+    match bytes.last_mut() {
+        Some(last) if *last & 0x80 != 0 => bytes.push(if n < 0 { 0x80 } else { 0 }),
+        Some(last) if n < 0 => *last |= 0x80,
+        _ => {}
+    }
+    bytes
+}
+
+fn decode_num(bytes: &[u8]) -> i64 {
+    let Some((last, _)) = bytes.split_last() else {
+        return 0;
+    };
+    let n = bytes.iter().rev().fold(0i64, |n, b| n << 8 | i64::from(*b))
+        & !(0x80 << (8 * (bytes.len() - 1)));
+    if last & 0x80 != 0 {
+        -n
+    } else {
+        n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::blockdata::opcodes::all::{OP_DROP, OP_PUSHNUM_1};
+    use bitcoin::script::Builder;
+
+    use super::*;
+
+    #[test]
+    fn long_tapscript() {
+        let script = (0..MAX_STEPS)
+            .fold(Builder::new(), |b, _| {
+                b.push_opcode(OP_PUSHNUM_1).push_opcode(OP_DROP)
+            })
+            .push_opcode(OP_PUSHNUM_1)
+            .into_script();
+        let program = Program {
+            name: "Tapscript",
+            script: &script,
+            context: ClassifyContext::TapScript,
+            verify_first: false,
+        };
+
+        let trace = execute(&[program], vec![], false);
+        assert_eq!(trace.steps.len(), MAX_STEPS);
+        assert_eq!(trace.skipped, MAX_STEPS + 1);
+        assert_eq!(
+            trace.outcome,
+            Outcome::Success {
+                assumptions: vec![]
+            }
+        );
+    }
+}
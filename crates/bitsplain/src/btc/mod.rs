@@ -1,4 +1,7 @@
+pub mod bip32;
 pub mod bip47;
 pub mod block;
 pub mod datatypes;
+pub mod interpreter;
+pub mod sighash;
 pub mod tx;
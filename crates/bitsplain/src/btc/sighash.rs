@@ -0,0 +1,302 @@
+//! Messages that signatures of transaction inputs commit to. They are
+//! computed according to BIP-341 for Taproot, BIP-143 for SegWit v0 and
+//! the original algorithm for everything else.
+//!
+//! Signatures are recognized in script of input or in witness, depending
+//! on type of spent output, and verified against public keys found there
+//! and in script in which they are checked. `OP_CODESEPARATOR` is not
+//! taken into account.
+
+use bitcoin::ecdsa;
+use bitcoin::hashes::Hash;
+use bitcoin::script::Instruction;
+use bitcoin::secp256k1::{Message, PublicKey, Secp256k1, XOnlyPublicKey};
+use bitcoin::sighash::{Annex, Prevouts, SighashCache};
+use bitcoin::taproot::{self, LeafVersion, TapLeafHash};
+use bitcoin::{Script, ScriptBuf, Transaction, TxOut};
+
+/// Algorithm of computation of sighash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Legacy,
+    SegwitV0,
+    Taproot,
+}
+
+impl Algorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Legacy => "legacy algorithm",
+            Algorithm::SegwitV0 => "BIP-143",
+            Algorithm::Taproot => "BIP-341",
+        }
+    }
+}
+
+/// Signature found in input together with message it commits to.
+#[derive(Clone, Debug)]
+pub struct Signed {
+    /// Signature as present in transaction, including sighash flag.
+    pub signature: Vec<u8>,
+
+    /// Name of sighash flag, e. g. `SIGHASH_ALL`.
+    pub flag: String,
+
+    pub algorithm: Algorithm,
+
+    /// Hash that the signature commits to.
+    pub sighash: [u8; 32],
+
+    /// Public keys that may have made the signature, i. e. keys found
+    /// in input and in script in which the signature is checked.
+    pub keys: Vec<Vec<u8>>,
+}
+
+impl Signed {
+    /// Key that made the signature over the sighash, `None` if there
+    /// is no such key.
+    pub fn signer(&self) -> Option<&[u8]> {
+        let secp = Secp256k1::verification_only();
+        let message = Message::from_digest(self.sighash);
+
+        match self.algorithm {
+            Algorithm::Taproot => {
+                let signature = taproot::Signature::from_slice(&self.signature).ok()?;
+                self.keys.iter().map(Vec::as_slice).find(|k| {
+                    XOnlyPublicKey::from_slice(k).is_ok_and(|k| {
+                        secp.verify_schnorr(&signature.signature, &message, &k)
+                            .is_ok()
+                    })
+                })
+            }
+            Algorithm::Legacy | Algorithm::SegwitV0 => {
+                let mut signature = ecdsa::Signature::from_slice(&self.signature)
+                    .ok()?
+                    .signature;
+                // Consensus accepts signatures with high S, libsecp256k1 does not.
+                signature.normalize_s();
+                self.keys.iter().map(Vec::as_slice).find(|k| {
+                    PublicKey::from_slice(k)
+                        .is_ok_and(|k| secp.verify_ecdsa(&message, &signature, &k).is_ok())
+                })
+            }
+        }
+    }
+}
+
+/// Signatures of input at `index` and messages they commit to. Outputs
+/// spent by inputs are given in order of inputs. Taproot requires all
+/// of them unless signature uses `SIGHASH_ANYONECANPAY`, other types
+/// only the one of the input.
+pub fn signatures(tx: &Transaction, index: usize, prevouts: &[Option<TxOut>]) -> Vec<Signed> {
+    let (Some(input), Some(Some(prevout))) = (tx.input.get(index), prevouts.get(index)) else {
+        return vec![];
+    };
+    let spk = &prevout.script_pubkey;
+    let cache = SighashCache::new(tx);
+
+    if spk.is_p2tr() {
+        taproot(cache, index, prevouts)
+    } else if spk.is_p2wpkh() {
+        segwit_v0(cache, index, prevout, spk, true)
+    } else if spk.is_p2wsh() {
+        segwit_v0(cache, index, prevout, spk, false)
+    } else if spk.is_p2sh() {
+        match pushes(&input.script_sig).last() {
+            Some(redeem) => {
+                let redeem = ScriptBuf::from_bytes(redeem.clone());
+                if redeem.is_p2wpkh() {
+                    segwit_v0(cache, index, prevout, &redeem, true)
+                } else if redeem.is_p2wsh() {
+                    segwit_v0(cache, index, prevout, &redeem, false)
+                } else {
+                    legacy(cache, index, &input.script_sig, &redeem)
+                }
+            }
+            None => vec![],
+        }
+    } else {
+        legacy(cache, index, &input.script_sig, spk)
+    }
+}
+
+fn legacy(
+    cache: SighashCache<&Transaction>,
+    index: usize,
+    script_sig: &Script,
+    script_code: &Script,
+) -> Vec<Signed> {
+    let keys = ecdsa_keys(pushes(script_sig).into_iter().chain(pushed(script_code)));
+
+    pushes(script_sig)
+        .into_iter()
+        .filter_map(|push| {
+            let signature = ecdsa::Signature::from_slice(&push).ok()?;
+            let sighash = cache
+                .legacy_signature_hash(index, script_code, signature.sighash_type.to_u32())
+                .ok()?;
+            Some(Signed {
+                signature: push,
+                flag: signature.sighash_type.to_string(),
+                algorithm: Algorithm::Legacy,
+                sighash: sighash.to_byte_array(),
+                keys: keys.clone(),
+            })
+        })
+        .collect()
+}
+
+/// SegWit v0 spend of output with witness program `program` (P2WPKH
+/// if `pubkey_hash`, P2WSH otherwise).
+fn segwit_v0(
+    mut cache: SighashCache<&Transaction>,
+    index: usize,
+    prevout: &TxOut,
+    program: &Script,
+    pubkey_hash: bool,
+) -> Vec<Signed> {
+    let witness = &cache.transaction().input[index].witness;
+    let (items, script) = if pubkey_hash {
+        (
+            witness.iter().take(1).collect::<Vec<_>>(),
+            program.to_owned(),
+        )
+    } else {
+        match witness.last() {
+            Some(script) => (
+                witness.iter().take(witness.len() - 1).collect(),
+                ScriptBuf::from_bytes(script.to_vec()),
+            ),
+            None => return vec![],
+        }
+    };
+    let items = items.into_iter().map(<[u8]>::to_vec).collect::<Vec<_>>();
+    let keys = ecdsa_keys(witness.iter().map(<[u8]>::to_vec).chain(pushed(&script)));
+
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let signature = ecdsa::Signature::from_slice(&item).ok()?;
+            let sighash = if pubkey_hash {
+                cache
+                    .p2wpkh_signature_hash(index, &script, prevout.value, signature.sighash_type)
+                    .ok()?
+            } else {
+                cache
+                    .p2wsh_signature_hash(index, &script, prevout.value, signature.sighash_type)
+                    .ok()?
+            };
+            Some(Signed {
+                signature: item,
+                flag: signature.sighash_type.to_string(),
+                algorithm: Algorithm::SegwitV0,
+                sighash: sighash.to_byte_array(),
+                keys: keys.clone(),
+            })
+        })
+        .collect()
+}
+
+fn taproot(
+    mut cache: SighashCache<&Transaction>,
+    index: usize,
+    prevouts: &[Option<TxOut>],
+) -> Vec<Signed> {
+    let witness = cache.transaction().input[index].witness.clone();
+    let annex = witness.taproot_annex().and_then(|a| Annex::new(a).ok());
+    let all = prevouts.iter().cloned().collect::<Option<Vec<_>>>();
+    let Some(Some(prevout)) = prevouts.get(index) else {
+        return vec![];
+    };
+
+    let mut items = witness.iter().collect::<Vec<_>>();
+    if annex.is_some() {
+        items.pop();
+    }
+
+    // Key path spend has only signature, script path spend ends with
+    // script and control block.
+    let (items, leaf, keys) = match items.as_slice() {
+        // Output key is the witness program.
+        [signature] => (
+            vec![*signature],
+            None,
+            vec![prevout.script_pubkey.as_bytes()[2..].to_vec()],
+        ),
+        [items @ .., script, control] if !control.is_empty() => {
+            let Ok(version) = LeafVersion::from_consensus(control[0] & taproot::TAPROOT_LEAF_MASK)
+            else {
+                return vec![];
+            };
+            let script = Script::from_bytes(script);
+            let leaf = TapLeafHash::from_script(script, version);
+            let keys = pushed(script)
+                .into_iter()
+                .filter(|k| k.len() == 32)
+                .collect();
+            (items.to_vec(), Some((leaf, u32::MAX)), keys)
+        }
+        _ => return vec![],
+    };
+
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let signature = taproot::Signature::from_slice(item).ok()?;
+            let anyone_can_pay = (signature.sighash_type as u8) & 0x80 != 0;
+            let sighash = match (&all, anyone_can_pay) {
+                (_, true) => cache.taproot_signature_hash(
+                    index,
+                    &Prevouts::One(index, prevout),
+                    annex.clone(),
+                    leaf,
+                    signature.sighash_type,
+                ),
+                (Some(all), false) => cache.taproot_signature_hash(
+                    index,
+                    &Prevouts::All(all),
+                    annex.clone(),
+                    leaf,
+                    signature.sighash_type,
+                ),
+                (None, false) => return None,
+            }
+            .ok()?;
+            Some(Signed {
+                signature: item.to_vec(),
+                flag: signature.sighash_type.to_string(),
+                algorithm: Algorithm::Taproot,
+                sighash: sighash.to_byte_array(),
+                keys: keys.clone(),
+            })
+        })
+        .collect()
+}
+
+/// All data pushed by script, regardless of other instructions.
+fn pushed(script: &Script) -> Vec<Vec<u8>> {
+    script
+        .instructions()
+        .filter_map(|i| match i {
+            Ok(Instruction::PushBytes(b)) => Some(b.as_bytes().to_vec()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Data that look like ECDSA public keys (compressed or uncompressed).
+fn ecdsa_keys(data: impl Iterator<Item = Vec<u8>>) -> Vec<Vec<u8>> {
+    data.filter(|d| matches!(d.len(), 33 | 65)).collect()
+}
+
+/// Data pushed by script, empty if script contains anything else.
+fn pushes(script: &Script) -> Vec<Vec<u8>> {
+    script
+        .instructions()
+        .map(|i| match i {
+            Ok(Instruction::PushBytes(b)) => Some(b.as_bytes().to_vec()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default()
+}
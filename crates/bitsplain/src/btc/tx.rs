@@ -1,13 +1,15 @@
 use bitcoin::absolute::LockTime;
+use bytes::Bytes;
 
 use crate::bitcoin::*;
 use crate::btc::datatypes::*;
 use crate::dsl::{ann, auto};
+use crate::ln::scripts::LnScript;
 use crate::nom::combinator::{peek, success};
 use crate::nom::multi::{length_count, many_m_n};
 use crate::nom::number::complete::be_u8;
 use crate::parse::*;
-use crate::tree::Tag;
+use crate::tree::{Category, Tag};
 use crate::types::*;
 use crate::value::*;
 
@@ -25,60 +27,58 @@ pub fn out_point(s: Span) -> Parsed<OutPoint> {
     Ok((s, OutPoint { txid, vout }))
 }
 
-pub fn tx_out(s: Span) -> Parsed<TxOut> {
+/// Splain of P2TR or P2WPKH address, which shows how it encodes witness
+/// program. It is generated from the address, so it can be regenerated
+/// when the address is encoded for another network.
+pub(crate) fn address_splain(address: &Address) -> Option<String> {
+    let (kind, encoding) = match address.address_type()? {
+        AddressType::P2tr => ("P2TR", "Bech32m"),
+        AddressType::P2wpkh => ("P2WPKH", "Bech32"),
+        _ => return None,
+    };
+    let program = address.witness_program()?;
+    let encoded = address.to_string();
+    // Human-readable part of Bech32 is everything before the last '1'.
+    let (hrp, _) = encoded.rsplit_once('1')?;
+    Some(format!(
+        "Address of {kind} output is {encoding} encoding of witness program, i. e. {encoded} = {encoding}(human_readable_part = \"{hrp}\", witness_version = {}, data = {})",
+        program.version().to_num(),
+        ::hex::encode(program.program().as_bytes())
+    ))
+}
+
+/// Parses transaction output, returns also bookmark of its amount,
+/// so annotations can be appended to the output.
+pub fn tx_out(s: Span) -> Parsed<(TxOut, Bookmark)> {
     let (s, value) = parse(
         sat,
         ann("Amount", auto()).doc("Amount of BTC being transfered in this output"),
     )(s)?;
     let bm = s.bookmark();
     let (s, script) = parse(output_script, ann("Output Script", Value::Nil))(s)?;
+    let script_bm = s.bookmark();
 
     let address = Address::from_script(&script, Network::Bitcoin).ok();
-    let address_str = address.as_ref().map(|a| a.to_string());
-
-    if script.is_p2tr() {
-        if let Some(addr) = address_str {
-            s
-            .insert_at(
-                &bm,
-                ann("Address", Value::Addr(address))
-                    .splain(
-                        format!(
-                            "Address of P2TR output is Bech32m encoding of witness program, i. e. {addr} = Bech32m(human_readable_part = \"bc\", witness_version = 1, data = {})",
-                            ::hex::encode(&script.as_bytes()[2..])
-                        )
-                    )
-                    .bip(350)
-            );
-        }
-    } else if script.is_p2wpkh() {
-        if let Some(addr) = address_str {
-            s
-            .insert_at(
-                &bm,
-                ann("Address", Value::Addr(address))
-                    .splain(
-                        format!(
-                            "Address of P2WPKH output is Bech32 encoding of witness program, i. e. {addr} = Bech32(human_readable_part = \"bc\", witness_version = 0, data = {})",
-                            ::hex::encode(&script.as_bytes()[2..])
-                        )
-                    )
-                    .bip(173)
-            );
-        }
+
+    if let Some(splain) = address.as_ref().and_then(address_splain) {
+        let bip = if script.is_p2tr() { 350 } else { 173 };
+        s.insert_before(
+            &script_bm,
+            ann("Address", Value::Addr(address)).splain(splain).bip(bip),
+        );
     } else if script.is_multisig() {
-        s.insert_at(
-            &bm,
+        s.insert_before(
+            &script_bm,
             ann("Address", Value::Addr(address))
                 .splain("Bare multisig outputs do not have a concept of addresses."),
         );
     } else {
-        s.insert_at(&bm, ann("Address", Value::Addr(address)));
+        s.insert_before(&script_bm, ann("Address", Value::Addr(address)));
     }
 
     if script.is_op_return() {
-        s.insert_at(
-            &bm,
+        s.insert_before(
+            &script_bm,
             ann(
                 "Data",
                 Value::display(String::from_utf8_lossy(script.as_bytes())),
@@ -119,6 +119,13 @@ pub fn tx_out(s: Span) -> Parsed<TxOut> {
                 color: None,
                 doc: None,
             },
+        )
+        .warn_if(
+            !script.is_op_return() && value.amount() < script.minimal_non_dust(),
+            format!(
+                "Amount is below dust limit of {} sat",
+                script.minimal_non_dust().to_sat()
+            ),
         );
 
     let tx_out = TxOut {
@@ -126,11 +133,12 @@ pub fn tx_out(s: Span) -> Parsed<TxOut> {
         script_pubkey: script,
     };
 
-    Ok((s, tx_out))
+    Ok((s, (tx_out, bm)))
 }
 
-/// Parses all transaction outputs.
-pub fn tx_outs(input: Span) -> Parsed<Vec<TxOut>> {
+/// Parses all transaction outputs together with bookmarks of their
+/// amounts, so annotations can be appended to them.
+pub fn tx_outs(input: Span) -> Parsed<Vec<(TxOut, Bookmark)>> {
     let (s, vout_n) = parse(
         varint,
         ann("Output Count", auto()).doc("Number of outputs of this transaction"),
@@ -142,20 +150,24 @@ pub fn tx_outs(input: Span) -> Parsed<Vec<TxOut>> {
     )(s)
 }
 
-/// Parses all transaction inputs.
-pub fn tx_ins(input: Span) -> Parsed<Vec<TxIn>> {
+/// Parses all transaction inputs together with bookmarks
+/// of their annotations, so they can be referred to, and of their
+/// last leaves, so annotations can be appended to them.
+pub fn tx_ins(input: Span) -> Parsed<Vec<(TxIn, Bookmark, Bookmark)>> {
     let (s, vin_n) = parse(
         varint,
         ann("Input Count", auto()).doc("Number of inputs participating in this transaction"),
     )(input)?;
-    many_m_n(
-        vin_n as usize,
-        vin_n as usize,
-        parse(with("list", "enumerate", tx_in), ann("vin", Value::Nil)),
-    )(s)
+    (0..vin_n).try_fold((s, vec![]), |(s, mut vin), _| {
+        let (s, (txin, last)) = parse(with("list", "enumerate", tx_in), ann("vin", Value::Nil))(s)?;
+        vin.push((txin, s.bookmark(), last));
+        Ok((s, vin))
+    })
 }
 
-pub fn tx_in(input: Span) -> Parsed<TxIn> {
+/// Parses transaction input, returns also bookmark of its last leaf,
+/// so annotations can be appended to the input.
+pub fn tx_in(input: Span) -> Parsed<(TxIn, Bookmark)> {
     let (s, out) = parse(
         out_point,
         ann("Outpoint", |o: &OutPoint| {
@@ -165,29 +177,71 @@ pub fn tx_in(input: Span) -> Parsed<TxIn> {
     let (s, scr) = parse(script, ann("Input Script", Value::Nil))(s)?;
     let (s, (seq, _)) = parse(
         alt(uint32, bytes(4u32)),
-        ann("Sequence", |(s, bin): &(u32, Vec<u8>)| {
+        ann("Sequence", |(s, bin): &(u32, Bytes)| {
             Value::alt(Value::Num(*s as i128), Value::bytes(bin.clone()))
-        }),
+        })
+        .category(Category::Locktime)
+        .doc("Number that signals replaceability of transaction and encodes relative lock time of this input. Originally meant for updating of transactions before they are mined.")
+        .splain(|(s, _): &(u32, Bytes)| splain_sequence(Sequence(*s)))
+        .bip(68)
+        .bip(125),
     )(s)?;
+    let bm = s.bookmark();
     Ok((
         s,
-        TxIn {
-            previous_output: out,
-            script_sig: scr,
-            sequence: Sequence(seq),
-            witness: Witness::new(),
-        },
+        (
+            TxIn {
+                previous_output: out,
+                script_sig: scr,
+                sequence: Sequence(seq),
+                witness: Witness::new(),
+            },
+            bm,
+        ),
     ))
 }
 
+/// Generate splain for sequence of input.
+fn splain_sequence(sequence: Sequence) -> String {
+    if sequence == Sequence::MAX {
+        return "Final sequence 0xffffffff: input neither signals replaceability nor has relative lock time. If all inputs are final, lock time of transaction is not enforced.".to_string();
+    }
+
+    let rbf = if sequence.is_rbf() {
+        "Sequence below 0xfffffffe signals that transaction may be replaced by one paying higher fee (BIP-125)."
+    } else {
+        "Sequence 0xfffffffe does not signal replaceability (BIP-125), but lets lock time of transaction be enforced."
+    };
+    let relative = match sequence.to_relative_lock_time() {
+        Some(relative::LockTime::Blocks(blocks)) => format!(
+            "Disable flag (bit 31) is not set and type flag (bit 22) is not set, therefore input can be mined only {} blocks after the spent output (BIP-68, transaction version 2 or higher).",
+            blocks.value()
+        ),
+        Some(relative::LockTime::Time(time)) => {
+            let seconds = u32::from(time.value()) * 512;
+            format!(
+                "Disable flag (bit 31) is not set and type flag (bit 22) is set, therefore input can be mined only {} × 512 = {seconds} seconds (≈ {}) after the spent output (BIP-68, transaction version 2 or higher).",
+                time.value(),
+                if seconds < 2 * 86400 {
+                    format!("{:.1} hours", seconds as f64 / 3600.0)
+                } else {
+                    format!("{:.1} days", seconds as f64 / 86400.0)
+                }
+            )
+        }
+        None => "Disable flag (bit 31) is set, therefore sequence does not encode relative lock time (BIP-68).".to_string(),
+    };
+    format!("{rbf} {relative}")
+}
+
 /// Parser of a script in transaction output.
 pub fn output_script(input: Span) -> Parsed<ScriptBuf> {
     let (s, len) = parse(varint, ann("Length", auto()))(input)?;
     let (s, script_data) = parse(
         bytes(len),
-        ann("Script Data", |b: &Vec<_>| Value::bytes(b.to_vec())),
+        ann("Script Data", |b: &Bytes| Value::bytes(b.clone())),
     )(s)?;
-    let script: ScriptBuf = script_data.into();
+    let script: ScriptBuf = script_data.to_vec().into();
     if script.is_witness_program() {
         s.insert(ann(
             "Witness Version",
@@ -198,7 +252,7 @@ pub fn output_script(input: Span) -> Parsed<ScriptBuf> {
         ));
 
         if script.is_p2wpkh() {
-            s.insert(ann("Length of Witness Program", Value::Size(20)));
+            s.insert(ann("Length of Witness Program", Value::num(20)).unit(Unit::Bytes));
             s.insert(
                 ann(
                     "Witness Program",
@@ -208,7 +262,7 @@ pub fn output_script(input: Span) -> Parsed<ScriptBuf> {
                 .splain("Witness version 0 and 20-byte program indicate P2WPKH output. In P2WPKH output, witness program is a HASH160 hash of public key."),
             );
         } else if script.is_p2wsh() {
-            s.insert(ann("Length of Witness Program", Value::Size(32)));
+            s.insert(ann("Length of Witness Program", Value::num(32)).unit(Unit::Bytes));
             s.insert(
                 ann(
                     "Witness Program",
@@ -218,7 +272,7 @@ pub fn output_script(input: Span) -> Parsed<ScriptBuf> {
                 .splain("Witness version 0 and 32-byte program indicate P2WSH output. In P2WSH output, witness program is SHA256 hash of script."),
             );
         } else if script.is_p2tr() {
-            s.insert(ann("Length of Witness Program", Value::Size(32)));
+            s.insert(ann("Length of Witness Program", Value::num(32)).unit(Unit::Bytes));
             s.insert(
                 ann(
                     "Witness Program",
@@ -233,70 +287,252 @@ pub fn output_script(input: Span) -> Parsed<ScriptBuf> {
     Ok((s.with("datatype", "script"), script))
 }
 
-/// Parse one witness item.
-///
-// TODO: in the future we might be able to interpret the witnesses
-// based on previous output. Once the previous output is available,
-// let us to do.
+/// Parse one witness item. Role of the item depends on output spent by
+/// the input, therefore it is added only once the output is known (see
+/// [`crate::enrich::with_witnesses`]).
 pub fn witness_item(_vin: TxIn) -> impl Fn(Span) -> Parsed<Vec<u8>> {
     move |s: Span| {
-        let (s, len) = parse(varint, ann("Length", |n: &u64| Value::Size(*n)))(s)?;
+        let (s, len) = parse(varint, ann("Length", auto()).unit(Unit::Bytes))(s)?;
         let (s, w) = parse(
             length_count(success(len), be_u8),
             ann("Witness Data", auto()),
         )(s)?;
+        let s = match LnScript::recognize(Script::from_bytes(&w)) {
+            Some(ln) => {
+                ln.annotate(&s);
+                s.add_tag(ln.tag())
+            }
+            None => s,
+        };
         Ok((s, w))
     }
 }
 
+/// Kind of witness item, recognized from shape of the whole witness stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WitnessKind {
+    Item,
+    Tapscript,
+    ControlBlock,
+    Annex,
+}
+
+/// Kinds of witness items. The last of at least two items is an annex if
+/// it starts with 0x50. The last two of the remaining items are tapscript
+/// and control block if they look like Taproot script path spend of
+/// tapscript.
+fn witness_kinds(items: &[Vec<u8>]) -> Vec<WitnessKind> {
+    let mut kinds = vec![WitnessKind::Item; items.len()];
+    let mut rest = items.len();
+    if rest > 1 && items[rest - 1].first() == Some(&taproot::TAPROOT_ANNEX_PREFIX) {
+        rest -= 1;
+        kinds[rest] = WitnessKind::Annex;
+    }
+    if rest > 1 {
+        let (script, control) = (&items[rest - 2], &items[rest - 1]);
+        // Only tapscript leaf version is accepted, other versions would
+        // also match public keys of P2WPKH.
+        let tapscript = taproot::ControlBlock::decode(control)
+            .is_ok_and(|c| c.leaf_version == taproot::LeafVersion::TapScript);
+        if tapscript && Script::from_bytes(script).instructions().all(|i| i.is_ok()) {
+            kinds[rest - 2] = WitnessKind::Tapscript;
+            kinds[rest - 1] = WitnessKind::ControlBlock;
+        }
+    }
+    kinds
+}
+
+/// Parser of witness item without any annotation.
+fn raw_witness_item(s: Span) -> Parsed<Vec<u8>> {
+    let (s, len) = varint(s)?;
+    let (s, b) = bytes(len)(s)?;
+    Ok((s, b.to_vec()))
+}
+
 /// Parses one witness stack, i. e. all witness items associated with one input.
+/// Annex, tapscript and control block are recognized, see [`witness_kinds`].
 pub fn witness_stack(vin: TxIn) -> impl Fn(Span) -> Parsed<Vec<Vec<u8>>> {
     move |s: Span| {
         let (s, cnt) = parse(varint, ann("Count", auto()))(s)?;
-        length_count(
-            success(cnt),
-            parse(witness_item(vin.clone()), ann("Witness Item", Value::Nil)),
-        )(s)
+        let (s, items) = peek(length_count(success(cnt), raw_witness_item))(s)?;
+        let s = witness_kinds(&items)
+            .into_iter()
+            .try_fold(s, |s, kind| {
+                let (s, _) = match kind {
+                    WitnessKind::Item => parse(witness_item(vin.clone()), ann("Witness Item", Value::Nil))(s)?,
+                    WitnessKind::Tapscript => parse(
+                        tapscript,
+                        ann("Tapscript", Value::Nil)
+                            .bip(342)
+                            .doc("Script of the spent leaf of Taproot script tree, executed with the preceding witness items as its initial stack."),
+                    )(s)
+                    .map(|(s, _)| (s, vec![]))?,
+                    WitnessKind::ControlBlock => parse(
+                        control_block,
+                        ann("Control Block", Value::Nil)
+                            .bip(341)
+                            .doc("Proof that the tapscript is a leaf of script tree committed to in output key of the spent output."),
+                    )(s)
+                    .map(|(s, _)| (s, vec![]))?,
+                    WitnessKind::Annex => parse(
+                        annex,
+                        ann("Annex", Value::Nil)
+                            .bip(341)
+                            .doc("Last witness item of Taproot input that starts with byte 0x50. It is reserved for future extensions and has no meaning under current consensus rules, although signatures commit to it. Transactions with annex are not standard, most nodes do not relay them."),
+                    )(s)?,
+                };
+                Ok(s)
+            })?;
+        Ok((s, items))
     }
 }
 
-/// Parses complete witness structure.
-fn witness_structure(vins: Vec<TxIn>) -> impl Fn(Span) -> Parsed<Vec<Vec<Vec<u8>>>> {
-    move |s: Span| {
-        s.insert(ann("Length", Value::Size(vins.len() as u64))); // FIXME: Does not render
-        let (s, w) = vins.iter().try_fold((s, vec![]), |(s, mut ws), vin| {
+/// Parses tapscript with its instructions annotated separately.
+fn tapscript(s: Span) -> Parsed<ScriptBuf> {
+    let (s, len) = parse(varint, ann("Length", auto()).unit(Unit::Bytes))(s)?;
+    let bm = s.bookmark();
+    let (s, script) = instructions(len as usize, opcodes::ClassifyContext::TapScript)(s)?;
+    s.insert_at(&bm, ann("Script", Value::Script(script.clone())));
+    Ok((s.with("datatype", "script"), script))
+}
+
+/// Parses control block of Taproot script path spend.
+fn control_block(s: Span) -> Parsed<()> {
+    let (s, len) = parse(varint, ann("Length", auto()).unit(Unit::Bytes))(s)?;
+    let (s, _) = parse(
+        be_u8,
+        ann("Leaf Version and Parity", |b: &u8| Value::bytes(vec![*b]))
+            .doc("Version of the spent leaf (upper 7 bits) and parity of Y coordinate of output key (the lowest bit).")
+            .splain(|b: &u8| {
+                format!(
+                    "Leaf version 0x{:02x}{}, Y coordinate of output key is {}.",
+                    b & taproot::TAPROOT_LEAF_MASK,
+                    if b & taproot::TAPROOT_LEAF_MASK == taproot::TAPROOT_LEAF_TAPSCRIPT {
+                        " (tapscript)"
+                    } else {
+                        ""
+                    },
+                    if b & 1 == 0 { "even" } else { "odd" }
+                )
+            }),
+    )(s)?;
+    let (s, _) = parse(
+        bytes(32usize),
+        ann("Internal Key", |b: &Bytes| Value::bytes(b.clone()))
+            .doc("X coordinate of internal key, which is tweaked by merkle root of script tree to get output key."),
+    )(s)?;
+    let (s, _) = parse(
+        length_count(
+            success((len.saturating_sub(33) / 32) as usize),
             parse(
-                with("list", "enumerate", witness_stack(vin.clone())),
-                ann("Witness Stack", Value::Nil),
-            )(s)
-            .map(|(s, w)| {
-                ws.push(w);
-                (s, ws)
-            })
-        })?;
-        s.insert(ann("Length 2", Value::Size(vins.len() as u64))); // FIXME: Does not render
+                bytes(32usize),
+                ann("Node", |b: &Bytes| Value::bytes(b.clone())),
+            ),
+        ),
+        ann("Merkle Path", Value::Nil)
+            .doc("Hashes of siblings on path from the spent leaf to root of script tree, empty if the tree has only one leaf."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+/// Parses annex of Taproot input.
+fn annex(s: Span) -> Parsed<Vec<u8>> {
+    let (s, len) = parse(varint, ann("Length", auto()).unit(Unit::Bytes))(s)?;
+    let (s, tag) = parse(
+        be_u8,
+        ann("Annex Tag", |t: &u8| Value::bytes(vec![*t]))
+            .doc("Byte 0x50, which distinguishes annex from other witness items."),
+    )(s)?;
+    let (s, data) = parse(
+        length_count(success(len - 1), be_u8),
+        ann("Annex Data", auto()).doc("Content of annex, currently without any meaning."),
+    )(s)?;
+    Ok((s, [vec![tag], data].concat()))
+}
+
+/// Parses complete witness structure. Every witness stack is linked to its input.
+fn witness_structure(
+    vins: Vec<(TxIn, Bookmark, Bookmark)>,
+) -> impl Fn(Span) -> Parsed<Vec<Vec<Vec<u8>>>> {
+    move |s: Span| {
+        let (s, w) = vins
+            .iter()
+            .try_fold((s, vec![]), |(s, mut ws), (vin, bookmark, _)| {
+                parse(
+                    with("list", "enumerate", witness_stack(vin.clone())),
+                    ann("Witness Stack", Value::Nil),
+                )(s)
+                .map(|(s, w)| {
+                    s.link(&s.bookmark(), bookmark, "Input");
+                    ws.push(w);
+                    (s, ws)
+                })
+            })?;
         Ok((s, w))
     }
 }
 
+/// Inserts size and weight of input or output of size `base` and with
+/// witness of size `witness` and its share of weight of the transaction.
+fn weight_share(s: &Span, bm: &Bookmark, tx: &Transaction, base: usize, witness: usize, of: &str) {
+    let weight = base * 4 + witness;
+    s.insert_at(
+        bm,
+        ann("Serialized Size", Value::num((base + witness) as u64))
+            .unit(Unit::Bytes)
+            .doc(format!(
+                "Number of bytes of this {of} in serialized transaction, including its witness."
+            )),
+    );
+    s.insert_at(
+        bm,
+        ann("Weight", Value::num(weight as u64))
+            .unit(Unit::WeightUnits)
+            .doc(format!("Weight units that this {of} contributes to weight of transaction. Non-witness bytes count four times, witness bytes once."))
+            .splain(if witness > 0 {
+                format!("{base} non-witness bytes × 4 + {witness} witness bytes = {weight} WU")
+            } else {
+                format!("{base} non-witness bytes × 4 = {weight} WU")
+            })
+            .bip(141),
+    );
+    s.insert_at(
+        bm,
+        ann(
+            "Share of Vsize",
+            Value::text(format!(
+                "{:.1} %",
+                weight as f64 * 100.0 / tx.weight().to_wu() as f64
+            )),
+        )
+        .doc(format!("Part of virtual size of transaction, and thus of its fee, that is caused by this {of}.")),
+    );
+}
+
 /// Parse Bitcoin transaction.
 pub fn tx(s: Span) -> Parsed<Transaction> {
     // let bm1 = s.bookmark();
     let (s, version) = parse(
         int32,
         ann("Transaction Version", auto())
-            .doc("Version number of transaction format indicating which set of rules should be used for validation. Currently only 1 and 2 are standard.")
+            .doc("Version number of transaction format indicating which set of rules should be used for validation. Currently 1, 2 and 3 are standard.")
             .splain(|v: &_| {
                 let s = if *v == 1 {
                     "Version 1 indicates original version without any additional features."
                 } else if *v == 2 {
                     "Version 2 allows to use OP_CHECKSEQUENCEVERIFY."
+                } else if *v == 3 {
+                    "Version 3 opts in to topologically restricted until confirmation (TRUC) policy."
                 } else {
                     "Non-standard version."
                 };
                 s.to_string()
             })
-            .bip(68),
+            .warn(|v: &i32| {
+                (!(1..=3).contains(v)).then(|| format!("Non-standard transaction version {v}"))
+            })
+            .bip(68)
+            .bip(431),
     )(s)?;
     let version = transaction::Version(version);
 
@@ -329,18 +565,30 @@ pub fn tx(s: Span) -> Parsed<Transaction> {
                     } else {
                         format!("Flag {} is non-standard.", f)
                     }
+                })
+                .warn(|f: &u8| {
+                    (*f != 1).then(|| format!("Unknown flag {f}, the transaction is invalid"))
                 }),
         )(s)?
     } else {
         (s, 0)
     };
     let bm2 = s.bookmark();
-    let (s, mut vin) = parse(tx_ins, ann("Input List", Value::Nil))(s)?;
-    let (s, vout) = parse(tx_outs, ann("Output List", Value::Nil))(s)?;
+    let (s, bookmarked_vin) = parse(tx_ins, ann("Input List", Value::Nil))(s)?;
+    let (s, bookmarked_vout) = parse(tx_outs, ann("Output List", Value::Nil))(s)?;
+    let mut vin = bookmarked_vin
+        .iter()
+        .map(|(vin, _, _)| vin.clone())
+        .collect::<Vec<_>>();
+    let (vout, vout_bookmarks): (Vec<_>, Vec<_>) = bookmarked_vout.into_iter().unzip();
+    let vin_bookmarks = bookmarked_vin
+        .iter()
+        .map(|(_, _, last)| *last)
+        .collect::<Vec<_>>();
 
     let (s, witnesses) = if flag == 1 {
         parse(
-            witness_structure(vin.clone()),
+            witness_structure(bookmarked_vin.clone()),
             ann("Witness Structure", Value::Nil),
         )(s)?
     } else {
@@ -356,6 +604,7 @@ pub fn tx(s: Span) -> Parsed<Transaction> {
     let (s, locktime) = parse(
         uint32,
         ann("Lock Time", auto())
+            .category(Category::Locktime)
             .doc("Earliest time the transaction can be mined in to a block.")
             .splain(|cons: &u32| {
                 if *cons == 0 {
@@ -395,9 +644,28 @@ pub fn tx(s: Span) -> Parsed<Transaction> {
         ann("Wtxid", Value::Hash(tx.compute_txid().to_raw_hash()))
             .doc("Segwit-aware ID of this transaction."),
     );
-    s.insert_at(&bm2, ann("Size", Value::Size(tx.base_size() as u64)));
-    s.insert_at(&bm2, ann("Vsize", Value::Size(tx.vsize() as u64)));
-    s.insert_at(&bm2, ann("Weight", Value::Size(tx.weight().to_wu())));
+    tx.input.iter().zip(&vin_bookmarks).for_each(|(txin, bm)| {
+        // Empty witness of legacy input still takes one byte in segwit transaction.
+        let witness = if flag == 1 { txin.witness.size() } else { 0 };
+        weight_share(&s, bm, &tx, txin.base_size(), witness, "input");
+    });
+    tx.output
+        .iter()
+        .zip(&vout_bookmarks)
+        .for_each(|(txout, bm)| weight_share(&s, bm, &tx, txout.size(), 0, "output"));
+
+    s.insert_at(
+        &bm2,
+        ann("Size", Value::num(tx.base_size() as u64)).unit(Unit::Bytes),
+    );
+    s.insert_at(
+        &bm2,
+        ann("Vsize", Value::num(tx.vsize() as u64)).unit(Unit::Vbytes),
+    );
+    s.insert_at(
+        &bm2,
+        ann("Weight", Value::num(tx.weight().to_wu())).unit(Unit::WeightUnits),
+    );
     s.insert_at(
         &bm2,
         ann("Total amount", Value::Sat(Sat::new(total)))
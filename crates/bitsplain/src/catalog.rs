@@ -0,0 +1,179 @@
+//! Catalog of documentation of annotations. Documentation and splains
+//! written in parsers serve as defaults, catalog can override them
+//! (e. g. to translate or improve them) without recompiling.
+//!
+//! Entries of catalog are keyed by stable IDs of annotations, see [`ids`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tree::{Information, Leaf, Node, Tree};
+
+/// Documentation of one annotation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Documentation string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+
+    /// Splain string. Occurrences of `{value}` are replaced by value
+    /// of the annotated node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub splain: Option<String>,
+}
+
+/// Entries of documentation keyed by IDs of annotations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Catalog {
+    pub entries: BTreeMap<String, Entry>,
+}
+
+impl Catalog {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entry of annotation with the ID.
+    pub fn get(&self, id: &str) -> Option<&Entry> {
+        self.entries.get(id)
+    }
+
+    /// Catalog of documentation of all annotations of the tree, as it is
+    /// written in parsers. Annotations without documentation are included
+    /// too, so the catalog can be used as template for a new one.
+    pub fn of(tree: &Tree, prefix: &str) -> Catalog {
+        let mut entries = BTreeMap::new();
+        walk(tree, prefix, &mut |id, information| {
+            entries.entry(id.to_string()).or_insert_with(|| Entry {
+                doc: information.doc.clone(),
+                splain: None,
+            });
+        });
+        Catalog { entries }
+    }
+}
+
+/// IDs of annotations of sibling nodes whose parent has ID `parent`.
+///
+/// ID of root is symbol of decoder (`<group>/<symbol>`). Every annotation
+/// then extends ID of its parent by its own ID, which is set by
+/// [`Ann::id`](crate::dsl::Ann::id) or, if there is none, derived from
+/// label in lowercase, with all characters other than letters and digits
+/// replaced by `_`. Items of enumerated lists are written as `*`, so all
+/// of them share one ID. Other siblings with the same ID are told apart
+/// by suffixes `~2`, `~3` and so on. For example `btc/tx:input_list/*/sequence`.
+pub fn ids(parent: &str, nodes: &[Node]) -> Vec<String> {
+    let separator = if parent.contains(':') { '/' } else { ':' };
+    let mut seen = HashMap::<String, usize>::new();
+    nodes
+        .iter()
+        .map(|node| {
+            let information = node.information();
+            if information.has_data("list", "enumerate") {
+                return format!("{parent}{separator}*");
+            }
+            let segment = match information.data.get("id") {
+                Some(id) => id.clone(),
+                None => slug(&information.label),
+            };
+            let count = seen.entry(segment.clone()).or_default();
+            *count += 1;
+            match count {
+                1 => format!("{parent}{separator}{segment}"),
+                n => format!("{parent}{separator}{segment}~{n}"),
+            }
+        })
+        .collect()
+}
+
+fn slug(label: &str) -> String {
+    let words = label
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    words.join("_")
+}
+
+/// Calls `f` on every node with ID of its annotation, parents before children.
+fn walk(nodes: &[Node], parent: &str, f: &mut impl FnMut(&str, &Information)) {
+    for (node, id) in nodes.iter().zip(ids(parent, nodes)) {
+        f(&id, node.information());
+        if let Node::Group { children, .. } = node {
+            walk(children, &id, f);
+        }
+    }
+}
+
+/// Replaces documentation and splains of nodes by entries of catalog.
+pub(crate) fn apply(nodes: &mut [Node], parent: &str, catalog: &Catalog) {
+    let ids = ids(parent, nodes);
+    for (node, id) in nodes.iter_mut().zip(ids) {
+        let (information, children) = match node {
+            Node::Group {
+                information,
+                children,
+                ..
+            } => (information, Some(children)),
+            Node::Leaf(Leaf::Real(l)) => (&mut l.information, None),
+            Node::Leaf(Leaf::Virtual(l)) => (&mut l.information, None),
+        };
+
+        if let Some(entry) = catalog.get(&id) {
+            if let Some(doc) = &entry.doc {
+                information.doc = Some(doc.clone());
+            }
+            if let Some(splain) = &entry.splain {
+                information.splain = Some(splain.replace("{value}", &information.value.plain()));
+            }
+        }
+
+        if let Some(children) = children {
+            apply(children, &id, catalog);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::VirtualLeaf;
+    use crate::value::Value;
+
+    fn leaf(label: &str, id: Option<&str>) -> Node {
+        let mut information = Information::derived(label, Value::Nil);
+        if let Some(id) = id {
+            information.data.insert("id".into(), id.to_string());
+        }
+        Node::Leaf(Leaf::Virtual(VirtualLeaf {
+            path: vec![],
+            information,
+        }))
+    }
+
+    #[test]
+    fn sibling_ids() {
+        let nodes = [
+            leaf("Public key", None),
+            leaf("Public Key", None),
+            leaf("Fee paid", Some("fee")),
+            leaf("Public key", None),
+        ];
+        assert_eq!(
+            ids("ln/node_ann", &nodes),
+            [
+                "ln/node_ann:public_key",
+                "ln/node_ann:public_key~2",
+                "ln/node_ann:fee",
+                "ln/node_ann:public_key~3"
+            ]
+        );
+        assert_eq!(
+            ids("ln/node_ann:signature", &nodes[2..3]),
+            ["ln/node_ann:signature/fee"]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Descriptors of datatypes of leaves, i. e. how values are encoded in
+//! their bytes. Parsers name datatype of leaf in data field `datatype`
+//! (see [`with`](crate::parse::with)), descriptors are found by the name.
+
+use std::fmt::Display;
+
+/// Order of bytes of numbers and hashes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// Type of data regardless of its encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base {
+    /// Unsigned integer of given number of bytes.
+    Uint(usize),
+    /// Signed integer of given number of bytes.
+    Int(usize),
+    /// Integer of variable length.
+    VarInt,
+    /// Byte array, optionally of fixed length.
+    Bytes(Option<usize>),
+    /// Text.
+    Text,
+}
+
+/// Descriptor of datatype.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Datatype {
+    /// Name of the datatype used in data field `datatype`.
+    pub name: &'static str,
+    pub base: Base,
+    pub endianness: Option<Endianness>,
+    /// Encoding or meaning of the data, e. g. `CompactSize` or `UTF-8`.
+    pub encoding: Option<&'static str>,
+}
+
+impl Datatype {
+    const fn new(name: &'static str, base: Base) -> Datatype {
+        Datatype {
+            name,
+            base,
+            endianness: None,
+            encoding: None,
+        }
+    }
+
+    const fn le(self) -> Datatype {
+        Datatype {
+            endianness: Some(Endianness::Little),
+            ..self
+        }
+    }
+
+    const fn be(self) -> Datatype {
+        Datatype {
+            endianness: Some(Endianness::Big),
+            ..self
+        }
+    }
+
+    const fn encoding(self, encoding: &'static str) -> Datatype {
+        Datatype {
+            encoding: Some(encoding),
+            ..self
+        }
+    }
+
+    /// Descriptor of datatype of given name.
+    pub fn from_name(name: &str) -> Option<&'static Datatype> {
+        DATATYPES.iter().find(|d| d.name == name)
+    }
+}
+
+/// Known datatypes.
+static DATATYPES: &[Datatype] = &[
+    Datatype::new("bytes", Base::Bytes(None)),
+    Datatype::new("script", Base::Bytes(None)).encoding("Bitcoin script"),
+    Datatype::new("push", Base::Bytes(None)).encoding("script push"),
+    Datatype::new("opcode", Base::Uint(1)).encoding("opcode"),
+    Datatype::new("public_key", Base::Bytes(Some(33))).encoding("compressed public key"),
+    Datatype::new("node_id", Base::Bytes(Some(33))).encoding("compressed public key"),
+    Datatype::new("signature", Base::Bytes(Some(64))).encoding("compact ECDSA signature"),
+    Datatype::new("chain_hash", Base::Bytes(Some(32)))
+        .le()
+        .encoding("block hash"),
+    Datatype::new("chain_hash_be", Base::Bytes(Some(32)))
+        .be()
+        .encoding("block hash"),
+    Datatype::new("txid", Base::Bytes(Some(32)))
+        .le()
+        .encoding("SHA-256d"),
+    Datatype::new("sha256", Base::Bytes(Some(32))).encoding("SHA-256"),
+    Datatype::new("uint32", Base::Uint(4)).le(),
+    Datatype::new("uint32_be", Base::Uint(4)).be(),
+    Datatype::new("int32", Base::Int(4)).le(),
+    Datatype::new("int32_be", Base::Int(4)).be(),
+    Datatype::new("sat", Base::Uint(8))
+        .le()
+        .encoding("satoshis"),
+    Datatype::new("timestamp", Base::Uint(4))
+        .le()
+        .encoding("Unix time"),
+    Datatype::new("tu64", Base::Uint(8))
+        .be()
+        .encoding("truncated, without leading zeros"),
+    Datatype::new("varint", Base::VarInt)
+        .le()
+        .encoding("CompactSize"),
+    Datatype::new("uvarint", Base::VarInt)
+        .le()
+        .encoding("LEB128"),
+    Datatype::new("string", Base::Text).encoding("UTF-8"),
+];
+
+impl Display for Base {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Base::Uint(n) => write!(f, "uint{}", n * 8),
+            Base::Int(n) => write!(f, "int{}", n * 8),
+            Base::VarInt => write!(f, "varint"),
+            Base::Bytes(Some(n)) => write!(f, "bytes[{n}]"),
+            Base::Bytes(None) => write!(f, "bytes"),
+            Base::Text => write!(f, "text"),
+        }
+    }
+}
+
+/// Datatype is shown as its base type followed by its endianness and
+/// encoding, e. g. `uint32 (LE)` or `varint (LE, CompactSize)`.
+impl Display for Datatype {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let endianness = self.endianness.map(|e| match e {
+            Endianness::Little => "LE",
+            Endianness::Big => "BE",
+        });
+        let details = [endianness, self.encoding]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        if details.is_empty() {
+            write!(f, "{}", self.base)
+        } else {
+            write!(f, "{} ({})", self.base, details.join(", "))
+        }
+    }
+}
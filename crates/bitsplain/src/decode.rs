@@ -1,9 +1,19 @@
 //! Core types and functions related to decoding of binary data.
 
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::RwLock;
+
 use bytes::Bytes;
+use serde::{Deserialize, Serialize};
 
 use crate::binary::*;
-use crate::tree::Tree;
+use crate::parse::{Annotated, Budget};
+use crate::tree::{
+    GroupLocation, Information, Leaf, LeafLocation, Node, RealLeaf, Tree, VirtualLeaf, Warning,
+};
+use crate::types::Sat;
+use crate::value::Value;
 
 /// Description of a function that can decode data.
 ///
@@ -22,8 +32,8 @@ pub struct Decoder {
     /// Simple identifier of the decoder.
     pub symbol: &'static str,
 
-    /// Decoding function.
-    pub decode: fn(&Binary) -> Option<Tree>,
+    /// Decoding function, which annotates data in detail within the budget.
+    pub decode: fn(&Binary, &Budget) -> Result<Tree, DecodeError>,
 }
 
 impl std::fmt::Debug for Decoder {
@@ -37,11 +47,122 @@ impl std::fmt::Debug for Decoder {
 // So instances of Decoder struct can be registered and used.
 inventory::collect!(Decoder);
 
+/// Decoders registered during program's execution, e. g. from plugins.
+static REGISTERED: RwLock<Vec<&'static Decoder>> = RwLock::new(Vec::new());
+
 /// List of all known decoders.
 pub fn all_decoders() -> Vec<&'static Decoder> {
-    inventory::iter::<Decoder>().collect()
+    let registered = REGISTERED.read().unwrap_or_else(|e| e.into_inner());
+    inventory::iter::<Decoder>()
+        .chain(registered.iter().copied())
+        .collect()
+}
+
+/// Registers decoders during program's execution. Statically known
+/// decoders are registered using macro [`decoder!`](crate::decoder).
+pub fn register_decoders(decoders: &[&'static Decoder]) {
+    REGISTERED
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .extend_from_slice(decoders);
+}
+
+/// User's preferences of decoders, part of [`DecodeOptions`].
+#[derive(Clone, Debug, Default)]
+pub struct Preferences {
+    /// Decoders that are not used unless a filter selects them explicitly
+    /// (e. g. the permissive `btc/script`).
+    pub disabled: Vec<DecoderFilter>,
+
+    /// Decoders whose candidates precede candidates of other decoders,
+    /// in this order.
+    pub order: Vec<DecoderFilter>,
+}
+
+/// Decoders selected by filter of the options, without disabled ones
+/// (unless the filter selects only some decoders) and ordered according
+/// to preferences.
+fn preferred_decoders(options: &DecodeOptions) -> Vec<&'static Decoder> {
+    let p = &options.preferences;
+    let mut decoders = options.filter.decoders();
+    if matches!(options.filter, DecoderFilter::All) {
+        decoders.retain(|d| !p.disabled.iter().any(|f| f.matches(d)));
+    }
+    decoders.sort_by_key(|d| {
+        p.order
+            .iter()
+            .position(|f| f.matches(d))
+            .unwrap_or(p.order.len())
+    });
+    decoders
+}
+
+/// Reason why a decoder did not decode data.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The decoder does not accept this kind of data.
+    Mismatch,
+
+    /// Parsing of the data failed.
+    Failed {
+        /// Offset of the first byte of the field that could not be read.
+        offset: usize,
+
+        /// Label of the field that could not be read, if known.
+        label: Option<String>,
+
+        /// Datatype of the field that could not be read, if known.
+        datatype: Option<&'static str>,
+
+        /// Annotations of the data parsed before the failure.
+        partial: Tree,
+    },
+
+    /// The data were parsed, however not all of them were consumed.
+    Trailing {
+        /// Offset of the first byte that was not consumed.
+        offset: usize,
+
+        /// Annotations of the parsed data.
+        partial: Tree,
+    },
+}
+
+impl DecodeError {
+    /// Annotations of data parsed before the decoding stopped, if any.
+    pub fn partial(&self) -> Option<&Tree> {
+        match self {
+            DecodeError::Mismatch => None,
+            DecodeError::Failed { partial, .. } => Some(partial),
+            DecodeError::Trailing { partial, .. } => Some(partial),
+        }
+    }
 }
 
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Mismatch => write!(f, "data not accepted by decoder"),
+            DecodeError::Failed {
+                offset,
+                label,
+                datatype,
+                ..
+            } => match (datatype, label) {
+                (Some(d), Some(l)) => write!(f, "failed at byte {offset} while reading {d} ({l})"),
+                (Some(d), None) => write!(f, "failed at byte {offset} while reading {d}"),
+                (None, Some(l)) => write!(f, "failed at byte {offset} while reading {l}"),
+                (None, None) => write!(f, "failed at byte {offset}"),
+            },
+            DecodeError::Trailing { offset, .. } => {
+                write!(f, "unexpected data after byte {offset}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
 /// Input from user.
 #[derive(Clone, Debug)]
 pub enum Input {
@@ -58,7 +179,7 @@ pub enum Input {
 /// Having a candidate does not necessarily mean that we know the
 /// exact meaning of the input, only that it could be successfully
 /// parsed using the given decoder.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Candidate {
     /// Decoder that successully parsed the data.
     pub decoder: &'static Decoder,
@@ -68,6 +189,168 @@ pub struct Candidate {
 
     /// Original binary input.
     pub data: Binary,
+
+    /// Whether the decoder did not consume all the data. If so, the
+    /// remaining bytes are covered by leaf 'Unparsed remainder'.
+    pub partial: bool,
+}
+
+impl Candidate {
+    /// Creates serializable summary of the candidate.
+    pub fn summary(&self) -> CandidateSummary {
+        CandidateSummary {
+            title: self.decoder.title.to_string(),
+            group: self.decoder.group.to_string(),
+            symbol: self.decoder.symbol.to_string(),
+            data: Bytes::copy_from_slice(&self.data),
+            annotations: self.annotations.clone(),
+            partial: self.partial,
+        }
+    }
+
+    /// Candidate of the same decoder decoded from other bytes of the same
+    /// origin, e. g. after a value was changed (see [`encode`](crate::encode)).
+    pub fn with_bytes(&self, bytes: Vec<u8>, budget: &Budget) -> Result<Candidate, DecodeError> {
+        let data = self.data.with_bytes(bytes);
+        let annotations = (self.decoder.decode)(data.payload(), budget)?;
+        Ok(Candidate {
+            decoder: self.decoder,
+            annotations,
+            data,
+            partial: false,
+        })
+    }
+}
+
+/// Candidate detached from its decoder, so it can be persisted and reloaded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CandidateSummary {
+    /// Title of decoder that parsed the data.
+    pub title: String,
+
+    /// Group of decoder that parsed the data.
+    pub group: String,
+
+    /// Symbol of decoder that parsed the data.
+    pub symbol: String,
+
+    /// Original binary input.
+    #[serde(with = "crate::serialize::hex_bytes")]
+    pub data: Bytes,
+
+    /// Annotations of the parsed data.
+    pub annotations: Tree,
+
+    /// Whether the decoder did not consume all the data.
+    #[serde(default)]
+    pub partial: bool,
+}
+
+/// Options of decoding.
+#[derive(Clone, Debug, Default)]
+pub struct DecodeOptions {
+    /// Accept candidates whose decoders did not consume all the data.
+    /// Such candidates are marked as partial and follow the complete ones.
+    pub allow_trailing: bool,
+
+    /// Decoders to be used.
+    pub filter: DecoderFilter,
+
+    /// Limits of detailed annotation.
+    pub budget: Budget,
+
+    /// Disabled and preferred decoders.
+    pub preferences: Preferences,
+}
+
+/// Selection of decoders to be used for decoding.
+///
+/// Filter can be parsed from a comma-separated list of patterns `group/symbol`,
+/// in which symbol can be `*` or omitted to select whole group (e. g. `btc/tx,ln/*`).
+#[derive(Clone, Debug, Default)]
+pub enum DecoderFilter {
+    /// All decoders.
+    #[default]
+    All,
+
+    /// Decoders belonging to the group.
+    Group(String),
+
+    /// Decoder with the group and symbol.
+    Symbol { group: String, symbol: String },
+
+    /// Decoders satisfying the predicate.
+    Predicate(fn(&Decoder) -> bool),
+
+    /// Decoders selected by any of the filters.
+    Any(Vec<DecoderFilter>),
+}
+
+impl DecoderFilter {
+    /// Whether the decoder is selected by this filter.
+    pub fn matches(&self, decoder: &Decoder) -> bool {
+        match self {
+            DecoderFilter::All => true,
+            DecoderFilter::Group(group) => decoder.group == group,
+            DecoderFilter::Symbol { group, symbol } => {
+                decoder.group == group && decoder.symbol == symbol
+            }
+            DecoderFilter::Predicate(p) => p(decoder),
+            DecoderFilter::Any(filters) => filters.iter().any(|f| f.matches(decoder)),
+        }
+    }
+
+    /// Decoders selected by this filter.
+    pub fn decoders(&self) -> Vec<&'static Decoder> {
+        all_decoders()
+            .into_iter()
+            .filter(|d| self.matches(d))
+            .collect()
+    }
+}
+
+impl FromStr for DecoderFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let filters = s
+            .split(',')
+            .map(str::trim)
+            .map(|p| match p.split_once('/') {
+                _ if p == "*" => Ok(DecoderFilter::All),
+                None if !p.is_empty() => Ok(DecoderFilter::Group(p.to_string())),
+                Some(("*", "*")) => Ok(DecoderFilter::All),
+                Some((group, "*")) if !group.is_empty() => {
+                    Ok(DecoderFilter::Group(group.to_string()))
+                }
+                Some((group, symbol)) if !group.is_empty() && !symbol.is_empty() => {
+                    Ok(DecoderFilter::Symbol {
+                        group: group.to_string(),
+                        symbol: symbol.to_string(),
+                    })
+                }
+                _ => Err(format!("invalid decoder pattern '{p}'")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match filters.len() {
+            1 => Ok(filters.into_iter().next().unwrap_or_default()),
+            _ => Ok(DecoderFilter::Any(filters)),
+        }
+    }
+}
+
+/// Unsuccessful attempt of a decoder to decode binary data.
+#[derive(Debug)]
+pub struct Failure {
+    /// Decoder that failed.
+    pub decoder: &'static Decoder,
+
+    /// Reason of the failure.
+    pub error: DecodeError,
+
+    /// Binary input that could not be decoded.
+    pub data: Binary,
 }
 
 /// Attempt to decode input with the best effort.
@@ -76,26 +359,527 @@ pub fn decode_input(input: Input) -> Vec<Candidate> {
     decode_binaries(input_to_binaries(input))
 }
 
+/// Attempt to decode input using only decoders selected by the filter.
+pub fn decode_input_with(input: Input, filter: DecoderFilter) -> Vec<Candidate> {
+    let options = DecodeOptions {
+        filter,
+        ..Default::default()
+    };
+    try_decode_binaries(input_to_binaries(input), &options).0
+}
+
 /// Attempt to decode given binaries.
 pub fn decode_binaries(binaries: Vec<Binary>) -> Vec<Candidate> {
-    binaries
-        .iter()
-        .flat_map(|b| {
-            all_decoders().into_iter().map(|d| {
-                (d.decode)(b).map(|a| Candidate {
+    try_decode_binaries(binaries, &DecodeOptions::default()).0
+}
+
+/// Attempt to decode input with the best effort and return
+/// both successful candidates and failures of all decoders.
+pub fn try_decode_input(input: Input, options: &DecodeOptions) -> (Vec<Candidate>, Vec<Failure>) {
+    try_decode_binaries(input_to_binaries(input), options)
+}
+
+/// Attempt to decode given binaries and return both successful
+/// candidates and failures of all decoders.
+pub fn try_decode_binaries(
+    binaries: Vec<Binary>,
+    options: &DecodeOptions,
+) -> (Vec<Candidate>, Vec<Failure>) {
+    let mut candidates = vec![];
+    let mut partials = vec![];
+    let mut failures = vec![];
+
+    let decoders = preferred_decoders(options);
+
+    binaries.iter().enumerate().for_each(|(i, b)| {
+        decoders
+            .iter()
+            .for_each(|&d| match (d.decode)(b.payload(), &options.budget) {
+                Ok(annotations) => candidates.push(Candidate {
                     decoder: d,
-                    annotations: a,
+                    annotations: with_uri(
+                        with_descriptor(
+                            with_encoding(with_embedded(annotations, b, options), b),
+                            b,
+                        ),
+                        b,
+                    ),
                     data: b.clone(),
-                })
+                    partial: false,
+                }),
+                Err(DecodeError::Trailing { offset, partial })
+                    if options.allow_trailing && offset > 0 =>
+                {
+                    partials.push((
+                        (i, std::cmp::Reverse(offset)),
+                        Candidate {
+                            decoder: d,
+                            annotations: with_uri(
+                                with_descriptor(
+                                    with_encoding(
+                                        with_remainder(
+                                            with_embedded(partial, b, options),
+                                            b,
+                                            offset,
+                                        ),
+                                        b,
+                                    ),
+                                    b,
+                                ),
+                                b,
+                            ),
+                            data: b.clone(),
+                            partial: true,
+                        },
+                    ))
+                }
+                Err(error) => failures.push(Failure {
+                    decoder: d,
+                    error,
+                    data: b.clone(),
+                }),
             })
-        })
-        .flatten()
-        .collect()
+    });
+
+    // Binaries keep their order of likelihood, within each of them
+    // candidates that consumed more data are more likely.
+    partials.sort_by_key(|(key, _)| *key);
+    candidates.extend(partials.into_iter().map(|(_, c)| c));
+
+    (candidates, failures)
+}
+
+/// Appends virtual leaves explaining structure of encoding from which
+/// the data were obtained, i. e. human-readable part and checksum of
+/// Bech32 and checksum of Base58Check.
+fn with_encoding(tree: Tree, binary: &Binary) -> Tree {
+    let mut nodes = tree.into_nodes();
+    let mut push = |label: &str, value: Value, doc: &str, splain: &str| {
+        nodes.push(Node::Leaf(Leaf::Virtual(VirtualLeaf {
+            path: vec![nodes.len().to_string()],
+            information: Information {
+                data: HashMap::from([("encoding".into(), binary.encoding().to_string())]),
+                doc: Some(doc.to_string()),
+                splain: Some(splain.to_string()),
+                ..Information::derived(label, value)
+            },
+        })))
+    };
+
+    match binary.payload() {
+        Binary::Bech32(hrp, _, checksum) => {
+            push(
+                "Human-readable part",
+                Value::text(hrp),
+                "Part of Bech32 string before the last separator '1', which tells what kind of data the string encodes. It is not part of the decoded data.",
+                &format!("Data were decoded from Bech32 string starting with {hrp}1."),
+            );
+            let (value, splain) = match checksum {
+                Bech32Checksum::Bech32(c) => (
+                    Value::text(c),
+                    "Checksum is valid Bech32 checksum (BIP-173) of the string.",
+                ),
+                Bech32Checksum::Bech32m(c) => (
+                    Value::text(c),
+                    "Checksum is valid Bech32m checksum (BIP-350) of the string.",
+                ),
+                Bech32Checksum::None => (
+                    Value::Nil,
+                    "String does not have any checksum (as in BOLT 12), errors in it cannot be detected.",
+                ),
+            };
+            push(
+                "Checksum",
+                value,
+                "Last six characters of Bech32 string, which allow to detect errors in the string. It is not part of the decoded data.",
+                splain,
+            );
+        }
+        Binary::Base58Check(_) => {
+            if let Some(checksum) = binary.base58_checksum() {
+                push(
+                    "Checksum",
+                    Value::bytes(checksum.to_vec()),
+                    "Last four bytes of Base58Check data, first four bytes of double SHA-256 of the rest. They allow to detect errors in the string and are not part of the decoded data.",
+                    "Checksum is valid, it matches double SHA-256 of the data.",
+                );
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(path) = binary.json_path() {
+        push(
+            "JSON field",
+            Value::text(path),
+            "Path of string field of JSON document in which the data were found. The rest of the document is not decoded.",
+            &format!("Data were taken from field {path} of JSON document."),
+        );
+    }
+
+    if let Some((compression, compressed)) = binary.compression() {
+        push(
+            "Compression",
+            Value::text(compression.name()),
+            "Format in which the data were compressed. They were decompressed before decoding, the compression is not part of the decoded data.",
+            &format!(
+                "Data were decompressed from {} bytes of {}.",
+                compressed.len(),
+                compression.name()
+            ),
+        );
+    }
+
+    Tree::from_nodes(nodes)
+}
+
+/// Appends virtual leaves with scheme and parameters of URI from which
+/// the data were taken, if any.
+fn with_uri(tree: Tree, binary: &Binary) -> Tree {
+    let Some(uri) = binary.uri() else {
+        return tree;
+    };
+
+    let mut nodes = tree.into_nodes();
+    let mut push =
+        |label: &str, value: Value, doc: &str, splain: Option<String>, warning: Option<&str>| {
+            nodes.push(Node::Leaf(Leaf::Virtual(VirtualLeaf {
+                path: vec![nodes.len().to_string()],
+                information: Information {
+                    data: HashMap::from([("encoding".into(), "uri".to_string())]),
+                    doc: Some(doc.to_string()),
+                    splain,
+                    warnings: warning
+                        .map(|m| Warning {
+                            message: m.to_string(),
+                        })
+                        .into_iter()
+                        .collect(),
+                    ..Information::derived(label, value)
+                },
+            })))
+        };
+
+    let scheme = format!("{}:", uri.scheme);
+    let splain = match &uri.source {
+        Some(param) => format!("Data were taken from parameter '{param}' of {scheme} URI."),
+        None => format!("Data were taken from {scheme} URI."),
+    };
+    push(
+        "URI scheme",
+        Value::text(&scheme),
+        "Scheme of URI from which the data were taken, it tells which application should handle the URI. It is not part of the decoded data.",
+        Some(splain),
+        None,
+    );
+
+    if uri.source.is_some() && !uri.path.is_empty() {
+        match uri.scheme.as_str() {
+            "bitcoin" => push(
+                "Address",
+                uri.path
+                    .parse::<bitcoin::Address<_>>()
+                    .map(|a| Value::Addr(Some(a.assume_checked())))
+                    .unwrap_or_else(|_| Value::text(&uri.path)),
+                "Bitcoin address to which the payment can be made on-chain, if the Lightning payment is not possible.",
+                None,
+                None,
+            ),
+            _ => push(
+                "URI path",
+                Value::text(&uri.path),
+                "Part of the URI between its scheme and parameters.",
+                None,
+                None,
+            ),
+        }
+    }
+
+    uri.params
+        .iter()
+        .filter(|(k, _)| Some(k) != uri.source.as_ref())
+        // Keys are case-insensitive, e. g. in URIs uppercased for QR codes.
+        .for_each(|(k, v)| match k.to_lowercase().as_str() {
+            "amount" => push(
+                "Amount",
+                bitcoin::Amount::from_str_in(v, bitcoin::Denomination::Bitcoin)
+                    .map(|a| Value::Sat(Sat::new(a.to_sat().into())))
+                    .unwrap_or_else(|_| Value::text(v)),
+                "Amount to be paid, in bitcoins (BIP-21).",
+                None,
+                None,
+            ),
+            "label" => push(
+                "Label",
+                Value::text(v),
+                "Label of the recipient (BIP-21).",
+                None,
+                None,
+            ),
+            "message" => push(
+                "Message",
+                Value::text(v),
+                "Message describing the payment (BIP-21).",
+                None,
+                None,
+            ),
+            "lightning" => push(
+                "Lightning invoice",
+                Value::text(v),
+                "BOLT 11 invoice, which can be paid instead of the on-chain payment.",
+                None,
+                None,
+            ),
+            "lno" => push(
+                "Lightning offer",
+                Value::text(v),
+                "BOLT 12 offer, which can be paid instead of the on-chain payment (BIP-321).",
+                None,
+                None,
+            ),
+            k if k.starts_with("req-") => push(
+                k,
+                Value::text(v),
+                "Parameter of the URI that is required to be understood (BIP-21).",
+                None,
+                Some("Required parameter is not known, wallets would reject the URI."),
+            ),
+            k => push(k, Value::text(v), "Parameter of the URI.", None, None),
+        });
+
+    Tree::from_nodes(nodes)
+}
+
+/// Appends virtual leaves with parts of output descriptor from which
+/// the key was taken, if any.
+fn with_descriptor(tree: Tree, binary: &Binary) -> Tree {
+    let Some(descriptor) = binary.descriptor() else {
+        return tree;
+    };
+
+    let mut nodes = tree.into_nodes();
+    let mut push = |label: &str, value: Value, doc: &str, warning: Option<&str>| {
+        nodes.push(Node::Leaf(Leaf::Virtual(VirtualLeaf {
+            path: vec![nodes.len().to_string()],
+            information: Information {
+                data: HashMap::from([("encoding".into(), "descriptor".to_string())]),
+                doc: Some(doc.to_string()),
+                warnings: warning
+                    .map(|m| Warning {
+                        message: m.to_string(),
+                    })
+                    .into_iter()
+                    .collect(),
+                ..Information::derived(label, value)
+            },
+        })))
+    };
+
+    push(
+        "Descriptor script",
+        Value::text(&descriptor.script),
+        "Script expression of output descriptor (BIP-380) from which the key was taken, it tells type of addresses derived from the key. It is not part of the decoded data.",
+        None,
+    );
+    if let Some(origin) = &descriptor.origin {
+        push(
+            "Key origin",
+            Value::text(origin),
+            "Fingerprint of master key and path by which the key was derived from it.",
+            None,
+        );
+    }
+    if !descriptor.path.is_empty() {
+        push(
+            "Derivation path",
+            Value::text(descriptor.path.join("/")),
+            "Path relative to the key, at which keys of addresses are derived. Step <a;b> stands for several branches (BIP-389), * for all children.",
+            None,
+        );
+    }
+    if let Some((checksum, valid)) = &descriptor.checksum {
+        push(
+            "Descriptor checksum",
+            Value::text(checksum),
+            "Checksum of the descriptor, which detects errors in it.",
+            (!valid).then_some("Checksum does not match the descriptor"),
+        );
+    }
+
+    Tree::from_nodes(nodes)
+}
+
+/// Decodes payloads embedded in data (see [`embedded`](crate::parse::embedded))
+/// and attaches annotations of the best candidate of each of them as children
+/// of annotation of the payload. Payloads that cannot be decoded completely
+/// remain as they are.
+fn with_embedded(tree: Tree, data: &[u8], options: &DecodeOptions) -> Tree {
+    let mut nodes = tree.into_nodes();
+    let embedded = nodes
+        .iter_mut()
+        .fold(false, |embedded, n| embed(n, data, options) | embedded);
+
+    // Nested annotations have leaves of their own, which shift indices
+    // of all following leaves.
+    if embedded {
+        Annotated::<&[u8]>::inject_indices(&mut nodes, &mut 0);
+    }
+
+    Tree::from_nodes(nodes)
+}
+
+/// Replaces leaf of embedded payload by group of its annotations. Payload is
+/// decoded with the same options as the data, except for selection of decoders.
+fn embed(node: &mut Node, data: &[u8], options: &DecodeOptions) -> bool {
+    match node {
+        Node::Group { children, .. } => children
+            .iter_mut()
+            .fold(false, |embedded, ch| embed(ch, data, options) | embedded),
+        Node::Leaf(Leaf::Real(leaf)) => {
+            let Some(filter) = leaf
+                .information
+                .data
+                .get("embedded")
+                .and_then(|f| f.parse::<DecoderFilter>().ok())
+            else {
+                return false;
+            };
+            let payload = Binary::Raw(Bytes::copy_from_slice(&data[leaf.location.range()]));
+            let options = DecodeOptions {
+                filter,
+                allow_trailing: false,
+                budget: options
+                    .budget
+                    .embedded(leaf.path.len(), leaf.location.range()),
+                ..options.clone()
+            };
+            match try_decode_binaries(vec![payload], &options)
+                .0
+                .into_iter()
+                .next()
+            {
+                Some(candidate) => {
+                    *node = payload_group(leaf, candidate);
+                    true
+                }
+                None => false,
+            }
+        }
+        Node::Leaf(Leaf::Virtual(_)) => false,
+    }
+}
+
+/// Group that takes place of leaf of embedded payload decoded as candidate.
+fn payload_group(leaf: &RealLeaf, candidate: Candidate) -> Node {
+    let mut information = leaf.information.clone();
+    information.data.remove("embedded");
+    information.data.insert(
+        "decoder".into(),
+        format!("{}/{}", candidate.decoder.group, candidate.decoder.symbol),
+    );
+    information.value = Value::text(candidate.decoder.title);
+
+    let mut children = candidate.annotations.into_nodes();
+    children
+        .iter_mut()
+        .for_each(|ch| relocate(ch, &leaf.path, leaf.location.from));
+
+    Node::Group {
+        path: leaf.path.clone(),
+        location: GroupLocation {
+            byte_from: leaf.location.from,
+            byte_to: leaf.location.to,
+            index_from: 0,
+            index_to: 0,
+        },
+        information,
+        children,
+    }
+}
+
+/// Moves node of embedded payload to its place in the enclosing data, i. e.
+/// shifts its bytes by `offset` and prefixes its paths (and paths of its
+/// links) by `path`.
+fn relocate(node: &mut Node, path: &[String], offset: usize) {
+    let prefix = |p: &mut Vec<String>| p.splice(0..0, path.iter().cloned()).for_each(drop);
+    let information = match node {
+        Node::Group {
+            path: p,
+            location,
+            information,
+            children,
+        } => {
+            prefix(p);
+            location.byte_from += offset;
+            location.byte_to += offset;
+            children
+                .iter_mut()
+                .for_each(|ch| relocate(ch, path, offset));
+            information
+        }
+        Node::Leaf(Leaf::Real(RealLeaf {
+            path: p,
+            location,
+            information,
+        })) => {
+            prefix(p);
+            location.from += offset;
+            location.to += offset;
+            information
+        }
+        Node::Leaf(Leaf::Virtual(leaf)) => {
+            prefix(&mut leaf.path);
+            &mut leaf.information
+        }
+    };
+    information
+        .links
+        .iter_mut()
+        .for_each(|l| prefix(&mut l.path));
+}
+
+/// Appends leaf covering data that were not consumed by decoder.
+fn with_remainder(tree: Tree, data: &[u8], offset: usize) -> Tree {
+    let index = tree.real_leaves().len();
+    let mut nodes = tree.into_nodes();
+
+    nodes.push(Node::Leaf(Leaf::Real(RealLeaf {
+        path: vec![nodes.len().to_string()],
+        location: LeafLocation {
+            from: offset,
+            to: data.len(),
+            index,
+            bits: None,
+        },
+        information: Information {
+            label: "Unparsed remainder".to_string(),
+            data: HashMap::from([("datatype".into(), "bytes".to_string())]),
+            tags: vec![],
+            refs: vec![],
+            value: Value::bytes(data[offset..].to_vec()),
+            unit: None,
+            doc: Some(
+                "Data that remained after the decoder finished. They may belong to another structure or indicate that the data were not interpreted correctly.".to_string(),
+            ),
+            splain: None,
+            warnings: vec![],
+            links: vec![],
+            categories: vec![],
+        },
+    })));
+
+    Tree::from_nodes(nodes)
 }
 
 /// From input extract all possible interpreations of binary data.
 /// The input can be interpreted either as raw binary data or as
 /// a string representing some known encoding of binary data.
+/// Payment URIs (`bitcoin:`, `lightning:`, `lnurl:`) are interpreted
+/// by their path and Lightning fallbacks, which keep the URI. Data
+/// compressed by zlib or gzip precede their compressed form. JSON
+/// documents (e. g. results of RPC) are interpreted by their string
+/// fields that are hex or Base64, the longest first. Strings of bits
+/// (`0101…`) precede hex, which they are valid as too.
 /// The results are ordered from more likely to less likely, i. e.
 /// since Base16 may also be a valid Base64, the decoded Base16
 /// will preceed the other.
@@ -118,17 +902,74 @@ pub fn input_to_binaries(input: Input) -> Vec<Binary> {
     }
     .into_iter()
     .flatten()
+    .flat_map(|b| decompress(&b).into_iter().chain(Some(b)))
     .collect()
 }
 
+/// Attempt to decode given string, or payloads of URI, as binary
+/// data according to various encoding schemes.
+fn try_decode_string(s: &str) -> Vec<Option<Binary>> {
+    if let Some(mut fields) = string_to_json_fields(s) {
+        fields.sort_by_key(|(_, value)| std::cmp::Reverse(value.len()));
+        return fields
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .flat_map(|(path, value)| {
+                [string_to_hex(&value), string_to_base64(&value)]
+                    .into_iter()
+                    .map(move |b| b.map(|b| Binary::Json(path.clone(), Box::new(b))))
+            })
+            .collect();
+    }
+
+    if let Some((descriptor, key)) = string_to_descriptor(s) {
+        let descriptor = Box::new(descriptor);
+        return vec![string_to_base58(key).map(|b| Binary::Descriptor(descriptor, Box::new(b)))];
+    }
+
+    match string_to_uri(s) {
+        Some(uri) => uri
+            .payloads()
+            .into_iter()
+            .flat_map(|(source, payload)| {
+                let uri = uri.with_source(source);
+                try_decode_encoding(payload)
+                    .into_iter()
+                    .map(move |b| b.map(|b| Binary::Uri(Box::new(uri.clone()), Box::new(b))))
+            })
+            .collect(),
+        None => try_decode_encoding(s),
+    }
+}
+
 /// Attempt to decode given string as binary data according
 /// to various encoding schemes.
 #[inline]
-fn try_decode_string(s: &str) -> Vec<Option<Binary>> {
-    vec![
+fn try_decode_encoding(s: &str) -> Vec<Option<Binary>> {
+    let base64 = string_to_base64(s);
+
+    // Base64 is the usual encoding of PSBTs (BIP-174), the magic
+    // makes it more likely than anything else.
+    if base64.as_ref().is_some_and(Binary::is_psbt) {
+        return vec![base64, string_to_hex(s), string_to_base58(s)];
+    }
+
+    let mut binaries = vec![
+        string_to_bits(s),
         string_to_hex(s),
+        string_to_hexdump(s),
         string_to_bech32(s),
         string_to_base58(s),
-        string_to_base64(s),
-    ]
+        base64,
+    ];
+
+    // Digits and uppercase letters alone are valid in too many encodings,
+    // such strings are tried as Base43 only as the last resort.
+    let symbols = s
+        .bytes()
+        .any(|c| !c.is_ascii_digit() && !c.is_ascii_uppercase());
+    if symbols || binaries.iter().all(Option::is_none) {
+        binaries.push(string_to_base43(s));
+    }
+    binaries
 }
@@ -0,0 +1,154 @@
+//! Comparison of two decoded inputs, e. g. two versions of the same
+//! PSBT or a `channel_update` before and after a change of fees.
+//!
+//! Children of groups are paired by their labels, so an added or removed
+//! node does not cause all of its following siblings to differ.
+
+use crate::decode::{try_decode_input, Candidate, DecodeOptions, DecoderFilter, Input};
+use crate::tree::{Leaf, Node, Tree};
+
+/// Difference between two trees.
+#[derive(Debug)]
+pub enum Change<'a> {
+    /// Node is present only in the new tree.
+    Added(&'a Node),
+
+    /// Node is present only in the old tree.
+    Removed(&'a Node),
+
+    /// Value of node differs.
+    Changed { old: &'a Node, new: &'a Node },
+
+    /// Value of leaf is the same, however it is located at different offset.
+    Moved { old: &'a Node, new: &'a Node },
+}
+
+/// Decodes both inputs by the same decoder. Decoders are tried in order
+/// in which they decoded the old input.
+pub fn decode_pair(
+    old: Input,
+    new: Input,
+    options: &DecodeOptions,
+) -> Option<(Candidate, Candidate)> {
+    let (old, _) = try_decode_input(old, options);
+    old.into_iter().find_map(|old| {
+        let options = DecodeOptions {
+            filter: DecoderFilter::Symbol {
+                group: old.decoder.group.to_string(),
+                symbol: old.decoder.symbol.to_string(),
+            },
+            ..options.clone()
+        };
+        try_decode_input(new.clone(), &options)
+            .0
+            .into_iter()
+            .next()
+            .map(|new| (old, new))
+    })
+}
+
+/// Differences between two trees in order of their appearance.
+pub fn diff<'a>(old: &'a Tree, new: &'a Tree) -> Vec<Change<'a>> {
+    let mut changes = vec![];
+    diff_nodes(old, new, &mut changes);
+    changes
+}
+
+fn diff_nodes<'a>(old: &'a [Node], new: &'a [Node], changes: &mut Vec<Change<'a>>) {
+    align(old, new).into_iter().for_each(|pair| match pair {
+        (Some(old), None) => changes.push(Change::Removed(old)),
+        (None, Some(new)) => changes.push(Change::Added(new)),
+        (Some(old), Some(new)) => diff_node(old, new, changes),
+        (None, None) => {}
+    });
+}
+
+fn diff_node<'a>(old: &'a Node, new: &'a Node, changes: &mut Vec<Change<'a>>) {
+    let same_value = old.information().value.plain() == new.information().value.plain();
+    match (old, new) {
+        (
+            Node::Group {
+                children: old_children,
+                ..
+            },
+            Node::Group {
+                children: new_children,
+                ..
+            },
+        ) => {
+            if !same_value {
+                changes.push(Change::Changed { old, new });
+            }
+            diff_nodes(old_children, new_children, changes);
+        }
+        (Node::Leaf(o), Node::Leaf(n)) if same_value => {
+            if let (Leaf::Real(o), Leaf::Real(n)) = (o, n) {
+                if o.location.range() != n.location.range() {
+                    changes.push(Change::Moved { old, new });
+                }
+            }
+        }
+        _ => changes.push(Change::Changed { old, new }),
+    }
+}
+
+/// Pairs nodes of the same label, keeping their order (longest common
+/// subsequence). Unpaired nodes are paired with `None`.
+fn align<'a>(old: &'a [Node], new: &'a [Node]) -> Vec<(Option<&'a Node>, Option<&'a Node>)> {
+    let same = |i: usize, j: usize| old[i].information().label == new[j].information().label;
+
+    // Length of common subsequence of old[i..] and new[j..].
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    (0..old.len()).rev().for_each(|i| {
+        (0..new.len()).rev().for_each(|j| {
+            lengths[i][j] = if same(i, j) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            }
+        })
+    });
+
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = vec![];
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && same(i, j) {
+            pairs.push((Some(&old[i]), Some(&new[j])));
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            pairs.push((Some(&old[i]), None));
+            i += 1;
+        } else {
+            pairs.push((None, Some(&new[j])));
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, Change};
+    use crate::tree::{Information, Leaf, Node, Tree, VirtualLeaf};
+    use crate::value::Value;
+
+    fn leaf(label: &str, value: Value) -> Node {
+        Node::Leaf(Leaf::Virtual(VirtualLeaf {
+            path: vec![label.to_string()],
+            information: Information::derived(label, value),
+        }))
+    }
+
+    #[test]
+    fn values_that_look_the_same() {
+        // Both fee rates are displayed as 1.00 sat/vB.
+        let old = Tree::from_nodes(vec![leaf("Fee rate", Value::FeeRate(1.001))]);
+        let new = Tree::from_nodes(vec![leaf("Fee rate", Value::FeeRate(1.004))]);
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(changes[0], Change::Changed { .. }));
+
+        assert!(diff(&old, &old).is_empty());
+    }
+}
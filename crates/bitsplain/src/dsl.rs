@@ -2,8 +2,12 @@
 
 use std::marker::PhantomData;
 
-use crate::tree::Tag;
-use crate::value::{ToValue, Value};
+use serde::{Deserialize, Serialize};
+
+pub use bitsplain_derive::Splain;
+
+use crate::tree::{Category, Tag};
+use crate::value::{ToValue, Unit, Value};
 
 /// Represents a function that can create a [`Value`] out of `T`.
 ///
@@ -44,6 +48,14 @@ impl<T> Make<T, Tag> {
             Make::Empty => None,
         }
     }
+
+    /// Make a tag if and only if it does not depend on any input.
+    pub fn resolve_static(&self) -> Option<Tag> {
+        match self {
+            Make::Static(t) => Some(t.clone()),
+            _ => None,
+        }
+    }
 }
 
 impl<T> Make<T, String> {
@@ -65,6 +77,17 @@ impl<T> Make<T, String> {
     }
 }
 
+impl<T> Make<T, Option<String>> {
+    /// Make a value out of input, if there is any.
+    pub fn resolve(&self, input: &T) -> Option<String> {
+        match self {
+            Make::Fn(f) => (f)(input),
+            Make::Static(v) => v.clone(),
+            Make::Empty => None,
+        }
+    }
+}
+
 impl<T> From<&'static str> for Make<T, Value> {
     fn from(s: &'static str) -> Self {
         Make::Static(Value::text(s))
@@ -92,6 +115,12 @@ where
     }
 }
 
+impl<T> From<Tag> for Make<T, Tag> {
+    fn from(tag: Tag) -> Self {
+        Make::Static(tag)
+    }
+}
+
 impl<T> From<&'static str> for Make<T, String> {
     fn from(s: &'static str) -> Self {
         Make::Static(s.to_string())
@@ -104,20 +133,93 @@ impl<T> From<String> for Make<T, String> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 /// External reference attached to an annotation.
 pub enum Reference {
     /// Reference to a web page.
     Www(String),
     /// Reference to a BIP.
     Bip(u16),
+    /// Reference to a BOLT, Lightning Network specification.
+    Bolt(u16),
+    /// Reference to a SLIP, SatoshiLabs Improvement Proposal.
+    Slip(u16),
+    /// Reference to other specification (e. g. bLIP or EIP), optionally
+    /// to one of its sections.
+    Spec {
+        /// Name of the specification, e. g. `bLIP 3`.
+        name: String,
+        /// Address of the specification.
+        url: String,
+        /// Anchor of the section within the specification.
+        section: Option<String>,
+    },
     // Code,
 }
 
+impl Reference {
+    /// Short name of the referenced document.
+    pub fn label(&self) -> String {
+        match self {
+            Reference::Www(_) => "WWW".to_string(),
+            Reference::Bip(n) => format!("BIP{n}"),
+            Reference::Bolt(n) => format!("BOLT {n}"),
+            Reference::Slip(n) => format!("SLIP-{n:04}"),
+            Reference::Spec {
+                name,
+                section: Some(section),
+                ..
+            } => format!("{name} § {section}"),
+            Reference::Spec { name, .. } => name.clone(),
+        }
+    }
+
+    /// Address of the referenced document.
+    pub fn url(&self) -> String {
+        match self {
+            Reference::Www(url) => url.clone(),
+            Reference::Bip(n) => format!("https://bips.xyz/{n}"),
+            Reference::Bolt(n) => bolt_url(*n),
+            Reference::Slip(n) => {
+                format!("https://github.com/satoshilabs/slips/blob/master/slip-{n:04}.md")
+            }
+            Reference::Spec {
+                url,
+                section: Some(section),
+                ..
+            } => format!("{url}#{section}"),
+            Reference::Spec { url, .. } => url.clone(),
+        }
+    }
+}
+
+/// Address of BOLT of given number. Each BOLT is a file named after its topic.
+fn bolt_url(n: u16) -> String {
+    let file = match n {
+        0 => "00-introduction",
+        1 => "01-messaging",
+        2 => "02-peer-protocol",
+        3 => "03-transactions",
+        4 => "04-onion-routing",
+        5 => "05-onchain",
+        7 => "07-routing-gossip",
+        8 => "08-transport",
+        9 => "09-features",
+        10 => "10-dns-bootstrap",
+        11 => "11-payment-encoding",
+        12 => "12-offer-encoding",
+        _ => return "https://github.com/lightning/bolts".to_string(),
+    };
+    format!("https://github.com/lightning/bolts/blob/master/{file}.md")
+}
+
 /// Collection of various annotations of a parsed field.
 pub struct Ann<T> {
     /// Label of the field.
     pub label: String,
+    /// Stable ID of the field among its siblings, see [`catalog::ids`](crate::catalog::ids).
+    pub id: Option<String>,
     /// Interpreted value of the content of the field.
     pub value: Make<T, Value>,
     /// Documentation string.
@@ -128,6 +230,12 @@ pub struct Ann<T> {
     pub tags: Vec<Make<T, Tag>>,
     /// Splain string.
     pub splain: Make<T, String>,
+    /// Unit of numeric value.
+    pub unit: Option<Unit>,
+    /// Generators of warnings, each of them may or may not warn.
+    pub warnings: Vec<Make<T, Option<String>>>,
+    /// Semantic categories of the field, in addition to those implied by its value.
+    pub categories: Vec<Category>,
 }
 
 impl<T> Ann<T> {
@@ -137,6 +245,25 @@ impl<T> Ann<T> {
         self
     }
 
+    /// Set unit of numeric value.
+    pub fn unit(mut self, unit: Unit) -> Ann<T> {
+        self.unit = Some(unit);
+        self
+    }
+
+    /// Add semantic category.
+    pub fn category(mut self, category: Category) -> Ann<T> {
+        self.categories.push(category);
+        self
+    }
+
+    /// Add warning, which is generated out of value if it is suspicious
+    /// or non-standard; may be called repeatedly.
+    pub fn warn(mut self, f: impl Fn(&T) -> Option<String> + 'static) -> Ann<T> {
+        self.warnings.push(f.into());
+        self
+    }
+
     /// Add documentation.
     pub fn doc(mut self, s: impl AsRef<str>) -> Ann<T> {
         self.doc = Some(s.as_ref().to_string());
@@ -155,6 +282,52 @@ impl<T> Ann<T> {
         self
     }
 
+    /// Add reference to a BOLT; may be called repeatedly.
+    pub fn bolt(mut self, boltno: u16) -> Ann<T> {
+        self.refs.push(Reference::Bolt(boltno));
+        self
+    }
+
+    /// Add reference to a section of a BOLT, given by its anchor (e. g.
+    /// `act-three`); may be called repeatedly.
+    pub fn bolt_section(mut self, boltno: u16, section: impl AsRef<str>) -> Ann<T> {
+        self.refs.push(Reference::Spec {
+            name: format!("BOLT {boltno}"),
+            url: bolt_url(boltno),
+            section: Some(section.as_ref().to_string()),
+        });
+        self
+    }
+
+    /// Add reference to a SLIP; may be called repeatedly.
+    pub fn slip(mut self, slipno: u16) -> Ann<T> {
+        self.refs.push(Reference::Slip(slipno));
+        self
+    }
+
+    /// Add reference to other specification, optionally to its section
+    /// given by anchor; may be called repeatedly.
+    pub fn spec(
+        mut self,
+        name: impl AsRef<str>,
+        url: impl AsRef<str>,
+        section: Option<&str>,
+    ) -> Ann<T> {
+        self.refs.push(Reference::Spec {
+            name: name.as_ref().to_string(),
+            url: url.as_ref().to_string(),
+            section: section.map(str::to_string),
+        });
+        self
+    }
+
+    /// Set ID, which identifies the field in catalog of documentation
+    /// even if its label changes.
+    pub fn id(mut self, s: impl AsRef<str>) -> Ann<T> {
+        self.id = Some(s.as_ref().to_string());
+        self
+    }
+
     /// Set label.
     pub fn label(mut self, s: impl AsRef<str>) -> Ann<T> {
         self.label = s.as_ref().to_string();
@@ -187,10 +360,14 @@ pub const fn auto<T: ToValue>() -> Auto<T> {
 pub fn ann<T>(label: impl AsRef<str>, value: impl Into<Make<T, Value>>) -> Ann<T> {
     Ann {
         label: label.as_ref().to_string(),
+        id: None,
         value: value.into(),
         tags: vec![],
         refs: vec![],
         doc: None,
         splain: Make::Empty,
+        unit: None,
+        warnings: vec![],
+        categories: vec![],
     }
 }
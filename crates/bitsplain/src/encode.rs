@@ -0,0 +1,133 @@
+//! Encoding of edited values back into binary data, so a value of a leaf
+//! (e. g. sequence of an input or amount of an output) can be changed and
+//! the data re-serialized.
+//!
+//! Values are encoded according to the datatype of leaf, which parsers
+//! attach as data `datatype`. Only values of the same length as the original
+//! ones are accepted, because other parts of the data (such as lengths of
+//! enclosing structures) would not be valid otherwise.
+
+use std::fmt::Display;
+
+use bitcoin::secp256k1::ecdsa::Signature;
+use bitcoin::PublicKey;
+
+use crate::tree::RealLeaf;
+
+/// Reasons why a value could not be encoded.
+#[derive(Debug)]
+pub enum EncodeError {
+    /// Leaf does not have datatype or values of the datatype cannot be encoded.
+    Unsupported(Option<String>),
+
+    /// Value is not valid for the datatype.
+    Invalid { datatype: String, value: String },
+
+    /// Encoded value does not have the same length as the original one.
+    Length { expected: usize, actual: usize },
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodeError::Unsupported(Some(datatype)) => {
+                write!(f, "values of type {datatype} cannot be encoded")
+            }
+            EncodeError::Unsupported(None) => write!(f, "value does not have known type"),
+            EncodeError::Invalid { datatype, value } => {
+                write!(f, "'{value}' is not valid {datatype}")
+            }
+            EncodeError::Length { expected, actual } => write!(
+                f,
+                "encoded value has {actual} bytes, however {expected} bytes are expected"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+/// Encodes value, written the same way as it is rendered, as the datatype.
+pub fn encode(datatype: &str, value: &str) -> Result<Vec<u8>, EncodeError> {
+    let invalid = || EncodeError::Invalid {
+        datatype: datatype.to_string(),
+        value: value.to_string(),
+    };
+    let value = value.trim();
+
+    match datatype {
+        "uint32" => value
+            .parse::<u32>()
+            .map(|n| n.to_le_bytes().to_vec())
+            .map_err(|_| invalid()),
+        "uint32_be" => value
+            .parse::<u32>()
+            .map(|n| n.to_be_bytes().to_vec())
+            .map_err(|_| invalid()),
+        "int32" => value
+            .parse::<i32>()
+            .map(|n| n.to_le_bytes().to_vec())
+            .map_err(|_| invalid()),
+        "int32_be" => value
+            .parse::<i32>()
+            .map(|n| n.to_be_bytes().to_vec())
+            .map_err(|_| invalid()),
+        "sat" => value
+            .parse::<u64>()
+            .map(|n| n.to_le_bytes().to_vec())
+            .map_err(|_| invalid()),
+        "varint" => value.parse::<u64>().map(varint).map_err(|_| invalid()),
+        // Hashes are displayed in reversed order of bytes.
+        "txid" | "sha256" => hex::decode(value)
+            .ok()
+            .filter(|b| b.len() == 32)
+            .map(|mut b| {
+                b.reverse();
+                b
+            })
+            .ok_or_else(invalid),
+        "public_key" => hex::decode(value)
+            .ok()
+            .filter(|b| PublicKey::from_slice(b).is_ok())
+            .ok_or_else(invalid),
+        "signature" => hex::decode(value)
+            .ok()
+            .filter(|b| Signature::from_compact(b).is_ok())
+            .ok_or_else(invalid),
+        "bytes" => hex::decode(value).map_err(|_| invalid()),
+        "string" => Ok(value.as_bytes().to_vec()),
+        d => Err(EncodeError::Unsupported(Some(d.to_string()))),
+    }
+}
+
+/// Bitcoin's variable-length integer in the shortest form.
+fn varint(n: u64) -> Vec<u8> {
+    match n {
+        0..=0xfc => vec![n as u8],
+        0xfd..=0xffff => [&[0xfd], &(n as u16).to_le_bytes()[..]].concat(),
+        0x10000..=0xffffffff => [&[0xfe], &(n as u32).to_le_bytes()[..]].concat(),
+        _ => [&[0xff], &n.to_le_bytes()[..]].concat(),
+    }
+}
+
+/// Copy of data in which bytes of the leaf are replaced by encoded value.
+pub fn replace(data: &[u8], leaf: &RealLeaf, value: &str) -> Result<Vec<u8>, EncodeError> {
+    let datatype = leaf
+        .information
+        .data
+        .get("datatype")
+        .ok_or(EncodeError::Unsupported(None))?;
+    let encoded = encode(datatype, value)?;
+    let range = leaf.location.range();
+
+    if encoded.len() != range.len() {
+        return Err(EncodeError::Length {
+            expected: range.len(),
+            actual: encoded.len(),
+        });
+    }
+
+    let mut data = data.to_vec();
+    data[range].copy_from_slice(&encoded);
+    Ok(data)
+}
@@ -0,0 +1,494 @@
+//! Enrichment of decoded data by information that is not contained in them
+//! and has to be obtained elsewhere (e. g. from Bitcoin Core or Esplora),
+//! such as outputs spent by inputs of a transaction, fee paid by it,
+//! messages its signatures commit to and whether they are valid, roles
+//! of its witness items, addresses derived from an extended key or aliases
+//! of Lightning nodes.
+//!
+//! Obtained information is added to the tree as virtual leaves, which are
+//! appended to existing groups, so paths of other nodes do not change.
+
+use std::collections::HashMap;
+
+use bitcoin::consensus::encode::deserialize_partial;
+use bitcoin::script::Instruction;
+use bitcoin::{ecdsa, taproot, Address, Network, PublicKey, Script, ScriptBuf, Transaction, TxOut};
+
+pub use crate::btc::bip32::Derivation;
+use crate::btc::sighash::{signatures, Signed};
+use crate::decode::Candidate;
+use crate::tree::{Category, Information, Leaf, Node, Tree, VirtualLeaf, Warning};
+use crate::types::Sat;
+use crate::value::Value;
+
+/// Transaction of candidate decoded as a transaction.
+pub fn transaction(candidate: &Candidate) -> Option<Transaction> {
+    if candidate.decoder.group == "btc" && candidate.decoder.symbol == "tx" {
+        deserialize_partial::<Transaction>(&candidate.data)
+            .ok()
+            .map(|(tx, _)| tx)
+    } else {
+        None
+    }
+}
+
+/// Tree of transaction with amounts, addresses and types of scripts
+/// of outputs spent by its inputs. Outputs are given in order of inputs,
+/// `None` if output of an input is not known (e. g. of coinbase).
+/// Total amount of inputs is added if all outputs are known.
+pub fn with_prevouts(tree: Tree, prevouts: &[Option<TxOut>]) -> Tree {
+    let mut nodes = tree.into_nodes();
+
+    if let Some(Node::Group { path, children, .. }) = nodes
+        .iter_mut()
+        .find(|n| matches!(n, Node::Group { .. }) && n.information().label == "Input List")
+    {
+        children
+            .iter_mut()
+            .filter(|n| matches!(n, Node::Group { .. }))
+            .zip(prevouts)
+            .for_each(|(input, prevout)| {
+                if let (Node::Group { path, children, .. }, Some(prevout)) = (input, prevout) {
+                    spent_output(path, children, prevout);
+                }
+            });
+
+        if let Some(total) = total(prevouts) {
+            push(
+                path,
+                children,
+                "Total amount",
+                Value::Sat(Sat::new(total.into())),
+                "Sum of amounts of all outputs spent by this transaction",
+            );
+        }
+    }
+
+    Tree::from_nodes(nodes)
+}
+
+/// Tree of transaction with sighashes, i. e. messages that signatures
+/// of its inputs commit to, and results of verification of the signatures.
+/// Sighash can be computed only if output spent by the input is known
+/// (for Taproot, outputs of all inputs).
+pub fn with_signatures(tree: Tree, tx: &Transaction, prevouts: &[Option<TxOut>]) -> Tree {
+    let mut nodes = tree.into_nodes();
+
+    if let Some(Node::Group { children, .. }) = nodes
+        .iter_mut()
+        .find(|n| matches!(n, Node::Group { .. }) && n.information().label == "Input List")
+    {
+        children
+            .iter_mut()
+            .filter(|n| matches!(n, Node::Group { .. }))
+            .enumerate()
+            .for_each(|(index, input)| {
+                if let Node::Group { path, children, .. } = input {
+                    signatures(tx, index, prevouts)
+                        .into_iter()
+                        .for_each(|s| signature(path, children, &s));
+                }
+            });
+    }
+
+    Tree::from_nodes(nodes)
+}
+
+/// Tree of transaction with roles of witness items (e. g. signature, public
+/// key, witness script), given scripts of outputs spent by its inputs in
+/// order of inputs. Role of an item is its value and is explained in splain.
+pub fn with_witnesses(tree: Tree, tx: &Transaction, scripts: &[Option<ScriptBuf>]) -> Tree {
+    let mut nodes = tree.into_nodes();
+
+    if let Some(Node::Group { children, .. }) = nodes
+        .iter_mut()
+        .find(|n| matches!(n, Node::Group { .. }) && n.information().label == "Witness Structure")
+    {
+        children
+            .iter_mut()
+            .filter(|n| matches!(n, Node::Group { .. }))
+            .zip(tx.input.iter().zip(scripts))
+            .for_each(|(stack, (input, script))| {
+                if let (Node::Group { children, .. }, Some(script)) = (stack, script) {
+                    let roles = witness_roles(&input.witness.to_vec(), script, &input.script_sig);
+                    children
+                        .iter_mut()
+                        .filter_map(|n| match n {
+                            Node::Group { information, .. } => Some(information),
+                            _ => None,
+                        })
+                        .zip(roles)
+                        .for_each(|(information, (role, splain))| {
+                            information.value = Value::text(role);
+                            information.splain = Some(splain.to_string());
+                        });
+                }
+            });
+    }
+
+    Tree::from_nodes(nodes)
+}
+
+/// Roles of witness items of input spending output with `script`, with their
+/// explanations. Empty if the output is not a witness program.
+fn witness_roles(
+    items: &[Vec<u8>],
+    script: &Script,
+    script_sig: &Script,
+) -> Vec<(&'static str, &'static str)> {
+    // Witness program of P2SH-wrapped output is the only push of input script.
+    let nested = || match script_sig.instructions().collect::<Vec<_>>().as_slice() {
+        [Ok(Instruction::PushBytes(redeem))] => {
+            Some(ScriptBuf::from_bytes(redeem.as_bytes().to_vec()))
+        }
+        _ => None,
+    };
+    let program = if script.is_p2sh() {
+        match nested() {
+            Some(redeem) => redeem,
+            None => return vec![],
+        }
+    } else {
+        script.to_owned()
+    };
+
+    if program.is_p2wpkh() {
+        vec![
+            (
+                "Signature",
+                "Signature of this transaction made by key of the spent P2WPKH output.",
+            ),
+            (
+                "Public Key",
+                "Public key, whose HASH160 is witness program of the spent P2WPKH output.",
+            ),
+        ]
+    } else if program.is_p2wsh() {
+        let mut roles = items
+            .iter()
+            .take(items.len().saturating_sub(1))
+            .map(|item| stack_item(item, false))
+            .collect::<Vec<_>>();
+        roles.push(("Witness Script", "Script, whose SHA256 is witness program of the spent P2WSH output. It is executed with the preceding items as its initial stack."));
+        roles
+    } else if program.is_p2tr() {
+        let annex = items.len() > 1
+            && items.last().and_then(|i| i.first()) == Some(&taproot::TAPROOT_ANNEX_PREFIX);
+        let count = items.len() - usize::from(annex);
+        let mut roles = match count {
+            1 => vec![("Signature", "Schnorr signature of this transaction made by output key of the spent P2TR output (key path spend).")],
+            _ => {
+                let mut roles = items
+                    .iter()
+                    .take(count.saturating_sub(2))
+                    .map(|item| stack_item(item, true))
+                    .collect::<Vec<_>>();
+                roles.push(("Tapscript", "Script of the spent leaf of taproot tree, executed with the preceding items as its initial stack (script path spend)."));
+                roles.push(("Control Block", "Leaf version, parity of output key, internal key and merkle path, which prove that the tapscript is committed to in output key of the spent P2TR output."));
+                roles
+            }
+        };
+        if annex {
+            roles.push(("Annex", "Data reserved for future extensions. They are committed to by signatures, but otherwise ignored."));
+        }
+        roles
+    } else {
+        vec![]
+    }
+}
+
+/// Role of item of initial stack of witness script or tapscript, guessed
+/// from its content.
+fn stack_item(item: &[u8], taproot: bool) -> (&'static str, &'static str) {
+    let signature = if taproot {
+        taproot::Signature::from_slice(item).is_ok()
+    } else {
+        ecdsa::Signature::from_slice(item).is_ok()
+    };
+    if item.is_empty() {
+        ("Empty Item", "Empty item of initial stack of the script, e. g. false value or dummy item consumed by OP_CHECKMULTISIG.")
+    } else if signature {
+        (
+            "Signature",
+            "Item of initial stack of the script, which looks like signature.",
+        )
+    } else if !taproot && PublicKey::from_slice(item).is_ok() {
+        (
+            "Public Key",
+            "Item of initial stack of the script, which looks like public key.",
+        )
+    } else {
+        ("Stack Item", "Item of initial stack of the script.")
+    }
+}
+
+fn signature(path: &[String], children: &mut Vec<Node>, signed: &Signed) {
+    push(
+        path,
+        children,
+        "Sighash",
+        Value::bytes(signed.sighash.to_vec()),
+        &format!(
+            "Message that signature of this input commits to, computed by {} with {}",
+            signed.algorithm.name(),
+            signed.flag
+        ),
+    )
+    .splain = Some(format!("Signature: {}", hex::encode(&signed.signature)));
+
+    if signed.keys.is_empty() {
+        return;
+    }
+
+    let signer = signed.signer();
+    let information = push(
+        path,
+        children,
+        "Verification",
+        Value::text(if signer.is_some() { "valid" } else { "INVALID" }),
+        "Result of verification of the signature against sighash and public keys of this input.",
+    );
+    information.splain = Some(match signer {
+        Some(key) => format!(
+            "Signature was made by public key {} over exactly this sighash.",
+            hex::encode(key)
+        ),
+        None => "Signature was not made by any of public keys of this input over this sighash. Either the transaction was altered or it was signed by a different key.".to_string(),
+    });
+    match signer {
+        Some(_) => {
+            information
+                .data
+                .insert("check".into(), "passed".to_string());
+        }
+        None => {
+            information
+                .data
+                .insert("check".into(), "failed".to_string());
+            information.warnings.push(Warning {
+                message: "Signature is not valid signature of any public key of this input"
+                    .to_string(),
+            });
+        }
+    }
+}
+
+/// Tree of extended key with addresses derived from the key as described
+/// by `derivation`, the first `count` of each branch.
+pub fn with_derived_addresses(tree: Tree, derivation: &Derivation, key: &[u8], count: u32) -> Tree {
+    let mut nodes = tree.into_nodes();
+
+    derivation
+        .addresses(key, count)
+        .into_iter()
+        .for_each(|(path, address)| {
+            push(
+                &[],
+                &mut nodes,
+                &format!("Address {path}"),
+                Value::Addr(Some(address)),
+                &format!("Address derived from this key at path {path}"),
+            );
+        });
+
+    Tree::from_nodes(nodes)
+}
+
+/// Tree in which IDs of Lightning nodes are shown together with aliases
+/// of the nodes, as known from snapshot of network graph. Aliases
+/// announced by nodes are not verified in any way.
+pub fn with_node_aliases(tree: Tree, aliases: &HashMap<PublicKey, String>) -> Tree {
+    let mut nodes = tree.into_nodes();
+    if !aliases.is_empty() {
+        nodes.iter_mut().for_each(|n| node_alias(n, aliases));
+    }
+    Tree::from_nodes(nodes)
+}
+
+fn node_alias(node: &mut Node, aliases: &HashMap<PublicKey, String>) {
+    match node {
+        Node::Group { children, .. } => children.iter_mut().for_each(|ch| node_alias(ch, aliases)),
+        Node::Leaf(Leaf::Real(l)) if l.information.has_data("datatype", "node_id") => {
+            if let Value::PublicKey(key) = &l.information.value {
+                if let Some(alias) = aliases.get(key) {
+                    l.information.value = Value::alt(Value::PublicKey(*key), Value::text(alias));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Sum of amounts of outputs if all of them are known and the sum
+/// does not overflow.
+pub fn total(prevouts: &[Option<TxOut>]) -> Option<u64> {
+    prevouts.iter().try_fold(0u64, |acc, p| {
+        p.as_ref().and_then(|p| acc.checked_add(p.value.to_sat()))
+    })
+}
+
+fn spent_output(path: &[String], children: &mut Vec<Node>, prevout: &TxOut) {
+    push(
+        path,
+        children,
+        "Amount",
+        Value::Sat(Sat::new(prevout.value.to_sat().into())),
+        "Amount of the output spent by this input",
+    );
+    if let Ok(address) = Address::from_script(&prevout.script_pubkey, Network::Bitcoin) {
+        push(
+            path,
+            children,
+            "Address",
+            Value::Addr(Some(address)),
+            "Address of the output spent by this input",
+        );
+    }
+    push(
+        path,
+        children,
+        "Script Type",
+        Value::text(script_type(&prevout.script_pubkey)),
+        "Type of script of the output spent by this input",
+    );
+}
+
+/// Tree of transaction with fee and fee rate, given amounts of outputs
+/// spent by its inputs in order of inputs. Nothing is added unless all
+/// the amounts are known and their sums do not overflow.
+pub fn with_fee(tree: Tree, tx: &Transaction, amounts: &[Option<u64>]) -> Tree {
+    let mut nodes = tree.into_nodes();
+
+    let inputs = (amounts.len() == tx.input.len())
+        .then(|| {
+            amounts
+                .iter()
+                .try_fold(0u64, |acc, a| a.and_then(|a| acc.checked_add(a)))
+        })
+        .flatten();
+    let outputs = tx
+        .output
+        .iter()
+        .try_fold(0u64, |acc, o| acc.checked_add(o.value.to_sat()));
+
+    if let Some(fee) = inputs.zip(outputs).and_then(|(i, o)| i.checked_sub(o)) {
+        let vsize = tx.vsize();
+        let rate = fee as f64 / vsize as f64;
+
+        push(
+            &[],
+            &mut nodes,
+            "Fee",
+            Value::Sat(Sat::new(fee.into())),
+            "Difference between amounts of spent outputs and amounts of outputs of this transaction, collected by miner",
+        )
+        .categories
+        .push(Category::Fee);
+        push(
+            &[],
+            &mut nodes,
+            "Fee rate",
+            Value::FeeRate(rate),
+            "Fee per virtual byte of this transaction, which determines how quickly miners include it in a block",
+        )
+        .splain = Some(format!(
+            "{fee} sat / {vsize} vB = {rate:.2} sat/vB, {}",
+            feerate_band(rate)
+        ));
+    }
+
+    Tree::from_nodes(nodes)
+}
+
+/// Description of fee rate (in sat/vB) compared to rates commonly paid.
+fn feerate_band(rate: f64) -> &'static str {
+    if rate < 1.0 {
+        "below default minimum relay fee rate of 1 sat/vB, most nodes will not relay it"
+    } else if rate < 5.0 {
+        "low, suitable when confirmation is not urgent"
+    } else if rate < 20.0 {
+        "moderate, usually confirmed within hours"
+    } else if rate < 100.0 {
+        "high, usually confirmed in one of the next blocks"
+    } else if rate < 1000.0 {
+        "very high, paid only in times of extreme congestion"
+    } else {
+        "absurdly high, likely a mistake"
+    }
+}
+
+/// Appends virtual leaf to children of group at the path and returns
+/// its information, so it can be further amended.
+fn push<'a>(
+    path: &[String],
+    children: &'a mut Vec<Node>,
+    label: &str,
+    value: Value,
+    doc: &str,
+) -> &'a mut Information {
+    let path = [path, &[children.len().to_string()]].concat();
+    children.push(Node::Leaf(Leaf::Virtual(VirtualLeaf {
+        path,
+        information: Information {
+            data: HashMap::from([("source".into(), "enrich".to_string())]),
+            doc: Some(doc.to_string()),
+            ..Information::derived(label, value)
+        },
+    })));
+    match children.last_mut() {
+        Some(Node::Leaf(Leaf::Virtual(leaf))) => &mut leaf.information,
+        _ => unreachable!("virtual leaf was just appended"),
+    }
+}
+
+/// Name of standard type of output script.
+pub fn script_type(script: &Script) -> &'static str {
+    if script.is_p2pk() {
+        "P2PK"
+    } else if script.is_p2pkh() {
+        "P2PKH"
+    } else if script.is_p2sh() {
+        "P2SH"
+    } else if script.is_p2wpkh() {
+        "P2WPKH"
+    } else if script.is_p2wsh() {
+        "P2WSH"
+    } else if script.is_p2tr() {
+        "P2TR"
+    } else if script.is_multisig() {
+        "Bare multisig"
+    } else if script.is_op_return() {
+        "OP_RETURN"
+    } else {
+        "Non-standard"
+    }
+}
+
+/// Outputs spent by inputs of the transaction, obtained by `fetch`, which
+/// is asked once for every distinct previous transaction. Outputs that
+/// cannot be obtained are `None`.
+pub fn prevouts<F, E>(tx: &Transaction, mut fetch: F) -> (Vec<Option<TxOut>>, Vec<E>)
+where
+    F: FnMut(&bitcoin::Txid) -> Result<Transaction, E>,
+{
+    let mut transactions = HashMap::new();
+    let mut errors = vec![];
+
+    let prevouts = tx
+        .input
+        .iter()
+        .map(|input| {
+            let outpoint = input.previous_output;
+            if outpoint.is_null() {
+                return None;
+            }
+            let prev = transactions
+                .entry(outpoint.txid)
+                .or_insert_with(|| fetch(&outpoint.txid).map_err(|e| errors.push(e)).ok());
+            prev.as_ref()
+                .and_then(|p| p.output.get(outpoint.vout as usize).cloned())
+        })
+        .collect();
+
+    (prevouts, errors)
+}
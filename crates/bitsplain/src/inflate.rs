@@ -0,0 +1,108 @@
+//! Decompression of data compressed by DEFLATE (RFC 1951) and wrapped
+//! in zlib (RFC 1950) or gzip (RFC 1952) format.
+
+use miniz_oxide::inflate::{decompress_to_vec_with_limit, decompress_to_vec_zlib_with_limit};
+
+/// Decompressed data larger than this are never accepted.
+const MAX_OUTPUT: usize = 16 * 1024 * 1024;
+
+/// Maximal ratio of decompressed and compressed size that DEFLATE
+/// can achieve. Anything larger is not valid stream.
+const MAX_RATIO: usize = 1032;
+
+/// Maximal size of decompressed `data`.
+fn limit(data: &[u8]) -> usize {
+    data.len().saturating_mul(MAX_RATIO).min(MAX_OUTPUT)
+}
+
+/// Decompresses zlib data, verifying their header and Adler-32 checksum.
+pub fn zlib(data: &[u8]) -> Option<Vec<u8>> {
+    let (&cmf, &flg) = (data.first()?, data.get(1)?);
+    let valid_header = cmf & 0x0f == 8
+        && cmf >> 4 <= 7
+        && (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0
+        // Preset dictionary is not supported.
+        && flg & 0x20 == 0;
+    if !valid_header {
+        return None;
+    }
+
+    decompress_to_vec_zlib_with_limit(data, limit(data)).ok()
+}
+
+/// Decompresses gzip data (single member), verifying their header,
+/// CRC-32 and size. Data must end right after the size.
+pub fn gzip(data: &[u8]) -> Option<Vec<u8>> {
+    if data.get(..3)? != [0x1f, 0x8b, 8] {
+        return None;
+    }
+    let flags = *data.get(3)?;
+    let mut pos = 10;
+
+    // FEXTRA
+    if flags & 0x04 != 0 {
+        let length = u16::from_le_bytes(data.get(pos..pos + 2)?.try_into().ok()?);
+        pos += 2 + usize::from(length);
+    }
+    // FNAME, FCOMMENT
+    for flag in [0x08, 0x10] {
+        if flags & flag != 0 {
+            pos += data.get(pos..)?.iter().position(|b| *b == 0)? + 1;
+        }
+    }
+    // FHCRC
+    if flags & 0x02 != 0 {
+        pos += 2;
+    }
+
+    let end = data.len().checked_sub(8)?;
+    let out = decompress_to_vec_with_limit(data.get(pos..end)?, limit(data)).ok()?;
+    let crc = u32::from_le_bytes(data[end..end + 4].try_into().ok()?);
+    let size = u32::from_le_bytes(data[end + 4..].try_into().ok()?);
+    (crc == crc32(&out) && size == out.len() as u32).then_some(out)
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: &[u8] = b"bitsplain bitsplain bitsplain";
+
+    #[test]
+    fn zlib_known_answer() {
+        let data = hex::decode("789c4bca2c292ec849cccc5348c2640100ada20b93").unwrap();
+        assert_eq!(zlib(&data).as_deref(), Some(PLAIN));
+
+        let mut corrupted = data.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(zlib(&corrupted), None);
+    }
+
+    #[test]
+    fn gzip_known_answer() {
+        let data =
+            hex::decode("1f8b08000000000002034bca2c292ec849cccc5348c26401000115197f1d000000")
+                .unwrap();
+        assert_eq!(gzip(&data).as_deref(), Some(PLAIN));
+
+        let mut corrupted = data.clone();
+        corrupted[data.len() - 8] ^= 1;
+        assert_eq!(gzip(&corrupted), None);
+    }
+
+    #[test]
+    fn output_bounded_by_input() {
+        let zeros = miniz_oxide::deflate::compress_to_vec_zlib(&[0; 65536], 9);
+        assert_eq!(zlib(&zeros).map(|d| d.len()), Some(65536));
+        assert_eq!(limit(&[0; 8]), 8 * MAX_RATIO);
+        assert_eq!(limit(&[0; 1 << 20]), MAX_OUTPUT);
+    }
+}
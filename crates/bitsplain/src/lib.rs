@@ -22,16 +22,31 @@
 //! in a tree of [`Values`](crate::value), which is then returned for interpretation. Writers of data parsers
 //! can use a convenient [`DSL`](dsl).
 //!
-pub use {bitcoin, hex, nom};
+pub use {bitcoin, hex, nom, time};
+
+// Code generated by derive macros refers to this crate as `::bitsplain`.
+extern crate self as bitsplain;
 
 pub mod binary;
+pub mod catalog;
+pub mod datatype;
 pub mod decode;
+pub mod diff;
 pub mod dsl;
+pub mod encode;
+pub mod enrich;
 pub mod parse;
+pub mod plugin;
+pub mod query;
+pub mod timestamp;
+pub mod trace;
 pub mod tree;
 pub mod types;
 pub mod value;
 
+mod inflate;
+mod serialize;
+
 mod btc;
 mod ln;
 pub mod output; //<- Waiting for new version which depends on 0.30+
@@ -59,18 +74,11 @@ macro_rules! decoder {
                 title: $title,
                 group: $group,
                 symbol: $symbol,
-                decode: |b| {
+                decode: |b, budget| {
                     if matches!(b, $( $pattern )|+ $( if $guard )?) {
-                        $func($crate::parse::Annotated::new(&b)).ok().and_then(|(x, _)| {
-                            use $crate::nom::InputLength;
-                            if x.input_len() > 0 {
-                                None
-                            } else {
-                                Some(x.annotations())
-                            }
-                        })
+                        $crate::parse::decode_with(&b, budget, $func)
                     } else {
-                        None
+                        Err($crate::decode::DecodeError::Mismatch)
                     }
                 }
             }
@@ -86,6 +94,14 @@ decoder!(
     b if b.len() == 80
 );
 
+decoder!(
+    title = "Bitcoin block",
+    group = "btc",
+    symbol = "block",
+    crate::btc::block::block,
+    b if b.len() > 80
+);
+
 decoder!(
     title = "Serialized Bitcoin transaction",
     group = "btc",
@@ -121,6 +137,100 @@ decoder!(
     crate::ln::gossip::node_announcement
 );
 
+decoder!(
+    title = "Lightning Network onion failure message",
+    group = "ln",
+    symbol = "onion_fail",
+    crate::ln::onion::failure_packet
+);
+
+decoder!(
+    title = "Lightning Network onion hop payload",
+    group = "ln",
+    symbol = "hop_payload",
+    crate::ln::onion::hop_payload
+);
+
+decoder!(
+    title = "Lightning Network onion message",
+    group = "ln",
+    symbol = "onion_message",
+    crate::ln::onion_message::onion_message
+);
+
+decoder!(
+    title = "Lightning Network onion message payload",
+    group = "ln",
+    symbol = "onionmsg_payload",
+    crate::ln::onion_message::onion_message_payload
+);
+
+decoder!(
+    title = "Lightning Network tx_add_input",
+    group = "ln",
+    symbol = "tx_add_input",
+    crate::ln::interactive::tx_add_input
+);
+
+decoder!(
+    title = "Lightning Network tx_add_output",
+    group = "ln",
+    symbol = "tx_add_output",
+    crate::ln::interactive::tx_add_output
+);
+
+decoder!(
+    title = "Lightning Network tx_complete",
+    group = "ln",
+    symbol = "tx_complete",
+    crate::ln::interactive::tx_complete
+);
+
+decoder!(
+    title = "Lightning Network tx_signatures",
+    group = "ln",
+    symbol = "tx_signatures",
+    crate::ln::interactive::tx_signatures
+);
+
+decoder!(
+    title = "Lightning Network splice_init",
+    group = "ln",
+    symbol = "splice_init",
+    crate::ln::interactive::splice_init
+);
+
+decoder!(
+    title = "Lightning Network splice_ack",
+    group = "ln",
+    symbol = "splice_ack",
+    crate::ln::interactive::splice_ack
+);
+
+decoder!(
+    title = "Lightning Network handshake act one or two",
+    group = "ln",
+    symbol = "act12",
+    crate::ln::transport::act_one_two,
+    b if b.len() == 50
+);
+
+decoder!(
+    title = "Lightning Network handshake act three",
+    group = "ln",
+    symbol = "act3",
+    crate::ln::transport::act_three,
+    b if b.len() == 66
+);
+
+decoder!(
+    title = "Macaroon",
+    group = "ln",
+    symbol = "macaroon",
+    crate::ln::macaroon::macaroon,
+    b if b.first() == Some(&0x02)
+);
+
 // decoder!(
 //     title = "Lightning Network gossip timestamp filter",
 //     group = "ln",
@@ -133,7 +243,7 @@ decoder!(
     group = "ln",
     symbol = "bolt12o",
     crate::ln::bolt12::bolt12,
-    crate::binary::Binary::Bech32(hrp, _ ) if hrp == "lno",
+    crate::binary::Binary::Bech32(hrp, ..) if hrp == "lno",
 );
 
 decoder!(
@@ -141,7 +251,7 @@ decoder!(
     group = "ln",
     symbol = "bolt12r",
     crate::ln::bolt12::bolt12,
-    crate::binary::Binary::Bech32(hrp, _ ) if hrp == "lnr",
+    crate::binary::Binary::Bech32(hrp, ..) if hrp == "lnr",
 );
 
 decoder!(
@@ -149,7 +259,22 @@ decoder!(
     group = "ln",
     symbol = "bolt12i",
     crate::ln::bolt12::bolt12,
-    crate::binary::Binary::Bech32(hrp, _ ) if hrp == "lni",
+    crate::binary::Binary::Bech32(hrp, ..) if hrp == "lni",
+);
+
+decoder!(
+    title = "Lightning Network BOLT 12 invoice error",
+    group = "ln",
+    symbol = "bolt12e",
+    crate::ln::bolt12::invoice_error
+);
+
+decoder!(
+    title = "BIP-32 extended key",
+    group = "btc",
+    symbol = "xkey",
+    crate::btc::bip32::extended_key,
+    crate::binary::Binary::Base58Check(b) if b.len() == 78
 );
 
 decoder!(
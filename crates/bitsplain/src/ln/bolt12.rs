@@ -1,10 +1,16 @@
+use std::time::Duration;
+
 use bytes::Bytes;
 
+use crate::bitcoin::hashes::{sha256, Hash};
+use crate::bitcoin::secp256k1::{schnorr, Message, Secp256k1};
 use crate::bitcoin::PublicKey;
 use crate::dsl::{ann, auto};
-use crate::ln::bigsize;
-use crate::nom::combinator::{peek, verify};
-use crate::nom::multi::{length_count, many0};
+use crate::ln::features::feature_bits;
+use crate::ln::tlv::*;
+use crate::ln::{bigsize, node_id, signature_check, tu64};
+use crate::nom::combinator::{map, peek, verify};
+use crate::nom::multi::{length_count, many0, many1};
 use crate::nom::number::complete::*;
 use crate::parse::*;
 use crate::types::*;
@@ -15,24 +21,30 @@ use super::{short_channel_id, ShortChannelId};
 #[derive(Debug, Clone)]
 pub enum Offer {
     ChainHashes(Vec<ChainHash>),
+    Duration(Duration),
     Description(String),
     Issuer(String),
     Currency(String),
     Paths,
     Other(Bytes),
     PublicKey(PublicKey),
+    Number(u64),
+    Error(String),
 }
 
 impl ToValue for Offer {
     fn to_value(&self) -> Value {
         match self {
-            Offer::ChainHashes(s) => Value::text(format!("{:?}", s)),
+            Offer::ChainHashes(s) => Value::list(s),
+            Offer::Duration(d) => d.to_value(),
             Offer::Description(s) => Value::text(s),
             Offer::Issuer(s) => Value::text(s),
             Offer::Currency(s) => Value::text(s),
             Offer::Paths => Value::Nil,
             Offer::Other(b) => Value::bytes(b.to_vec()),
             Offer::PublicKey(pk) => pk.to_value(),
+            Offer::Number(n) => n.to_value(),
+            Offer::Error(s) => Value::text(s),
         }
     }
 }
@@ -68,8 +80,18 @@ pub fn offer_chain_hashes(s: Span) -> Parsed<Offer> {
     Ok((s, Offer::ChainHashes(chs)))
 }
 
+pub fn relative_expiry(s: Span) -> Parsed<Offer> {
+    let (s, seconds) = tu64(s)?;
+    Ok((s, Offer::Duration(Duration::from_secs(seconds))))
+}
+
+pub fn features(s: Span) -> Parsed<Offer> {
+    let (s, bitmap) = feature_bits(s)?;
+    Ok((s, Offer::Other(bitmap.into())))
+}
+
 pub fn offer_node_id(s: Span) -> Parsed<Offer> {
-    let (s, pk) = public_key(s)?;
+    let (s, pk) = node_id(s)?;
     Ok((s, Offer::PublicKey(pk)))
 }
 
@@ -165,50 +187,297 @@ pub fn other(s: Span) -> Parsed<Offer> {
     Ok((s, Offer::Other(bytes.into())))
 }
 
-pub fn tlv_record(s: Span) -> Parsed<Offer> {
-    let (s, typ) = parse(bigsize, ann("Type", auto()))(s)?;
-    let (s, length) = parse(bigsize, ann("Length", auto()))(s)?;
-
-    let (s, value) = parse_slice(
-        length,
-        parse(
-            match typ {
-                2 => offer_chain_hashes,
-                6 => currency,
-                10 => description,
-                16 => paths,
-                18 => issuer,
-                22 => offer_node_id,
-                _ => other,
+fn other_value(s: Span) -> Parsed<Offer> {
+    parse(other, ann("Value", auto()))(s)
+}
+
+/// Records of offer, invoice request and invoice.
+static OFFER_TLVS: Registry<Offer> = tlv! {
+    unknown: other_value;
+    0 => "Invoice request metadata": other_value;
+    2 => "Offer chains": |s| parse(offer_chain_hashes, ann("Value", auto()))(s), "Chains for which the offer is valid. If missing, only Bitcoin is implied.";
+    4 => "Offer metadata": other_value, "Arbitrary data for use by the issuer of the offer.";
+    6 => "Offer currency": |s| parse(currency, ann("Value", auto()))(s), "ISO 4217 code of currency of the amount. If missing, the amount is in bitcoin.";
+    8 => "Offer amount": other_value, "Minimal amount expected per item.";
+    10 => "Offer description": |s| parse(description, ann("Value", auto()))(s), "Description of purpose of the payment.";
+    12 => "Offer features": |s| parse(features, ann("Value", auto()))(s);
+    14 => "Offer absolute expiry": other_value, "Number of seconds from 1970-01-01 UTC after which the offer should not be accepted.";
+    16 => "Offer paths": |s| parse(paths, ann("Value", auto()))(s), "Blinded paths to the issuer of the offer.";
+    18 => "Offer issuer": |s| parse(issuer, ann("Value", auto()))(s), "Name of the issuer of the offer.";
+    20 => "Offer quantity max": other_value, "Maximal number of items that can be requested.";
+    22 => "Offer node ID": |s| parse(offer_node_id, ann("Value", auto()))(s), "Public key of the issuer of the offer.";
+    80 => "Invoice request chain": other_value;
+    82 => "Invoice request amount": other_value;
+    84 => "Invoice request features": |s| parse(features, ann("Value", auto()))(s);
+    86 => "Invoice request quantity": other_value;
+    88 => "Invoice request payer ID": other_value, "Public key of the payer, which signs the invoice request.";
+    89 => "Invoice request payer note": other_value;
+    90 => "Invoice request paths": other_value;
+    160 => "Invoice paths": other_value;
+    162 => "Invoice blinded pay": other_value;
+    164 => "Invoice created at": other_value;
+    166 => "Invoice relative expiry": |s| parse(relative_expiry, ann("Value", auto()))(s), "Number of seconds from creation of the invoice after which it should not be paid.";
+    168 => "Invoice payment hash": other_value;
+    170 => "Invoice amount": other_value;
+    172 => "Invoice fallbacks": other_value;
+    174 => "Invoice features": |s| parse(features, ann("Value", auto()))(s);
+    176 => "Invoice node ID": other_value, "Public key of the node issuing the invoice, which signs the invoice.";
+    240 => "Signature": other_value, "BIP-340 signature of the merkle root of all the other records.";
+};
+
+pub fn bolt12(s: Span) -> Parsed<String> {
+    let (s, records) = parse(tlv_stream, ann("TLV Stream", Value::Nil))(s)?;
+
+    Ok((s, format!("{records:?}")))
+}
+
+/// Parses TLV records till the end of input. Signature records are linked
+/// to all the records they sign, i. e. all records outside of signature range,
+/// and verified against public key of signer.
+fn tlv_stream(s: Span) -> Parsed<Vec<Offer>> {
+    let mut s = s;
+    let mut records = vec![];
+    let mut signatures = vec![];
+    let mut signed = vec![];
+    let mut signed_records = vec![];
+
+    while !s.is_empty() {
+        let (_, typ) = peek(bigsize)(s.clone())?;
+        let start = s.view();
+        // Bookmark of value of the record, to which result of verification is attached.
+        let (next, (record, value)) = parse(
+            |s| {
+                let (s, (_, record)) = tlv_record(&OFFER_TLVS)(s)?;
+                let value = s.bookmark();
+                Ok((s, (record, value)))
             },
-            ann("Value", auto()),
-        ),
-    )(s)?;
+            ann("TLV Record", Value::Nil),
+        )(s)?;
+        if (240..=1000).contains(&typ) {
+            signatures.push((next.bookmark(), value, typ, record.clone()));
+        } else {
+            signed.push(next.bookmark());
+            signed_records.push((typ, start.slice(..start.len() - next.len()), record.clone()));
+        }
+        records.push(record);
+        s = next;
+    }
+
+    signatures.iter().for_each(|(signature, _, _, _)| {
+        signed
+            .iter()
+            .for_each(|record| s.link(signature, record, "Signed record"))
+    });
+
+    signatures
+        .iter()
+        .filter(|(_, _, typ, _)| *typ == 240)
+        .for_each(|(_, value, _, signature)| {
+            if let Some((valid, signer)) = verify_signature(&signed_records, signature) {
+                signature_check(&s, value, valid, signer);
+            }
+        });
+
+    Ok((s, records))
+}
+
+/// Verifies BOLT 12 signature of records. Returns whether the signature
+/// is valid and who is supposed to have made it, or `None` if the signature
+/// or public key of signer cannot be found.
+fn verify_signature(
+    records: &[(u64, Bytes, Offer)],
+    signature: &Offer,
+) -> Option<(bool, &'static str)> {
+    // Invoice is signed by node issuing it, invoice request by payer.
+    let (message, key_type, signer) = if records.iter().any(|(typ, _, _)| *typ == 176) {
+        ("invoice", 176, "the node issuing invoice")
+    } else {
+        ("invoice_request", 88, "the payer")
+    };
 
-    let annotation = match typ {
-        2 => "Offer chains",
-        4 => "Offer metadata",
-        6 => "Offer currency",
-        8 => "Offer amount",
-        10 => "Offer description",
-        12 => "Offer features",
-        14 => "Offer absolute expiry",
-        16 => "Offer paths",
-        18 => "Offer issuer",
-        20 => "Offer quantity max",
-        22 => "Offer node ID",
-        240 => "Signature",
-        _ => "Unknown type",
+    let key = records.iter().find_map(|(typ, _, record)| match record {
+        Offer::Other(b) if *typ == key_type => PublicKey::from_slice(b).ok(),
+        _ => None,
+    })?;
+    let signature = match signature {
+        Offer::Other(b) => schnorr::Signature::from_slice(b).ok()?,
+        _ => return None,
     };
 
-    Ok((s.with("annotation", annotation), value))
+    let tag = format!("lightning{message}signature");
+    let digest = tagged_hash(tag.as_bytes(), &merkle_root(records)?.to_byte_array());
+    let valid = Secp256k1::verification_only()
+        .verify_schnorr(
+            &signature,
+            &Message::from_digest(digest.to_byte_array()),
+            &key.inner.x_only_public_key().0,
+        )
+        .is_ok();
+
+    Some((valid, signer))
 }
 
-pub fn bolt12(s: Span) -> Parsed<String> {
-    let (s, records) = parse(
-        many0(parse(tlv_record, ann("TLV Record", Value::Nil))),
+/// Merkle root of TLV records (without signatures) as defined by BOLT 12.
+/// Every record contributes leaf of the record and a nonce leaf derived
+/// from type of the record and the first record.
+fn merkle_root(records: &[(u64, Bytes, Offer)]) -> Option<sha256::Hash> {
+    let (_, first, _) = records.first()?;
+    let nonce_tag = [b"LnNonce".as_slice(), first].concat();
+
+    let mut leaves = records
+        .iter()
+        .flat_map(|(typ, bytes, _)| {
+            let type_len = match typ {
+                0..=0xfc => 1,
+                0xfd..=0xffff => 3,
+                0x10000..=0xffffffff => 5,
+                _ => 9,
+            };
+            [
+                tagged_hash(b"LnLeaf", bytes),
+                tagged_hash(&nonce_tag, &bytes[..type_len]),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    // Pairs of neighbouring nodes are joined level by level, odd node
+    // is promoted to the next level.
+    while leaves.len() > 1 {
+        leaves = leaves
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] if a < b => tagged_hash(b"LnBranch", &[&a[..], &b[..]].concat()),
+                [a, b] => tagged_hash(b"LnBranch", &[&b[..], &a[..]].concat()),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    leaves.first().copied()
+}
+
+/// `SHA256(SHA256(tag) || SHA256(tag) || msg)`
+fn tagged_hash(tag: &[u8], msg: &[u8]) -> sha256::Hash {
+    let tag = sha256::Hash::hash(tag);
+    sha256::Hash::hash(&[&tag[..], &tag[..], msg].concat())
+}
+
+fn erroneous_field(s: Span) -> Parsed<Offer> {
+    parse(
+        map(tu64, Offer::Number),
+        ann("Value", |o: &Offer| match o {
+            Offer::Number(n) => Value::alt(Value::num(*n), Value::text(OFFER_TLVS.name(*n))),
+            o => o.to_value(),
+        })
+        .doc("Number of the TLV field in the invoice request or invoice that caused the error."),
+    )(s)
+}
+
+fn suggested_value(s: Span) -> Parsed<Offer> {
+    parse(
+        other,
+        ann("Value", auto()).doc("Value of the erroneous field that would have been acceptable."),
+    )(s)
+}
+
+fn error_message(s: Span) -> Parsed<Offer> {
+    parse(
+        map(many0(u8), |b| {
+            Offer::Error(String::from_utf8_lossy(&b).to_string())
+        }),
+        ann("Value", auto()).doc("Explanation of the error."),
+    )(s)
+}
+
+/// Records of `invoice_error`.
+static INVOICE_ERROR_TLVS: Registry<Offer> = tlv! {
+    unknown: other_value;
+    1 => "Erroneous field": erroneous_field;
+    3 => "Suggested value": suggested_value;
+    5 => "Error": error_message;
+};
+
+/// Parser of `invoice_error`, which is sent back in response to invalid
+/// invoice request or invoice.
+pub fn invoice_error(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        verify(
+            many1(parse(
+                tlv_record(&INVOICE_ERROR_TLVS),
+                ann("TLV Record", Value::Nil),
+            )),
+            |records: &Vec<(u64, Offer)>| {
+                records.windows(2).all(|w| w[0].0 < w[1].0)
+                    && records.iter().any(|(typ, _)| *typ == 5)
+            },
+        ),
         ann("TLV Stream", Value::Nil),
     )(s)?;
 
-    Ok((s, format!("{records:?}")))
+    Ok((s, ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::{string_to_bech32, Binary};
+
+    /// Records of TLV stream whose types and lengths fit into one byte.
+    fn records(stream: &str) -> Vec<(u64, Bytes, Offer)> {
+        let stream = Bytes::from(hex::decode(stream).unwrap());
+        let mut records = vec![];
+        let mut i = 0;
+        while i < stream.len() {
+            let end = i + 2 + usize::from(stream[i + 1]);
+            let record = stream.slice(i..end);
+            records.push((u64::from(stream[i]), record, Offer::Other(Bytes::new())));
+            i = end;
+        }
+        records
+    }
+
+    /// Vectors from `bolt12/signature-test.json` of BOLT 12.
+    #[test]
+    fn merkle_root_vectors() {
+        [
+            (
+                "010203e8",
+                "b013756c8fee86503a0b4abdab4cddeb1af5d344ca6fc2fa8b6c08938caa6f93",
+            ),
+            (
+                "010203e802080000010000020003",
+                "c3774abbf4815aa54ccaa026bff6581f01f3be5fe814c620a252534f434bc0d1",
+            ),
+            (
+                concat!(
+                    "010203e802080000010000020003",
+                    "03310266e4598d1d3c415f572a8488830b60f7e744ed9235eb0b1ba93283b315c0351800000000000000010000000000000002",
+                ),
+                "ab2e79b1283b0b31e0b035258de23782df6b89a38cfa7237bde69aed1a658c5d",
+            ),
+        ]
+        .into_iter()
+        .for_each(|(stream, root)| {
+            assert_eq!(merkle_root(&records(stream)).unwrap().to_string(), root);
+        });
+    }
+
+    /// Invoice request from `bolt12/signature-test.json` of BOLT 12, signed by Bob.
+    #[test]
+    fn invoice_request_signature() {
+        let request = concat!(
+            "lnr1qqyqqqqqqqqqqqqqqcp4256ypqqkgzshgysy6ct5dpjk6ct5d93kzmpq23ex2ct5d9ek293pqthvwfzadd7",
+            "jejes8q9lhc4rvjxd022zv5l44g6qah82ru5rdpnpjkppqvjx204vgdzgsqpvcp4mldl3plscny0rt707gvpdh6",
+            "ndydfacz43euzqhrurageg3n7kafgsek6gz3e9w52parv8gs2hlxzk95tzeswywffxlkeyhml0hh46kndmwf4m6",
+            "xma3tkq2lu04qz3slje2rfthc89vss",
+        );
+        let Some(Binary::Bech32(_, bytes, _)) = string_to_bech32(request) else {
+            panic!("invoice request not decoded");
+        };
+        let tree = decode(&Binary::Raw(bytes), bolt12).unwrap();
+        let verification = tree.find_by_label("Verification");
+        assert_eq!(verification.len(), 1);
+        assert_eq!(verification[0].information().value.plain(), "valid");
+    }
 }
@@ -0,0 +1,76 @@
+use crate::dsl::ann;
+use crate::nom::multi::many0;
+use crate::nom::number::complete::u8;
+use crate::parse::*;
+use crate::tree::BitRange;
+use crate::value::Value;
+
+/// Name of feature as defined in BOLT 9. Both bits of the pair (even
+/// and odd) share the same name.
+fn feature_name(bit: u16) -> Option<&'static str> {
+    let name = match bit & !1 {
+        0 => "option_data_loss_protect",
+        2 => "initial_routing_sync",
+        4 => "option_upfront_shutdown_script",
+        6 => "gossip_queries",
+        8 => "var_onion_optin",
+        10 => "gossip_queries_ex",
+        12 => "option_static_remotekey",
+        14 => "payment_secret",
+        16 => "basic_mpp",
+        18 => "option_support_large_channel",
+        20 => "option_anchor_outputs",
+        22 => "option_anchors_zero_fee_htlc_tx",
+        24 => "option_route_blinding",
+        26 => "option_shutdown_anysegwit",
+        28 => "option_dual_fund",
+        34 => "option_quiesce",
+        38 => "option_onion_messages",
+        44 => "option_channel_type",
+        46 => "option_scid_alias",
+        48 => "option_payment_metadata",
+        50 => "option_zeroconf",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn splain_feature(bit: &u16) -> String {
+    let name = feature_name(*bit).unwrap_or("Unknown feature");
+    if bit & 1 == 0 {
+        format!("Feature {name} is compulsory (bit {bit} is even). Peers that do not understand it must not connect.")
+    } else {
+        format!("Feature {name} is optional (bit {bit} is odd). Peers that do not understand it may ignore it.")
+    }
+}
+
+/// Parser of feature bitmap as defined in BOLT 9. Consumes all the input
+/// and produces a leaf for every set bit.
+pub fn feature_bits(s: Span) -> Parsed<Vec<u8>> {
+    let (mut s, bitmap) = many0(u8)(s)?;
+
+    // Bits are numbered from the least significant bit of the last byte,
+    // leaves follow order of bytes, so the highest bits go first.
+    let set = bitmap.iter().enumerate().flat_map(|(i, byte)| {
+        let n = bitmap.len();
+        (0..8)
+            .rev()
+            .filter(move |b| byte & 1 << b > 0)
+            .map(move |b| (n - i, 7 - b, ((n - 1 - i) * 8 + b) as u16))
+    });
+
+    for (back, offset, bit) in set {
+        (s, _) = parse(
+            parsed_bits(back, BitRange { offset, len: 1 }, bit),
+            ann(feature_name(bit).unwrap_or("Unknown feature"), |b: &u16| {
+                Value::alt(
+                    Value::num(*b),
+                    Value::text(if b & 1 == 0 { "compulsory" } else { "optional" }),
+                )
+            })
+            .splain(splain_feature),
+        )(s)?;
+    }
+
+    Ok((s.with("datatype", "bytes"), bitmap))
+}
@@ -1,14 +1,20 @@
-use lightning::ln::features::{ChannelFeatures, NodeFeatures};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use bytes::Bytes;
 use lightning::ln::msgs::*;
 
 use crate::dsl::{ann, auto};
-use crate::ln::{rgb_color, short_channel_id};
-use crate::nom::combinator::{success, value};
-use crate::nom::multi::length_count;
+use crate::ln::features::feature_bits;
+use crate::ln::{
+    extra_data, message_type, node_id, rgb_color, short_channel_id, signature_check, verify_sha256d,
+};
+use crate::nom::combinator::{map, opt, verify};
+use crate::nom::multi::many0;
 use crate::nom::number::complete::*;
 use crate::parse::*;
+use crate::tree::Category;
 use crate::types::*;
-use crate::value::Value;
+use crate::value::{Unit, Value};
 
 // pub fn gossip_timestamp_filter(s: Span) -> Parsed<GossipTimestampFilter> {
 //     let (s, _) = value(265, be_u16)(s)?;
@@ -27,44 +33,174 @@ use crate::value::Value;
 // }
 
 pub fn node_announcement(s: Span) -> Parsed<()> {
-    let (s, _) = value(257, be_u16)(s)?;
-    let (s, _signature) = parse(signature, ann("Signature", auto()))(s)?;
+    let (s, _) = message_type(257)(s)?;
+    let (s, signature) = parse(signature, ann("Signature", auto()))(s)?;
+    let signature_bm = s.bookmark();
+    let signed = s.view();
     let (s, len) = parse(be_u16, ann("Features length", auto()))(s)?;
 
-    let (s, features) = parse(
-        length_count(success(len), u8),
-        ann("Features", |b: &Vec<u8>| Value::bytes(b.clone())),
+    let (s, _features) = parse(
+        parse_slice(len, feature_bits),
+        ann("Features", |b: &Vec<u8>| Value::bytes(b.clone())).bolt(9),
     )(s)?;
 
-    let _features = NodeFeatures::from_le_bytes({
-        let mut b = features;
-        b.reverse();
-        b
-    });
-
     let (s, _timestamp) = parse(timestamp(be_u32), ann("Timestamp", auto()))(s)?;
-    let (s, _node_id) = parse(public_key, ann("Node ID", auto()))(s)?;
+    let (s, node_id) = parse(
+        node_id,
+        ann("Node ID", auto()).category(Category::Identifier),
+    )(s)?;
     let (s, _rgb_color) = parse(rgb_color, ann("RGB Color", auto()))(s)?;
     let (s, _alias) = parse(
         bytes(32usize),
-        ann("Alias", |b: &Vec<u8>| {
+        ann("Alias", |b: &Bytes| {
             Value::text(String::from_utf8_lossy(b).trim_end_matches('\0'))
         }),
     )(s)?;
     let (s, addr_len) = parse(be_u16, ann("Addresses length", auto()))(s)?;
     let (s, _addresses) = parse(
-        length_count(success(addr_len), u8),
-        ann("Addresses", "TODO"),
+        parse_slice(addr_len, addresses),
+        ann("Addresses", Value::Nil),
     )(s)?;
+    let (s, _) = extra_data(s)?;
+
+    // Signature covers also extra data, which the node may add.
+    let signed = &signed[..signed.len() - s.len()];
+    signature_check(
+        &s,
+        &signature_bm,
+        verify_sha256d(signed, &signature, &node_id),
+        "the node",
+    );
 
     Ok((s, ()))
 }
 
+/// Lowercase RFC 4648 base32 without padding, as used by onion addresses.
+fn base32(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut out = String::new();
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for b in bytes {
+        buffer = buffer << 8 | u16::from(*b);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[usize::from(buffer >> bits & 31)] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[usize::from(buffer << (5 - bits) & 31)] as char);
+    }
+    out
+}
+
+fn address_type_name(typ: u8) -> &'static str {
+    match typ {
+        1 => "IPv4",
+        2 => "IPv6",
+        3 => "Tor v2",
+        4 => "Tor v3",
+        5 => "DNS hostname",
+        _ => "unknown",
+    }
+}
+
+fn port(s: Span) -> Parsed<u16> {
+    parse(be_u16, ann("Port", auto()))(s)
+}
+
+/// Parser of onion service, whose name is derived from `len` bytes.
+fn onion(len: usize) -> impl Fn(Span) -> Parsed<Host> {
+    move |s| {
+        parse(
+            map(bytes(len), |b| Host::Onion(format!("{}.onion", base32(&b)))),
+            ann("Onion service", |h: &Host| match h {
+                Host::Onion(name) => Value::text(name),
+                _ => Value::Nil,
+            }),
+        )(s)
+    }
+}
+
+/// Parser of single address descriptor, as announced in `node_announcement`.
+pub fn node_address(s: Span) -> Parsed<NetAddr> {
+    let (s, typ) = parse(
+        verify(u8, |t| (1..=5).contains(t)),
+        ann("Type", |t: &u8| {
+            Value::alt(Value::num(*t), Value::text(address_type_name(*t)))
+        }),
+    )(s)?;
+
+    match typ {
+        1 => {
+            let (s, ip) = parse(
+                map(be_u32, Ipv4Addr::from),
+                ann("IPv4 address", |ip: &Ipv4Addr| Value::text(ip.to_string())),
+            )(s)?;
+            let (s, port) = port(s)?;
+            Ok((s, NetAddr::new(Host::Ipv4(ip), port)))
+        }
+        2 => {
+            let (s, ip) = parse(
+                map(be_u128, Ipv6Addr::from),
+                ann("IPv6 address", |ip: &Ipv6Addr| Value::text(ip.to_string())),
+            )(s)?;
+            let (s, port) = port(s)?;
+            Ok((s, NetAddr::new(Host::Ipv6(ip), port)))
+        }
+        3 => {
+            let (s, onion) = onion(10)(s)?;
+            let (s, port) = port(s)?;
+            Ok((s, NetAddr::new(onion, port)))
+        }
+        4 => {
+            let (s, onion) = onion(35)(s)?;
+            let (s, port) = port(s)?;
+            Ok((s, NetAddr::new(onion, port)))
+        }
+        _ => {
+            let (s, len) = parse(u8, ann("Hostname length", auto()))(s)?;
+            let (s, host) = parse(
+                map(bytes(len), |b| String::from_utf8_lossy(&b).to_string()),
+                ann("Hostname", |h: &String| Value::text(h)),
+            )(s)?;
+            let (s, port) = port(s)?;
+            Ok((s, NetAddr::new(Host::Dns(host), port)))
+        }
+    }
+}
+
+/// Parser of list of address descriptors. Parsing stops at first unknown type,
+/// remaining bytes are kept uninterpreted.
+pub fn addresses(s: Span) -> Parsed<Vec<NetAddr>> {
+    let (s, addresses) = many0(parse(
+        with("list", "enumerate", node_address),
+        ann("Address", auto()),
+    ))(s)?;
+    let (s, _) = opt(parse(
+        verify(many0(u8), |b: &Vec<u8>| !b.is_empty()),
+        ann("Unknown addresses", auto()).doc(
+            "Addresses of unknown type. Descriptors following an unknown type cannot be parsed.",
+        ),
+    ))(s)?;
+
+    Ok((s, addresses))
+}
+
 pub fn channel_update(s: Span) -> Parsed<()> {
-    let (s, _) = value(258, be_u16)(s)?;
+    let (s, _) = message_type(258)(s)?;
+    channel_update_body(s)
+}
+
+/// Parser of `channel_update` fields that follow the message type.
+pub fn channel_update_body(s: Span) -> Parsed<()> {
     let (s, _) = parse(signature, ann("Signature", auto()))(s)?;
     let (s, _) = parse(chain_hash_be, ann("Chain hash", auto()))(s)?;
-    let (s, _) = parse(short_channel_id, ann("Short channel ID", auto()))(s)?;
+    let (s, _) = parse(
+        short_channel_id,
+        ann("Short channel ID", auto()).category(Category::Identifier),
+    )(s)?;
     let (s, _) = parse(timestamp(be_u32), ann("Timestamp", auto()))(s)?;
     let (s, _) = parse(
         flags(
@@ -111,17 +247,27 @@ pub fn channel_update(s: Span) -> Parsed<()> {
     let (s, _) = parse(
         be_u16,
         ann("CLTV expiry delta", auto())
+            .category(Category::Locktime)
+            .unit(Unit::Blocks)
             .doc("Number of blocks to substract from incoming HTLCs' cltv_expiry."),
     )(s)?;
     let (s, _) = parse(
-        be_u64,
+        map(be_u64, MSat),
         ann("HTLC minimum msat", auto())
             .doc("Minimum HTLC value in millisatoshi that the channel peer will accept."),
     )(s)?;
-    let (s, _) = parse(be_u32, ann("Fee base msat", auto()))(s)?;
-    let (s, _) = parse(be_u32, ann("Fee proportional millionths", auto()))(s)?;
     let (s, _) = parse(
-        be_u64,
+        map(be_u32, |n| MSat(n.into())),
+        ann("Fee base msat", auto()).category(Category::Fee),
+    )(s)?;
+    let (s, _) = parse(
+        be_u32,
+        ann("Fee proportional millionths", auto())
+            .unit(Unit::Ppm)
+            .category(Category::Fee),
+    )(s)?;
+    let (s, _) = parse(
+        map(be_u64, MSat),
         ann("HTLC maximum msat", auto()).doc(
             "Maximum value in millisatoshi that the channel peer will send for a single HTLC.",
         ),
@@ -130,32 +276,142 @@ pub fn channel_update(s: Span) -> Parsed<()> {
 }
 
 pub fn channel_announcement(s: Span) -> Parsed<()> {
-    let (s, _) = value(256, be_u16)(s)?;
-    let (s, _node_signature_1) = parse(signature, ann("Node signature 1", auto()))(s)?;
-    let (s, _node_signature_2) = parse(signature, ann("Node signature 2", auto()))(s)?;
-    let (s, _bitcoin_signature_1) = parse(signature, ann("Bitcoin signature 1", auto()))(s)?;
-    let (s, _bitcoin_signature_2) = parse(signature, ann("Bitcoin signature 2", auto()))(s)?;
+    let (s, _) = message_type(256)(s)?;
+    let (s, node_signature_1) = parse(signature, ann("Node signature 1", auto()))(s)?;
+    let node_signature_1_bm = s.bookmark();
+    let (s, node_signature_2) = parse(signature, ann("Node signature 2", auto()))(s)?;
+    let node_signature_2_bm = s.bookmark();
+    let (s, bitcoin_signature_1) = parse(signature, ann("Bitcoin signature 1", auto()))(s)?;
+    let bitcoin_signature_1_bm = s.bookmark();
+    let (s, bitcoin_signature_2) = parse(signature, ann("Bitcoin signature 2", auto()))(s)?;
+    let bitcoin_signature_2_bm = s.bookmark();
+    let signed = s.view();
     let (s, len) = parse(be_u16, ann("Features length", auto()))(s)?;
 
-    let (s, features) = parse(
-        length_count(success(len), u8),
-        ann("Features", |b: &Vec<u8>| Value::bytes(b.to_vec())),
+    let (s, _features) = parse(
+        parse_slice(len, feature_bits),
+        ann("Features", |b: &Vec<u8>| Value::bytes(b.clone())).bolt(9),
     )(s)?;
 
-    let _features = ChannelFeatures::from_le_bytes({
-        let mut b = features;
-        b.reverse();
-        b
+    let (s, _chain_hash) = parse(chain_hash_be, ann("Chain hash", auto()))(s)?;
+    let (s, _scid) = parse(
+        short_channel_id,
+        ann("Short channel ID", auto()).category(Category::Identifier),
+    )(s)?;
+    let (s, node_id_1) = parse(node_id, ann("Node 1 ID", auto()))(s)?;
+    let (s, node_id_2) = parse(node_id, ann("Node 2 ID", auto()))(s)?;
+    let (s, bitcoin_key_1) = parse(public_key, ann("Bitcoin key 1", auto()))(s)?;
+    let (s, bitcoin_key_2) = parse(public_key, ann("Bitcoin key 2", auto()))(s)?;
+    let (s, _) = extra_data(s)?;
+
+    // All four signatures sign the same data, everything that follows them.
+    let signed = &signed[..signed.len() - s.len()];
+    [
+        (
+            &node_signature_1_bm,
+            &node_signature_1,
+            &node_id_1,
+            "node 1",
+        ),
+        (
+            &node_signature_2_bm,
+            &node_signature_2,
+            &node_id_2,
+            "node 2",
+        ),
+        (
+            &bitcoin_signature_1_bm,
+            &bitcoin_signature_1,
+            &bitcoin_key_1,
+            "owner of Bitcoin key 1",
+        ),
+        (
+            &bitcoin_signature_2_bm,
+            &bitcoin_signature_2,
+            &bitcoin_key_2,
+            "owner of Bitcoin key 2",
+        ),
+    ]
+    .into_iter()
+    .for_each(|(bookmark, signature, key, signer)| {
+        signature_check(&s, bookmark, verify_sha256d(signed, signature, key), signer)
     });
 
-    // TODO: print interpeted features
+    Ok((s, ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::{sha256d, Hash};
+    use bitcoin::secp256k1::{Message, Secp256k1, SecretKey};
 
-    let (s, _chain_hash) = parse(chain_hash_be, ann("Chain hash", auto()))(s)?;
-    let (s, _scid) = parse(short_channel_id, ann("Short channel ID", auto()))(s)?;
-    let (s, _node_id_1) = parse(public_key, ann("Node 1 ID", auto()))(s)?;
-    let (s, _node_id_2) = parse(public_key, ann("Node 2 ID", auto()))(s)?;
-    let (s, _bitcoin_key_1) = parse(public_key, ann("Bitcoin key 1", auto()))(s)?;
-    let (s, _bitcoin_key_2) = parse(public_key, ann("Bitcoin key 2", auto()))(s)?;
+    use super::*;
+    use crate::binary::Binary;
 
-    Ok((s, ()))
+    /// Channel announcement shown in README, signed by both nodes and both
+    /// Bitcoin keys.
+    const CHANNEL_ANNOUNCEMENT: &str = concat!(
+        "01002e8faaa0fd5119e595949386f3f2f48090ceebb7048335327eaaf402b522ef4c4a60abd477f6a98395d4",
+        "ab8c30d0e94d05f89200a36713b603ffd51effc007481e2a7e5e31cf4e51ce39fd8268bd3cfd63afb9d7a3ae",
+        "23789b9d5a5250a1413d5aa7e78ad7536fe313a50b9e0db2cc786cdb134b6a5b3a2ec16a63a15290cfc6585c",
+        "55cbe42670a2bdc74d663d38eb894243194b6fd112d3f8e6a57f1d10d2c30700092553319f51d76e1446674b",
+        "e4a65bd696638c7a8983ca09b5a94e35cbd53dceaac31298d3be8ca2db7214a4641079a2ef7406740614eb4e",
+        "3f1c5bb20fda0302c4089fd3b15b8027ff201c475010932b281ec73f9cf83f9816424aad5342000006226e46",
+        "111a0b59caaf126043eb5bbf28c34f3a5e332a1fc7b2b73cf188910f00006c000001000102c5c74c58f37aed",
+        "b64886d0345f732e45d3c9789216913a93a4ab7853dc4e8b0a039d33009dc6b3e36bb1915a0f1aeb6d10e412",
+        "a9b8bc818828746733fef204732702b6be0f40f167e9c0ee495cca63904fbf06cc4d4576df68dd929cf678b1",
+        "3aada00272fa7dcb25e15bae639497920116ec7c47251fcf947b4bbd0f3548b210e88440",
+    );
+
+    /// Values of all verifications of signatures in the tree.
+    fn verifications(data: Vec<u8>, parser: fn(Span) -> Parsed<()>) -> (Vec<String>, usize) {
+        let data = Binary::Raw(data.into());
+        let tree = decode(&data, parser).unwrap();
+        let results = tree
+            .find_by_label("Verification")
+            .iter()
+            .map(|n| n.information().value.plain())
+            .collect();
+        (results, tree.find_by_label("Extra data").len())
+    }
+
+    #[test]
+    fn channel_announcement_signatures() {
+        let data = hex::decode(CHANNEL_ANNOUNCEMENT).unwrap();
+        assert_eq!(
+            verifications(data.clone(), channel_announcement),
+            (vec!["valid".to_string(); 4], 0)
+        );
+
+        // Extra data are signed too, so appending them breaks all signatures.
+        let data = [data, vec![0xab, 0xcd]].concat();
+        assert_eq!(
+            verifications(data, channel_announcement),
+            (vec!["INVALID".to_string(); 4], 1)
+        );
+    }
+
+    #[test]
+    fn node_announcement_signature_covers_extra_data() {
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&[0x11; 32]).unwrap();
+        let body = [
+            hex::decode("0000").unwrap(),
+            hex::decode("5f000000").unwrap(),
+            key.public_key(&secp).serialize().to_vec(),
+            hex::decode("ff8800").unwrap(),
+            b"alice".iter().copied().chain([0; 27]).collect(),
+            hex::decode("0000").unwrap(),
+            hex::decode("abcd").unwrap(),
+        ]
+        .concat();
+        let message = Message::from_digest(sha256d::Hash::hash(&body).to_byte_array());
+        let signature = secp.sign_ecdsa(&message, &key).serialize_compact();
+        let data = [vec![1, 1], signature.to_vec(), body].concat();
+
+        assert_eq!(
+            verifications(data, node_announcement),
+            (vec!["valid".to_string()], 1)
+        );
+    }
 }
@@ -0,0 +1,200 @@
+use bytes::Bytes;
+
+use crate::bitcoin::FeeRate;
+use crate::btc::tx::tx;
+use crate::dsl::{ann, auto};
+use crate::ln::message_type;
+use crate::ln::tlv::*;
+use crate::nom::combinator::{map, success};
+use crate::nom::multi::length_count;
+use crate::nom::number::complete::*;
+use crate::parse::*;
+use crate::tree::Category;
+use crate::types::*;
+use crate::value::{Unit, Value};
+
+fn channel_id(s: Span) -> Parsed<Bytes> {
+    parse(
+        bytes(32usize),
+        ann("Channel ID", auto())
+            .category(Category::Identifier)
+            .doc("ID of the channel which is being funded or spliced."),
+    )(s)
+}
+
+fn serial_id(s: Span) -> Parsed<u64> {
+    parse(
+        be_u64,
+        ann("Serial ID", auto())
+            .doc("ID of the input or output within the negotiation. Even IDs are used by the initiator, odd IDs by the non-initiator.")
+            .splain(|id: &u64| {
+                if id & 1 == 0 {
+                    "Even serial ID, the input or output was added by the initiator.".to_string()
+                } else {
+                    "Odd serial ID, the input or output was added by the non-initiator.".to_string()
+                }
+            }),
+    )(s)
+}
+
+static TX_ADD_INPUT_TLVS: Registry<()> = tlv! {
+    unknown: unknown_value;
+    0 => "Shared input TXID": unknown_value, "ID of the transaction whose output shared by both nodes (the funding output being spliced) is added.";
+};
+
+static TX_SIGNATURES_TLVS: Registry<()> = tlv! {
+    unknown: unknown_value;
+    0 => "Shared input signature": unknown_value, "Signature of the sender for the input shared by both nodes.";
+};
+
+static SPLICE_TLVS: Registry<()> = tlv! {
+    unknown: unknown_value;
+    2 => "Require confirmed inputs": unknown_value, "The sender requires the other node to contribute only confirmed inputs.";
+};
+
+/// Parser of `tx_add_input`.
+pub fn tx_add_input(s: Span) -> Parsed<()> {
+    let (s, _) = message_type(66)(s)?;
+    let (s, _) = channel_id(s)?;
+    let (s, _) = serial_id(s)?;
+    let (s, len) = parse(be_u16, ann("Previous transaction length", auto()))(s)?;
+    let (s, _) = parse(
+        parse_slice(len, tx),
+        ann("Previous transaction", Value::Nil)
+            .doc("Complete transaction whose output is being added as an input."),
+    )(s)?;
+    let (s, _) = parse(
+        be_u32,
+        ann("Previous transaction output", auto())
+            .doc("Index of the output of the previous transaction that is spent."),
+    )(s)?;
+    let (s, _) = parse(
+        be_u32,
+        ann("Sequence", auto())
+            .category(Category::Locktime)
+            .doc("Sequence number of the input."),
+    )(s)?;
+    let (s, _) = tlv_stream(&TX_ADD_INPUT_TLVS)(s)?;
+    Ok((s, ()))
+}
+
+/// Parser of `tx_add_output`.
+pub fn tx_add_output(s: Span) -> Parsed<()> {
+    let (s, _) = message_type(67)(s)?;
+    let (s, _) = channel_id(s)?;
+    let (s, _) = serial_id(s)?;
+    let (s, _) = parse(
+        map(be_u64, |n| Sat::new(n.into())),
+        ann("Amount", auto()).doc("Amount of the output."),
+    )(s)?;
+    let (s, len) = parse(be_u16, ann("Script length", auto()))(s)?;
+    let (s, _) = parse(
+        map(bytes(len), |b| Value::Script(b.to_vec().into())),
+        ann("Script", |v: &Value| v.clone()).doc("Script of the output."),
+    )(s)?;
+    let (s, _) = tlv_stream(&NO_TYPES)(s)?;
+    Ok((s, ()))
+}
+
+/// Parser of `tx_complete`.
+pub fn tx_complete(s: Span) -> Parsed<()> {
+    let (s, _) = message_type(70)(s)?;
+    let (s, _) = channel_id(s)?;
+    let (s, _) = tlv_stream(&NO_TYPES)(s)?;
+    Ok((s, ()))
+}
+
+fn witness(s: Span) -> Parsed<()> {
+    let (s, len) = parse(be_u16, ann("Length", auto()))(s)?;
+    let (s, _) = parse_slice(len, |s| {
+        let (s, cnt) = parse(varint, ann("Count", auto()))(s)?;
+        length_count(
+            map(success(cnt), |c| c as usize),
+            parse(
+                |s| {
+                    let (s, len) = parse(varint, ann("Length", auto()))(s)?;
+                    parse(bytes(len), ann("Witness Data", auto()))(s)
+                },
+                ann("Witness Item", Value::Nil),
+            ),
+        )(s)
+    })(s)?;
+    Ok((s, ()))
+}
+
+/// Parser of `tx_signatures`.
+pub fn tx_signatures(s: Span) -> Parsed<()> {
+    let (s, _) = message_type(71)(s)?;
+    let (s, _) = channel_id(s)?;
+    let (s, _) = parse(
+        txid,
+        ann("TXID", auto())
+            .category(Category::Identifier)
+            .doc("ID of the negotiated transaction being signed."),
+    )(s)?;
+    let (s, _) = parse(
+        length_count(
+            parse(be_u16, ann("Number of witnesses", auto())),
+            parse(
+                with("list", "enumerate", witness),
+                ann("Witness", Value::Nil),
+            ),
+        ),
+        ann("Witnesses", Value::Nil).doc("Witnesses of the sender's inputs, ordered by serial ID."),
+    )(s)?;
+    let (s, _) = tlv_stream(&TX_SIGNATURES_TLVS)(s)?;
+    Ok((s, ()))
+}
+
+fn funding_contribution(s: Span) -> Parsed<i64> {
+    parse(
+        be_i64,
+        ann("Funding contribution satoshis", auto())
+            .unit(Unit::Sat)
+            .category(Category::Amount)
+            .doc(
+            "Amount the sender adds to (if positive) or removes from (if negative) the channel.",
+        ),
+    )(s)
+}
+
+fn funding_pubkey(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        public_key,
+        ann("Funding public key", auto()).doc(
+            "Key of the sender used in the 2-of-2 multisig output of the new funding transaction.",
+        ),
+    )(s)?;
+    Ok((s, ()))
+}
+
+/// Parser of `splice_init`.
+pub fn splice_init(s: Span) -> Parsed<()> {
+    let (s, _) = message_type(80)(s)?;
+    let (s, _) = channel_id(s)?;
+    let (s, _) = funding_contribution(s)?;
+    let (s, _) = parse(
+        map(be_u32, |n| FeeRate::from_sat_per_kwu(n.into())),
+        ann("Funding feerate per kw", auto())
+            .doc("Feerate of the splice transaction in satoshi per 1000 weight units."),
+    )(s)?;
+    let (s, _) = parse(
+        be_u32,
+        ann("Locktime", auto())
+            .category(Category::Locktime)
+            .doc("Locktime of the splice transaction."),
+    )(s)?;
+    let (s, _) = funding_pubkey(s)?;
+    let (s, _) = tlv_stream(&SPLICE_TLVS)(s)?;
+    Ok((s, ()))
+}
+
+/// Parser of `splice_ack`.
+pub fn splice_ack(s: Span) -> Parsed<()> {
+    let (s, _) = message_type(81)(s)?;
+    let (s, _) = channel_id(s)?;
+    let (s, _) = funding_contribution(s)?;
+    let (s, _) = funding_pubkey(s)?;
+    let (s, _) = tlv_stream(&SPLICE_TLVS)(s)?;
+    Ok((s, ()))
+}
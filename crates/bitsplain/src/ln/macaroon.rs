@@ -0,0 +1,386 @@
+use crate::dsl::{ann, auto};
+use crate::nom::combinator::{eof, opt, peek, verify};
+use crate::nom::error::ErrorKind;
+use crate::nom::multi::many0;
+use crate::nom::number::complete::*;
+use crate::nom::Parser;
+use crate::parse::*;
+use crate::types::*;
+use crate::value::{ToValue, Value};
+
+/// Parser of unsigned variable-length integer (LEB128), as used by
+/// macaroons and protocol buffers.
+pub fn uvarint(s: Span) -> Parsed<u64> {
+    let mut s = s;
+    let mut n = 0u64;
+    let mut shift = 0;
+    loop {
+        let (rest, b) = u8(s)?;
+        s = rest;
+        n |= u64::from(b & 0x7f) << shift;
+        if b & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift > 63 {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                s,
+                ErrorKind::TooLarge,
+            )));
+        }
+    }
+    Ok((s.with("datatype", "uvarint"), n))
+}
+
+/// Name of macaroon field type.
+fn field_name(typ: u64) -> &'static str {
+    match typ {
+        0 => "EOS",
+        1 => "Location",
+        2 => "Identifier",
+        4 => "Verification ID",
+        6 => "Signature",
+        _ => "unknown",
+    }
+}
+
+/// Parser of macaroon field of type `typ`, whose content is parsed by `data`.
+fn field<'a, O, P>(typ: u64, mut data: P) -> impl FnMut(Span<'a>) -> Parsed<'a, O>
+where
+    P: Parser<Span<'a>, O, nom::error::Error<Span<'a>>>,
+{
+    move |s: Span<'a>| {
+        let (s, _) = parse(
+            verify(uvarint, |t: &u64| *t == typ),
+            ann("Field type", |t: &u64| {
+                Value::alt(Value::num(*t), Value::text(field_name(*t)))
+            }),
+        )(s)?;
+        let (s, len) = parse(uvarint, ann("Length", auto()))(s)?;
+        parse_slice(len, |s| data.parse(s))(s)
+    }
+}
+
+/// Parser of end of section.
+fn eos(s: Span) -> Parsed<u64> {
+    parse(
+        verify(uvarint, |t: &u64| *t == 0),
+        ann("End of section", auto()).doc("Field type 0 terminates a section."),
+    )(s)
+}
+
+fn text(s: Span) -> Parsed<String> {
+    let (s, bytes) = many0(u8)(s)?;
+    Ok((
+        s.with("datatype", "string"),
+        String::from_utf8_lossy(&bytes).to_string(),
+    ))
+}
+
+fn rest(s: Span) -> Parsed<Vec<u8>> {
+    with("datatype", "bytes", many0(u8))(s)
+}
+
+/// Field of LND's macaroon identifier.
+#[derive(Clone, Debug)]
+pub enum MacaroonId {
+    Nonce(Vec<u8>),
+    StorageId(Vec<u8>),
+    Permission(String, Vec<String>),
+    Other(Vec<u8>),
+}
+
+impl ToValue for MacaroonId {
+    fn to_value(&self) -> Value {
+        match self {
+            MacaroonId::Nonce(b) => Value::bytes(b.clone()),
+            MacaroonId::StorageId(b) => Value::text(String::from_utf8_lossy(b)),
+            MacaroonId::Permission(entity, actions) => {
+                Value::text(format!("{entity}: {}", actions.join(", ")))
+            }
+            MacaroonId::Other(b) => Value::bytes(b.clone()),
+        }
+    }
+}
+
+/// Describe LND entity to which permissions are granted.
+fn describe_entity(entity: &str) -> &'static str {
+    match entity {
+        "onchain" => "on-chain funds and transactions",
+        "offchain" => "channels and off-chain payments",
+        "address" => "on-chain addresses",
+        "message" => "signing and verifying messages",
+        "peers" => "connections to peers",
+        "info" => "general information about the node",
+        "invoices" => "invoices",
+        "signer" => "the signer sub-server",
+        "macaroon" => "macaroons",
+        "uri" => "individual RPC methods",
+        _ => "an unknown entity",
+    }
+}
+
+/// Generate splain for LND permission.
+fn splain_permission(id: &MacaroonId) -> String {
+    match id {
+        MacaroonId::Permission(entity, actions) if entity == "uri" => format!(
+            "Holder may call the following RPC methods: {}.",
+            actions.join(", ")
+        ),
+        MacaroonId::Permission(entity, actions) => format!(
+            "Holder may {} {}.",
+            actions.join(" and "),
+            describe_entity(entity)
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Generate splain for entity of LND permission.
+fn splain_entity(entity: &str) -> String {
+    format!("Permission concerns {}.", describe_entity(entity))
+}
+
+/// Generate splain for action of LND permission.
+fn splain_action(action: &str) -> String {
+    match action {
+        "read" => "Allows read-only access.".to_string(),
+        "write" => "Allows modifications.".to_string(),
+        "generate" => "Allows generating new items (e.g. addresses or macaroons).".to_string(),
+        a if a.starts_with('/') => format!("Allows calling RPC method {a}."),
+        a => format!("Allows action '{a}'."),
+    }
+}
+
+/// Parser of one protobuf-encoded `Op` of LND's macaroon identifier.
+fn lnd_op(s: Span) -> Parsed<(String, Vec<String>)> {
+    let (s, fields) = many0(parse(
+        |s| {
+            let (s, key) = parse(
+                verify(uvarint, |k: &u64| *k == 0x0a || *k == 0x12),
+                ann("Key", auto()).doc("Protobuf key: field number and wire type."),
+            )(s)?;
+            let (s, len) = parse(uvarint, ann("Length", auto()))(s)?;
+            let (label, value_ann) = if key == 0x0a {
+                (
+                    "Entity",
+                    ann("Value", |t: &String| Value::text(t)).splain(|e: &String| splain_entity(e)),
+                )
+            } else {
+                (
+                    "Action",
+                    ann("Value", |t: &String| Value::text(t)).splain(|a: &String| splain_action(a)),
+                )
+            };
+            let (s, value) = parse_slice(len, parse(text, value_ann))(s)?;
+            Ok((s.with("annotation", label), (key, value)))
+        },
+        ann("Field", Value::Nil),
+    ))(s)?;
+
+    let entity = fields
+        .iter()
+        .find(|(k, _)| *k == 0x0a)
+        .map(|(_, v)| v.clone())
+        .unwrap_or_default();
+    let actions = fields
+        .into_iter()
+        .filter(|(k, _)| *k == 0x12)
+        .map(|(_, v)| v)
+        .collect();
+
+    Ok((s, (entity, actions)))
+}
+
+/// Parser of one protobuf field of LND's macaroon identifier.
+fn lnd_id_field(s: Span) -> Parsed<MacaroonId> {
+    let (s, key) = parse(
+        uvarint,
+        ann("Key", auto()).doc("Protobuf key: field number and wire type."),
+    )(s)?;
+    let (s, len) = parse(uvarint, ann("Length", auto()))(s)?;
+    match key {
+        0x0a => {
+            let (s, nonce) = parse_slice(len, parse(rest, ann("Value", auto())))(s)?;
+            Ok((s.with("annotation", "Nonce"), MacaroonId::Nonce(nonce)))
+        }
+        0x12 => {
+            let (s, id) = parse_slice(
+                len,
+                parse(
+                    rest,
+                    ann("Value", |b: &Vec<u8>| {
+                        Value::text(String::from_utf8_lossy(b))
+                    }),
+                ),
+            )(s)?;
+            Ok((
+                s.with("annotation", "Storage ID"),
+                MacaroonId::StorageId(id),
+            ))
+        }
+        0x1a => {
+            let (s, (entity, actions)) =
+                parse_slice(len, parse(lnd_op, ann("Value", Value::Nil)))(s)?;
+            Ok((
+                s.with("annotation", "Permission"),
+                MacaroonId::Permission(entity, actions),
+            ))
+        }
+        _ => {
+            let (s, other) = parse_slice(len, parse(rest, ann("Value", auto())))(s)?;
+            Ok((s, MacaroonId::Other(other)))
+        }
+    }
+}
+
+/// Parser of identifier generated by LND, i. e. version 3 followed by
+/// protobuf-encoded `MacaroonId`.
+fn lnd_identifier(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        verify(u8, |v| *v == 3),
+        ann("Identifier version", auto())
+            .doc("Version of identifier. LND uses version 3, which is followed by protobuf-encoded nonce, storage ID and permissions."),
+    )(s)?;
+    let (s, _) = many0(parse(
+        lnd_id_field,
+        ann("Field", auto()).splain(splain_permission),
+    ))(s)?;
+    let (s, _) = eof(s)?;
+    Ok((s, ()))
+}
+
+fn identifier(s: Span) -> Parsed<()> {
+    nom::branch::alt((lnd_identifier, |s| {
+        let (s, _) = parse(rest, ann("Value", auto()))(s)?;
+        Ok((s, ()))
+    }))(s)
+}
+
+/// Caveat of a macaroon.
+#[derive(Clone, Debug)]
+pub struct Caveat {
+    pub location: Option<String>,
+    pub condition: String,
+    pub third_party: bool,
+}
+
+impl ToValue for Caveat {
+    fn to_value(&self) -> Value {
+        Value::text(&self.condition)
+    }
+}
+
+/// Generate splain for condition of first-party caveat.
+fn splain_condition(condition: &String) -> String {
+    let (name, args) = condition
+        .split_once(' ')
+        .unwrap_or((condition.as_str(), ""));
+
+    match name {
+        "time-before" => format!("Macaroon is valid only before {args}."),
+        "ipaddr" => format!("Macaroon can only be used from IP address {args}."),
+        "ip-range" => format!("Macaroon can only be used from IP range {args}."),
+        "lnd-custom" => {
+            let (custom, value) = args.split_once(' ').unwrap_or((args, ""));
+            format!("Custom caveat '{custom}' with value '{value}', it is not checked by LND itself but by a registered RPC middleware.")
+        }
+        _ => format!("Verifier must satisfy condition '{condition}'."),
+    }
+}
+
+/// Generate splain for caveat.
+fn splain_caveat(caveat: &Caveat) -> String {
+    if caveat.third_party {
+        format!(
+            "Third-party caveat, which has to be discharged by {}.",
+            caveat.location.as_deref().unwrap_or("a third party")
+        )
+    } else {
+        format!(
+            "First-party caveat. {}",
+            splain_condition(&caveat.condition)
+        )
+    }
+}
+
+fn caveat(s: Span) -> Parsed<Caveat> {
+    let (s, _) = peek(verify(uvarint, |t: &u64| *t != 0))(s)?;
+    let (s, location) = opt(parse(
+        field(1, parse(text, ann("Value", |t: &String| Value::text(t)))),
+        ann("Location", |t: &String| Value::text(t))
+            .doc("Hint to the location of the third party that discharges the caveat."),
+    ))(s)?;
+    let (s, condition) = parse(
+        field(
+            2,
+            parse(
+                text,
+                ann("Value", |t: &String| Value::text(t)).splain(splain_condition),
+            ),
+        ),
+        ann("Identifier", |t: &String| Value::text(t))
+            .doc("Condition that must be satisfied for the macaroon to be valid."),
+    )(s)?;
+    let (s, vid) = opt(parse(
+        field(4, parse(rest, ann("Value", auto()))),
+        ann("Verification ID", Value::Nil)
+            .doc("Encrypted root key of the discharge macaroon of a third-party caveat."),
+    ))(s)?;
+    let (s, _) = eos(s)?;
+
+    Ok((
+        s,
+        Caveat {
+            location,
+            condition,
+            third_party: vid.is_some(),
+        },
+    ))
+}
+
+/// Parser of macaroon serialized in binary format version 2.
+pub fn macaroon(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        verify(u8, |v| *v == 2),
+        ann("Version", auto()).doc("Version of serialization format of the macaroon."),
+    )(s)?;
+
+    let (s, _) = parse(
+        |s| {
+            let (s, _) = opt(parse(
+                field(1, parse(text, ann("Value", |t: &String| Value::text(t)))),
+                ann("Location", |t: &String| Value::text(t))
+                    .doc("Hint to the target location, not covered by the signature."),
+            ))(s)?;
+            let (s, _) = parse(
+                field(2, identifier),
+                ann("Identifier", Value::Nil).doc(
+                    "Identifier by which the issuer finds the root key of the macaroon. LND encodes granted permissions into it.",
+                ),
+            )(s)?;
+            eos(s)
+        },
+        ann("Header", Value::Nil),
+    )(s)?;
+
+    let (s, _) = parse(
+        |s| {
+            let (s, _) = many0(parse(
+                with("list", "enumerate", caveat),
+                ann("Caveat", auto()).splain(splain_caveat),
+            ))(s)?;
+            eos(s)
+        },
+        ann("Caveats", Value::Nil).doc("Restrictions of the macaroon's authority."),
+    )(s)?;
+
+    let (s, _) = parse(
+        field(6, parse(bytes(32usize), ann("Value", auto()))),
+        ann("Signature", Value::Nil).doc(
+            "HMAC chain over identifier and all caveats, keyed by the root key of the macaroon.",
+        ),
+    )(s)?;
+
+    Ok((s, ()))
+}
@@ -1,4 +1,9 @@
-use nom::combinator::{map, success};
+use bitcoin::hashes::{sha256d, Hash};
+use bitcoin::secp256k1::ecdsa::Signature;
+use bitcoin::secp256k1::{Message, Secp256k1};
+use bitcoin::PublicKey;
+use nom::combinator::{map, opt, success, verify};
+use nom::multi::many0;
 use nom::number::complete::{be_u32, be_u64};
 
 use crate::dsl::{ann, auto};
@@ -8,7 +13,15 @@ use crate::value::{ToValue, Value};
 use crate::*;
 
 pub mod bolt12;
+pub mod features;
 pub mod gossip;
+pub mod interactive;
+pub mod macaroon;
+pub mod onion;
+pub mod onion_message;
+pub mod scripts;
+pub mod tlv;
+pub mod transport;
 
 pub fn bigsize(s: Span) -> Parsed<u64> {
     let (s, first) = u8(s)?;
@@ -20,6 +33,79 @@ pub fn bigsize(s: Span) -> Parsed<u64> {
     }
 }
 
+/// Parser of type of Lightning Network message, which is expected to be `expected`.
+pub fn message_type<'a>(expected: u16) -> impl Fn(Span<'a>) -> Parsed<'a, u16> {
+    move |s| {
+        parse(
+            |s: Span<'a>| {
+                let (s, typ) = be_u16(s)?;
+                Ok((
+                    s.check(typ == expected, format!("Expected message type {expected}")),
+                    typ,
+                ))
+            },
+            ann("Message Type", auto()).doc("Type of the message, which determines its structure."),
+        )(s)
+    }
+}
+
+/// Bytes left after known fields of a message, which newer versions
+/// of the protocol may add.
+pub fn extra_data(s: Span) -> Parsed<()> {
+    let (s, _) = opt(parse(
+        verify(many0(u8), |b: &Vec<u8>| !b.is_empty()),
+        ann("Extra data", auto())
+            .doc("Data following the known fields, not understood by this decoder."),
+    ))(s)?;
+    Ok((s, ()))
+}
+
+/// Inserts result of verification of signature made by `signer` at the
+/// bookmark, which is usually position of the signature.
+pub fn signature_check(s: &Span, bookmark: &Bookmark, valid: bool, signer: &str) {
+    let splain = if valid {
+        format!("Signature was made by {signer} over exactly these data.")
+    } else {
+        format!("Signature was not made by {signer} over these data. Either the data were altered or they were signed by a different key.")
+    };
+    s.insert_check_at(
+        bookmark,
+        valid,
+        ann(
+            "Verification",
+            Value::text(if valid { "valid" } else { "INVALID" }),
+        )
+        .doc(
+            "Result of verification of the signature against signed data and public key of signer.",
+        )
+        .splain(splain),
+        format!("Signature is not valid signature of {signer}"),
+    );
+}
+
+/// Whether ECDSA signature of double SHA-256 of data is valid, as used
+/// by gossip messages.
+pub fn verify_sha256d(data: &[u8], signature: &Signature, key: &PublicKey) -> bool {
+    let message = Message::from_digest(sha256d::Hash::hash(data).to_byte_array());
+    Secp256k1::verification_only()
+        .verify_ecdsa(&message, signature, &key.inner)
+        .is_ok()
+}
+
+/// Public key identifying node of the Lightning Network, which can be
+/// resolved to alias of the node (see [`with_node_aliases`](crate::enrich::with_node_aliases)).
+pub fn node_id(s: Span) -> Parsed<PublicKey> {
+    with("datatype", "node_id", types::public_key)(s)
+}
+
+/// Parser of truncated unsigned 64-bit integer (`tu64`), i. e. big endian
+/// number with leading zeros omitted. Consumes all the input.
+pub fn tu64(s: Span) -> Parsed<u64> {
+    let (s, bytes) = verify(many0(u8), |b: &Vec<u8>| b.len() <= 8)(s)?;
+    let n = bytes.iter().fold(0u64, |n, b| n << 8 | u64::from(*b));
+    Ok((s.with("datatype", "tu64"), n))
+}
+
 /// Internal representation of short channel ID (SCID). Crate `lightning` normally
 /// uses `u64` representation to which `ShortChannelId` can be converted.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -0,0 +1,466 @@
+use crate::dsl::{ann, auto};
+use crate::ln::gossip::channel_update_body;
+use crate::ln::tlv::*;
+use crate::ln::{bigsize, extra_data, short_channel_id, tu64};
+use crate::nom::combinator::{complete, eof, map, opt, verify};
+use crate::nom::multi::{many0, many1};
+use crate::nom::number::complete::*;
+use crate::nom::sequence::terminated;
+use crate::parse::*;
+use crate::tree::Category;
+use crate::types::*;
+use crate::value::Value;
+
+const BADONION: u16 = 0x8000;
+const PERM: u16 = 0x4000;
+const NODE: u16 = 0x2000;
+const UPDATE: u16 = 0x1000;
+
+/// Name of failure code as defined in BOLT 4.
+fn failure_name(code: u16) -> Option<&'static str> {
+    let name = match code {
+        c if c == PERM | 1 => "invalid_realm",
+        c if c == NODE | 2 => "temporary_node_failure",
+        c if c == PERM | NODE | 2 => "permanent_node_failure",
+        c if c == PERM | NODE | 3 => "required_node_feature_missing",
+        c if c == BADONION | PERM | 4 => "invalid_onion_version",
+        c if c == BADONION | PERM | 5 => "invalid_onion_hmac",
+        c if c == BADONION | PERM | 6 => "invalid_onion_key",
+        c if c == UPDATE | 7 => "temporary_channel_failure",
+        c if c == PERM | 8 => "permanent_channel_failure",
+        c if c == PERM | 9 => "required_channel_feature_missing",
+        c if c == PERM | 10 => "unknown_next_peer",
+        c if c == UPDATE | 11 => "amount_below_minimum",
+        c if c == UPDATE | 12 => "fee_insufficient",
+        c if c == UPDATE | 13 => "incorrect_cltv_expiry",
+        c if c == UPDATE | 14 => "expiry_too_soon",
+        c if c == PERM | 15 => "incorrect_or_unknown_payment_details",
+        18 => "final_incorrect_cltv_expiry",
+        19 => "final_incorrect_htlc_amount",
+        c if c == UPDATE | 20 => "channel_disabled",
+        21 => "expiry_too_far",
+        c if c == PERM | 22 => "invalid_onion_payload",
+        23 => "mpp_timeout",
+        c if c == BADONION | PERM | 24 => "invalid_onion_blinding",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Generate splain for failure code.
+fn splain_failure_code(code: &u16) -> String {
+    let name = failure_name(*code).unwrap_or("unknown failure");
+    let mut flags = vec![];
+    if code & BADONION > 0 {
+        flags.push("the onion could not be parsed by the failing node (BADONION)");
+    }
+    if code & PERM > 0 {
+        flags.push("the failure is permanent (PERM)");
+    }
+    if code & NODE > 0 {
+        flags.push("the failure concerns the node rather than a channel (NODE)");
+    }
+    if code & UPDATE > 0 {
+        flags.push("a new channel update is enclosed (UPDATE)");
+    }
+    if flags.is_empty() {
+        format!("Failure {name} (type {}) without any flags.", code & 0xff)
+    } else {
+        format!(
+            "Failure {name} (type {}): {}.",
+            code & 0xff,
+            flags.join(", ")
+        )
+    }
+}
+
+/// Parser of `channel_update` embedded in a failure message, including its length.
+fn failure_channel_update(s: Span) -> Parsed<()> {
+    let (s, len) = parse(
+        be_u16,
+        ann("Channel update length", auto()).doc("Length of the enclosed channel update. Nodes are no longer required to enclose it, in which case the length is 0."),
+    )(s)?;
+    if len == 0 {
+        return Ok((s, ()));
+    }
+    let doc = "Most recent channel update of the failing channel, which the sender can use to correct its routing.";
+    // Complete message is decoded by its decoder, once the failure is decoded.
+    let (s, update) = opt(parse(
+        embedded(
+            "ln/chan_upd",
+            verify(bytes(len), |b: &[u8]| b.starts_with(&[1, 2])),
+        ),
+        ann("Channel update", auto()).doc(doc),
+    ))(s)?;
+    if update.is_some() {
+        return Ok((s, ()));
+    }
+    // Some implementations omit message type.
+    let (s, _) = parse(
+        parse_slice(len, |s| {
+            let (s, _) = channel_update_body(s)?;
+            extra_data(s)
+        }),
+        ann("Channel update", Value::Nil).doc(doc),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn htlc_msat(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        map(be_u64, MSat),
+        ann("HTLC amount msat", auto()).doc("Amount of the HTLC that caused the failure."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn htlc_msat_update(s: Span) -> Parsed<()> {
+    let (s, _) = htlc_msat(s)?;
+    failure_channel_update(s)
+}
+
+fn cltv_expiry_update(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        be_u32,
+        ann("CLTV expiry", auto())
+            .category(Category::Locktime)
+            .doc("CLTV expiry of the HTLC that caused the failure."),
+    )(s)?;
+    failure_channel_update(s)
+}
+
+fn channel_disabled(s: Span) -> Parsed<()> {
+    let (s, _) = parse(be_u16, ann("Disabled flags", auto()))(s)?;
+    failure_channel_update(s)
+}
+
+fn sha256_of_onion(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        bytes(32usize),
+        ann("SHA256 of onion", auto())
+            .doc("Hash of the onion that the failing node was unable to process."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn payment_details(s: Span) -> Parsed<()> {
+    let (s, _) = htlc_msat(s)?;
+    let (s, _) = parse(
+        be_u32,
+        ann("Block height", auto()).doc("Current block height as seen by the final node."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn final_cltv_expiry(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        be_u32,
+        ann("CLTV expiry", auto())
+            .category(Category::Locktime)
+            .doc("CLTV expiry of the incoming HTLC."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn final_htlc_amount(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        map(be_u64, MSat),
+        ann("Incoming HTLC amount", auto()).doc("Amount of the incoming HTLC in millisatoshi."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn invalid_onion_payload(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        bigsize,
+        ann("Type", auto()).doc("Type of the TLV record that caused the failure."),
+    )(s)?;
+    let (s, _) = parse(
+        be_u16,
+        ann("Offset", auto()).doc("Offset in the decrypted payload where the failure occurred."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn no_data(s: Span) -> Parsed<()> {
+    Ok((s, ()))
+}
+
+fn unknown_data(s: Span) -> Parsed<()> {
+    let (s, _) = opt(parse(
+        verify(many0(u8), |b: &Vec<u8>| !b.is_empty()),
+        ann("Failure data", auto()),
+    ))(s)?;
+    Ok((s, ()))
+}
+
+/// Data of unknown failure whose flag UPDATE says that it encloses channel
+/// update, which is decoded if the data are nothing else.
+fn unknown_update_data(s: Span) -> Parsed<()> {
+    let (s, _) = opt(parse(
+        embedded(
+            "ln/chan_upd",
+            verify(many0(u8), |b: &Vec<u8>| !b.is_empty()),
+        ),
+        ann("Failure data", auto()),
+    ))(s)?;
+    Ok((s, ()))
+}
+
+/// Parser of failure message, i. e. failure code followed by code-specific data.
+pub fn failure_message(s: Span) -> Parsed<u16> {
+    let (s, code) = parse(
+        flags(
+            be_u16,
+            &[
+                (
+                    15,
+                    ann("BADONION", auto()).doc("Unparsable onion encrypted by sending peer."),
+                ),
+                (
+                    14,
+                    ann("PERM", auto()).doc("Permanent failure (otherwise transient)."),
+                ),
+                (
+                    13,
+                    ann("NODE", auto()).doc("Node failure (otherwise channel)."),
+                ),
+                (
+                    12,
+                    ann("UPDATE", auto()).doc("New channel update enclosed."),
+                ),
+            ],
+        ),
+        ann("Failure code", |c: &u16| {
+            Value::alt(
+                Value::num(*c),
+                Value::text(failure_name(*c).unwrap_or("unknown")),
+            )
+        })
+        .splain(splain_failure_code),
+    )(s)?;
+
+    let data = match code {
+        c if c == BADONION | PERM | 4
+            || c == BADONION | PERM | 5
+            || c == BADONION | PERM | 6
+            || c == BADONION | PERM | 24 =>
+        {
+            sha256_of_onion
+        }
+        c if c == UPDATE | 7 || c == UPDATE | 14 => failure_channel_update,
+        c if c == UPDATE | 11 || c == UPDATE | 12 => htlc_msat_update,
+        c if c == UPDATE | 13 => cltv_expiry_update,
+        c if c == UPDATE | 20 => channel_disabled,
+        c if c == PERM | 15 => payment_details,
+        18 => final_cltv_expiry,
+        19 => final_htlc_amount,
+        c if c == PERM | 22 => invalid_onion_payload,
+        c if failure_name(c).is_some() => no_data,
+        c if c & UPDATE != 0 => unknown_update_data,
+        _ => unknown_data,
+    };
+
+    let (s, _) = data(s)?;
+    let (s, _) = extra_data(s)?;
+
+    Ok((s, code))
+}
+
+/// Parser of decrypted onion error packet as returned by a failing node.
+pub fn failure_packet(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        bytes(32usize),
+        ann("HMAC", auto())
+            .doc("HMAC of the rest of the packet, keyed by the shared secret of the failing node."),
+    )(s)?;
+    let (s, len) = parse(
+        be_u16,
+        ann("Failure length", auto()).doc("Length of the failure message."),
+    )(s)?;
+    let (s, _) = parse(
+        parse_slice(len, failure_message),
+        ann("Failure message", Value::Nil)
+            .doc("Reason of the failure and additional data.")
+            .bolt_section(4, "returning-errors"),
+    )(s)?;
+    let (s, pad_len) = parse(
+        be_u16,
+        ann("Padding length", auto()).doc("Length of the padding."),
+    )(s)?;
+    let (s, _) = parse(
+        bytes(pad_len),
+        ann("Padding", auto()).doc("Padding that hides the length of the failure message. All error packets should have the same size."),
+    )(s)?;
+
+    Ok((s, ()))
+}
+
+/// TLV type of keysend preimage, which is not part of BOLTs but is widely
+/// used for spontaneous payments.
+const KEYSEND: u64 = 5482373484;
+
+/// Records of per-hop payload.
+static HOP_TLVS: Registry<()> = tlv! {
+    unknown: unknown_value;
+    2 => "Amount to forward": amt_to_forward;
+    4 => "Outgoing CLTV value": outgoing_cltv_value;
+    6 => "Short channel ID": hop_short_channel_id;
+    8 => "Payment data": payment_data;
+    10 => "Encrypted recipient data": encrypted_recipient_data;
+    12 => "Current path key": current_path_key;
+    16 => "Payment metadata": payment_metadata;
+    18 => "Total amount msat": total_amount_msat;
+    KEYSEND => "Keysend preimage": keysend_preimage;
+};
+
+fn amt_to_forward(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        map(tu64, MSat),
+        ann("Value", auto()).doc("Amount in millisatoshi to forward to the next hop or, for the final node, the amount to receive."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn outgoing_cltv_value(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        tu64,
+        ann("Value", auto()).category(Category::Locktime).doc("CLTV expiry to use for the outgoing HTLC or, for the final node, the expected CLTV expiry."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn hop_short_channel_id(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        short_channel_id,
+        ann("Value", auto())
+            .doc("Channel which should be used to forward the payment to the next hop."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn payment_data(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        bytes(32usize),
+        ann("Payment secret", auto())
+            .doc("Secret from the invoice that proves the sender knows the invoice."),
+    )(s)?;
+    let (s, _) = parse(
+        map(tu64, MSat),
+        ann("Total amount msat", auto())
+            .doc("Total amount of the payment, which may be split into several parts."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn encrypted_recipient_data(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        many0(u8),
+        ann("Value", auto())
+            .doc("Data of a blinded path, encrypted for this hop by the recipient."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn current_path_key(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        public_key,
+        ann("Value", auto()).doc("Key used to decrypt the encrypted recipient data when the hop is entry of a blinded path."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn payment_metadata(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        many0(u8),
+        ann("Value", auto()).doc("Metadata from the invoice that is passed back to the recipient."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn total_amount_msat(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        map(tu64, MSat),
+        ann("Value", auto()).doc("Total amount of a payment received through a blinded path."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn keysend_preimage(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        bytes(32usize),
+        ann("Value", auto())
+            .doc("Payment preimage chosen by the sender of a spontaneous payment.")
+            .spec(
+                "bLIP 3",
+                "https://github.com/lightning/blips/blob/master/blip-0003.md",
+                None,
+            ),
+    )(s)?;
+    Ok((s, ()))
+}
+
+/// Parser of decrypted per-hop payload of payment onion, i. e. length
+/// followed by TLV stream and, optionally, HMAC for the next hop.
+pub fn hop_payload(s: Span) -> Parsed<()> {
+    let (s, len) = parse(
+        bigsize,
+        ann("Length", auto()).doc("Length of the TLV stream of the payload."),
+    )(s)?;
+    let (s, _) = parse(
+        parse_slice(
+            len,
+            terminated(
+                verify(
+                    many1(parse(tlv_record(&HOP_TLVS), ann("TLV Record", Value::Nil))),
+                    |records: &Vec<(u64, ())>| {
+                        let types = records.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+                        types.windows(2).all(|w| w[0] < w[1])
+                            && (types.contains(&2) && types.contains(&4) || types.contains(&10))
+                    },
+                ),
+                eof,
+            ),
+        ),
+        ann("TLV Stream", Value::Nil).bolt_section(4, "payload-format"),
+    )(s)?;
+    let (s, _) = opt(parse(
+        complete(bytes(32usize)),
+        ann("HMAC", auto())
+            .doc("HMAC of the onion packet for the next hop. All zeros if this is the final node."),
+    ))(s)?;
+    let (s, _) = eof(s)?;
+
+    Ok((s, ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::Binary;
+
+    #[test]
+    fn failure_message_with_extra_data() {
+        // amount_below_minimum: HTLC amount, empty channel update and two extra bytes.
+        let data = Binary::Raw(hex::decode("100b00000000000003e80000abcd").unwrap().into());
+        let tree = decode(&data, failure_message).unwrap();
+        assert_eq!(tree.find_by_label("Extra data").len(), 1);
+
+        // temporary_channel_failure: channel update without message type
+        // followed by an extra byte within its length.
+        let data = Binary::Raw(
+            hex::decode(concat!(
+                "10070089",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "6fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000",
+                "0000010000010000660000000100002800000000000000000000",
+                "03e8000000010000000005f5e100ff",
+            ))
+            .unwrap()
+            .into(),
+        );
+        let tree = decode(&data, failure_message).unwrap();
+        assert_eq!(tree.find_by_label("Extra data").len(), 1);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::dsl::{ann, auto};
+use crate::ln::bolt12::{bolt12, invoice_error, path};
+use crate::ln::tlv::*;
+use crate::ln::{bigsize, message_type};
+use crate::nom::combinator::{complete, eof, opt, verify};
+use crate::nom::multi::{many0, many1};
+use crate::nom::number::complete::*;
+use crate::nom::sequence::terminated;
+use crate::parse::*;
+use crate::types::*;
+use crate::value::Value;
+
+/// Parser of onion packet carried by `onion_message`. Length of the whole
+/// packet has to be known upfront as hop payloads are of variable size.
+fn onion_message_packet(len: u16) -> impl Fn(Span) -> Parsed<()> {
+    move |s| {
+        let (s, _) = parse(
+            verify(u8, |v| *v == 0),
+            ann("Version", auto()).doc("Version of the onion packet. Currently only 0 is defined."),
+        )(s)?;
+        let (s, _) = parse(
+            public_key,
+            ann("Public key", auto())
+                .doc("Ephemeral key from which the first node derives the shared secret."),
+        )(s)?;
+        let (s, _) = parse(
+            bytes(len.saturating_sub(66)),
+            ann("Onion message payloads", auto()).doc(
+                "Encrypted payloads of all the hops. Each node can decrypt only its own payload.",
+            ),
+        )(s)?;
+        let (s, _) = parse(
+            bytes(32usize),
+            ann("HMAC", auto()).doc("HMAC of the packet for the first node."),
+        )(s)?;
+        Ok((s, ()))
+    }
+}
+
+/// Parser of `onion_message`.
+pub fn onion_message(s: Span) -> Parsed<()> {
+    let (s, _) = message_type(513)(s)?;
+    let (s, _) = parse(
+        public_key,
+        ann("Path key", auto())
+            .doc("Blinding point from which the receiving node derives the key to decrypt its part of the blinded path."),
+    )(s)?;
+    let (s, len) = parse(
+        verify(be_u16, |l| *l >= 66),
+        ann("Onion message packet length", auto()),
+    )(s)?;
+    let (s, _) = parse(
+        parse_slice(len, onion_message_packet(len)),
+        ann("Onion message packet", Value::Nil).bolt_section(4, "onion-messages"),
+    )(s)?;
+    let (s, _) = eof(s)?;
+
+    Ok((s, ()))
+}
+
+/// Records of `onionmsg_tlv`.
+static ONIONMSG_TLVS: Registry<()> = tlv! {
+    unknown: unknown_value;
+    2 => "Reply path": reply_path;
+    4 => "Encrypted recipient data": encrypted_recipient_data;
+    64 => "Invoice request": invoice_request;
+    66 => "Invoice": invoice;
+    68 => "Invoice error": onionmsg_invoice_error;
+};
+
+fn reply_path(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        path,
+        ann("Value", Value::Nil).doc("Blinded path through which the recipient can send a reply."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn encrypted_recipient_data(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        many0(u8),
+        ann("Value", auto())
+            .doc("Data of a blinded path, encrypted for this hop by the recipient."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn invoice_request(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        bolt12,
+        ann("Value", Value::Nil).doc("Invoice request for an offer."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn invoice(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        bolt12,
+        ann("Value", Value::Nil).doc("Invoice in response to an invoice request."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+fn onionmsg_invoice_error(s: Span) -> Parsed<()> {
+    let (s, _) = parse(
+        invoice_error,
+        ann("Value", Value::Nil).doc("Error in response to an invalid invoice request or invoice."),
+    )(s)?;
+    Ok((s, ()))
+}
+
+/// Parser of decrypted payload of onion message, i. e. length followed
+/// by `onionmsg_tlv` stream and, optionally, HMAC for the next hop.
+pub fn onion_message_payload(s: Span) -> Parsed<()> {
+    let (s, len) = parse(
+        bigsize,
+        ann("Length", auto()).doc("Length of the TLV stream of the payload."),
+    )(s)?;
+    let (s, _) = parse(
+        parse_slice(
+            len,
+            terminated(
+                verify(
+                    many1(parse(
+                        tlv_record(&ONIONMSG_TLVS),
+                        ann("TLV Record", Value::Nil),
+                    )),
+                    |records: &Vec<(u64, ())>| {
+                        records.windows(2).all(|w| w[0].0 < w[1].0)
+                            && records
+                                .iter()
+                                .all(|(t, _)| t % 2 == 1 || ONIONMSG_TLVS.knows(*t))
+                    },
+                ),
+                eof,
+            ),
+        ),
+        ann("TLV Stream", Value::Nil).bolt_section(4, "onion-messages"),
+    )(s)?;
+    let (s, _) = opt(parse(
+        complete(bytes(32usize)),
+        ann("HMAC", auto())
+            .doc("HMAC of the onion packet for the next hop. All zeros if this is the final node."),
+    ))(s)?;
+    let (s, _) = eof(s)?;
+
+    Ok((s, ()))
+}
@@ -0,0 +1,363 @@
+use crate::bitcoin::opcodes::all::*;
+use crate::bitcoin::opcodes::Opcode;
+use crate::bitcoin::script::Instruction;
+use crate::bitcoin::{PublicKey, Script};
+use crate::dsl::ann;
+use crate::parse::*;
+use crate::tree::Tag;
+use crate::value::{Unit, Value};
+
+/// Element of script template.
+#[derive(Clone, Copy)]
+enum T {
+    /// Exactly this opcode.
+    Op(Opcode),
+    /// Exactly this number.
+    Int(i64),
+    /// Any public key, captured.
+    Key,
+    /// Any 20-byte hash, captured.
+    Hash,
+    /// Any number, captured.
+    Num,
+}
+
+enum Captured {
+    Key(PublicKey),
+    Hash(Vec<u8>),
+    Num(i64),
+}
+
+/// Match script against template and return captured elements.
+fn capture(script: &Script, template: &[T]) -> Option<Vec<Captured>> {
+    let instructions = script.instructions().collect::<Result<Vec<_>, _>>().ok()?;
+    if instructions.len() != template.len() {
+        return None;
+    }
+
+    instructions
+        .iter()
+        .zip(template)
+        .try_fold(vec![], |mut captured, (i, t)| {
+            match (t, i) {
+                (T::Op(op), Instruction::Op(o)) if op == o => {}
+                (T::Int(n), i) if i.script_num() == Some(*n) => {}
+                (T::Key, Instruction::PushBytes(b)) => {
+                    captured.push(Captured::Key(PublicKey::from_slice(b.as_bytes()).ok()?))
+                }
+                (T::Hash, Instruction::PushBytes(b)) if b.len() == 20 => {
+                    captured.push(Captured::Hash(b.as_bytes().to_vec()))
+                }
+                (T::Num, i) => captured.push(Captured::Num(i.script_num()?)),
+                _ => return None,
+            }
+            Some(captured)
+        })
+}
+
+const TO_LOCAL: &[T] = &[
+    T::Op(OP_IF),
+    T::Key,
+    T::Op(OP_ELSE),
+    T::Num,
+    T::Op(OP_CSV),
+    T::Op(OP_DROP),
+    T::Key,
+    T::Op(OP_ENDIF),
+    T::Op(OP_CHECKSIG),
+];
+
+const TO_REMOTE: &[T] = &[T::Key, T::Op(OP_CHECKSIGVERIFY), T::Int(1), T::Op(OP_CSV)];
+
+const ANCHOR: &[T] = &[
+    T::Key,
+    T::Op(OP_CHECKSIG),
+    T::Op(OP_IFDUP),
+    T::Op(OP_NOTIF),
+    T::Int(16),
+    T::Op(OP_CSV),
+    T::Op(OP_ENDIF),
+];
+
+const OFFERED_HTLC: &[T] = &[
+    T::Op(OP_DUP),
+    T::Op(OP_HASH160),
+    T::Hash,
+    T::Op(OP_EQUAL),
+    T::Op(OP_IF),
+    T::Op(OP_CHECKSIG),
+    T::Op(OP_ELSE),
+    T::Key,
+    T::Op(OP_SWAP),
+    T::Op(OP_SIZE),
+    T::Int(32),
+    T::Op(OP_EQUAL),
+    T::Op(OP_NOTIF),
+    T::Op(OP_DROP),
+    T::Int(2),
+    T::Op(OP_SWAP),
+    T::Key,
+    T::Int(2),
+    T::Op(OP_CHECKMULTISIG),
+    T::Op(OP_ELSE),
+    T::Op(OP_HASH160),
+    T::Hash,
+    T::Op(OP_EQUALVERIFY),
+    T::Op(OP_CHECKSIG),
+    T::Op(OP_ENDIF),
+];
+
+const RECEIVED_HTLC: &[T] = &[
+    T::Op(OP_DUP),
+    T::Op(OP_HASH160),
+    T::Hash,
+    T::Op(OP_EQUAL),
+    T::Op(OP_IF),
+    T::Op(OP_CHECKSIG),
+    T::Op(OP_ELSE),
+    T::Key,
+    T::Op(OP_SWAP),
+    T::Op(OP_SIZE),
+    T::Int(32),
+    T::Op(OP_EQUAL),
+    T::Op(OP_IF),
+    T::Op(OP_HASH160),
+    T::Hash,
+    T::Op(OP_EQUALVERIFY),
+    T::Int(2),
+    T::Op(OP_SWAP),
+    T::Key,
+    T::Int(2),
+    T::Op(OP_CHECKMULTISIG),
+    T::Op(OP_ELSE),
+    T::Op(OP_DROP),
+    T::Num,
+    T::Op(OP_CLTV),
+    T::Op(OP_DROP),
+    T::Op(OP_CHECKSIG),
+    T::Op(OP_ENDIF),
+];
+
+/// Suffix of HTLC scripts in channels with anchor outputs, which
+/// delays spending by one block.
+const ANCHORS_SUFFIX: &[T] = &[T::Int(1), T::Op(OP_CSV), T::Op(OP_DROP)];
+
+/// Match HTLC script with or without anchors suffix. Returns captured
+/// elements and whether the suffix was present.
+fn capture_htlc(script: &Script, template: &[T]) -> Option<(Vec<Captured>, bool)> {
+    let plain = [template, &[T::Op(OP_ENDIF)]].concat();
+    let anchors = [template, ANCHORS_SUFFIX, &[T::Op(OP_ENDIF)]].concat();
+    capture(script, &plain)
+        .map(|c| (c, false))
+        .or_else(|| capture(script, &anchors).map(|c| (c, true)))
+}
+
+/// Script of Lightning Network commitment transaction output as defined in BOLT 3.
+#[derive(Debug, Clone)]
+pub enum LnScript {
+    ToLocal {
+        revocation_key: PublicKey,
+        to_self_delay: i64,
+        delayed_key: PublicKey,
+    },
+    ToRemote {
+        remote_key: PublicKey,
+    },
+    Anchor {
+        funding_key: PublicKey,
+    },
+    OfferedHtlc {
+        revocation_hash: Vec<u8>,
+        remote_htlc_key: PublicKey,
+        local_htlc_key: PublicKey,
+        payment_hash: Vec<u8>,
+        anchors: bool,
+    },
+    ReceivedHtlc {
+        revocation_hash: Vec<u8>,
+        remote_htlc_key: PublicKey,
+        local_htlc_key: PublicKey,
+        payment_hash: Vec<u8>,
+        cltv_expiry: i64,
+        anchors: bool,
+    },
+}
+
+impl LnScript {
+    /// Try to recognize script as one of the commitment transaction scripts.
+    pub fn recognize(script: &Script) -> Option<LnScript> {
+        use Captured::*;
+
+        if let Some([Key(revocation_key), Num(to_self_delay), Key(delayed_key)]) =
+            capture(script, TO_LOCAL).as_deref()
+        {
+            return Some(LnScript::ToLocal {
+                revocation_key: *revocation_key,
+                to_self_delay: *to_self_delay,
+                delayed_key: *delayed_key,
+            });
+        }
+
+        if let Some([Key(remote_key)]) = capture(script, TO_REMOTE).as_deref() {
+            return Some(LnScript::ToRemote {
+                remote_key: *remote_key,
+            });
+        }
+
+        if let Some([Key(funding_key)]) = capture(script, ANCHOR).as_deref() {
+            return Some(LnScript::Anchor {
+                funding_key: *funding_key,
+            });
+        }
+
+        if let Some((captured, anchors)) = capture_htlc(script, OFFERED_HTLC) {
+            if let [Hash(revocation_hash), Key(remote_htlc_key), Key(local_htlc_key), Hash(payment_hash)] =
+                captured.as_slice()
+            {
+                return Some(LnScript::OfferedHtlc {
+                    revocation_hash: revocation_hash.clone(),
+                    remote_htlc_key: *remote_htlc_key,
+                    local_htlc_key: *local_htlc_key,
+                    payment_hash: payment_hash.clone(),
+                    anchors,
+                });
+            }
+        }
+
+        if let Some((captured, anchors)) = capture_htlc(script, RECEIVED_HTLC) {
+            if let [Hash(revocation_hash), Key(remote_htlc_key), Hash(payment_hash), Key(local_htlc_key), Num(cltv_expiry)] =
+                captured.as_slice()
+            {
+                return Some(LnScript::ReceivedHtlc {
+                    revocation_hash: revocation_hash.clone(),
+                    remote_htlc_key: *remote_htlc_key,
+                    local_htlc_key: *local_htlc_key,
+                    payment_hash: payment_hash.clone(),
+                    cltv_expiry: *cltv_expiry,
+                    anchors,
+                });
+            }
+        }
+
+        None
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LnScript::ToLocal { .. } => "to_local",
+            LnScript::ToRemote { .. } => "to_remote",
+            LnScript::Anchor { .. } => "anchor",
+            LnScript::OfferedHtlc { .. } => "offered HTLC",
+            LnScript::ReceivedHtlc { .. } => "received HTLC",
+        }
+    }
+
+    fn splain(&self) -> &'static str {
+        match self {
+            LnScript::ToLocal { .. } => "Output of commitment transaction paying to its owner. It can be spent by the owner after the to-self delay, or immediately by the counterparty with the revocation key if the commitment was revoked.",
+            LnScript::ToRemote { .. } => "Output of commitment transaction paying to the counterparty, spendable one block after confirmation.",
+            LnScript::Anchor { .. } => "Anchor output that allows either party to bump the fee of commitment transaction by CPFP. After 16 blocks anyone can spend it.",
+            LnScript::OfferedHtlc { .. } => "HTLC offered by the owner of commitment transaction. The counterparty can claim it with the payment preimage, otherwise it times out back to the owner through HTLC-timeout transaction.",
+            LnScript::ReceivedHtlc { .. } => "HTLC received by the owner of commitment transaction. The owner can claim it with the payment preimage through HTLC-success transaction, otherwise it times out back to the counterparty after CLTV expiry.",
+        }
+    }
+
+    /// Tag marking group containing the script.
+    pub fn tag(&self) -> Tag {
+        Tag {
+            label: format!("LN {}", self.name()),
+            color: None,
+            doc: Some(self.splain().to_string()),
+        }
+    }
+
+    /// Insert virtual leaves describing the script and its keys and timeouts
+    /// after the last parsed leaf.
+    pub fn annotate(&self, s: &Span) {
+        s.insert(
+            ann("Lightning script", Value::text(self.name()))
+                .splain(self.splain())
+                .bolt(3),
+        );
+
+        match self {
+            LnScript::ToLocal {
+                revocation_key,
+                to_self_delay,
+                delayed_key,
+            } => {
+                s.insert(
+                    ann("Revocation public key", Value::PublicKey(*revocation_key))
+                        .doc("Key with which the counterparty can spend the output immediately if the commitment was revoked."),
+                );
+                s.insert(
+                    ann("To-self delay", Value::num(*to_self_delay))
+                        .unit(Unit::Blocks)
+                        .doc("Number of blocks the owner has to wait before spending the output."),
+                );
+                s.insert(
+                    ann("Local delayed public key", Value::PublicKey(*delayed_key)).doc(
+                        "Key with which the owner can spend the output after the to-self delay.",
+                    ),
+                );
+            }
+            LnScript::ToRemote { remote_key } => {
+                s.insert(
+                    ann("Remote public key", Value::PublicKey(*remote_key))
+                        .doc("Key of the counterparty."),
+                );
+            }
+            LnScript::Anchor { funding_key } => {
+                s.insert(
+                    ann("Funding public key", Value::PublicKey(*funding_key))
+                        .doc("Funding key of the party to which the anchor belongs."),
+                );
+            }
+            LnScript::OfferedHtlc {
+                revocation_hash,
+                remote_htlc_key,
+                local_htlc_key,
+                payment_hash,
+                anchors,
+            }
+            | LnScript::ReceivedHtlc {
+                revocation_hash,
+                remote_htlc_key,
+                local_htlc_key,
+                payment_hash,
+                anchors,
+                ..
+            } => {
+                s.insert(
+                    ann("Revocation public key hash", Value::bytes(revocation_hash.clone()))
+                        .doc("RIPEMD160(SHA256) of the revocation public key, with which the counterparty can spend the output if the commitment was revoked."),
+                );
+                s.insert(
+                    ann("Remote HTLC public key", Value::PublicKey(*remote_htlc_key))
+                        .doc("HTLC key of the counterparty."),
+                );
+                s.insert(
+                    ann("Local HTLC public key", Value::PublicKey(*local_htlc_key))
+                        .doc("HTLC key of the owner of commitment transaction."),
+                );
+                s.insert(
+                    ann("Payment hash RIPEMD160", Value::bytes(payment_hash.clone()))
+                        .doc("RIPEMD160 of the payment hash. The HTLC can be claimed by revealing its preimage."),
+                );
+                if let LnScript::ReceivedHtlc { cltv_expiry, .. } = self {
+                    s.insert(
+                        ann("CLTV expiry", Value::num(*cltv_expiry)).doc(
+                            "Block height after which the counterparty can claim the HTLC back.",
+                        ),
+                    );
+                }
+                if *anchors {
+                    s.insert(
+                        ann("Anchors", Value::text("yes"))
+                            .doc("Script contains 1 OP_CHECKSEQUENCEVERIFY, which is used in channels with anchor outputs."),
+                    );
+                }
+            }
+        }
+    }
+}
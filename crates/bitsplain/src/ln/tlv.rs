@@ -0,0 +1,132 @@
+//! Streams of TLV (type-length-value) records, which are used by many
+//! Lightning messages and payloads. Each of them defines its own types
+//! of records in a [`Registry`].
+
+use crate::dsl::{ann, auto};
+use crate::ln::bigsize;
+use crate::nom::multi::many0;
+use crate::nom::number::complete::u8;
+use crate::parse::*;
+use crate::value::Value;
+
+/// Known type of TLV record.
+pub struct TlvType<T: 'static> {
+    /// Number of the type.
+    pub typ: u64,
+    /// Name of the record, which is used as its annotation.
+    pub name: &'static str,
+    /// Documentation of the record.
+    pub doc: Option<&'static str>,
+    /// Parser of value of the record.
+    pub value: fn(Span) -> Parsed<T>,
+}
+
+/// Types of records of a TLV stream.
+pub struct Registry<T: 'static> {
+    /// Known types.
+    pub types: &'static [TlvType<T>],
+    /// Parser of value of records whose types are not known.
+    pub unknown: fn(Span) -> Parsed<T>,
+}
+
+impl<T> Registry<T> {
+    fn get(&self, typ: u64) -> Option<&TlvType<T>> {
+        self.types.iter().find(|t| t.typ == typ)
+    }
+
+    /// Whether the type is known.
+    pub fn knows(&self, typ: u64) -> bool {
+        self.get(typ).is_some()
+    }
+
+    /// Name of record of the type.
+    pub fn name(&self, typ: u64) -> &'static str {
+        self.get(typ).map(|t| t.name).unwrap_or(match typ % 2 {
+            0 => "Unknown even type",
+            _ => "Unknown odd type",
+        })
+    }
+}
+
+/// Defines [`Registry`] of TLV types. The first line names parser of values
+/// of unknown types, each following line defines one known type:
+///
+/// ```ignore
+/// static TLVS: Registry<()> = tlv! {
+///     unknown: unknown_value;
+///     2 => "Amount to forward": amt_to_forward;
+///     4 => "Outgoing CLTV value": outgoing_cltv_value, "Optional documentation.";
+/// };
+/// ```
+macro_rules! tlv {
+    (@doc) => { None };
+    (@doc $doc: literal) => { Some($doc) };
+    (
+        unknown: $unknown: expr;
+        $( $typ: expr => $name: literal : $value: expr $(, $doc: literal)? ; )*
+    ) => {
+        $crate::ln::tlv::Registry {
+            types: &[
+                $(
+                    $crate::ln::tlv::TlvType {
+                        typ: $typ,
+                        name: $name,
+                        doc: $crate::ln::tlv::tlv!(@doc $($doc)?),
+                        value: $value,
+                    },
+                )*
+            ],
+            unknown: $unknown,
+        }
+    };
+}
+
+pub(crate) use tlv;
+
+/// Parser of value of unknown record, all its bytes.
+pub fn unknown_value(s: Span) -> Parsed<()> {
+    let (s, _) = parse(many0(u8), ann("Value", auto()))(s)?;
+    Ok((s, ()))
+}
+
+/// Registry of streams that do not have any known types.
+pub static NO_TYPES: Registry<()> = Registry {
+    types: &[],
+    unknown: unknown_value,
+};
+
+/// Parser of single TLV record, its type, length and value. Value is parsed
+/// by parser of the type found in `registry`, the record is named after it.
+pub fn tlv_record<'a, T>(
+    registry: &'static Registry<T>,
+) -> impl Fn(Span<'a>) -> Parsed<'a, (u64, T)> {
+    move |s| {
+        let (s, typ) = parse(bigsize, ann("Type", auto()))(s)?;
+        let (s, length) = parse(bigsize, ann("Length", auto()))(s)?;
+
+        let known = registry.get(typ);
+        let value = known.map(|t| t.value).unwrap_or(registry.unknown);
+        let (s, value) = parse_slice(length, value)(s)?;
+
+        let s = s.with("annotation", registry.name(typ));
+        // It's OK to be odd: unknown odd types may be ignored, unknown
+        // even types must not be (BOLT 1).
+        let s = match (known, typ % 2) {
+            (Some(TlvType { doc: Some(doc), .. }), _) => s.with("doc", doc),
+            (Some(_), _) => s,
+            (None, 0) => s
+                .with("doc", "Type of the record is not known. It is even, so the record must be understood and the whole stream would be rejected by a reader that does not know it.")
+                .warn("Unknown even type, the stream would be rejected."),
+            (None, _) => s.with("doc", "Type of the record is not known. It is odd, so the record is optional and may be safely ignored by a reader that does not know it."),
+        };
+
+        Ok((s, (typ, value)))
+    }
+}
+
+/// Parser of TLV stream, i. e. TLV records till the end of input.
+pub fn tlv_stream<'a, T>(
+    registry: &'static Registry<T>,
+) -> impl Fn(Span<'a>) -> Parsed<'a, Vec<(u64, T)>> {
+    move |s| many0(parse(tlv_record(registry), ann("TLV Record", Value::Nil)))(s)
+}
@@ -0,0 +1,72 @@
+use bitcoin::PublicKey;
+use bytes::Bytes;
+
+use crate::dsl::{ann, auto, Splain};
+use crate::nom::combinator::verify;
+use crate::nom::number::complete::u8;
+use crate::parse::*;
+use crate::types::*;
+
+fn version(s: Span) -> Parsed<u8> {
+    parse(
+        verify(u8, |v| *v == 0),
+        ann("Version", auto())
+            .doc("Handshake version. Currently only 0 is defined, any other version causes the connection to be aborted."),
+    )(s)
+}
+
+fn tag(s: Span) -> Parsed<Bytes> {
+    parse(
+        bytes(16usize),
+        ann("Tag", auto())
+            .doc("Poly1305 authentication tag of encryption of empty plaintext, which proves the sender knows the key derived so far."),
+    )(s)
+}
+
+/// Act one or act two of Noise_XK handshake. Both acts have the same
+/// structure and can only be told apart by their direction.
+// Only the annotations of the fields are used so far, not their values.
+#[allow(dead_code)]
+#[derive(Splain)]
+pub struct ActOneTwo {
+    #[splain(parse = version)]
+    pub version: u8,
+    /// Ephemeral key generated by the sender for this handshake only. In act one it is sent by the initiator, in act two by the responder.
+    #[splain(
+        label = "Ephemeral public key",
+        parse = public_key,
+        bolt = (8, "authenticated-key-exchange-handshake-specification")
+    )]
+    pub ephemeral_key: PublicKey,
+    #[splain(parse = tag)]
+    pub tag: Bytes,
+}
+
+/// Act three of Noise_XK handshake.
+#[allow(dead_code)]
+#[derive(Splain)]
+pub struct ActThree {
+    #[splain(parse = version)]
+    pub version: u8,
+    /// Static public key (node ID) of the initiator, encrypted with ChaCha20-Poly1305 and followed by its 16-byte tag.
+    #[splain(
+        label = "Encrypted static public key",
+        parse = bytes(49usize),
+        bolt = (8, "act-three")
+    )]
+    pub encrypted_key: Bytes,
+    #[splain(parse = tag)]
+    pub tag: Bytes,
+}
+
+/// Parser of act one or act two of Noise_XK handshake.
+pub fn act_one_two(s: Span) -> Parsed<()> {
+    let (s, _) = ActOneTwo::parse(s)?;
+    Ok((s, ()))
+}
+
+/// Parser of act three of Noise_XK handshake.
+pub fn act_three(s: Span) -> Parsed<()> {
+    let (s, _) = ActThree::parse(s)?;
+    Ok((s, ()))
+}
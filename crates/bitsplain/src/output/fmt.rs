@@ -0,0 +1,303 @@
+//! Rendering of [`Value`] into text shared by all user interfaces, so
+//! a value looks the same in terminal, HTML and GTK. Values are rendered
+//! into spans of text with a style, which every user interface translates
+//! into its own means (escape sequences, CSS classes etc.).
+
+use bitcoin::blockdata::opcodes::Ordinary::*;
+use bitcoin::blockdata::opcodes::{Class, ClassifyContext};
+use bitcoin::blockdata::script::{Instruction, Script};
+
+use crate::timestamp::TimeFormat;
+use crate::value::{Unit, Value};
+
+/// Unit in which amounts of bitcoin are shown.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BtcUnit;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NumFmt;
+
+/// Rendering of byte arrays.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HexFmt {
+    /// Maximum number of hex characters, longer byte arrays are shortened.
+    pub max_len: Option<usize>,
+
+    /// Whether length in bytes is appended to shortened byte arrays.
+    pub append_len: bool,
+}
+
+/// Options of rendering of values.
+#[derive(Clone, Debug, Default)]
+pub struct Fmt {
+    pub btcunit: BtcUnit,
+    pub num: NumFmt,
+    pub hex: HexFmt,
+    pub time: TimeFormat,
+}
+
+/// Meaning of a span of text, user interfaces decide how it looks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    Plain,
+    Number,
+    Hash,
+    Hex,
+    Time,
+    Address,
+    Amount,
+
+    /// Placeholder of a missing value, e. g. `(empty)`.
+    Missing,
+
+    /// Opcode of script.
+    Opcode,
+
+    /// Opcode hashing data or verifying signatures.
+    HashOpcode,
+
+    /// `OP_EQUAL` or `OP_EQUALVERIFY`.
+    EqualOpcode,
+
+    /// Text in colors chosen by decoder.
+    Colored {
+        foreground: Option<[u8; 3]>,
+        background: Option<[u8; 3]>,
+    },
+}
+
+/// Part of rendered value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+impl Span {
+    pub fn new(text: impl Into<String>, style: Style) -> Span {
+        Span {
+            text: text.into(),
+            style,
+        }
+    }
+}
+
+impl Fmt {
+    /// Value rendered into spans. Scripts are rendered as instructions
+    /// separated by spaces (see [`Fmt::instructions`]).
+    pub fn spans(&self, value: &Value) -> Vec<Span> {
+        match value {
+            Value::Num(n) => vec![Span::new(n.to_string(), Style::Number)],
+            Value::Hash(h) => vec![Span::new(h.to_string(), Style::Hash)],
+            Value::Bytes(b) => self.hex(b),
+            Value::Signature(s) => self.hex(&s.serialize_compact()),
+            Value::PublicKey(k) => self.hex(&k.to_bytes()),
+            Value::Script(s) => match self.instructions(s) {
+                Some(instructions) => {
+                    let mut spans = vec![];
+                    instructions.into_iter().for_each(|i| {
+                        if !spans.is_empty() {
+                            spans.push(Span::new(" ", Style::Plain));
+                        }
+                        spans.push(i);
+                    });
+                    spans
+                }
+                None => vec![Span::new("(invalid script)", Style::Missing)],
+            },
+            Value::Timestamp(ts) => vec![Span::new(self.time.format(ts), Style::Time)],
+            Value::Text {
+                text,
+                foreground: None,
+                background: None,
+            } => vec![Span::new(text, Style::Plain)],
+            Value::Text {
+                text,
+                foreground,
+                background,
+            } => vec![Span::new(
+                text,
+                Style::Colored {
+                    foreground: *foreground,
+                    background: *background,
+                },
+            )],
+            Value::Addr(Some(a)) => vec![Span::new(a.to_string(), Style::Address)],
+            Value::Addr(None) => vec![Span::new("(No address)", Style::Missing)],
+            Value::Sat(s) => vec![Span::new(s.as_str(), Style::Amount)],
+            Value::MSat(n) => with_conversion(
+                Span::new(format!("{n} msat"), Style::Amount),
+                convert(*n as i128, Unit::Msat),
+            ),
+            Value::FeeRate(r) => vec![Span::new(format!("{r:.2} sat/vB"), Style::Number)],
+            Value::Duration(d) => with_conversion(
+                Span::new(format!("{d} s"), Style::Number),
+                convert(*d as i128, Unit::Seconds),
+            ),
+            Value::Bool(b) => vec![Span::new(b.to_string(), Style::Plain)],
+            Value::NetAddr(a) => vec![Span::new(a.to_string(), Style::Address)],
+            Value::List(items) if items.is_empty() => {
+                vec![Span::new("(empty)", Style::Missing)]
+            }
+            Value::List(items) => {
+                let mut spans = vec![];
+                items.iter().for_each(|i| {
+                    if !spans.is_empty() {
+                        spans.push(Span::new(", ", Style::Plain));
+                    }
+                    spans.extend(self.spans(i));
+                });
+                spans
+            }
+            Value::Alt(v1, v2) => {
+                let mut spans = self.spans(v1);
+                spans.push(Span::new(" (", Style::Plain));
+                spans.extend(self.spans(v2));
+                spans.push(Span::new(")", Style::Plain));
+                spans
+            }
+            Value::Nil => vec![],
+        }
+    }
+
+    /// Value rendered into text without any style.
+    pub fn text(&self, value: &Value) -> String {
+        self.spans(value).into_iter().map(|s| s.text).collect()
+    }
+
+    /// Value in `unit` rendered into spans. Numbers are followed by
+    /// the unit and, if it helps readability, converted to other unit.
+    pub fn spans_in(&self, value: &Value, unit: Option<Unit>) -> Vec<Span> {
+        match (value, unit) {
+            (Value::Num(n), Some(unit)) => with_conversion(
+                Span::new(format!("{n} {}", unit.suffix()), Style::Number),
+                convert(*n, unit),
+            ),
+            (Value::Alt(v1, v2), Some(_)) => {
+                let mut spans = self.spans_in(v1, unit);
+                spans.push(Span::new(" (", Style::Plain));
+                spans.extend(self.spans_in(v2, unit));
+                spans.push(Span::new(")", Style::Plain));
+                spans
+            }
+            _ => self.spans(value),
+        }
+    }
+
+    /// Value in `unit` rendered into text without any style.
+    pub fn text_in(&self, value: &Value, unit: Option<Unit>) -> String {
+        self.spans_in(value, unit)
+            .into_iter()
+            .map(|s| s.text)
+            .collect()
+    }
+
+    /// Instructions of script, one span each, or `None` if the script
+    /// cannot be parsed. Data pushed after `OP_RETURN` are shown as text.
+    pub fn instructions(&self, script: &Script) -> Option<Vec<Span>> {
+        let mut is_op_return = false;
+        script
+            .instructions()
+            .map(|i| {
+                i.ok().map(|i| match i {
+                    Instruction::PushBytes(b) if b.is_empty() => Span::new("OP_0", Style::Opcode),
+                    Instruction::PushBytes(b) if is_op_return => {
+                        Span::new(printable(b.as_bytes()), Style::Plain)
+                    }
+                    Instruction::PushBytes(b) => Span::new(hex::encode(b), Style::Hex),
+                    Instruction::Op(op) => {
+                        let class = op.classify(ClassifyContext::Legacy);
+                        if matches!(class, Class::ReturnOp) {
+                            is_op_return = true;
+                        }
+                        Span::new(op.to_string(), opcode_style(class))
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Byte array in hex, shortened according to [`HexFmt`].
+    fn hex(&self, bytes: &[u8]) -> Vec<Span> {
+        if bytes.is_empty() {
+            return vec![Span::new("(empty)", Style::Missing)];
+        }
+        let hex = hex::encode(bytes);
+        match self.hex.max_len {
+            Some(max) if hex.len() > max => {
+                let mut spans = vec![Span::new(format!("{}…", &hex[..max]), Style::Hex)];
+                if self.hex.append_len {
+                    spans.push(Span::new(format!(" ({} bytes)", bytes.len()), Style::Plain));
+                }
+                spans
+            }
+            _ => vec![Span::new(hex, Style::Hex)],
+        }
+    }
+}
+
+/// Span of number followed by its conversion to other unit, if there is any.
+fn with_conversion(number: Span, converted: Option<String>) -> Vec<Span> {
+    let mut spans = vec![number];
+    if let Some(converted) = converted {
+        spans.push(Span::new(format!(" ({converted})"), Style::Plain));
+    }
+    spans
+}
+
+/// Number in `unit` converted to more readable unit, if there is any.
+fn convert(n: i128, unit: Unit) -> Option<String> {
+    match unit {
+        Unit::Msat if n.abs() >= 1000 => Some(format!("{} sat", n as f64 / 1000.0)),
+        Unit::Sat if n.abs() >= 100_000 => Some(format!("{} ₿", n as f64 / 100_000_000.0)),
+        Unit::Seconds if n >= 60 => Some(duration(n)),
+        // Blocks are mined every ten minutes on average.
+        Unit::Blocks if n >= 6 => Some(format!("≈ {}", duration(n * 600))),
+        Unit::Ppm if n != 0 => Some(format!("{} %", n as f64 / 10_000.0)),
+        _ => None,
+    }
+}
+
+/// Duration in seconds as days, hours and minutes.
+fn duration(seconds: i128) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    [(days, "d"), (hours, "h"), (minutes, "min")]
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, u)| format!("{n} {u}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn opcode_style(class: Class) -> Style {
+    match class {
+        Class::Ordinary(
+            OP_RIPEMD160
+            | OP_SHA1
+            | OP_SHA256
+            | OP_HASH160
+            | OP_HASH256
+            | OP_CODESEPARATOR
+            | OP_CHECKSIG
+            | OP_CHECKSIGVERIFY
+            | OP_CHECKMULTISIG
+            | OP_CHECKMULTISIGVERIFY,
+        ) => Style::HashOpcode,
+        Class::Ordinary(OP_EQUAL | OP_EQUALVERIFY) => Style::EqualOpcode,
+        _ => Style::Opcode,
+    }
+}
+
+/// Text of bytes with non-printable characters replaced by dots.
+fn printable(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .chars()
+        .map(|c| {
+            if c.is_alphabetic() || c.is_ascii_graphic() || c.is_ascii_punctuation() {
+                c
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
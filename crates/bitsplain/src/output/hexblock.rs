@@ -35,7 +35,9 @@ impl HexBlock {
             .real_leaves()
             .iter()
             .fold(HexBlock::default(), |r, &l| {
-                r.add_leave(l.location.index, &data[l.location.range()])
+                // Bytes shared with previous leaf (leaves of bits) are shown only once.
+                let from = l.location.from.max(r.len).min(l.location.to);
+                r.add_leave(l.location.index, &data[from..l.location.to])
             })
     }
 
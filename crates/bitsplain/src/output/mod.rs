@@ -1,3 +1,4 @@
+pub mod fmt;
 pub mod hexblock;
 pub mod legend;
 pub mod xml;
@@ -1,10 +1,13 @@
+use std::io::Write;
+
 use nom::AsBytes;
 use xml_builder::{XMLBuilder, XMLElement, XMLVersion};
 
 use crate::decode::Candidate;
 use crate::tree::{Information, Leaf, Node, Tree};
 
-pub fn tree_to_xml(candidate: &Candidate) {
+/// Writes tree of annotations of `candidate` as XML into `out`.
+pub fn tree_to_xml<W: Write>(candidate: &Candidate, out: W) -> xml_builder::Result<()> {
     let mut xml = XMLBuilder::new()
         .version(XMLVersion::XML1_1)
         .encoding("UTF-8".into())
@@ -20,7 +23,7 @@ pub fn tree_to_xml(candidate: &Candidate) {
 
     xml.set_root_element(nodes);
 
-    xml.generate(std::io::stdout()).unwrap();
+    xml.generate(out)
 }
 
 fn nodes_to_xml(nodes: &[Node], data: &[u8], element: &mut XMLElement) {
@@ -49,6 +52,12 @@ fn node_to_xml(node: &Node, data: &[u8]) -> XMLElement {
         }
         Node::Leaf(Leaf::Real(leaf)) => {
             let mut element = XMLElement::new("leaf");
+            if let Some(bits) = leaf.location.bits {
+                element.add_attribute(
+                    "bits",
+                    &format!("{}..{}", bits.offset, bits.offset + bits.len),
+                );
+            }
             attach_data(&data[leaf.location.range()], &mut element);
             attach_information(&leaf.information, &mut element);
             element
@@ -89,6 +98,27 @@ fn attach_information(information: &Information, element: &mut XMLElement) {
         element.add_child(el).unwrap();
     }
 
+    if !information.warnings.is_empty() {
+        let mut warnings = XMLElement::new("warnings");
+        for w in &information.warnings {
+            let mut el = XMLElement::new("warning");
+            el.add_text(w.message.clone()).unwrap();
+            warnings.add_child(el).unwrap();
+        }
+        element.add_child(warnings).unwrap();
+    }
+
+    if !information.links.is_empty() {
+        let mut links = XMLElement::new("links");
+        for l in &information.links {
+            let mut el = XMLElement::new("link");
+            el.add_attribute("path", &l.path.join("/"));
+            el.add_text(l.label.clone()).unwrap();
+            links.add_child(el).unwrap();
+        }
+        element.add_child(links).unwrap();
+    }
+
     if !information.tags.is_empty() {
         let mut tags = XMLElement::new("tags");
         for t in &information.tags {
@@ -1,17 +1,20 @@
 //! Customization of [`nom`] parser and all related functions and types.
 
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::ops::{Deref, RangeFrom, RangeTo};
+use std::ops::{Deref, Range, RangeFrom, RangeTo};
 use std::rc::Rc;
 
+use bytes::Bytes;
 use nom::combinator::success;
-use nom::error::ParseError;
+use nom::error::{ErrorKind, ParseError};
 use nom::{AsBytes, IResult, InputIter, InputLength, InputTake, Needed, Offset, Parser, Slice};
 
-use crate::dsl::Ann;
+use crate::binary::Binary;
+use crate::decode::DecodeError;
+use crate::dsl::{Ann, Make};
 use crate::tree::*;
 use crate::value::*;
 
@@ -59,6 +62,247 @@ struct Appendix {
     information: Information,
 }
 
+/// Link between two annotations that is resolved once the tree is built.
+#[derive(Clone, Debug)]
+struct PendingLink {
+    /// Byte range of the annotation from which the link leads.
+    source: (usize, usize),
+    /// Byte range of the referenced annotation.
+    target: (usize, usize),
+    /// Description of the relationship.
+    label: String,
+}
+
+/// Nodes of one level of the tree, collected during parsing, in reversed order.
+///
+/// Nom slices and clones spans very often, so nodes are kept in a persistent
+/// list, which spans can share, and adding a node or copying the list do not
+/// copy any nodes.
+#[derive(Clone, Default)]
+struct Nodes(Option<Rc<Cons>>);
+
+struct Cons {
+    node: Node,
+    previous: Nodes,
+}
+
+impl Nodes {
+    /// List with the node added after all the existing ones.
+    fn push(self, node: Node) -> Nodes {
+        Nodes(Some(Rc::new(Cons {
+            node,
+            previous: self,
+        })))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Nodes in order in which they were added. Nodes that are not shared
+    /// with other spans are moved, the others are cloned.
+    fn into_vec(mut self) -> Vec<Node> {
+        let mut nodes = vec![];
+        while let Some(cons) = self.0.take() {
+            match Rc::try_unwrap(cons) {
+                Ok(mut cons) => {
+                    self = std::mem::take(&mut cons.previous);
+                    nodes.push(cons.node);
+                }
+                Err(cons) => {
+                    self = cons.previous.clone();
+                    nodes.push(cons.node.clone());
+                }
+            }
+        }
+        nodes.reverse();
+        nodes
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Node> {
+        std::iter::successors(self.0.as_deref(), |cons| cons.previous.0.as_deref())
+            .map(|cons| &cons.node)
+    }
+}
+
+impl Drop for Nodes {
+    // Dropping long lists recursively could overflow stack.
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+        while let Some(cons) = next {
+            next = Rc::try_unwrap(cons)
+                .ok()
+                .and_then(|mut cons| cons.previous.0.take());
+        }
+    }
+}
+
+impl Debug for Nodes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut nodes = self.iter().collect::<Vec<_>>();
+        nodes.reverse();
+        f.debug_list().entries(nodes).finish()
+    }
+}
+
+/// Failure of an annotated parser. It is created by the innermost failing
+/// [`parse`] and completed by the enclosing ones, as the error propagates.
+#[derive(Clone, Debug)]
+struct Breakage {
+    /// Nesting depth of the annotation that failed most recently.
+    depth: usize,
+    /// Offset of the first byte of the innermost annotation that failed.
+    offset: usize,
+    /// Label of the innermost annotation that failed.
+    label: String,
+    /// Datatype that the innermost annotation expected, if known.
+    datatype: Option<&'static str>,
+    /// Nodes parsed before the failure, on the level of the most
+    /// recently failed annotation.
+    tree: Vec<Node>,
+}
+
+/// Limits of how much of the data is annotated in detail, which keeps
+/// memory bounded for huge inputs (e. g. block with thousands of transactions).
+///
+/// Annotations nested deeper than `depth` are not kept, instead their
+/// enclosing annotation becomes a leaf covering all their bytes, marked
+/// with data `collapsed`. Structures overlapping `focus` are always annotated
+/// in detail, so a collapsed structure can be expanded on demand by decoding
+/// the data again with its range as the focus.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// Maximum nesting depth of annotations, top-level annotations have depth 1.
+    pub depth: Option<usize>,
+
+    /// Byte range which is annotated in detail regardless of depth.
+    pub focus: Option<Range<usize>>,
+}
+
+impl Budget {
+    /// Budget of data embedded at `depth` in bytes `range` of the data.
+    pub fn embedded(&self, depth: usize, range: Range<usize>) -> Budget {
+        Budget {
+            depth: self.depth.map(|d| d.saturating_sub(depth).max(1)),
+            focus: self
+                .focus
+                .as_ref()
+                .filter(|f| f.start < range.end && range.start < f.end)
+                .map(|f| f.start.saturating_sub(range.start)..f.end.min(range.end) - range.start),
+        }
+    }
+
+    /// Whether annotation at depth covering bytes `from..to` is collapsed into leaf.
+    fn collapses(&self, depth: usize, from: usize, to: usize) -> bool {
+        self.depth.is_some_and(|d| depth >= d)
+            && !self
+                .focus
+                .as_ref()
+                .is_some_and(|f| f.start < to && from < f.end)
+    }
+}
+
+/// Progress of parsing, which allows to report where and why parsing failed.
+#[derive(Debug, Default)]
+struct Progress {
+    /// Limits of detailed annotations.
+    budget: Budget,
+    /// Number of annotations that are currently being parsed.
+    depth: usize,
+    /// Datatype declared by a failed parser and depth at which it happened.
+    datatype: Option<(usize, &'static str)>,
+    /// Failure that has not been recovered from (yet).
+    breakage: Option<Breakage>,
+}
+
+impl Progress {
+    /// Starts parsing of an annotation and returns its depth.
+    fn enter(&mut self) -> usize {
+        self.depth += 1;
+        self.datatype = None;
+        self.discard(self.depth);
+        self.depth
+    }
+
+    /// Finishes successful parsing of an annotation at given depth.
+    fn succeed(&mut self, depth: usize) {
+        self.depth = depth - 1;
+        self.discard(depth);
+    }
+
+    /// Finishes failed parsing of an annotation at given depth. If the failure
+    /// was caused by a failed nested annotation, it is wrapped into a group.
+    fn fail<O>(
+        &mut self,
+        depth: usize,
+        from: usize,
+        index: usize,
+        ann: &Ann<O>,
+        mut tree: Vec<Node>,
+    ) {
+        self.depth = depth - 1;
+
+        let breakage = match self.breakage.take() {
+            Some(inner) if inner.depth == depth + 1 => {
+                let index_to = inner
+                    .tree
+                    .iter()
+                    .map(|n| n.index_range().1)
+                    .max()
+                    .unwrap_or_default()
+                    .max(index);
+                tree.push(Node::Group {
+                    path: vec![],
+                    location: GroupLocation {
+                        byte_from: from,
+                        byte_to: inner.offset,
+                        index_from: index,
+                        index_to,
+                    },
+                    information: Information {
+                        label: ann.label.clone(),
+                        data: with_id(HashMap::new(), ann),
+                        tags: vec![],
+                        warnings: vec![],
+                        links: vec![],
+                        categories: ann.categories.clone(),
+                        refs: ann.refs.clone(),
+                        value: Value::Nil,
+                        unit: None,
+                        doc: ann.doc.clone(),
+                        splain: None,
+                    },
+                    children: inner.tree,
+                });
+                Breakage {
+                    depth,
+                    tree,
+                    ..inner
+                }
+            }
+            _ => Breakage {
+                depth,
+                offset: from,
+                label: ann.label.clone(),
+                datatype: self
+                    .datatype
+                    .filter(|(d, _)| *d == depth)
+                    .map(|(_, datatype)| datatype),
+                tree,
+            },
+        };
+
+        self.breakage = Some(breakage);
+    }
+
+    /// Forgets failures at given depth or deeper, they were recovered from.
+    fn discard(&mut self, depth: usize) {
+        if self.breakage.as_ref().is_some_and(|b| b.depth >= depth) {
+            self.breakage = None;
+        }
+    }
+}
+
 /// Nom parser that stores user-defined annotations during parsing.
 #[derive(Clone, Debug)]
 pub struct Annotated<Fragment> {
@@ -69,16 +313,27 @@ pub struct Annotated<Fragment> {
     next_offset: usize,
     /// Fragment (raw data) to be parsed next.
     pub(crate) next_fragment: Fragment,
+    /// All the data being parsed, fragments are its slices.
+    source: Bytes,
     /// Tree of annotations.
-    tree: Vec<Node>,
+    tree: Nodes,
     /// Most recently inserted range. None if no range inserted yet.
     last_range: Option<(usize, usize)>,
+    /// Bytes and their bits that leaf of the current span covers instead
+    /// of the parsed bytes (see [`bit_fields`]).
+    bits: Option<(usize, usize, BitRange)>,
     /// Additional data that parsers can provide.
-    data: HashMap<&'static str, String>,
+    data: HashMap<Cow<'static, str>, String>,
     /// Tags.
     tags: Vec<Tag>,
+    /// Warnings about the current span.
+    warnings: Vec<Warning>,
     /// Additional annotations that parsers can insert.
     appendices: Rc<RefCell<Vec<Appendix>>>,
+    /// Links between annotations that parsers can insert.
+    links: Rc<RefCell<Vec<PendingLink>>>,
+    /// How far the parsing got, shared by all spans.
+    progress: Rc<RefCell<Progress>>,
 }
 
 impl<Fragment> Annotated<Fragment> {
@@ -89,103 +344,254 @@ impl<Fragment> Annotated<Fragment> {
 
     /// Insert an annotation at the bookmark's position.
     pub fn insert_at(&self, bookmark: &Bookmark, ann: Ann<NoValue>) {
-        if let Some((from, to)) = bookmark.0 {
-            self.appendices.borrow_mut().push(Appendix {
-                from,
-                to,
-                place: Place::After,
-                information: Information {
-                    label: ann.label,
-                    value: ann.value.resolve_static().unwrap_or(Value::Nil),
-                    doc: ann.doc.clone(),
-                    refs: ann.refs.clone(),
-                    splain: ann.splain.resolve_static(),
-                    data: HashMap::new(),
-                    tags: vec![],
-                },
-            });
-        }
+        self.append(bookmark.0, Place::After, ann, HashMap::new(), vec![]);
     }
 
-    // pub fn insert_before(
-    //     &self,
-    //     bookmark: &Bookmark,
-    //     annotation: &'static str,
-    //     value: Value,
-    //     doc: Option<String>,
-    // ) {
-    //     if let Some((from, to)) = bookmark.0 {
-    //         self.appendices.borrow_mut().push((
-    //             from,
-    //             to,
-    //             Place::Before,
-    //             Information {
-    //                 annotation: annotation.to_string(),
-    //                 value,
-    //                 doc,
-    //                 data: HashMap::new(),
-    //             },
-    //         ));
-    //     }
-    // }
+    /// Insert an annotation recording result of verification of an invariant
+    /// of data (e. g. signature) at the bookmark's position. Failed verification
+    /// is also reported as a warning, see [`Self::check`].
+    pub fn insert_check_at<S: Into<String>>(
+        &self,
+        bookmark: &Bookmark,
+        passed: bool,
+        ann: Ann<NoValue>,
+        message: S,
+    ) {
+        let (check, warnings) = if passed {
+            ("passed", vec![])
+        } else {
+            (
+                "failed",
+                vec![Warning {
+                    message: message.into(),
+                }],
+            )
+        };
+        self.append(
+            bookmark.0,
+            Place::After,
+            ann,
+            HashMap::from([("check".into(), check.to_string())]),
+            warnings,
+        );
+    }
+
+    /// Insert an annotation in front of the bookmark's position, e. g. a value
+    /// that is computed from the bookmarked data but should precede them.
+    pub fn insert_before(&self, bookmark: &Bookmark, ann: Ann<NoValue>) {
+        self.append(bookmark.0, Place::Before, ann, HashMap::new(), vec![]);
+    }
 
     /// Insert an annotation to current position.
     ///
     /// To insert annotations to a previous position, see [`Self::insert_at`].
     pub fn insert(&self, ann: Ann<NoValue>) {
-        if let Some((from, to)) = self.last_range {
+        self.append(self.last_range, Place::After, ann, HashMap::new(), vec![]);
+    }
+
+    /// Appends annotation to the byte range, it is placed before or after it.
+    fn append(
+        &self,
+        range: Option<(usize, usize)>,
+        place: Place,
+        ann: Ann<NoValue>,
+        data: HashMap<Cow<'static, str>, String>,
+        warnings: Vec<Warning>,
+    ) {
+        if let Some((from, to)) = range {
+            let value = ann.value.resolve_static().unwrap_or(Value::Nil);
+            let data = with_id(data, &ann);
             self.appendices.borrow_mut().push(Appendix {
                 from,
                 to,
-                place: Place::After,
+                place,
                 information: Information {
                     label: ann.label,
-                    value: ann.value.resolve_static().unwrap_or(Value::Nil),
+                    categories: categories(&ann.categories, &value),
+                    value,
+                    unit: ann.unit,
                     doc: ann.doc.clone(),
                     refs: ann.refs.clone(),
                     splain: ann.splain.resolve_static(),
-                    data: HashMap::new(),
-                    tags: vec![],
+                    data,
+                    tags: ann
+                        .tags
+                        .iter()
+                        .filter_map(Make::<NoValue, Tag>::resolve_static)
+                        .collect(),
+                    warnings,
+                    links: vec![],
                 },
             });
         }
     }
 
+    /// Link annotation at bookmark `from` to annotation at bookmark `to`, so
+    /// renderers can show their relationship (e. g. witness stack and its input).
+    ///
+    /// If more annotations cover the bookmarked bytes, the innermost one is used.
+    pub fn link(&self, from: &Bookmark, to: &Bookmark, label: impl AsRef<str>) {
+        if let (Some(source), Some(target)) = (from.0, to.0) {
+            self.links.borrow_mut().push(PendingLink {
+                source,
+                target,
+                label: label.as_ref().to_string(),
+            });
+        }
+    }
+
     /// Place appendices to the proper place inside tree. Returns a copy of the original tree.
     fn inject_appendices(tree: Vec<Node>, app: &[Appendix]) -> Vec<Node> {
+        let mut pending: HashMap<(usize, usize), Vec<&Appendix>> = HashMap::new();
+        app.iter()
+            .for_each(|app| pending.entry((app.from, app.to)).or_default().push(app));
+        Self::place_appendices(tree, &mut pending)
+    }
+
+    /// Appendices are placed next to the innermost node whose byte range they
+    /// are attached to, each of them only once.
+    fn place_appendices(
+        tree: Vec<Node>,
+        pending: &mut HashMap<(usize, usize), Vec<&Appendix>>,
+    ) -> Vec<Node> {
         let mut new_tree = vec![];
 
-        tree.into_iter().for_each(|t| match t {
-            Node::Group {
-                path,
-                location,
-                information,
-                children,
-            } => new_tree.push(Node::Group {
-                path,
-                location,
-                information,
-                children: Self::inject_appendices(children, app),
-            }),
-            Node::Leaf(Leaf::Real(r)) => {
-                let from = r.location.from;
-                let to = r.location.to;
-                new_tree.push(Node::Leaf(Leaf::Real(r)));
-                app.iter()
-                    .filter(|app| app.place == Place::After && app.from == from && app.to == to)
-                    .for_each(|app| {
-                        new_tree.push(Node::Leaf(Leaf::Virtual(VirtualLeaf {
+        tree.into_iter().for_each(|t| {
+            let (node, range) = match t {
+                Node::Group {
+                    path,
+                    location,
+                    information,
+                    children,
+                } => {
+                    let children = Self::place_appendices(children, pending);
+                    let range = (location.byte_from, location.byte_to);
+                    let group = Node::Group {
+                        path,
+                        location,
+                        information,
+                        children,
+                    };
+                    (group, Some(range))
+                }
+                Node::Leaf(Leaf::Real(r)) => {
+                    let range = (r.location.from, r.location.to);
+                    (Node::Leaf(Leaf::Real(r)), Some(range))
+                }
+                leaf => (leaf, None),
+            };
+
+            let apps = range.and_then(|r| pending.remove(&r)).unwrap_or_default();
+            let placed = |place: Place| {
+                apps.iter()
+                    .filter(move |app| app.place == place)
+                    .map(|app| {
+                        Node::Leaf(Leaf::Virtual(VirtualLeaf {
                             information: app.information.clone(),
                             path: vec![],
-                        })))
-                    });
+                        }))
+                    })
+            };
+            new_tree.extend(placed(Place::Before));
+            new_tree.push(node);
+            new_tree.extend(placed(Place::After));
+        });
+
+        new_tree
+    }
+
+    /// Insert leaves covering bytes in range `from..to` that are not covered by any
+    /// annotation, so gaps in parsers are visible instead of silently missing.
+    fn inject_uncovered(tree: Vec<Node>, (from, to): (usize, usize), data: &[u8]) -> Vec<Node> {
+        let uncovered = |from: usize, to: usize| {
+            Node::Leaf(Leaf::Real(RealLeaf {
+                path: vec![],
+                location: LeafLocation {
+                    from,
+                    to,
+                    index: 0,
+                    bits: None,
+                },
+                information: Information {
+                    label: "Uncovered bytes".to_string(),
+                    data: HashMap::from([
+                        ("datatype".into(), "bytes".to_string()),
+                        ("uncovered".into(), "true".to_string()),
+                    ]),
+                    tags: vec![],
+                    refs: vec![],
+                    value: Value::bytes(data[from..to].to_vec()),
+                    unit: None,
+                    doc: Some("Bytes that were read by the decoder, however they are not explained by any annotation.".to_string()),
+                    splain: None,
+                    warnings: vec![],
+                    links: vec![],
+                    categories: vec![],
+                },
+            }))
+        };
+
+        let mut new_tree = vec![];
+        let mut covered = from;
+
+        tree.into_iter().for_each(|node| {
+            let (node, range) = match node {
+                Node::Group {
+                    path,
+                    location,
+                    information,
+                    children,
+                } => {
+                    let range = (location.byte_from, location.byte_to);
+                    let children = Self::inject_uncovered(children, range, data);
+                    let group = Node::Group {
+                        path,
+                        location,
+                        information,
+                        children,
+                    };
+                    (group, Some(range))
+                }
+                Node::Leaf(Leaf::Real(r)) => {
+                    let range = (r.location.from, r.location.to);
+                    (Node::Leaf(Leaf::Real(r)), Some(range))
+                }
+                leaf => (leaf, None),
+            };
+            if let Some((from, to)) = range {
+                if from > covered {
+                    new_tree.push(uncovered(covered, from));
+                }
+                covered = covered.max(to);
             }
-            leaf => new_tree.push(leaf),
+            new_tree.push(node);
         });
 
+        if covered < to {
+            new_tree.push(uncovered(covered, to));
+        }
+
         new_tree
     }
 
+    /// Traverse the tree and number real leaves in order of their appearance.
+    pub(crate) fn inject_indices(tree: &mut [Node], next: &mut usize) {
+        tree.iter_mut().for_each(|t| match t {
+            Node::Leaf(Leaf::Real(RealLeaf { location, .. })) => {
+                location.index = *next;
+                *next += 1;
+            }
+            Node::Leaf(Leaf::Virtual(_)) => {}
+            Node::Group {
+                location, children, ..
+            } => {
+                location.index_from = *next;
+                Self::inject_indices(children, next);
+                location.index_to = next.saturating_sub(1); // inclusive
+            }
+        });
+    }
+
     /// Traverse the tree and set path of each node.
     fn inject_paths(tree: &mut [Node], prefix: Vec<String>) {
         tree.iter_mut().enumerate().for_each(|(i, t)| match t {
@@ -205,13 +611,77 @@ impl<Fragment> Annotated<Fragment> {
         });
     }
 
-    /// Replace annotations by data field 'annotation' if it exists and bake
-    /// enumerations. This allows the specify annotation ex post.
+    /// Attach links to nodes they lead from. Links whose either end
+    /// cannot be found are dropped.
+    fn inject_links(tree: &mut [Node], links: &[PendingLink]) {
+        links.iter().for_each(|l| {
+            let source = Self::find_path(tree, l.source);
+            let target = Self::find_path(tree, l.target);
+            if let (Some(source), Some(path)) = (source, target) {
+                if let Some(node) = Self::select_mut(tree, &source) {
+                    let link = Link {
+                        label: l.label.clone(),
+                        path,
+                    };
+                    match node {
+                        Node::Group { information, .. } => information.links.push(link),
+                        Node::Leaf(Leaf::Real(RealLeaf { information, .. })) => {
+                            information.links.push(link)
+                        }
+                        Node::Leaf(Leaf::Virtual(VirtualLeaf { information, .. })) => {
+                            information.links.push(link)
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Path of the innermost node that covers exactly the byte range.
+    fn find_path(tree: &[Node], (from, to): (usize, usize)) -> Option<Vec<String>> {
+        tree.iter().find_map(|node| match node {
+            Node::Group {
+                path,
+                location,
+                children,
+                ..
+            } if location.byte_from <= from && to <= location.byte_to => {
+                Self::find_path(children, (from, to)).or_else(|| {
+                    (location.byte_from == from && location.byte_to == to).then(|| path.clone())
+                })
+            }
+            Node::Group { .. } => None,
+            Node::Leaf(Leaf::Real(RealLeaf { path, location, .. }))
+                if location.from == from && location.to == to =>
+            {
+                Some(path.clone())
+            }
+            Node::Leaf(_) => None,
+        })
+    }
+
+    fn select_mut<'t>(tree: &'t mut [Node], path: &[String]) -> Option<&'t mut Node> {
+        let (head, tail) = path.split_first()?;
+        let node = tree.get_mut(head.parse::<usize>().ok()?)?;
+        match node {
+            _ if tail.is_empty() => Some(node),
+            Node::Group { children, .. } => Self::select_mut(children, tail),
+            Node::Leaf(_) => None,
+        }
+    }
+
+    /// Replace annotations and documentation by data fields 'annotation' and 'doc'
+    /// if they exist and bake enumerations. This allows the specify annotation ex post.
     fn bake_annotations(tree: &mut Node, enumeration: usize) {
         match tree {
             Node::Leaf(Leaf::Real(RealLeaf { information, .. })) => {
+                if let Some(doc) = information.data.remove("doc") {
+                    information.doc = Some(doc);
+                }
                 if let Some(annotation) = information.data.remove("annotation") {
                     information.label = annotation;
+                } else if information.has_data("list", "enumerate") {
+                    information.label = enumeration.to_string();
                 };
             }
             Node::Leaf(Leaf::Virtual(VirtualLeaf { information, .. })) => {
@@ -224,6 +694,9 @@ impl<Fragment> Annotated<Fragment> {
                 children,
                 ..
             } => {
+                if let Some(doc) = information.data.remove("doc") {
+                    information.doc = Some(doc);
+                }
                 if let Some(annotation) = information.data.remove("annotation") {
                     information.label = annotation;
                 } else if information.has_data("list", "enumerate") {
@@ -247,22 +720,45 @@ impl<Fragment> Annotated<Fragment> {
 
     /// Render annotations.
     pub fn annotations(self) -> Tree {
-        let mut tree = Self::inject_appendices(self.tree, &self.appendices.as_ref().borrow());
+        Self::build_tree(
+            self.tree.into_vec(),
+            &self.appendices.as_ref().borrow(),
+            &self.links.as_ref().borrow(),
+            &self.source[..self.next_offset],
+        )
+    }
+
+    /// Turn nodes, collected during parsing, into final tree.
+    fn build_tree(
+        tree: Vec<Node>,
+        appendices: &[Appendix],
+        links: &[PendingLink],
+        data: &[u8],
+    ) -> Tree {
+        let mut tree = Self::inject_uncovered(tree, (0, data.len()), data);
+        Self::inject_indices(&mut tree, &mut 0);
+        let mut tree = Self::inject_appendices(tree, appendices);
         Self::inject_paths(&mut tree, vec![]);
         tree.iter_mut().for_each(|t| Self::bake_annotations(t, 0));
+        Self::inject_links(&mut tree, links);
         Tree::from_nodes(tree)
     }
 
-    pub fn new(fragment: Fragment) -> Annotated<Fragment> {
+    pub fn new(fragment: Fragment, source: Bytes) -> Annotated<Fragment> {
         Annotated {
             next_index: 0,
             next_offset: 0,
             next_fragment: fragment,
-            tree: vec![],
+            source,
+            tree: Nodes::default(),
             data: HashMap::new(),
             tags: vec![],
+            warnings: vec![],
             appendices: Rc::new(RefCell::new(vec![])),
+            links: Rc::new(RefCell::new(vec![])),
+            progress: Rc::new(RefCell::new(Progress::default())),
             last_range: None,
+            bits: None,
         }
     }
 
@@ -276,11 +772,16 @@ impl<Fragment> Annotated<Fragment> {
             next_index: self.next_index,
             next_offset: self.next_offset,
             next_fragment: self.next_fragment,
+            source: self.source,
             data: self.data,
             tags,
+            warnings: self.warnings,
             tree: self.tree,
             appendices: self.appendices,
+            links: self.links,
+            progress: self.progress,
             last_range: self.last_range,
+            bits: self.bits,
         }
     }
 
@@ -295,35 +796,100 @@ impl<Fragment> Annotated<Fragment> {
                 next_index: self.next_index,
                 next_offset: self.next_offset,
                 next_fragment: self.next_fragment,
+                source: self.source,
                 data: self.data,
                 tags,
+                warnings: self.warnings,
                 tree: self.tree,
                 appendices: self.appendices,
+                links: self.links,
+                progress: self.progress,
                 last_range: self.last_range,
+                bits: self.bits,
             }
         } else {
             self
         }
     }
 
+    /// Record result of verification of an invariant of data (e. g. checksum
+    /// or signature). Failed verification is also reported as a warning.
+    #[must_use]
+    #[inline]
+    pub fn check<S: Into<String>>(self, passed: bool, message: S) -> Self {
+        if passed {
+            self.with("check", "passed")
+        } else {
+            self.with("check", "failed").warn(message)
+        }
+    }
+
+    /// Add a warning to the current span.
+    #[must_use]
+    #[inline]
+    pub fn warn<S: Into<String>>(self, message: S) -> Self {
+        let mut warnings = self.warnings;
+        warnings.push(Warning {
+            message: message.into(),
+        });
+        Annotated {
+            next_index: self.next_index,
+            next_offset: self.next_offset,
+            next_fragment: self.next_fragment,
+            source: self.source,
+            data: self.data,
+            tags: self.tags,
+            warnings,
+            tree: self.tree,
+            appendices: self.appendices,
+            links: self.links,
+            progress: self.progress,
+            last_range: self.last_range,
+            bits: self.bits,
+        }
+    }
+
+    /// Add a warning to the current span if condition is met.
+    #[must_use]
+    #[inline]
+    pub fn warn_if<S: Into<String>>(self, condition: bool, message: S) -> Self {
+        if condition {
+            self.warn(message)
+        } else {
+            self
+        }
+    }
+
     #[must_use]
     #[inline]
     pub fn with(self, key: &'static str, value: &'static str) -> Self {
         let mut data = self.data;
-        data.insert(key, value.to_string());
+        data.insert(key.into(), value.to_string());
         Annotated {
             next_index: self.next_index,
             next_offset: self.next_offset,
             next_fragment: self.next_fragment,
+            source: self.source,
             data,
             tags: self.tags,
+            warnings: self.warnings,
             tree: self.tree,
             appendices: self.appendices,
+            links: self.links,
+            progress: self.progress,
             last_range: self.last_range,
+            bits: self.bits,
         }
     }
 }
 
+impl Annotated<&[u8]> {
+    /// Fragment to be parsed next as a view of the parsed data, without copying.
+    pub fn view(&self) -> Bytes {
+        self.source.slice_ref(self.next_fragment)
+    }
+}
+
 impl<Fragment> Deref for Annotated<Fragment> {
     type Target = Fragment;
 
@@ -405,11 +971,77 @@ where
             next_index: self.next_index,
             next_offset,
             next_fragment,
+            source: self.source.clone(),
             data: HashMap::new(),
             tags: vec![],
+            warnings: vec![],
             tree: self.tree.clone(),
             appendices: self.appendices.clone(),
+            links: self.links.clone(),
+            progress: self.progress.clone(),
             last_range: self.last_range,
+            bits: self.bits,
+        }
+    }
+}
+
+/// Runs parser of a decoder on the data. Decoding succeeds only if the parser
+/// succeeds and consumes all the data, otherwise the error describes how far
+/// the parser got.
+pub fn decode<'a, Output>(
+    data: &'a Binary,
+    parser: impl FnMut(Span<'a>) -> Parsed<'a, Output>,
+) -> Result<Tree, DecodeError> {
+    decode_with(data, &Budget::default(), parser)
+}
+
+/// Runs parser of a decoder on the data, annotating them in detail only
+/// within the budget. See [`decode`].
+pub fn decode_with<'a, Output>(
+    data: &'a Binary,
+    budget: &Budget,
+    mut parser: impl FnMut(Span<'a>) -> Parsed<'a, Output>,
+) -> Result<Tree, DecodeError> {
+    let span = Annotated::new(&data[..], data.bytes().clone());
+    let progress = span.progress.clone();
+    progress.borrow_mut().budget = budget.clone();
+    let appendices = span.appendices.clone();
+    let links = span.links.clone();
+
+    match parser(span) {
+        Ok((span, _)) if span.input_len() == 0 => Ok(span.annotations()),
+        Ok((span, _)) => Err(DecodeError::Trailing {
+            offset: span.next_offset,
+            partial: span.annotations(),
+        }),
+        Err(e) => {
+            let breakage = progress.borrow_mut().breakage.take();
+            match (breakage.filter(|b| b.depth == 1), e) {
+                (Some(b), _) => Err(DecodeError::Failed {
+                    offset: b.offset,
+                    label: Some(b.label),
+                    datatype: b.datatype,
+                    partial: Annotated::<&[u8]>::build_tree(
+                        b.tree,
+                        &appendices.as_ref().borrow(),
+                        &links.as_ref().borrow(),
+                        &data[..b.offset],
+                    ),
+                }),
+                // Parser failed outside of any annotation.
+                (None, nom::Err::Error(e) | nom::Err::Failure(e)) => Err(DecodeError::Failed {
+                    offset: e.input.next_offset,
+                    label: None,
+                    datatype: None,
+                    partial: e.input.annotations(),
+                }),
+                (None, nom::Err::Incomplete(_)) => Err(DecodeError::Failed {
+                    offset: data.len(),
+                    label: None,
+                    datatype: None,
+                    partial: Tree::from_nodes(vec![]),
+                }),
+            }
         }
     }
 }
@@ -424,14 +1056,39 @@ where
     Error: ParseError<Annotated<Fragment>>,
 {
     move |input: Annotated<Fragment>| {
-        let (span, out) = parse.parse(input)?;
-        Ok((span.with(key, value), out))
+        let progress = input.progress.clone();
+        match parse.parse(input) {
+            Ok((span, out)) => Ok((span.with(key, value), out)),
+            Err(e) => {
+                if key == "datatype" {
+                    let mut progress = progress.borrow_mut();
+                    progress.datatype = Some((progress.depth, value));
+                }
+                Err(e)
+            }
+        }
     }
 }
 
-/// Parse flag bitfields by providing parser for numeric value and definitions
-/// of bit positions and their annotations. Result of the parser is the original
-/// numeric value.
+/// Marks bytes consumed by the parser as a payload embedded in the data,
+/// which is decoded once the whole data are decoded. Only decoders selected
+/// by pattern `decoders` (see [`DecoderFilter`](crate::decode::DecoderFilter),
+/// e. g. `ln/*`) are tried. If one of them decodes the payload completely,
+/// its annotations become children of annotation of the payload.
+pub fn embedded<Parse, Error, Output, Fragment>(
+    decoders: &'static str,
+    parse: Parse,
+) -> impl FnMut(Annotated<Fragment>) -> IResult<Annotated<Fragment>, Output, Error>
+where
+    Parse: Parser<Annotated<Fragment>, Output, Error>,
+    Error: ParseError<Annotated<Fragment>>,
+{
+    with("embedded", decoders, parse)
+}
+
+/// Parse flag bitfields by providing parser for big-endian numeric value
+/// and definitions of bit positions and their annotations. Result of the
+/// parser is the original numeric value.
 ///
 /// ## Example
 ///
@@ -450,22 +1107,145 @@ where
     Output: Into<u64> + Copy,
 {
     move |input: Annotated<Fragment>| {
+        let from = input.next_offset;
         let (span, out) = parse_num.parse(input)?;
         let numeric = out.into();
+        let size = span.next_offset - from;
 
         let span = anns.iter().fold(span, |s, (idx, ann)| {
-            let parsed_flag: IResult<_, _, Error> = parse(success(numeric & 1 << idx > 0), ann)(s);
-            if let Ok((span, _flag)) = parsed_flag {
-                span
-            } else {
-                unreachable!("Parser won't fail here")
-            }
+            let bits = bit_location(from, size, true, *idx..*idx + 1);
+            annotate_bits::<_, Error, _>(s, bits, numeric & 1 << idx > 0, ann)
         });
 
         Ok((span, out))
     }
 }
 
+/// Parse fields of numeric value that occupy only some of its bits, by
+/// providing parser for the value, its byte order and definitions of
+/// ranges of bits (counted from the least significant bit) and their
+/// annotations. Every field is annotated by a leaf covering only its bits.
+/// Result of the parser is the original numeric value.
+///
+/// Fields of little-endian values should not cross boundaries of bytes,
+/// otherwise their bits are not contiguous and they cover the whole bytes.
+///
+/// ## Example
+///
+/// ```ignore
+///  let (s, version) = parse(
+///        bit_fields(le_u32, false, &[(29..32, ann("Top Bits", auto())), (0..29, ann("Bits", auto()))])
+///     )(s)?;
+///```
+pub fn bit_fields<'a, Parse, Error, Output, Fragment>(
+    mut parse_num: Parse,
+    big_endian: bool,
+    fields: &'a [(Range<usize>, Ann<u64>)],
+) -> impl FnMut(Annotated<Fragment>) -> IResult<Annotated<Fragment>, Output, Error> + 'a
+where
+    Parse: Parser<Annotated<Fragment>, Output, Error> + 'a,
+    Error: ParseError<Annotated<Fragment>>,
+    Output: Into<u64> + Copy,
+{
+    move |input: Annotated<Fragment>| {
+        let from = input.next_offset;
+        let (span, out) = parse_num.parse(input)?;
+        let numeric = out.into();
+        let size = span.next_offset - from;
+
+        let span = fields.iter().fold(span, |s, (bits, ann)| {
+            let mask = 1u64
+                .checked_shl(bits.len() as u32)
+                .unwrap_or(0)
+                .wrapping_sub(1);
+            let field = numeric.checked_shr(bits.start as u32).unwrap_or(0) & mask;
+            annotate_bits::<_, Error, _>(
+                s,
+                bit_location(from, size, big_endian, bits.clone()),
+                field,
+                ann,
+            )
+        });
+
+        Ok((span, out))
+    }
+}
+
+/// Bytes and their bits occupied by range of bits (counted from the least
+/// significant bit) of numeric value of `size` bytes starting at `offset`.
+fn bit_location(
+    offset: usize,
+    size: usize,
+    big_endian: bool,
+    bits: Range<usize>,
+) -> (usize, usize, BitRange) {
+    // Position of bit in order in which bits are written.
+    let position = |bit: usize| match big_endian {
+        true => size * 8 - 1 - bit,
+        false => bit / 8 * 8 + 7 - bit % 8,
+    };
+    let (first, last) = (position(bits.end - 1), position(bits.start));
+    let (first, last) = match big_endian || bits.start / 8 == (bits.end - 1) / 8 {
+        true => (first, last),
+        false => (bits.start / 8 * 8, (bits.end - 1) / 8 * 8 + 7),
+    };
+    let from = first / 8;
+    (
+        offset + from,
+        offset + last / 8 + 1,
+        BitRange {
+            offset: first - from * 8,
+            len: last - first + 1,
+        },
+    )
+}
+
+/// Parser that does not consume any input and returns `value`, which is
+/// annotated by a leaf covering `bits` of already parsed bytes, starting
+/// `back` bytes before the current position.
+///
+/// ## Example
+///
+/// ```ignore
+///  // Top three bits of the last parsed byte.
+///  let (s, _) = parse(parsed_bits(1, BitRange { offset: 0, len: 3 }, top), ann("Top Bits", auto()))(s)?;
+///```
+pub fn parsed_bits<T, Error, Fragment>(
+    back: usize,
+    bits: BitRange,
+    value: T,
+) -> impl FnMut(Annotated<Fragment>) -> IResult<Annotated<Fragment>, T, Error>
+where
+    T: Clone,
+    Error: ParseError<Annotated<Fragment>>,
+{
+    move |mut input: Annotated<Fragment>| {
+        let from = input.next_offset - back;
+        let to = from + (bits.offset + bits.len).div_ceil(8);
+        input.bits = Some((from, to, bits));
+        Ok((input, value.clone()))
+    }
+}
+
+/// Annotates `value` by a leaf covering bits of already parsed bytes.
+fn annotate_bits<T, Error, Fragment>(
+    mut span: Annotated<Fragment>,
+    bits: (usize, usize, BitRange),
+    value: T,
+    ann: &Ann<T>,
+) -> Annotated<Fragment>
+where
+    T: Clone,
+    Error: ParseError<Annotated<Fragment>>,
+{
+    span.bits = Some(bits);
+    let parsed: IResult<_, _, Error> = parse(success(value), ann)(span);
+    match parsed {
+        Ok((span, _)) => span,
+        Err(_) => unreachable!("Parser won't fail here"),
+    }
+}
+
 pub fn parse<'a, Annotation, Parse, Error, Output, Fragment>(
     mut parse: Parse,
     ann: Annotation,
@@ -478,25 +1258,88 @@ where
     move |mut input: Annotated<Fragment>| {
         let from = input.next_offset;
         let index = input.next_index;
-        let mut next_tree = input.tree;
-        input.tree = vec![];
-        let (span, out) = parse.parse(input)?;
-        let to = span.next_offset;
+        let next_tree = std::mem::take(&mut input.tree);
 
         let ann = ann.borrow();
+        let progress = input.progress.clone();
+        let depth = progress.borrow_mut().enter();
+
+        let (mut span, out) = match parse.parse(input) {
+            Ok(parsed) => {
+                progress.borrow_mut().succeed(depth);
+                parsed
+            }
+            Err(e) => {
+                progress
+                    .borrow_mut()
+                    .fail(depth, from, index, ann, next_tree.into_vec());
+                return Err(e);
+            }
+        };
+        let to = span.next_offset;
+        span.warnings.extend(
+            ann.warnings
+                .iter()
+                .filter_map(|w| w.resolve(&out))
+                .map(|message| Warning { message }),
+        );
+        let collapsed =
+            !span.tree.is_empty() && progress.as_ref().borrow().budget.collapses(depth, from, to);
+        let location = match span.bits {
+            Some((from, to, bits)) => LeafLocation {
+                from,
+                to,
+                index,
+                bits: Some(bits),
+            },
+            None => LeafLocation {
+                from,
+                to,
+                index,
+                bits: None,
+            },
+        };
+        let range = (location.from, location.to);
+        let value = ann.value.resolve(&out);
+        let categories = categories(&ann.categories, &value);
+        let mut data = with_id(span.data, ann);
 
         // If the tree returned by parser does not have any new items,
         // we are in the leaf situation (parser did not produce any new branches).
-        let node = if span.tree.is_empty() {
+        let node = if collapsed {
+            // Nested annotations are dropped, the whole structure is a single leaf.
+            data.insert("collapsed".into(), "true".to_string());
             Node::Leaf(Leaf::Real(RealLeaf {
                 path: vec![],
-                location: LeafLocation { from, to, index },
+                location,
                 information: Information {
                     label: ann.label.clone(),
-                    data: span.data,
+                    data,
+                    tags: span.tags,
+                    warnings: span.warnings,
+                    links: vec![],
+                    categories: categories.clone(),
+                    refs: ann.refs.clone(),
+                    value: value.clone(),
+                    unit: ann.unit,
+                    doc: ann.doc.clone(),
+                    splain: ann.splain.resolve(&out),
+                },
+            }))
+        } else if span.tree.is_empty() {
+            Node::Leaf(Leaf::Real(RealLeaf {
+                path: vec![],
+                location,
+                information: Information {
+                    label: ann.label.clone(),
+                    data,
                     tags: ann.tags.iter().filter_map(|t| t.resolve(&out)).collect(),
+                    warnings: span.warnings,
+                    links: vec![],
+                    categories: categories.clone(),
                     refs: ann.refs.clone(),
-                    value: ann.value.resolve(&out),
+                    value: value.clone(),
+                    unit: ann.unit,
                     doc: ann.doc.clone(),
                     splain: ann.splain.resolve(&out),
                 },
@@ -512,62 +1355,143 @@ where
                 },
                 information: Information {
                     label: ann.label.clone(),
-                    data: span.data,
+                    data,
                     tags: span.tags,
+                    warnings: span.warnings,
+                    links: vec![],
+                    categories,
                     refs: ann.refs.clone(),
-                    value: ann.value.resolve(&out),
+                    value,
+                    unit: ann.unit,
                     doc: ann.doc.clone(),
                     splain: ann.splain.resolve(&out),
                 },
-                children: span.tree,
+                children: span.tree.into_vec(),
             }
         };
 
         // Only leaves advance index, groups are only logical collections of
         // leaves, they do not represent anything in the raw data.
-        let next_index = if matches!(node, Node::Leaf(_)) {
+        let next_index = if collapsed {
+            index + 1
+        } else if matches!(node, Node::Leaf(_)) {
             span.next_index + 1
         } else {
             span.next_index
         };
 
-        next_tree.push(node);
+        let next_tree = next_tree.push(node);
 
         let next_span = Annotated {
             next_index,
             next_offset: span.next_offset,
             next_fragment: span.next_fragment,
+            source: span.source,
             data: HashMap::new(),
             tags: vec![],
+            warnings: vec![],
             tree: next_tree,
             appendices: span.appendices,
-            last_range: Some((from, to)),
+            links: span.links,
+            progress: span.progress,
+            last_range: Some(range),
+            bits: None,
         };
         Ok((next_span, out))
     }
 }
 
-/// Applies `parse` to only first `length` bytes. It is similar to nom's `length_value`, however
-/// it returns span produce by `parse` (nom's `length_value` returns span of the rest and therefore
-/// all annotations produced by `parse` were lost).
-pub fn parse_slice<'a, Parse, Error, Output, Fragment, Length>(
+/// Applies `parse` to exactly `length` bytes, as needed by length-prefixed fields.
+/// It is similar to nom's `length_value`, however it returns span produced by `parse`
+/// (nom's `length_value` returns span of the rest and therefore all annotations
+/// produced by `parse` would be lost).
+///
+/// Fails with [`ErrorKind::Eof`] if there are fewer than `length` bytes left,
+/// with [`ErrorKind::TooLarge`] if `length` does not fit into memory and with
+/// [`ErrorKind::LengthValue`] if `parse` does not consume all the `length` bytes
+/// or if it needs more than `length` bytes. Fields whose unknown tail is to be
+/// skipped can use [`parse_slice_lenient`].
+pub fn parse_slice<'a, Parse, Output, Fragment, Length>(
+    length: Length,
+    parse: Parse,
+) -> impl FnMut(Annotated<Fragment>) -> IResult<Annotated<Fragment>, Output> + 'a
+where
+    Parse: Parser<Annotated<Fragment>, Output, nom::error::Error<Annotated<Fragment>>> + 'a,
+    Length: TryInto<usize> + Copy + 'a,
+    Annotated<Fragment>: InputTake + InputLength + Slice<RangeFrom<usize>> + Slice<RangeTo<usize>>,
+{
+    slice_parser(length, parse, true)
+}
+
+/// Applies `parse` to first `length` bytes like [`parse_slice`], bytes of the slice
+/// that `parse` does not consume are skipped, so fields extended by newer versions
+/// of protocols can be read. Reading beyond the slice is still an error.
+pub fn parse_slice_lenient<'a, Parse, Output, Fragment, Length>(
+    length: Length,
+    parse: Parse,
+) -> impl FnMut(Annotated<Fragment>) -> IResult<Annotated<Fragment>, Output> + 'a
+where
+    Parse: Parser<Annotated<Fragment>, Output, nom::error::Error<Annotated<Fragment>>> + 'a,
+    Length: TryInto<usize> + Copy + 'a,
+    Annotated<Fragment>: InputTake + InputLength + Slice<RangeFrom<usize>> + Slice<RangeTo<usize>>,
+{
+    slice_parser(length, parse, false)
+}
+
+/// Runs `parse` on first `length` bytes, failing if it leaves some of them unread
+/// and `strict` is set.
+fn slice_parser<'a, Parse, Output, Fragment, Length>(
     length: Length,
     mut parse: Parse,
-) -> impl FnMut(Annotated<Fragment>) -> IResult<Annotated<Fragment>, Output, Error> + 'a
+    strict: bool,
+) -> impl FnMut(Annotated<Fragment>) -> IResult<Annotated<Fragment>, Output> + 'a
 where
-    Parse: Parser<Annotated<Fragment>, Output, Error> + 'a,
-    Error: ParseError<Annotated<Fragment>>,
+    Parse: Parser<Annotated<Fragment>, Output, nom::error::Error<Annotated<Fragment>>> + 'a,
     Length: TryInto<usize> + Copy + 'a,
-    Annotated<Fragment>: InputTake + Slice<RangeFrom<usize>> + Slice<RangeTo<usize>>,
+    Annotated<Fragment>: InputTake + InputLength + Slice<RangeFrom<usize>> + Slice<RangeTo<usize>>,
 {
     move |input: Annotated<Fragment>| {
-        let (s, rest) = input.take_split(
-            length
-                .try_into()
-                .unwrap_or_else(|_| panic!("Cannot run this on smaller than 64bit platform.")),
-        );
-        let (mut s, out) = parse.parse(s)?;
+        let Ok(length) = length.try_into() else {
+            return Err(nom::Err::Error(nom::error::Error::from_error_kind(
+                input,
+                ErrorKind::TooLarge,
+            )));
+        };
 
+        if input.input_len() < length {
+            return Err(nom::Err::Error(nom::error::Error::from_error_kind(
+                input,
+                ErrorKind::Eof,
+            )));
+        }
+
+        let (s, rest) = input.take_split(length);
+        let (mut s, out) = match parse.parse(s) {
+            Ok(r) => r,
+            // Parser wanted bytes past the end of the slice.
+            Err(nom::Err::Incomplete(_)) => {
+                return Err(nom::Err::Error(nom::error::Error::from_error_kind(
+                    rest,
+                    ErrorKind::LengthValue,
+                )))
+            }
+            Err(nom::Err::Error(e)) if e.code == ErrorKind::Eof => {
+                return Err(nom::Err::Error(nom::error::Error::from_error_kind(
+                    rest,
+                    ErrorKind::LengthValue,
+                )))
+            }
+            Err(e) => return Err(e),
+        };
+
+        if strict && s.input_len() > 0 {
+            return Err(nom::Err::Error(nom::error::Error::from_error_kind(
+                s,
+                ErrorKind::LengthValue,
+            )));
+        }
+
+        s.next_offset = rest.next_offset;
         s.next_fragment = rest.next_fragment;
 
         Ok((s, out))
@@ -591,6 +1515,73 @@ where
     }
 }
 
+/// Data of node with explicit ID of its annotation, if it has one.
+fn with_id<T>(
+    mut data: HashMap<Cow<'static, str>, String>,
+    ann: &Ann<T>,
+) -> HashMap<Cow<'static, str>, String> {
+    if let Some(id) = &ann.id {
+        data.insert("id".into(), id.clone());
+    }
+    data
+}
+
+/// Categories declared by annotation together with the one implied by value.
+fn categories(declared: &[Category], value: &Value) -> Vec<Category> {
+    let mut categories = declared.to_vec();
+    if let Some(implied) = Category::of(value).filter(|c| !categories.contains(c)) {
+        categories.push(implied);
+    }
+    categories
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::number::complete::{be_u16, u8};
+
+    use super::*;
+
+    /// Result of `parser` on the data, offset of next byte or of the error.
+    fn run<'a, O>(
+        data: &'a [u8],
+        mut parser: impl FnMut(Span<'a>) -> Parsed<'a, O>,
+    ) -> Result<usize, (ErrorKind, usize)> {
+        match parser(Annotated::new(data, Bytes::copy_from_slice(data))) {
+            Ok((s, _)) => Ok(s.next_offset),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Err((e.code, e.input.next_offset)),
+            Err(nom::Err::Incomplete(_)) => panic!("incomplete"),
+        }
+    }
+
+    #[test]
+    fn slice() {
+        let data = [0xaa, 0xbb, 0xcc];
+
+        assert_eq!(run(&data, parse_slice(2u8, be_u16)), Ok(2));
+        assert_eq!(
+            run(&data, parse_slice(4u8, be_u16)),
+            Err((ErrorKind::Eof, 0))
+        );
+
+        // Parser reads less than the slice.
+        assert_eq!(
+            run(&data, parse_slice(2u8, u8)),
+            Err((ErrorKind::LengthValue, 1))
+        );
+        assert_eq!(run(&data, parse_slice_lenient(2u8, u8)), Ok(2));
+
+        // Parser reads more than the slice.
+        assert_eq!(
+            run(&data, parse_slice(1u8, be_u16)),
+            Err((ErrorKind::LengthValue, 1))
+        );
+        assert_eq!(
+            run(&data, parse_slice_lenient(1u8, be_u16)),
+            Err((ErrorKind::LengthValue, 1))
+        );
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use nom::combinator::success;
@@ -0,0 +1,169 @@
+//! Decoders provided by plugins, i. e. shared libraries loaded during
+//! program's execution. Plugins allow to support additional formats
+//! without recompiling bitsplain.
+//!
+//! A plugin is a crate of type `cdylib` that depends on bitsplain, defines
+//! its decoders as statics and exports them using macro [`plugin!`](crate::plugin!):
+//!
+//! ```ignore
+//! static ENVELOPE: Decoder = Decoder {
+//!     title: "Proprietary envelope",
+//!     group: "acme",
+//!     symbol: "envelope",
+//!     decode: |b, budget| bitsplain::parse::decode_with(b, budget, envelope),
+//! };
+//!
+//! bitsplain::plugin!(ENVELOPE);
+//! ```
+//!
+//! Rust does not have stable ABI, therefore plugins have to be compiled
+//! by the same compiler, for the same target and against the same version
+//! of bitsplain as the program that loads them. Plugins built otherwise
+//! are rejected.
+//!
+//! Loading of plugins requires feature `plugins`.
+
+/// Version of bitsplain, compiler and target, with which plugins are built.
+/// It is terminated by NUL, so it can be passed as C string.
+pub const VERSION: &str = concat!(
+    "bitsplain ",
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("BITSPLAIN_RUSTC"),
+    ", ",
+    env!("BITSPLAIN_TARGET"),
+    ")\0"
+);
+
+/// Exports decoders from a plugin, so they can be found by [`load_plugin`].
+#[macro_export]
+macro_rules! plugin {
+    ($($decoder: path),+ $(,)?) => {
+        #[no_mangle]
+        pub extern "C" fn bitsplain_plugin_version() -> *const ::std::os::raw::c_char {
+            $crate::plugin::VERSION.as_ptr() as *const ::std::os::raw::c_char
+        }
+
+        #[no_mangle]
+        pub fn bitsplain_plugin_decoders() -> &'static [&'static $crate::decode::Decoder] {
+            static DECODERS: &[&$crate::decode::Decoder] = &[$(&$decoder),+];
+            DECODERS
+        }
+    };
+}
+
+#[cfg(feature = "plugins")]
+pub use loading::*;
+
+#[cfg(feature = "plugins")]
+mod loading {
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+    use std::path::{Path, PathBuf};
+
+    use libloading::{Library, Symbol};
+
+    use super::VERSION;
+    use crate::decode::{register_decoders, Decoder};
+
+    /// Plugin whose decoders were registered.
+    #[derive(Debug)]
+    pub struct Plugin {
+        /// Path to the shared library.
+        pub path: PathBuf,
+
+        /// Decoders provided by the plugin.
+        pub decoders: Vec<&'static Decoder>,
+    }
+
+    /// Reason why a plugin could not be loaded.
+    #[derive(Debug)]
+    pub enum PluginError {
+        /// Directory with plugins could not be read.
+        Io(PathBuf, std::io::Error),
+
+        /// The shared library could not be loaded or is not a plugin.
+        Load(PathBuf, libloading::Error),
+
+        /// The plugin was built against another version of bitsplain,
+        /// by another compiler or for another target.
+        Version { path: PathBuf, version: String },
+    }
+
+    impl std::fmt::Display for PluginError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                PluginError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+                PluginError::Load(path, e) => write!(f, "{}: {e}", path.display()),
+                PluginError::Version { path, version } => write!(
+                    f,
+                    "{}: built with {version}, expected {}",
+                    path.display(),
+                    VERSION.trim_end_matches('\0')
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for PluginError {}
+
+    /// Loads plugin from shared library and registers its decoders.
+    ///
+    /// The library is never unloaded, since the registered decoders refer to it.
+    pub fn load_plugin(path: &Path) -> Result<Plugin, PluginError> {
+        let error = |e| PluginError::Load(path.to_path_buf(), e);
+
+        // SAFETY: Loading a library runs its initialization code, we have to
+        // trust the plugins that users placed to the directory.
+        let library: &'static Library =
+            Box::leak(Box::new(unsafe { Library::new(path) }.map_err(error)?));
+
+        // SAFETY: Plugins export the symbols with these types using macro `plugin!`.
+        let version = unsafe {
+            let version: Symbol<extern "C" fn() -> *const c_char> =
+                library.get(b"bitsplain_plugin_version\0").map_err(error)?;
+            CStr::from_ptr(version()).to_string_lossy().into_owned()
+        };
+
+        if version != VERSION.trim_end_matches('\0') {
+            return Err(PluginError::Version {
+                path: path.to_path_buf(),
+                version,
+            });
+        }
+
+        // SAFETY: As above, and the version check makes sure that type
+        // `Decoder` is compiled the same way in the plugin.
+        let decoders = unsafe {
+            let decoders: Symbol<fn() -> &'static [&'static Decoder]> =
+                library.get(b"bitsplain_plugin_decoders\0").map_err(error)?;
+            decoders().to_vec()
+        };
+
+        register_decoders(&decoders);
+
+        Ok(Plugin {
+            path: path.to_path_buf(),
+            decoders,
+        })
+    }
+
+    /// Loads all plugins, i. e. shared libraries, found in the directory.
+    pub fn load_plugins(dir: &Path) -> Vec<Result<Plugin, PluginError>> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => return vec![Err(PluginError::Io(dir.to_path_buf(), e))],
+        };
+
+        let mut paths = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|e| e == std::env::consts::DLL_EXTENSION)
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        paths.iter().map(|p| load_plugin(p)).collect()
+    }
+}
@@ -0,0 +1,165 @@
+//! Selection of nodes of [`Tree`](crate::tree::Tree) by queries such as
+//! `Input List/0/Sequence`, `**/Signature` or `**/[datatype=txid]`.
+//!
+//! Query consists of steps separated by `/`, every step matches nodes on one
+//! level of the tree:
+//!
+//! - label of node, compared case-insensitively and ignoring all characters
+//!   except letters and digits (i. e. `input_list` matches `Input List`),
+//! - `*` in label matches any sequence of characters (e. g. `Witness*`),
+//!   alone it matches any node,
+//! - `**` matches any number of levels, including none,
+//! - `[key=value]` after label (or instead of it) requires the node
+//!   to have the data attached (e. g. `*[datatype=txid]`).
+
+use std::str::FromStr;
+
+use crate::tree::Node;
+
+/// Parsed query, see [module documentation](self) for syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Query(Vec<Step>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Step {
+    /// Any number of levels (`**`).
+    Descend,
+    /// Node on the current level.
+    Node {
+        /// Normalized label pattern, `None` matches any label.
+        label: Option<String>,
+        /// Required data of node.
+        filters: Vec<(String, String)>,
+    },
+}
+
+impl Step {
+    fn matches(&self, node: &Node) -> bool {
+        match self {
+            Step::Descend => true,
+            Step::Node { label, filters } => {
+                let information = node.information();
+                label
+                    .as_ref()
+                    .map(|l| glob(l, &normalize(&information.label)))
+                    .unwrap_or(true)
+                    && filters
+                        .iter()
+                        .all(|(k, v)| information.data.get(k.as_str()) == Some(v))
+            }
+        }
+    }
+}
+
+impl Query {
+    /// Nodes matching the query, in order of their appearance in the tree.
+    pub fn select<'a>(&self, nodes: &'a [Node]) -> Vec<&'a Node> {
+        let mut selected = vec![];
+        nodes
+            .iter()
+            .for_each(|node| select(node, &self.0, &mut selected));
+        selected
+    }
+}
+
+fn select<'a>(node: &'a Node, steps: &[Step], selected: &mut Vec<&'a Node>) {
+    let children = match node {
+        Node::Group { children, .. } => children.as_slice(),
+        Node::Leaf(_) => &[],
+    };
+
+    match steps.split_first() {
+        None => {}
+        Some((Step::Descend, [])) => {
+            push(node, selected);
+            children.iter().for_each(|ch| select(ch, steps, selected));
+        }
+        Some((Step::Descend, rest)) => {
+            select(node, rest, selected);
+            children.iter().for_each(|ch| select(ch, steps, selected));
+        }
+        Some((step, rest)) if step.matches(node) => {
+            if rest.is_empty() {
+                push(node, selected);
+            } else {
+                children.iter().for_each(|ch| select(ch, rest, selected));
+            }
+        }
+        Some(_) => {}
+    }
+}
+
+/// Adds node unless it was already selected by other way.
+fn push<'a>(node: &'a Node, selected: &mut Vec<&'a Node>) {
+    if !selected.iter().any(|n| std::ptr::eq(*n, node)) {
+        selected.push(node);
+    }
+}
+
+/// Lowercase letters and digits of label, other characters are removed
+/// (except `*` which is kept in patterns).
+fn normalize(label: &str) -> String {
+    label
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '*')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether text matches pattern, in which `*` matches any sequence of characters.
+fn glob(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            text.starts_with(prefix)
+                && text[prefix.len()..]
+                    .char_indices()
+                    .map(|(i, _)| i)
+                    .chain([text.len() - prefix.len()])
+                    .any(|i| glob(rest, &text[prefix.len() + i..]))
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let steps = s
+            .trim()
+            .trim_matches('/')
+            .split('/')
+            .map(str::trim)
+            .map(|step| match step {
+                "" => Err(format!("empty step in query '{s}'")),
+                "**" => Ok(Step::Descend),
+                step => {
+                    let (label, mut filters) = match step.split_once('[') {
+                        Some((label, filters)) => (label, format!("[{filters}")),
+                        None => (step, String::new()),
+                    };
+                    let mut parsed = vec![];
+                    while !filters.is_empty() {
+                        let (filter, rest) = filters
+                            .strip_prefix('[')
+                            .and_then(|f| f.split_once(']'))
+                            .ok_or_else(|| format!("invalid filter in step '{step}'"))?;
+                        let (key, value) = filter
+                            .split_once('=')
+                            .filter(|(k, _)| !k.trim().is_empty())
+                            .ok_or_else(|| format!("filter '{filter}' is not 'key=value'"))?;
+                        parsed.push((key.trim().to_string(), value.trim().to_string()));
+                        filters = rest.to_string();
+                    }
+                    let label = normalize(label);
+                    Ok(Step::Node {
+                        label: Some(label).filter(|l| !l.is_empty() && l != "*"),
+                        filters: parsed,
+                    })
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Query(steps))
+    }
+}
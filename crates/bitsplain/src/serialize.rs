@@ -0,0 +1,96 @@
+//! Helpers for serialization of types that do not have suitable
+//! serde representation on their own.
+
+/// Bytes as hex-encoded string.
+pub(crate) mod hex_bytes {
+    use bytes::Bytes;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Bytes, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Bytes, D::Error> {
+        let s = String::deserialize(d)?;
+        hex::decode(s)
+            .map(Bytes::from)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Optional address as string. Deserialized addresses are
+/// assumed to be valid for any network.
+pub(crate) mod address {
+    use bitcoin::address::NetworkUnchecked;
+    use bitcoin::Address;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(addr: &Option<Address>, s: S) -> Result<S::Ok, S::Error> {
+        addr.as_ref().map(|a| a.to_string()).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Address>, D::Error> {
+        let addr = Option::<Address<NetworkUnchecked>>::deserialize(d)?;
+        Ok(addr.map(|a| a.assume_checked()))
+    }
+}
+
+/// Network address in its textual form.
+pub(crate) mod net_addr {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::types::NetAddr;
+
+    pub fn serialize<S: Serializer>(addr: &NetAddr, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(addr)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<NetAddr, D::Error> {
+        String::deserialize(d)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Number as integer if it fits into 64 bits, otherwise as string,
+/// since many formats do not support 128-bit integers.
+pub(crate) mod num {
+    use std::fmt;
+
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(num: &i128, s: S) -> Result<S::Ok, S::Error> {
+        match (i64::try_from(*num), u64::try_from(*num)) {
+            (Ok(n), _) => s.serialize_i64(n),
+            (_, Ok(n)) => s.serialize_u64(n),
+            _ => s.serialize_str(&num.to_string()),
+        }
+    }
+
+    struct NumVisitor;
+
+    impl Visitor<'_> for NumVisitor {
+        type Value = i128;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("integer or string containing integer")
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<i128, E> {
+            Ok(v.into())
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<i128, E> {
+            Ok(v.into())
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<i128, E> {
+            v.parse().map_err(E::custom)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<i128, D::Error> {
+        d.deserialize_any(NumVisitor)
+    }
+}
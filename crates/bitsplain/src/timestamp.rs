@@ -0,0 +1,149 @@
+//! Rendering of [`Value::Timestamp`](crate::value::Value::Timestamp) according
+//! to preferences of user: format, time zone and whether the time is shown
+//! relatively to now (e. g. “3 years ago”).
+
+use serde::Deserialize;
+use time::format_description::{self, OwnedFormatItem};
+use time::{Duration, OffsetDateTime, UtcOffset};
+
+/// Format used when none is configured.
+pub const DEFAULT_FORMAT: &str = "[year]-[month]-[day] [hour]:[minute]:[second]";
+
+/// Time zone in which timestamps are shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Zone {
+    Utc,
+    /// Current offset of time zone of the system, UTC if it cannot be found.
+    Local,
+    Offset(UtcOffset),
+}
+
+impl Zone {
+    /// Parses `utc`, `local` or offset such as `+02:00` or `-0530`.
+    pub fn parse(s: &str) -> Result<Zone, String> {
+        let invalid = || format!("'{s}' is not a valid time zone");
+
+        match s.to_lowercase().as_str() {
+            "utc" | "z" => Ok(Zone::Utc),
+            "local" => Ok(Zone::Local),
+            _ => {
+                let (sign, rest) = match s.split_at_checked(1) {
+                    Some(("+", rest)) => (1, rest),
+                    Some(("-", rest)) => (-1, rest),
+                    _ => return Err(invalid()),
+                };
+                let digits = rest.replace(':', "");
+                if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(invalid());
+                }
+                let hours = digits[..2].parse::<i8>().map_err(|_| invalid())?;
+                let minutes = digits[2..].parse::<i8>().unwrap_or(0);
+                UtcOffset::from_hms(sign * hours, sign * minutes, 0)
+                    .map(Zone::Offset)
+                    .map_err(|_| invalid())
+            }
+        }
+    }
+
+    /// Offset from UTC.
+    fn offset(&self) -> UtcOffset {
+        match self {
+            Zone::Utc => UtcOffset::UTC,
+            Zone::Local => UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+            Zone::Offset(o) => *o,
+        }
+    }
+}
+
+/// Whether timestamps are shown as absolute time, relative to now or both.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    #[default]
+    Absolute,
+    Relative,
+    /// Absolute time followed by relative one in parentheses.
+    Both,
+}
+
+/// Complete description of how timestamps are rendered.
+#[derive(Clone, Debug)]
+pub struct TimeFormat {
+    format: OwnedFormatItem,
+    offset: UtcOffset,
+    style: Style,
+    /// Time to which timestamps are relative.
+    now: OffsetDateTime,
+}
+
+impl TimeFormat {
+    /// Format of `format` is described at
+    /// <https://time-rs.github.io/book/api/format-description.html>.
+    /// Timestamps are shown relatively to `now`, if `style` says so.
+    pub fn new(
+        format: &str,
+        zone: Zone,
+        style: Style,
+        now: OffsetDateTime,
+    ) -> Result<TimeFormat, String> {
+        let format = format_description::parse_owned::<1>(format).map_err(|e| e.to_string())?;
+        Ok(TimeFormat {
+            format,
+            offset: zone.offset(),
+            style,
+            now,
+        })
+    }
+
+    pub fn format(&self, time: &OffsetDateTime) -> String {
+        let absolute = || {
+            time.to_offset(self.offset)
+                .format(&self.format)
+                .unwrap_or_else(|_| time.to_string())
+        };
+        let relative = || relative(*time - self.now);
+
+        match self.style {
+            Style::Absolute => absolute(),
+            Style::Relative => relative(),
+            Style::Both => format!("{} ({})", absolute(), relative()),
+        }
+    }
+}
+
+impl Default for TimeFormat {
+    fn default() -> Self {
+        // Absolute style does not need current time.
+        TimeFormat::new(
+            DEFAULT_FORMAT,
+            Zone::Utc,
+            Style::Absolute,
+            OffsetDateTime::UNIX_EPOCH,
+        )
+        .unwrap()
+    }
+}
+
+/// Approximate duration in the largest whole unit, e. g. “3 years ago”
+/// or “in 2 hours”.
+fn relative(duration: Duration) -> String {
+    let seconds = duration.whole_seconds().unsigned_abs();
+    let (n, unit) = [
+        (365 * 24 * 3600, "year"),
+        (30 * 24 * 3600, "month"),
+        (24 * 3600, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+    ]
+    .into_iter()
+    .find(|(s, _)| seconds >= *s)
+    .map(|(s, unit)| (seconds / s, unit))
+    .unwrap_or((seconds, "second"));
+
+    let plural = if n == 1 { "" } else { "s" };
+    if duration.is_negative() {
+        format!("{n} {unit}{plural} ago")
+    } else {
+        format!("in {n} {unit}{plural}")
+    }
+}
@@ -0,0 +1,269 @@
+//! Step-by-step traces of execution of scripts of transaction, attached
+//! to the tree as groups of virtual leaves, one per instruction, showing
+//! stack after the instruction.
+//!
+//! Script of output is executed alone, with unknown items of stack
+//! represented by symbols. Scripts of input are executed together with
+//! script of spent output, if the output is known.
+
+use std::collections::HashMap;
+
+use bitcoin::blockdata::opcodes::ClassifyContext;
+use bitcoin::{Script, ScriptBuf, Transaction, TxOut};
+
+use crate::btc::interpreter::{execute, Item, Outcome, Program, Trace};
+use crate::tree::{Information, Leaf, Node, Tree, VirtualLeaf, Warning};
+use crate::value::Value;
+
+/// Tree of transaction with traces of execution of scripts of outputs
+/// and of inputs whose spent outputs are given (in order of inputs).
+pub fn with_traces(tree: Tree, tx: &Transaction, prevouts: &[Option<TxOut>]) -> Tree {
+    let mut nodes = tree.into_nodes();
+
+    nodes.iter_mut().for_each(|node| match node {
+        Node::Group {
+            information,
+            children,
+            ..
+        } if information.label == "Input List" => groups(children)
+            .zip(&tx.input)
+            .zip(prevouts)
+            .for_each(|((input, txin), prevout)| {
+                if let Some(prevout) = prevout {
+                    let witness = txin.witness.iter().map(<[u8]>::to_vec).collect();
+                    if let Some(trace) =
+                        input_trace(&txin.script_sig, witness, &prevout.script_pubkey)
+                    {
+                        attach(input, trace);
+                    }
+                }
+            }),
+        Node::Group {
+            information,
+            children,
+            ..
+        } if information.label == "Output List" => {
+            groups(children)
+                .zip(&tx.output)
+                .for_each(|(output, txout)| {
+                    let program = Program {
+                        name: "Output Script",
+                        script: &txout.script_pubkey,
+                        context: ClassifyContext::Legacy,
+                        verify_first: false,
+                    };
+                    attach(
+                        output,
+                        (vec!["Output Script"], execute(&[program], vec![], true)),
+                    );
+                })
+        }
+        _ => {}
+    });
+
+    Tree::from_nodes(nodes)
+}
+
+fn groups(nodes: &mut [Node]) -> impl Iterator<Item = &mut Node> {
+    nodes.iter_mut().filter(|n| matches!(n, Node::Group { .. }))
+}
+
+/// Trace of spending of output with script `spk` by input with `script_sig`
+/// and `witness`, together with names of executed scripts.
+fn input_trace(
+    script_sig: &Script,
+    witness: Vec<Vec<u8>>,
+    spk: &Script,
+) -> Option<(Vec<&'static str>, Trace)> {
+    let legacy = |name, script| Program {
+        name,
+        script,
+        context: ClassifyContext::Legacy,
+        verify_first: false,
+    };
+
+    // Nested SegWit has witness program as the only item of script of input.
+    let redeem = script_sig
+        .instructions()
+        .last()
+        .and_then(|i| {
+            i.ok()?
+                .push_bytes()
+                .map(|b| ScriptBuf::from_bytes(b.as_bytes().to_vec()))
+        })
+        .filter(|_| spk.is_p2sh());
+    let program = match &redeem {
+        Some(r) if r.is_witness_program() => r.as_script(),
+        _ => spk,
+    };
+
+    if program.is_p2wpkh() {
+        let code = program.p2wpkh_script_code()?;
+        let trace = execute(
+            &[legacy("Public Key Hash Script", &code)],
+            items(witness),
+            false,
+        );
+        Some((vec!["Public Key Hash Script"], trace))
+    } else if program.is_p2wsh() {
+        let (script, stack) = witness.split_last()?;
+        let script = ScriptBuf::from_bytes(script.clone());
+        let trace = execute(
+            &[legacy("Witness Script", &script)],
+            items(stack.to_vec()),
+            false,
+        );
+        Some((vec!["Witness Script"], trace))
+    } else if program.is_p2tr() {
+        let mut witness = witness;
+        if witness.len() > 1 && witness.last().and_then(|a| a.first()) == Some(&0x50) {
+            witness.pop();
+        }
+        // Key path spend contains no script.
+        let [stack @ .., script, _control] = witness.as_slice() else {
+            return None;
+        };
+        let script = ScriptBuf::from_bytes(script.clone());
+        let program = Program {
+            name: "Tapscript",
+            script: &script,
+            context: ClassifyContext::TapScript,
+            verify_first: false,
+        };
+        Some((
+            vec!["Tapscript"],
+            execute(&[program], items(stack.to_vec()), false),
+        ))
+    } else if let Some(redeem) = &redeem {
+        let programs = [
+            legacy("Input Script", script_sig),
+            legacy("Output Script", spk),
+            Program {
+                verify_first: true,
+                ..legacy("Redeem Script", redeem)
+            },
+        ];
+        Some((
+            vec!["Input Script", "Output Script", "Redeem Script"],
+            execute(&programs, vec![], false),
+        ))
+    } else {
+        let programs = [
+            legacy("Input Script", script_sig),
+            legacy("Output Script", spk),
+        ];
+        Some((
+            vec!["Input Script", "Output Script"],
+            execute(&programs, vec![], false),
+        ))
+    }
+}
+
+fn items(data: Vec<Vec<u8>>) -> Vec<Item> {
+    data.into_iter().map(Item::Data).collect()
+}
+
+/// Appends trace as a group to children of `parent`.
+fn attach(parent: &mut Node, (names, trace): (Vec<&'static str>, Trace)) {
+    let Node::Group {
+        path,
+        location,
+        children,
+        ..
+    } = parent
+    else {
+        return;
+    };
+    let path = [path.as_slice(), &[children.len().to_string()]].concat();
+    let leaf = |index: usize, label: String, value: Value, splain: Option<String>| {
+        Node::Leaf(Leaf::Virtual(VirtualLeaf {
+            path: [path.as_slice(), &[index.to_string()]].concat(),
+            information: information(label, value, None, splain),
+        }))
+    };
+
+    let mut steps = vec![];
+    let mut program = None;
+    trace.steps.iter().for_each(|step| {
+        if program != Some(step.program) && names.len() > 1 {
+            program = Some(step.program);
+            steps.push(leaf(
+                steps.len(),
+                format!("[{}]", names[step.program]),
+                Value::Nil,
+                None,
+            ));
+        }
+        let stack = step
+            .stack
+            .iter()
+            .map(Item::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        steps.push(leaf(
+            steps.len(),
+            step.instruction.clone(),
+            Value::text(if stack.is_empty() {
+                "(empty stack)".to_string()
+            } else {
+                stack
+            }),
+            step.note.clone(),
+        ));
+    });
+
+    let mut result = information(
+        "Result".to_string(),
+        Value::text(trace.outcome.to_string()),
+        Some(
+            "Outcome of execution: script succeeds if it finishes with true value on top of stack."
+                .to_string(),
+        ),
+        (trace.skipped > 0).then(|| {
+            format!(
+                "Only first {} steps are shown, further {} steps were executed but not recorded.",
+                trace.steps.len(),
+                trace.skipped
+            )
+        }),
+    );
+    if let Outcome::Failure(reason) = &trace.outcome {
+        result.warnings.push(Warning {
+            message: format!("Script fails: {reason}"),
+        });
+    }
+    steps.push(Node::Leaf(Leaf::Virtual(VirtualLeaf {
+        path: [path.as_slice(), &[steps.len().to_string()]].concat(),
+        information: result,
+    })));
+
+    let location = location.clone();
+    children.push(Node::Group {
+        path,
+        location,
+        information: information(
+            "Script Trace".to_string(),
+            Value::Nil,
+            Some(format!(
+                "Step-by-step execution of {}. Every step shows stack after the instruction, its top is on the right.",
+                names.join(", ")
+            )),
+            None,
+        ),
+        children: steps,
+    });
+}
+
+fn information(
+    label: String,
+    value: Value,
+    doc: Option<String>,
+    splain: Option<String>,
+) -> Information {
+    Information {
+        data: HashMap::from([("source".into(), "trace".to_string())]),
+        doc,
+        splain,
+        ..Information::derived(label, value)
+    }
+}
@@ -1,14 +1,25 @@
 //! Hierarchical structure of [`Values`](crate::value) that is built
 //! during parsing of the binary input.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::ops::{Deref, Range};
+use std::str::FromStr;
+use std::sync::OnceLock;
 
+use bitcoin::{Address, Network};
+use serde::{Deserialize, Serialize};
+
+use crate::btc::tx::address_splain;
+use crate::catalog::{self, Catalog};
+use crate::datatype::Datatype;
 use crate::dsl::Reference;
-use crate::value::Value;
+use crate::query::Query;
+use crate::value::{Unit, Value};
 
 /// Node in the [`Tree`] of [`Values`](crate::value).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "node", rename_all = "lowercase")]
 pub enum Node {
     Group {
         /// Path to this group.
@@ -39,6 +50,23 @@ impl Node {
         }
     }
 
+    /// Range of bytes covered by this node. Virtual leaves do not cover any.
+    pub fn byte_range(&self) -> Option<Range<usize>> {
+        match self {
+            Node::Group { location, .. } => Some(location.byte_from..location.byte_to),
+            Node::Leaf(Leaf::Real(l)) => Some(l.location.range()),
+            Node::Leaf(Leaf::Virtual(_)) => None,
+        }
+    }
+
+    /// Returns path of this node.
+    pub fn path(&self) -> &[String] {
+        match self {
+            Node::Group { path, .. } => path,
+            Node::Leaf(leaf) => leaf.path(),
+        }
+    }
+
     pub fn information(&self) -> &Information {
         match self {
             Node::Group { information, .. } => information,
@@ -53,7 +81,7 @@ impl Node {
 ///
 /// The location is exclusive in the upper bound (`to`), i. e.
 /// the number of bytes in the range is `from - to`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LeafLocation {
     /// Offset of the first byte of the leaf.
     pub from: usize,
@@ -63,6 +91,11 @@ pub struct LeafLocation {
 
     /// Ordinal index of this leaf.
     pub index: usize,
+
+    /// Bits of the bytes that the leaf covers, if it does not cover
+    /// them whole (e. g. flags or fields of bitmaps).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bits: Option<BitRange>,
 }
 
 impl LeafLocation {
@@ -71,11 +104,32 @@ impl LeafLocation {
     }
 }
 
+/// Range of bits within bytes of a leaf. Bits are counted from the most
+/// significant bit of the first byte, i. e. in order in which they are
+/// written, regardless of how they are numbered by the format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BitRange {
+    /// Position of the first bit.
+    pub offset: usize,
+
+    /// Number of bits.
+    pub len: usize,
+}
+
+impl std::fmt::Display for BitRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.len {
+            1 => write!(f, "bit {}", self.offset),
+            n => write!(f, "bits {}–{}", self.offset, self.offset + n - 1),
+        }
+    }
+}
+
 /// Range of bytes in the binary input that is further divided,
 /// i. e. may contains leaves or other groups.
 ///
 /// The upper bounds are exclusive.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GroupLocation {
     /// Offset of the first byte of the group.
     pub byte_from: usize,
@@ -91,13 +145,13 @@ pub struct GroupLocation {
 }
 
 /// Details about leaf or group.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Information {
     /// Label of the leaf or group.
     pub label: String,
 
     /// Auxiliary data attached to the leaf or group.
-    pub data: HashMap<&'static str, String>,
+    pub data: HashMap<Cow<'static, str>, String>,
 
     /// Tags attached to the leaf or group.
     pub tags: Vec<Tag>,
@@ -107,14 +161,60 @@ pub struct Information {
     /// Value of the leaf or group.
     pub value: Value,
 
+    /// Unit of numeric value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<Unit>,
+
     /// Documentation string.
     pub doc: Option<String>,
 
     /// Splain string.
     pub splain: Option<String>,
+
+    /// Warnings about suspicious or non-standard data.
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
+
+    /// References to other nodes of the tree.
+    #[serde(default)]
+    pub links: Vec<Link>,
+
+    /// Semantic categories of the leaf or group.
+    #[serde(default)]
+    pub categories: Vec<Category>,
 }
 
 impl Information {
+    /// Information of a node that was not parsed, e. g. a virtual leaf
+    /// computed from other data. Its categories are those implied by
+    /// the value, all other fields are empty.
+    pub fn derived(label: impl Into<String>, value: Value) -> Information {
+        Information {
+            label: label.into(),
+            data: HashMap::new(),
+            tags: vec![],
+            refs: vec![],
+            categories: Category::of(&value).into_iter().collect(),
+            value,
+            unit: None,
+            doc: None,
+            splain: None,
+            warnings: vec![],
+            links: vec![],
+        }
+    }
+
+    /// Descriptor of datatype of the node, if it is known.
+    pub fn datatype(&self) -> Option<&'static Datatype> {
+        self.data
+            .get("datatype")
+            .and_then(|d| Datatype::from_name(d))
+    }
+
+    pub fn has_category(&self, category: Category) -> bool {
+        self.categories.contains(&category)
+    }
+
     pub fn has_data(&self, key: &'static str, value: &str) -> bool {
         match self.data.get(key) {
             Some(v) => v == value,
@@ -124,16 +224,104 @@ impl Information {
 }
 
 /// Tag attached to leaf or group.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tag {
     pub label: String,
     pub color: Option<String>,
     pub doc: Option<String>,
 }
 
+/// Semantic category of leaf or group, i. e. what kind of thing it is
+/// regardless of how it is encoded. Users may focus on some of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    /// Identifier of something (txid, channel ID, node ID etc.).
+    Identifier,
+    Amount,
+    Signature,
+    /// Public or private key.
+    Key,
+    Time,
+    /// Absolute or relative lock time, including CLTV expiries.
+    Locktime,
+    Fee,
+}
+
+impl Category {
+    pub const ALL: [Category; 7] = [
+        Category::Identifier,
+        Category::Amount,
+        Category::Signature,
+        Category::Key,
+        Category::Time,
+        Category::Locktime,
+        Category::Fee,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Identifier => "identifier",
+            Category::Amount => "amount",
+            Category::Signature => "signature",
+            Category::Key => "key",
+            Category::Time => "time",
+            Category::Locktime => "locktime",
+            Category::Fee => "fee",
+        }
+    }
+
+    /// Category implied by kind of value, if there is any.
+    pub fn of(value: &Value) -> Option<Category> {
+        match value {
+            Value::Sat(_) | Value::MSat(_) => Some(Category::Amount),
+            Value::FeeRate(_) => Some(Category::Fee),
+            Value::Signature(_) => Some(Category::Signature),
+            Value::PublicKey(_) => Some(Category::Key),
+            Value::Timestamp(_) | Value::Duration(_) => Some(Category::Time),
+            Value::Hash(_) => Some(Category::Identifier),
+            Value::Alt(v1, v2) => Category::of(v1).or_else(|| Category::of(v2)),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Category::ALL
+            .into_iter()
+            .find(|c| c.name() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                let names = Category::ALL.map(|c| c.name()).join(", ");
+                format!("Unknown category '{s}', expected one of {names}.")
+            })
+    }
+}
+
+/// Warning about data that are valid, however suspicious or non-standard
+/// (e. g. non-canonical encoding or amount below dust limit).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Warning {
+    /// Description of the problem.
+    pub message: String,
+}
+
+/// Reference from one node to another node of the same tree (e. g.
+/// from a witness stack to the input it belongs to).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Link {
+    /// Description of the relationship.
+    pub label: String,
+
+    /// Path to the referenced node.
+    pub path: Vec<String>,
+}
+
 /// Leaf that is not directly represented in binary input. Its value is
 /// calculated from other available data.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VirtualLeaf {
     /// Path to this leaf.
     pub path: Vec<String>,
@@ -144,7 +332,7 @@ pub struct VirtualLeaf {
 
 /// Leaf that is represented in binary input. Its value is interpretation
 /// of the input.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RealLeaf {
     /// Path to this leaf.
     pub path: Vec<String>,
@@ -157,7 +345,8 @@ pub struct RealLeaf {
 }
 
 /// A leaf in the tree.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "leaf", rename_all = "lowercase")]
 pub enum Leaf {
     /// Real leaf, represented in binary input.
     Real(RealLeaf),
@@ -200,22 +389,46 @@ impl Leaf {
     }
 }
 
+/// Leaf in the index of byte ranges of [`Tree`].
+#[derive(Debug, Clone)]
+struct Interval {
+    /// Bytes covered by the leaf.
+    range: Range<usize>,
+    /// Positions of nodes on the way to the leaf.
+    position: Vec<usize>,
+}
+
 /// Tree of annotations.
-#[derive(Debug)]
-pub struct Tree(Vec<Node>);
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Tree {
+    nodes: Vec<Node>,
+
+    /// Real leaves ordered by their location, built when first needed.
+    #[serde(skip)]
+    index: OnceLock<Vec<Interval>>,
+}
 
 impl Tree {
     #[inline]
     pub fn from_nodes(trees: Vec<Node>) -> Tree {
-        Tree(trees)
+        Tree {
+            nodes: trees,
+            index: OnceLock::new(),
+        }
+    }
+
+    #[inline]
+    pub fn into_nodes(self) -> Vec<Node> {
+        self.nodes
     }
 
     pub fn leaves(&self) -> Vec<&Leaf> {
-        Self::tree_leaves(&self.0)
+        Self::tree_leaves(&self.nodes)
     }
 
     pub fn real_leaves(&self) -> Vec<&RealLeaf> {
-        Self::tree_leaves(&self.0)
+        Self::tree_leaves(&self.nodes)
             .iter()
             .filter_map(|l| match l {
                 Leaf::Real(r) => Some(r),
@@ -234,8 +447,244 @@ impl Tree {
             .collect()
     }
 
+    /// All warnings in the tree, in order of appearance, together
+    /// with information of nodes they belong to.
+    pub fn warnings(&self) -> Vec<(&Information, &Warning)> {
+        fn collect<'a>(nodes: &'a [Node], warnings: &mut Vec<(&'a Information, &'a Warning)>) {
+            nodes.iter().for_each(|node| {
+                let information = node.information();
+                warnings.extend(information.warnings.iter().map(|w| (information, w)));
+                if let Node::Group { children, .. } = node {
+                    collect(children, warnings);
+                }
+            });
+        }
+
+        let mut warnings = vec![];
+        collect(&self.nodes, &mut warnings);
+        warnings
+    }
+
+    /// Nodes that record verification of an invariant of data,
+    /// in order of appearance, together with whether it passed.
+    pub fn checks(&self) -> Vec<(&Node, bool)> {
+        self.find(|i| i.data.contains_key("check"))
+            .into_iter()
+            .map(|node| (node, node.information().has_data("check", "passed")))
+            .collect()
+    }
+
+    /// All links in the tree, in order of appearance, together
+    /// with nodes they lead from.
+    pub fn links(&self) -> Vec<(&Node, &Link)> {
+        fn collect<'a>(nodes: &'a [Node], links: &mut Vec<(&'a Node, &'a Link)>) {
+            nodes.iter().for_each(|node| {
+                links.extend(node.information().links.iter().map(|l| (node, l)));
+                if let Node::Group { children, .. } = node {
+                    collect(children, links);
+                }
+            });
+        }
+
+        let mut links = vec![];
+        collect(&self.nodes, &mut links);
+        links
+    }
+
+    /// All nodes whose information satisfies the predicate, groups as well
+    /// as leaves, in order of appearance (parents before their children).
+    /// Paths of found nodes are available through [`Node::path`].
+    pub fn find<F>(&self, predicate: F) -> Vec<&Node>
+    where
+        F: Fn(&Information) -> bool,
+    {
+        fn collect<'a, F>(nodes: &'a [Node], predicate: &F, found: &mut Vec<&'a Node>)
+        where
+            F: Fn(&Information) -> bool,
+        {
+            nodes.iter().for_each(|node| {
+                if predicate(node.information()) {
+                    found.push(node);
+                }
+                if let Node::Group { children, .. } = node {
+                    collect(children, predicate, found);
+                }
+            });
+        }
+
+        let mut found = vec![];
+        collect(&self.nodes, &predicate, &mut found);
+        found
+    }
+
+    /// Nodes whose labels contain the text, compared case-insensitively.
+    pub fn find_by_label(&self, text: &str) -> Vec<&Node> {
+        let text = text.to_lowercase();
+        self.find(|i| i.label.to_lowercase().contains(&text))
+    }
+
+    /// Nodes of the datatype of given name (see [`Datatype`]).
+    pub fn find_by_datatype(&self, name: &str) -> Vec<&Node> {
+        self.find(|i| i.datatype().is_some_and(|d| d.name == name))
+    }
+
+    /// Labels of all nodes on the path, starting at the root.
+    pub fn labels<'a>(&'a self, path: &'a [String]) -> Vec<&'a str> {
+        (1..=path.len())
+            .map_while(|i| self.select(&path[..i]))
+            .map(|n| n.information().label.as_str())
+            .collect()
+    }
+
+    /// The most nested node that covers byte at the offset, i. e. a real leaf.
+    pub fn node_at(&self, offset: usize) -> Option<&Node> {
+        let index = self.index();
+        let i = index.partition_point(|l| l.range.end <= offset);
+        index
+            .get(i)
+            .filter(|l| l.range.contains(&offset))
+            .and_then(|l| self.node(&l.position))
+    }
+
+    /// Real leaves that cover at least one byte of the range, in order of their location.
+    pub fn nodes_in(&self, range: Range<usize>) -> Vec<&Node> {
+        let index = self.index();
+        let i = index.partition_point(|l| l.range.end <= range.start);
+        index[i..]
+            .iter()
+            .take_while(|l| l.range.start < range.end)
+            .filter_map(|l| self.node(&l.position))
+            .collect()
+    }
+
+    fn index(&self) -> &[Interval] {
+        fn collect(nodes: &[Node], position: &[usize], index: &mut Vec<Interval>) {
+            nodes.iter().enumerate().for_each(|(i, node)| {
+                let position = [position, &[i]].concat();
+                match node {
+                    Node::Group { children, .. } => collect(children, &position, index),
+                    Node::Leaf(Leaf::Real(leaf)) if !leaf.location.range().is_empty() => index
+                        .push(Interval {
+                            range: leaf.location.range(),
+                            position,
+                        }),
+                    Node::Leaf(_) => (),
+                }
+            });
+        }
+
+        self.index.get_or_init(|| {
+            let mut index = vec![];
+            collect(&self.nodes, &[], &mut index);
+            index.sort_by_key(|l| l.range.start);
+            index
+        })
+    }
+
+    /// Cursor at the first node of the tree, `None` if the tree is empty.
+    pub fn cursor(&self) -> Option<Cursor<'_>> {
+        Cursor::new(self, vec![0])
+    }
+
+    /// Cursor at the node of the path, `None` if there is no such node.
+    pub fn cursor_at(&self, path: &[String]) -> Option<Cursor<'_>> {
+        let position = path
+            .iter()
+            .map(|i| i.parse::<usize>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        Cursor::new(self, position)
+    }
+
+    /// Node at the position, i. e. indices of nodes on the way to it.
+    fn node(&self, position: &[usize]) -> Option<&Node> {
+        let (first, rest) = position.split_first()?;
+        rest.iter()
+            .try_fold(self.nodes.get(*first)?, |node, i| match node {
+                Node::Group { children, .. } => children.get(*i),
+                Node::Leaf(_) => None,
+            })
+    }
+
+    /// Tree in which addresses, which decoders derive for the main network,
+    /// are encoded for the given network, including their explanations.
+    pub fn with_network(mut self, network: Network) -> Tree {
+        if network != Network::Bitcoin {
+            self.nodes.iter_mut().for_each(|n| readdress(n, network));
+        }
+        self
+    }
+
+    /// Tree in which nodes, whose values are keys of `labels` (public
+    /// keys, addresses, hashes or bytes in hex), are tagged by the
+    /// corresponding labels. Keys are matched regardless of case.
+    pub fn with_labels(mut self, labels: &HashMap<String, String>) -> Tree {
+        if !labels.is_empty() {
+            let labels = labels
+                .iter()
+                .map(|(k, v)| (k.to_lowercase(), v.as_str()))
+                .collect::<HashMap<_, _>>();
+            self.nodes.iter_mut().for_each(|n| relabel(n, &labels));
+        }
+        self
+    }
+
+    /// Tree in which documentation and splains of nodes are replaced by
+    /// entries of `catalog`. IDs of annotations start with `prefix`, which
+    /// is symbol of decoder (see [`catalog::ids`](crate::catalog::ids)).
+    pub fn with_catalog(mut self, prefix: &str, catalog: &Catalog) -> Tree {
+        if !catalog.is_empty() {
+            catalog::apply(&mut self.nodes, prefix, catalog);
+        }
+        self
+    }
+
+    /// Tree of only nodes of any of the categories, together with groups
+    /// enclosing them. Nodes within the matching groups are kept as well.
+    pub fn with_categories(self, categories: &[Category]) -> Tree {
+        fn keep(node: Node, categories: &[Category]) -> Option<Node> {
+            if categories
+                .iter()
+                .any(|c| node.information().has_category(*c))
+            {
+                return Some(node);
+            }
+            match node {
+                Node::Group {
+                    path,
+                    location,
+                    information,
+                    children,
+                } => {
+                    let children = children
+                        .into_iter()
+                        .filter_map(|ch| keep(ch, categories))
+                        .collect::<Vec<_>>();
+                    (!children.is_empty()).then_some(Node::Group {
+                        path,
+                        location,
+                        information,
+                        children,
+                    })
+                }
+                Node::Leaf(_) => None,
+            }
+        }
+
+        Tree::from_nodes(
+            self.nodes
+                .into_iter()
+                .filter_map(|n| keep(n, categories))
+                .collect(),
+        )
+    }
+
+    /// Nodes matching the query, in order of their appearance.
+    pub fn query(&self, query: &Query) -> Vec<&Node> {
+        query.select(&self.nodes)
+    }
+
     pub fn select<'a>(&'a self, path: &'a [String]) -> Option<&'a Node> {
-        Self::select_path(&self.0, path)
+        Self::select_path(&self.nodes, path)
     }
 
     fn select_path<'a>(tree: &'a [Node], path: &'a [String]) -> Option<&'a Node> {
@@ -259,10 +708,180 @@ impl Tree {
     }
 }
 
+fn relabel(node: &mut Node, labels: &HashMap<String, &str>) {
+    let information = match node {
+        Node::Group {
+            information,
+            children,
+            ..
+        } => {
+            children.iter_mut().for_each(|ch| relabel(ch, labels));
+            information
+        }
+        Node::Leaf(Leaf::Real(l)) => &mut l.information,
+        Node::Leaf(Leaf::Virtual(l)) => &mut l.information,
+    };
+
+    if let Some(label) = label_of(&information.value, labels) {
+        information.tags.push(Tag {
+            label: label.to_string(),
+            color: None,
+            doc: Some("Label given to this value in dictionary of labels.".to_string()),
+        });
+    }
+}
+
+/// Label of value that identifies something, either of its alternatives.
+fn label_of<'a>(value: &Value, labels: &HashMap<String, &'a str>) -> Option<&'a str> {
+    match value {
+        Value::Addr(Some(_)) | Value::PublicKey(_) | Value::Hash(_) | Value::Bytes(_) => {
+            labels.get(&value.plain().to_lowercase()).copied()
+        }
+        Value::Alt(v1, v2) => label_of(v1, labels).or_else(|| label_of(v2, labels)),
+        _ => None,
+    }
+}
+
+fn readdress(node: &mut Node, network: Network) {
+    let information = match node {
+        Node::Group {
+            information,
+            children,
+            ..
+        } => {
+            children.iter_mut().for_each(|ch| readdress(ch, network));
+            information
+        }
+        Node::Leaf(Leaf::Real(l)) => &mut l.information,
+        Node::Leaf(Leaf::Virtual(l)) => &mut l.information,
+    };
+
+    if let Value::Addr(Some(old)) = &information.value {
+        let new = Address::from_script(&old.script_pubkey(), network).ok();
+        // Only splains generated from the address depend on its network.
+        if information.splain.is_some() && information.splain == address_splain(old) {
+            information.splain = new.as_ref().and_then(address_splain);
+        }
+        information.value = Value::Addr(new);
+    }
+}
+
+/// Position of a node in [`Tree`] that can move to the nodes around it
+/// (parent, children, siblings or neighbouring leaves). Moves return new
+/// cursor, `None` if there is no node to move to.
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    tree: &'a Tree,
+    /// Indices of nodes on the way to the node, starting at the root.
+    position: Vec<usize>,
+    node: &'a Node,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(tree: &'a Tree, position: Vec<usize>) -> Option<Cursor<'a>> {
+        let node = tree.node(&position)?;
+        Some(Cursor {
+            tree,
+            position,
+            node,
+        })
+    }
+
+    /// Node at the cursor.
+    pub fn node(&self) -> &'a Node {
+        self.node
+    }
+
+    /// Number of groups enclosing the node, 0 for nodes at the root.
+    pub fn depth(&self) -> usize {
+        self.position.len() - 1
+    }
+
+    /// Index of the node among its siblings.
+    pub fn index(&self) -> usize {
+        self.position[self.depth()]
+    }
+
+    /// The node and its siblings, i. e. all children of its parent.
+    pub fn siblings(&self) -> &'a [Node] {
+        match self.parent().map(|p| p.node) {
+            Some(Node::Group { children, .. }) => children,
+            _ => &self.tree.nodes,
+        }
+    }
+
+    /// Group enclosing the node.
+    pub fn parent(&self) -> Option<Cursor<'a>> {
+        let (_, parent) = self.position.split_last()?;
+        if parent.is_empty() {
+            None
+        } else {
+            Cursor::new(self.tree, parent.to_vec())
+        }
+    }
+
+    /// The first child of the node, if it is a non-empty group.
+    pub fn first_child(&self) -> Option<Cursor<'a>> {
+        Cursor::new(self.tree, [&self.position[..], &[0]].concat())
+    }
+
+    /// The last child of the node, if it is a non-empty group.
+    pub fn last_child(&self) -> Option<Cursor<'a>> {
+        match self.node {
+            Node::Group { children, .. } => Cursor::new(
+                self.tree,
+                [&self.position[..], &[children.len().checked_sub(1)?]].concat(),
+            ),
+            Node::Leaf(_) => None,
+        }
+    }
+
+    pub fn next_sibling(&self) -> Option<Cursor<'a>> {
+        self.sibling(self.index() + 1)
+    }
+
+    pub fn prev_sibling(&self) -> Option<Cursor<'a>> {
+        self.sibling(self.index().checked_sub(1)?)
+    }
+
+    fn sibling(&self, index: usize) -> Option<Cursor<'a>> {
+        let mut position = self.position.clone();
+        position[self.depth()] = index;
+        Cursor::new(self.tree, position)
+    }
+
+    /// The following node in order of appearance (i. e. the first child,
+    /// or the next sibling of the node or of the closest of its ancestors).
+    pub fn next(&self) -> Option<Cursor<'a>> {
+        self.first_child().or_else(|| {
+            std::iter::successors(Some(self.clone()), Cursor::parent).find_map(|c| c.next_sibling())
+        })
+    }
+
+    /// The preceding node in order of appearance (i. e. the most nested
+    /// last descendant of the previous sibling, or the parent).
+    pub fn prev(&self) -> Option<Cursor<'a>> {
+        match self.prev_sibling() {
+            Some(sibling) => std::iter::successors(Some(sibling), Cursor::last_child).last(),
+            None => self.parent(),
+        }
+    }
+
+    /// The following leaf in order of appearance.
+    pub fn next_leaf(&self) -> Option<Cursor<'a>> {
+        std::iter::successors(self.next(), Cursor::next).find(|c| matches!(c.node, Node::Leaf(_)))
+    }
+
+    /// The preceding leaf in order of appearance.
+    pub fn prev_leaf(&self) -> Option<Cursor<'a>> {
+        std::iter::successors(self.prev(), Cursor::prev).find(|c| matches!(c.node, Node::Leaf(_)))
+    }
+}
+
 impl Deref for Tree {
     type Target = [Node];
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.nodes
     }
 }
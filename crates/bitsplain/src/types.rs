@@ -1,21 +1,26 @@
 //! Basic and common data types and their parsers.
 
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+
 use bitcoin::blockdata::constants::genesis_block;
 use bitcoin::hashes::{sha256d, Hash};
 use bitcoin::secp256k1::ecdsa::Signature;
 use bitcoin::{Amount, BlockHash, Network, PublicKey, Txid};
+use bytes::Bytes;
 use nom::combinator::success;
-use nom::multi::length_count;
 use nom::number::streaming::*;
-use nom::{Parser, ToUsize};
+use nom::{InputLength, InputTake, Needed, Parser, ToUsize};
 use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
 use crate::parse::*;
 use crate::value::*;
 use crate::*;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Sat(Decimal);
 
 const SATS: Decimal = Decimal::from_parts(100000000, 0, 0, false, 0);
@@ -42,6 +47,10 @@ impl Sat {
     }
 }
 
+/// Lightning amount in millisatoshis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MSat(pub u64);
+
 pub fn sat(input: Span) -> Parsed<Sat> {
     with("datatype", "sat", le_u64)(input).map(|(s, n)| (s, Sat::new(n.into())))
 }
@@ -71,11 +80,77 @@ impl ToValue for ChainHash {
     }
 }
 
+/// Host part of [`NetAddr`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Host {
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    /// Tor onion service, name including `.onion`.
+    Onion(String),
+    /// DNS hostname.
+    Dns(String),
+}
+
+/// Network address at which a node can be reached, host and port. It is
+/// written as `host:port` with IPv6 addresses in brackets (e. g. `[::1]:9735`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetAddr {
+    pub host: Host,
+    pub port: u16,
+}
+
+impl NetAddr {
+    pub fn new(host: Host, port: u16) -> NetAddr {
+        NetAddr { host, port }
+    }
+}
+
+impl Display for NetAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.host {
+            Host::Ipv4(ip) => write!(f, "{ip}:{}", self.port),
+            Host::Ipv6(ip) => write!(f, "[{ip}]:{}", self.port),
+            Host::Onion(name) | Host::Dns(name) => write!(f, "{name}:{}", self.port),
+        }
+    }
+}
+
+impl FromStr for NetAddr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(addr) = s.parse::<SocketAddr>() {
+            let host = match addr.ip() {
+                IpAddr::V4(ip) => Host::Ipv4(ip),
+                IpAddr::V6(ip) => Host::Ipv6(ip),
+            };
+            return Ok(NetAddr::new(host, addr.port()));
+        }
+
+        let (name, port) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("Missing port in network address '{s}'."))?;
+        let port = port
+            .parse()
+            .map_err(|_| format!("Invalid port in network address '{s}'."))?;
+        let host = if name.ends_with(".onion") {
+            Host::Onion(name.to_string())
+        } else {
+            Host::Dns(name.to_string())
+        };
+        Ok(NetAddr::new(host, port))
+    }
+}
+
+impl ToValue for NetAddr {
+    fn to_value(&self) -> Value {
+        Value::NetAddr(self.clone())
+    }
+}
+
 /// Parser of chain hash, little endian.
 pub fn chain_hash_le(s: Span) -> Parsed<ChainHash> {
-    let (s, mut b) = bytes(32_usize)(s)?;
-
-    b.reverse();
+    let (s, b) = bytes_be(32_usize)(s)?;
 
     let block_hash = BlockHash::from_slice(&b).unwrap();
 
@@ -119,7 +194,7 @@ pub fn chain_hash_be(s: Span) -> Parsed<ChainHash> {
     };
 
     Ok((
-        s.with("datatype", "chain_hash"),
+        s.with("datatype", "chain_hash_be"),
         ChainHash {
             block_hash,
             network,
@@ -127,17 +202,29 @@ pub fn chain_hash_be(s: Span) -> Parsed<ChainHash> {
     ))
 }
 
+/// Parser of `len` bytes. Returned bytes are a view of the parsed data, they are not copied.
 pub fn bytes<'a, U: ToUsize + std::fmt::Debug + Copy>(
     len: U,
-) -> impl Fn(Span<'a>) -> Parsed<'a, Vec<u8>> {
-    move |input: Span<'a>| with("datatype", "bytes", length_count(success(len), u8))(input)
+) -> impl Fn(Span<'a>) -> Parsed<'a, Bytes> {
+    move |input: Span<'a>| {
+        with("datatype", "bytes", |s: Span<'a>| {
+            let len = len.to_usize();
+            if s.input_len() < len {
+                return Err(nom::Err::Incomplete(Needed::new(len - s.input_len())));
+            }
+            let (b, s) = s.take_split(len);
+            Ok((s, b.view()))
+        })(input)
+    }
 }
 
+/// Parser of `len` bytes in reversed order.
 pub fn bytes_be<'a, U: ToUsize + std::fmt::Debug + Copy>(
     len: U,
 ) -> impl Fn(Span<'a>) -> Parsed<'a, Vec<u8>> {
     move |input: Span<'a>| {
-        let (s, mut x) = with("datatype", "bytes", length_count(success(len), u8))(input)?;
+        let (s, b) = bytes(len)(input)?;
+        let mut x = b.to_vec();
         x.reverse();
         Ok((s, x))
     }
@@ -157,7 +244,17 @@ pub fn txid(input: Span) -> Parsed<Txid> {
 pub fn signature(input: Span) -> Parsed<Signature> {
     let (s, b) = bytes(64_usize)(input)?;
     match Signature::from_compact(&b) {
-        Ok(sig) => Ok((s.with("datatype", "signature"), sig)),
+        Ok(sig) => {
+            let mut normalized = sig;
+            normalized.normalize_s();
+            Ok((
+                s.with("datatype", "signature").warn_if(
+                    normalized != sig,
+                    "Signature with high S value is not standard",
+                ),
+                sig,
+            ))
+        }
         Err(_) => Err(nom::Err::Failure(nom::error::Error {
             input: s,
             code: nom::error::ErrorKind::Fail,
@@ -179,19 +276,33 @@ pub fn public_key(input: Span) -> Parsed<PublicKey> {
 fn varint_impl(input: Span) -> Parsed<u64> {
     let (s, byte) = le_u8(input)?;
 
-    let s_int = match byte {
+    // Values have to be encoded in the shortest possible form.
+    let (s, int, minimal) = match byte {
         0xfd => {
             let (s, a) = le_u16(s)?;
-            (s, a as u64)
+            (s, a as u64, 0xfd)
         }
         0xfe => {
             let (s, a) = le_u32(s)?;
-            (s, a as u64)
+            (s, a as u64, 0x10000)
+        }
+        0xff => {
+            let (s, a) = le_u64(s)?;
+            (s, a, 0x100000000)
+        }
+        n => {
+            let (s, a) = success(n as u64)(s)?;
+            (s, a, 0)
         }
-        0xff => le_u64(s)?,
-        n => success(n as u64)(s)?,
     };
-    Ok(s_int)
+
+    Ok((
+        s.warn_if(
+            int < minimal,
+            "Non-canonical varint, value could be encoded in fewer bytes",
+        ),
+        int,
+    ))
 }
 
 pub fn uint32(input: Span) -> Parsed<u32> {
@@ -199,7 +310,7 @@ pub fn uint32(input: Span) -> Parsed<u32> {
 }
 
 pub fn uint32_be(input: Span) -> Parsed<u32> {
-    with("datatype", "uint32", be_u32)(input)
+    with("datatype", "uint32_be", be_u32)(input)
 }
 
 pub fn int32(input: Span) -> Parsed<i32> {
@@ -207,13 +318,19 @@ pub fn int32(input: Span) -> Parsed<i32> {
 }
 
 pub fn int32_be(input: Span) -> Parsed<i32> {
-    with("datatype", "int32", be_i32)(input)
+    with("datatype", "int32_be", be_i32)(input)
 }
 
 pub fn varint(input: Span) -> Parsed<u64> {
     with("datatype", "varint", varint_impl)(input)
 }
 
+/// Whether `time` is later than current time. Current time is not available
+/// on `wasm32-unknown-unknown`, where no time is considered to be in the future.
+fn in_future(time: OffsetDateTime) -> bool {
+    !cfg!(all(target_arch = "wasm32", target_os = "unknown")) && time > OffsetDateTime::now_utc()
+}
+
 /// Unix timestamp parser. Provided parser is used for the numeric value,
 /// typically `uint32` or `be_u32`.
 pub fn timestamp<'a, Parse>(mut parser: Parse) -> impl FnMut(Span<'a>) -> Parsed<OffsetDateTime>
@@ -222,9 +339,11 @@ where
 {
     move |input: Span| {
         parser.parse(input).map(|(s, ts)| {
+            let time = OffsetDateTime::from_unix_timestamp(ts.into()).unwrap();
             (
-                s.with("datatype", "timestamp"),
-                OffsetDateTime::from_unix_timestamp(ts.into()).unwrap(),
+                s.with("datatype", "timestamp")
+                    .warn_if(in_future(time), "Timestamp is in the future"),
+                time,
             )
         })
     }
@@ -1,30 +1,36 @@
 //! `Value` is intermediate type between domain-specific types and final presentation.
 
 use std::fmt::Display;
+use std::time::Duration;
 
 // use bitcoin::hashes::hex::ToHex;
 use bitcoin::hashes::{sha256d, Hash};
 use bitcoin::secp256k1::ecdsa::Signature;
-use bitcoin::{Address, BlockHash, PublicKey, ScriptBuf, Txid};
+use bitcoin::{Address, BlockHash, FeeRate, PublicKey, ScriptBuf, Txid};
 use bytes::Bytes;
+use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
-use crate::types::Sat;
+use crate::timestamp::TimeFormat;
+use crate::types::{MSat, NetAddr, Sat};
 
 /// Set of primitive values that can be formatted depending on the context.
 /// Parsing any binary data will result in a [`Tree`](crate::tree::Tree) of these values.
-#[derive(Clone, Debug)]
+///
+/// Values are serialized with tag `type` and content `value`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
 pub enum Value {
     /// Bitcoin address.
+    #[serde(with = "crate::serialize::address")]
     Addr(Option<Address>),
 
     /// Any integral value.
+    #[serde(with = "crate::serialize::num")]
     Num(i128),
 
-    /// Byte size.
-    Size(u64),
-
     /// Any arbitrary byte array.
+    #[serde(with = "crate::serialize::hex_bytes")]
     Bytes(Bytes),
 
     /// Bitcoin script.
@@ -47,6 +53,7 @@ pub enum Value {
     Hash(sha256d::Hash),
 
     /// Any sort of timestamp.x
+    #[serde(with = "time::serde::rfc3339")]
     Timestamp(time::OffsetDateTime),
 
     /// Alternative values.
@@ -55,10 +62,69 @@ pub enum Value {
     /// Bitcoin amount in satoshis.
     Sat(Sat),
 
+    /// Lightning amount in millisatoshis.
+    MSat(u64),
+
+    /// Fee rate in satoshis per virtual byte.
+    FeeRate(f64),
+
+    /// Duration in seconds.
+    Duration(u64),
+
+    /// Boolean.
+    Bool(bool),
+
+    /// Values of the same kind.
+    List(Vec<Value>),
+
+    /// Network address of a node (IP, onion service or hostname and port).
+    #[serde(with = "crate::serialize::net_addr")]
+    NetAddr(NetAddr),
+
     /// No value.
     Nil,
 }
 
+/// Unit of numeric value, which user interfaces use as suffix of the
+/// value and for conversions to more readable units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    /// Satoshis.
+    Sat,
+    /// Millisatoshis (Lightning Network).
+    Msat,
+    /// Bytes.
+    Bytes,
+    /// Virtual bytes, i. e. weight units divided by four.
+    Vbytes,
+    /// Weight units.
+    #[serde(rename = "wu")]
+    WeightUnits,
+    /// Seconds.
+    Seconds,
+    /// Number of blocks.
+    Blocks,
+    /// Parts per million.
+    Ppm,
+}
+
+impl Unit {
+    /// Abbreviation of the unit appended to values.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Unit::Sat => "sat",
+            Unit::Msat => "msat",
+            Unit::Bytes => "B",
+            Unit::Vbytes => "vB",
+            Unit::WeightUnits => "WU",
+            Unit::Seconds => "s",
+            Unit::Blocks => "blocks",
+            Unit::Ppm => "ppm",
+        }
+    }
+}
+
 impl Value {
     /// Creates alternative from two distinct values.
     #[inline]
@@ -78,6 +144,12 @@ impl Value {
         Value::Num(num.into())
     }
 
+    /// Creates list of values.
+    #[inline]
+    pub fn list<T: ToValue>(items: &[T]) -> Value {
+        Value::List(items.iter().map(ToValue::to_value).collect())
+    }
+
     /// Creates textual value from anything that has [`Display`].
     #[inline]
     pub fn display<S: Display>(value: S) -> Value {
@@ -124,7 +196,6 @@ impl Value {
         match self {
             Value::Addr(a) => format!("{:?}", a),
             Value::Num(n) => n.to_string(),
-            Value::Size(s) => s.to_string(),
             Value::Bytes(b) => hex::encode(b),
             Value::Script(s) => s.to_string(),
             Value::Signature(s) => s.to_string(), //.serialize_compact().to_hex(),
@@ -133,8 +204,37 @@ impl Value {
             Value::Hash(id) => id.to_string(),
             Value::Alt(v1, v2) => format!("{}/{}", v1.preview(), v2.preview()),
             Value::Sat(s) => s.as_str(),
+            Value::MSat(n) => format!("{n} msat"),
+            Value::FeeRate(r) => format!("{r:.2} sat/vB"),
+            Value::Duration(d) => format!("{d} s"),
+            Value::Bool(b) => b.to_string(),
+            Value::NetAddr(a) => a.to_string(),
+            Value::List(items) => items
+                .iter()
+                .map(Value::preview)
+                .collect::<Vec<_>>()
+                .join(", "),
             Value::Nil => "".to_string(),
-            Value::Timestamp(ts) => ts.to_string(),
+            Value::Timestamp(ts) => TimeFormat::default().format(ts),
+        }
+    }
+
+    /// Plain representation of the value meant to be processed by other
+    /// programs: amounts in satoshis or millisatoshis, fee rates in sat/vB,
+    /// durations in seconds, timestamps in Unix time, alternatives by their
+    /// first value and lists separated by commas.
+    pub fn plain(&self) -> String {
+        match self {
+            Value::Addr(a) => a.as_ref().map(|a| a.to_string()).unwrap_or_default(),
+            Value::Text { text, .. } => text.to_string(),
+            Value::Alt(v, _) => v.plain(),
+            Value::Sat(s) => s.sat().to_string(),
+            Value::MSat(n) => n.to_string(),
+            Value::FeeRate(r) => r.to_string(),
+            Value::Duration(d) => d.to_string(),
+            Value::List(items) => items.iter().map(Value::plain).collect::<Vec<_>>().join(","),
+            Value::Timestamp(ts) => ts.unix_timestamp().to_string(),
+            v => v.preview(),
         }
     }
 }
@@ -155,6 +255,12 @@ impl ToValue for i32 {
     }
 }
 
+impl ToValue for i64 {
+    fn to_value(&self) -> Value {
+        Value::Num(*self as i128)
+    }
+}
+
 impl ToValue for u16 {
     fn to_value(&self) -> Value {
         Value::Num(*self as i128)
@@ -215,6 +321,12 @@ impl ToValue for Vec<u8> {
     }
 }
 
+impl ToValue for Bytes {
+    fn to_value(&self) -> Value {
+        Value::Bytes(self.clone())
+    }
+}
+
 impl ToValue for &str {
     fn to_value(&self) -> Value {
         Value::text(self)
@@ -223,11 +335,27 @@ impl ToValue for &str {
 
 impl ToValue for bool {
     fn to_value(&self) -> Value {
-        if *self {
-            Value::Num(1.into())
-        } else {
-            Value::Num(0.into())
-        }
+        Value::Bool(*self)
+    }
+}
+
+impl ToValue for MSat {
+    fn to_value(&self) -> Value {
+        Value::MSat(self.0)
+    }
+}
+
+/// Fee rate in sat/vB, which may be fractional.
+impl ToValue for FeeRate {
+    fn to_value(&self) -> Value {
+        Value::FeeRate(self.to_sat_per_kwu() as f64 * 4.0 / 1000.0)
+    }
+}
+
+/// Duration in whole seconds.
+impl ToValue for Duration {
+    fn to_value(&self) -> Value {
+        Value::Duration(self.as_secs())
     }
 }
 